@@ -0,0 +1,239 @@
+use core::convert::TryFrom;
+use core::fmt;
+use core::mem;
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use {GroveError, ITree, NodeId};
+
+/// Shared Tree of `Arc`s
+///
+/// An immutable tree where every node keeps its children behind an `Arc`, so a
+/// subtree can be part of any number of trees at the same time without
+/// copying it. Cloning an `ArcTree` is cheap and changes like `graft` or
+/// `replace` only copy the nodes on the path from the root to the change,
+/// everything else is shared with the original tree.
+///
+/// Nodes are addressed by their path from the root, the position of every
+/// child to go down to in turn. The empty path is the root itself.
+pub struct ArcTree<T> {
+    node: Arc<ArcNode<T>>,
+}
+
+struct ArcNode<T> {
+    value: T,
+    children: Vec<ArcTree<T>>,
+}
+
+impl<T> ArcTree<T> {
+    /// Create a tree with a single node
+    pub fn new(value: T) -> Self {
+        ArcTree::with_children(value, Vec::new())
+    }
+
+    /// Create a tree out of a root value and the subtrees of its children
+    pub fn with_children(value: T, children: Vec<ArcTree<T>>) -> Self {
+        ArcTree { node: Arc::new(ArcNode { value, children }) }
+    }
+
+    /// Get the value of the root
+    pub fn value(&self) -> &T {
+        &self.node.value
+    }
+
+    /// Get the subtrees of the root's children
+    pub fn children(&self) -> &[ArcTree<T>] {
+        &self.node.children
+    }
+
+    /// Get the subtree at the given path if it exists
+    pub fn get(&self, path: &[usize]) -> Option<&ArcTree<T>> {
+        let mut tree = self;
+        for &x in path {
+            tree = tree.node.children.get(x)?;
+        }
+        Some(tree)
+    }
+
+    /// Get the number of nodes in the tree. Shared subtrees are counted every
+    /// time they show up.
+    pub fn len(&self) -> usize {
+        let mut len = 0;
+        let mut stack = vec![self];
+        while let Some(tree) = stack.pop() {
+            len += 1;
+            stack.extend(tree.children());
+        }
+        len
+    }
+
+    /// Trees always have a root, so they are never empty
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Check if two trees share the same root node
+    pub fn ptr_eq(a: &ArcTree<T>, b: &ArcTree<T>) -> bool {
+        Arc::ptr_eq(&a.node, &b.node)
+    }
+
+    /// Add a subtree after the last child of the node at the given path.
+    /// Returns the new tree, or `None` if the path doesn't lead to a node.
+    pub fn graft(&self, path: &[usize], subtree: ArcTree<T>) -> Option<ArcTree<T>>
+        where T: Clone
+    {
+        self.update(path, |node| {
+            let mut children = node.children().to_vec();
+            children.push(subtree);
+            ArcTree::with_children(node.value().clone(), children)
+        })
+    }
+
+    /// Put a subtree in the place of the node at the given path. Returns the
+    /// new tree, or `None` if the path doesn't lead to a node.
+    pub fn replace(&self, path: &[usize], subtree: ArcTree<T>) -> Option<ArcTree<T>>
+        where T: Clone
+    {
+        self.update(path, |_| subtree)
+    }
+
+    /// Copy the path down to a node and replace the node with the result of
+    /// `f`, sharing every subtree off the path
+    fn update<F>(&self, path: &[usize], f: F) -> Option<ArcTree<T>>
+        where F: FnOnce(&ArcTree<T>) -> ArcTree<T>,
+              T: Clone
+    {
+        let mut trees = vec![self];
+        for &x in path {
+            trees.push(trees[trees.len() - 1].children().get(x)?);
+        }
+        let mut new = f(trees.pop().unwrap());
+        for (tree, &x) in trees.into_iter().zip(path).rev() {
+            let mut children = tree.children().to_vec();
+            children[x] = new;
+            new = ArcTree::with_children(tree.value().clone(), children);
+        }
+        Some(new)
+    }
+
+    /// Copy the tree into an `ITree`. Shared subtrees are copied every time they
+    /// show up and nodes get their `NodeId`s in pre-order.
+    pub fn to_itree(&self) -> ITree<T>
+        where T: Clone
+    {
+        let mut tree = ITree::new();
+        let mut stack: Vec<(&ArcTree<T>, Option<NodeId>)> = vec![(self, None)];
+        while let Some((node, parent)) = stack.pop() {
+            let value = node.value().clone();
+            let id = match parent {
+                Some(parent) => tree.add_node(parent, value),
+                None => tree.add_root(value).unwrap(),
+            };
+            stack.extend(node.children().iter().rev().map(|child| (child, Some(id))));
+        }
+        tree
+    }
+}
+
+impl<T> Clone for ArcTree<T> {
+    /// Create another handle to the same tree without copying any nodes
+    fn clone(&self) -> Self {
+        ArcTree { node: Arc::clone(&self.node) }
+    }
+}
+
+impl<T> Drop for ArcTree<T> {
+    /// Free nodes that are no longer shared one at a time, so dropping deep
+    /// trees doesn't overflow the stack
+    fn drop(&mut self) {
+        let mut stack = match Arc::get_mut(&mut self.node) {
+            Some(node) => mem::take(&mut node.children),
+            None => return,
+        };
+        while let Some(mut tree) = stack.pop() {
+            if let Some(node) = Arc::get_mut(&mut tree.node) {
+                stack.append(&mut node.children);
+            }
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for ArcTree<T> {
+    fn eq(&self, other: &ArcTree<T>) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some((a, b)) = stack.pop() {
+            if ArcTree::ptr_eq(a, b) {
+                continue;
+            }
+            if a.value() != b.value() || a.children().len() != b.children().len() {
+                return false;
+            }
+            stack.extend(a.children().iter().zip(b.children()));
+        }
+        true
+    }
+}
+
+impl<T: Eq> Eq for ArcTree<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for ArcTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ArcTree")
+            .field("value", self.value())
+            .field("children", &self.children())
+            .finish()
+    }
+}
+
+impl<T> TryFrom<ITree<T>> for ArcTree<T> {
+    type Error = GroveError;
+
+    /// Convert an `ITree` into an `ArcTree`, failing if the tree is empty
+    fn try_from(tree: ITree<T>) -> Result<Self, GroveError> {
+        if tree.is_empty() {
+            return Err(GroveError::EmptyTree);
+        }
+        let mut done: Vec<Option<ArcTree<T>>> = Vec::with_capacity(tree.len());
+        done.resize_with(tree.len(), || None);
+        let ITree { values, children, .. } = tree;
+        for (x, (value, children)) in values.into_iter().zip(children).enumerate().rev() {
+            let children = children
+                .iter()
+                .map(|c| done[c.index()].take().unwrap())
+                .collect();
+            done[x] = Some(ArcTree::with_children(value, children));
+        }
+        Ok(done.swap_remove(0).unwrap())
+    }
+}
+
+#[test]
+fn sharing() {
+    let base = ArcTree::try_from(tree!{ "root" => ["a" => ["a1", "a2"], "b"] }).unwrap();
+    assert_eq!(base.len(), 5);
+    assert_eq!(base.get(&[0, 1]).unwrap().value(), &"a2");
+    assert!(base.get(&[2]).is_none());
+
+    // Both overlays share the untouched subtree of "a" with the base
+    let extra = ArcTree::new("c");
+    let one = base.graft(&[], extra.clone()).unwrap();
+    let two = base.replace(&[1], extra.clone()).unwrap();
+    assert!(ArcTree::ptr_eq(one.get(&[0]).unwrap(), base.get(&[0]).unwrap()));
+    assert!(ArcTree::ptr_eq(two.get(&[0]).unwrap(), base.get(&[0]).unwrap()));
+    assert!(ArcTree::ptr_eq(&one.children()[2], &extra));
+    assert!(base.graft(&[5], extra.clone()).is_none());
+
+    assert_eq!(one.to_itree(), tree!{ "root" => ["a" => ["a1", "a2"], "b", "c"] });
+    assert_eq!(two.to_itree(), tree!{ "root" => ["a" => ["a1", "a2"], "c"] });
+    assert_eq!(base, ArcTree::try_from(base.to_itree()).unwrap());
+    assert_ne!(base, one);
+    assert_eq!(ArcTree::try_from(ITree::<u8>::new()), Err(GroveError::EmptyTree));
+
+    // Deep trees drop without blowing the stack
+    let mut deep = ArcTree::new(0);
+    for x in 1..100_000 {
+        deep = ArcTree::with_children(x, vec![deep]);
+    }
+    drop(deep);
+}
@@ -0,0 +1,76 @@
+use core::ops::{Index, IndexMut};
+
+use alloc::vec::Vec;
+
+/// Nodes of a keyed tree stored by index
+///
+/// Slots of removed nodes get reused by later insertions. The keyed trees of
+/// this crate link their nodes together through these indices and never hand
+/// them out, so unlike `MTree` indices of removed nodes don't need a
+/// generation.
+#[derive(Debug, Clone)]
+pub struct Arena<N> {
+    slots: Vec<Option<N>>,
+    /// Slots of removed nodes that can be reused
+    free: Vec<usize>,
+}
+
+impl<N> Arena<N> {
+    pub fn new() -> Self {
+        Arena { slots: Vec::new(), free: Vec::new() }
+    }
+
+    /// Store a node and return its index
+    pub fn insert(&mut self, node: N) -> usize {
+        match self.free.pop() {
+            Some(x) => {
+                self.slots[x] = Some(node);
+                x
+            }
+            None => {
+                self.slots.push(Some(node));
+                self.slots.len() - 1
+            }
+        }
+    }
+
+    /// Take a node out of the arena, freeing its slot
+    pub fn remove(&mut self, x: usize) -> N {
+        let node = self.slots[x].take().expect("node was removed already");
+        self.free.push(x);
+        node
+    }
+
+    /// Get mutable references to two different nodes
+    pub fn pair_mut(&mut self, a: usize, b: usize) -> (&mut N, &mut N) {
+        assert!(a != b, "can't borrow a node twice");
+        let (low, high) = self.slots.split_at_mut(a.max(b));
+        let (low, high) = (low[a.min(b)].as_mut().unwrap(), high[0].as_mut().unwrap());
+        if a < b { (low, high) } else { (high, low) }
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.free.clear();
+    }
+}
+
+impl<N> Default for Arena<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N> Index<usize> for Arena<N> {
+    type Output = N;
+
+    fn index(&self, x: usize) -> &N {
+        self.slots[x].as_ref().expect("node was removed")
+    }
+}
+
+impl<N> IndexMut<usize> for Arena<N> {
+    fn index_mut(&mut self, x: usize) -> &mut N {
+        self.slots[x].as_mut().expect("node was removed")
+    }
+}
@@ -0,0 +1,149 @@
+use core::borrow::Borrow;
+use core::mem;
+
+use bst::Bst;
+
+/// AVL Tree Map
+///
+/// An ordered map on a binary search tree that keeps the heights of the two
+/// subtrees of every node within one of each other, rotating nodes around
+/// after insertions and removals whenever they drift further apart. The
+/// strict balance keeps lookups fast at the cost of some more rotations than
+/// a red-black tree needs.
+#[derive(Clone)]
+pub struct AvlMap<K, V> {
+    /// Nodes keep the height of their subtree
+    tree: Bst<K, V, u8>,
+}
+
+impl<K: Ord, V> AvlMap<K, V> {
+    /// Create a new empty `AvlMap`
+    pub fn new() -> Self {
+        AvlMap { tree: Bst::new() }
+    }
+
+    /// Insert a value under a key, returning the value the key had before
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.tree.search(&key) {
+            Ok(x) => Some(mem::replace(&mut self.tree.nodes[x].value, value)),
+            Err(at) => {
+                self.tree.attach(at, key, value, 1);
+                self.tree.retrace(at.map(|(p, _)| p));
+                None
+            }
+        }
+    }
+
+    /// Remove a key and return its value
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let x = self.tree.find(key)?;
+        let x = self.tree.leafward(x);
+        let (node, parent, _) = self.tree.unlink(x);
+        self.tree.retrace(parent);
+        Some(node.value)
+    }
+}
+
+/// What the nodes of an AVL tree keep to balance it
+///
+/// Besides the height of their subtree nodes can keep more information about
+/// it, which `update` works out again whenever the children of a node change.
+pub(crate) trait AvlMeta<K, V>: Sized {
+    fn height(&self) -> u8;
+
+    /// Work out the meta of a node from the ones of its children
+    fn update(tree: &mut Bst<K, V, Self>, x: usize);
+}
+
+impl<K, V> AvlMeta<K, V> for u8 {
+    fn height(&self) -> u8 {
+        *self
+    }
+
+    fn update(tree: &mut Bst<K, V, u8>, x: usize) {
+        let node = &tree.nodes[x];
+        let height = 1 + tree.height_of(node.left).max(tree.height_of(node.right));
+        tree.nodes[x].meta = height;
+    }
+}
+
+impl<K, V, M: AvlMeta<K, V>> Bst<K, V, M> {
+    /// Rebalance every node from `x` up to the root
+    pub(crate) fn retrace(&mut self, mut x: Option<usize>) {
+        while let Some(n) = x {
+            let n = self.balance(n);
+            x = self.nodes[n].parent;
+        }
+    }
+
+    /// Rotate a node whose subtrees are off balance, returning the node that
+    /// ends up in its place
+    fn balance(&mut self, x: usize) -> usize {
+        M::update(self, x);
+        let (left, right) = (self.nodes[x].left, self.nodes[x].right);
+        let tilt = self.height_of(left) as i16 - self.height_of(right) as i16;
+        let top = if tilt > 1 {
+            let left = left.unwrap();
+            if self.height_of(self.nodes[left].left) < self.height_of(self.nodes[left].right) {
+                let inner = self.rotate_left(left);
+                M::update(self, left);
+                M::update(self, inner);
+            }
+            self.rotate_right(x)
+        } else if tilt < -1 {
+            let right = right.unwrap();
+            if self.height_of(self.nodes[right].right) < self.height_of(self.nodes[right].left) {
+                let inner = self.rotate_right(right);
+                M::update(self, right);
+                M::update(self, inner);
+            }
+            self.rotate_left(x)
+        } else {
+            return x;
+        };
+        M::update(self, x);
+        M::update(self, top);
+        top
+    }
+
+    pub(crate) fn height_of(&self, x: Option<usize>) -> u8 {
+        x.map_or(0, |x| self.nodes[x].meta.height())
+    }
+}
+
+bst_map!(AvlMap, u8);
+bst_lookup!(AvlMap);
+
+#[test]
+fn avl_map() {
+    /// Check that the stored heights are right and balanced, returning the
+    /// height of the subtree
+    fn check<K, V>(map: &AvlMap<K, V>, x: Option<usize>) -> u8 {
+        let x = match x {
+            Some(x) => x,
+            None => return 0,
+        };
+        let node = &map.tree.nodes[x];
+        let (left, right) = (check(map, node.left), check(map, node.right));
+        assert!((left as i16 - right as i16).abs() <= 1);
+        assert_eq!(node.meta, 1 + left.max(right));
+        node.meta
+    }
+
+    // Keys in order would make a plain binary search tree a list
+    let mut map: AvlMap<_, _> = (0..1000).map(|k| (k, k)).collect();
+    assert_eq!(check(&map, map.tree.root), 10);
+    assert_eq!(map.insert(500, 0), Some(500));
+    assert!(map.range(10..20).map(|(&k, _)| k).eq(10..20));
+    for k in (0..1000).filter(|k| k % 4 != 0) {
+        assert_eq!(map.remove(&k), Some(k));
+    }
+    assert_eq!(map.remove(&1), None);
+    assert_eq!(map.len(), 250);
+    check(&map, map.tree.root);
+    assert!(map.iter().map(|(&k, _)| k).eq((0..1000).step_by(4)));
+    assert_eq!(map.tree.height(), check(&map, map.tree.root) as usize);
+}
@@ -0,0 +1,161 @@
+use core::iter::FromIterator;
+
+use alloc::vec::Vec;
+
+use {ITree, NodeId};
+
+/// Distance between two items for a `BkTree`
+///
+/// The distance has to be a metric: zero only between equal items, the same
+/// in both directions and never more than a detour through a third item.
+pub trait Metric<T: ?Sized> {
+    fn distance(&self, a: &T, b: &T) -> usize;
+}
+
+/// Levenshtein distance between strings, the number of characters that have
+/// to be inserted, removed or replaced to turn one string into the other
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Levenshtein;
+
+impl<T: AsRef<str> + ?Sized> Metric<T> for Levenshtein {
+    fn distance(&self, a: &T, b: &T) -> usize {
+        let b: Vec<char> = b.as_ref().chars().collect();
+        // Distances from the part of `a` seen so far to every prefix of `b`
+        let mut row: Vec<usize> = (0..=b.len()).collect();
+        for (i, ca) in a.as_ref().chars().enumerate() {
+            let mut diagonal = row[0];
+            row[0] = i + 1;
+            for (j, &cb) in b.iter().enumerate() {
+                let replace = diagonal + (ca != cb) as usize;
+                diagonal = row[j + 1];
+                row[j + 1] = replace.min(row[j] + 1).min(diagonal + 1);
+            }
+        }
+        row[b.len()]
+    }
+}
+
+/// Burkhard-Keller Tree
+///
+/// An `ITree` of items where every child sits at a different distance from
+/// its parent, so a search for the items close to a query only has to follow
+/// the children whose distance is close to the distance of the query. Items
+/// can only be added, which is all a BK-tree supports without rebuilding.
+#[derive(Debug, Clone)]
+pub struct BkTree<T, M> {
+    metric: M,
+    /// Items along with their distance to their parent
+    tree: ITree<(usize, T)>,
+}
+
+impl<T, M: Metric<T>> BkTree<T, M> {
+    /// Create a new empty `BkTree` that measures distances with `metric`
+    pub fn new(metric: M) -> Self {
+        BkTree { metric, tree: ITree::new() }
+    }
+
+    pub fn metric(&self) -> &M {
+        &self.metric
+    }
+
+    /// Get the number of items in the tree
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Add an item, returning false if an equal one is in the tree already
+    pub fn insert(&mut self, item: T) -> bool {
+        let mut node = match self.tree.root_id() {
+            Some(root) => root,
+            None => {
+                self.tree.add_root((0, item)).unwrap();
+                return true;
+            }
+        };
+        loop {
+            let distance = self.metric.distance(&self.tree[node].1, &item);
+            if distance == 0 {
+                return false;
+            }
+            match self.child_at(node, distance) {
+                Some(child) => node = child,
+                None => {
+                    self.tree.add_node(node, (distance, item));
+                    return true;
+                }
+            }
+        }
+    }
+
+    /// Find every item at most `max_distance` away from `query`, closest
+    /// first, along with its distance
+    pub fn find_within(&self, query: &T, max_distance: usize) -> Vec<(usize, &T)> {
+        let mut found = Vec::new();
+        let mut stack: Vec<_> = self.tree.root_id().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let distance = self.metric.distance(&self.tree[node].1, query);
+            if distance <= max_distance {
+                found.push((distance, &self.tree[node].1));
+            }
+            // Anything within reach of the query is at a distance from this
+            // node within `max_distance` of the distance of the query
+            let (low, high) = (distance.saturating_sub(max_distance), distance + max_distance);
+            let children = self.tree.get(node).unwrap().children();
+            stack.extend(children.iter().filter(|&&c| (low..=high).contains(&self.tree[c].0)));
+        }
+        found.sort_by_key(|&(distance, _)| distance);
+        found
+    }
+
+    /// Get the child of a node at the given distance from it
+    fn child_at(&self, node: NodeId, distance: usize) -> Option<NodeId> {
+        let children = self.tree.get(node)?.children();
+        children.iter().cloned().find(|&c| self.tree[c].0 == distance)
+    }
+}
+
+impl<T, M: Metric<T>> Extend<T> for BkTree<T, M> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
+
+impl<T, M: Metric<T> + Default> FromIterator<T> for BkTree<T, M> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = BkTree::new(M::default());
+        tree.extend(iter);
+        tree
+    }
+}
+
+#[test]
+fn bk_tree() {
+    assert_eq!(Levenshtein.distance("kitten", "sitting"), 3);
+    assert_eq!(Levenshtein.distance("", "abc"), 3);
+    assert_eq!(Levenshtein.distance("flaw", "lawn"), 2);
+
+    let words = ["book", "books", "cake", "boo", "cape", "cart", "boon", "cook", "bake"];
+    let mut tree: BkTree<&str, Levenshtein> = words.iter().cloned().collect();
+    assert_eq!(tree.len(), 9);
+    assert!(!tree.insert("cake"));
+
+    let found: Vec<_> = tree.find_within(&"bock", 1).into_iter().map(|(d, &w)| (d, w)).collect();
+    assert_eq!(found, vec![(1, "book")]);
+    for &(query, max) in &[("cae", 1), ("bake", 2), ("zzz", 3), ("boo", 0)] {
+        let mut expected: Vec<_> = words
+            .iter()
+            .map(|w| (Levenshtein.distance(w, &query), *w))
+            .filter(|&(d, _)| d <= max)
+            .collect();
+        let mut found: Vec<_> = tree.find_within(&query, max).into_iter().map(|(d, &w)| (d, w)).collect();
+        expected.sort();
+        found.sort();
+        assert_eq!(found, expected);
+    }
+}
@@ -0,0 +1,582 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::iter::FromIterator;
+use core::mem;
+use core::ops::{Bound, RangeBounds};
+
+use alloc::vec::Vec;
+
+use arena::Arena;
+
+/// Fan-out of a `BPlusTree` made with `new`
+const FANOUT: usize = 16;
+
+/// B+ Tree
+///
+/// An ordered map that stores its entries in leaves holding up to `fanout`
+/// entries each. Inner nodes only hold keys to find the way down and have up
+/// to `fanout` children. Leaves are linked to their neighbours, so walking the
+/// entries in order just moves from one leaf to the next.
+///
+/// Every node but the root stays at least half full, so the tree stays
+/// shallow and most of the work in lookups and scans happens in a run of
+/// consecutive keys.
+#[derive(Debug, Clone)]
+pub struct BPlusTree<K, V> {
+    nodes: Arena<BNode<K, V>>,
+    root: usize,
+    len: usize,
+    fanout: usize,
+}
+
+#[derive(Debug, Clone)]
+enum BNode<K, V> {
+    /// Keys split the children, child `i` holds the keys from `keys[i - 1]` up
+    /// to but not including `keys[i]`
+    Inner { keys: Vec<K>, children: Vec<usize> },
+    Leaf {
+        keys: Vec<K>,
+        values: Vec<V>,
+        prev: Option<usize>,
+        next: Option<usize>,
+    },
+}
+
+impl<K, V> BNode<K, V> {
+    fn empty_leaf() -> Self {
+        BNode::Leaf { keys: Vec::new(), values: Vec::new(), prev: None, next: None }
+    }
+
+    fn keys(&self) -> &[K] {
+        match *self {
+            BNode::Inner { ref keys, .. } | BNode::Leaf { ref keys, .. } => keys,
+        }
+    }
+
+    /// Get the number of children of an inner node or entries of a leaf
+    fn size(&self) -> usize {
+        match *self {
+            BNode::Inner { ref children, .. } => children.len(),
+            BNode::Leaf { ref keys, .. } => keys.len(),
+        }
+    }
+}
+
+impl<K: Ord + Clone, V> BPlusTree<K, V> {
+    /// Create a new empty `BPlusTree` with a fan-out of 16
+    pub fn new() -> Self {
+        Self::with_fanout(FANOUT)
+    }
+
+    /// Create a new empty tree with nodes of up to `fanout` children or entries
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fanout` is less than 3.
+    pub fn with_fanout(fanout: usize) -> Self {
+        assert!(fanout >= 3, "the fan-out of a B+ tree has to be at least 3");
+        let mut nodes = Arena::new();
+        let root = nodes.insert(BNode::empty_leaf());
+        BPlusTree { nodes, root, len: 0, fanout }
+    }
+
+    /// Get the most children or entries a node can hold
+    pub fn fanout(&self) -> usize {
+        self.fanout
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Insert a value under a key, returning the value the key had before
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (leaf, mut path) = self.descend(&key);
+        match self.nodes[leaf] {
+            BNode::Leaf { ref mut keys, ref mut values, .. } => match keys.binary_search(&key) {
+                Ok(i) => return Some(mem::replace(&mut values[i], value)),
+                Err(i) => {
+                    keys.insert(i, key);
+                    values.insert(i, value);
+                }
+            },
+            BNode::Inner { .. } => unreachable!(),
+        }
+        self.len += 1;
+
+        // Split full nodes on the way back up
+        let mut node = leaf;
+        while self.nodes[node].size() > self.fanout {
+            let (key, right) = self.split(node);
+            match path.pop() {
+                Some((parent, i)) => {
+                    if let BNode::Inner { ref mut keys, ref mut children } = self.nodes[parent] {
+                        keys.insert(i, key);
+                        children.insert(i + 1, right);
+                    }
+                    node = parent;
+                }
+                None => {
+                    let root = BNode::Inner { keys: vec![key], children: vec![self.root, right] };
+                    self.root = self.nodes.insert(root);
+                }
+            }
+        }
+        None
+    }
+
+    /// Get the value of a key
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let (leaf, i) = self.find(key)?;
+        match self.nodes[leaf] {
+            BNode::Leaf { ref values, .. } => Some(&values[i]),
+            BNode::Inner { .. } => unreachable!(),
+        }
+    }
+
+    /// Get a mutable reference to the value of a key
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let (leaf, i) = self.find(key)?;
+        match self.nodes[leaf] {
+            BNode::Leaf { ref mut values, .. } => Some(&mut values[i]),
+            BNode::Inner { .. } => unreachable!(),
+        }
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        self.find(key).is_some()
+    }
+
+    /// Remove a key and return its value
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let (leaf, mut path) = self.descend(key);
+        let value = match self.nodes[leaf] {
+            BNode::Leaf { ref mut keys, ref mut values, .. } => {
+                let i = keys.binary_search_by(|k| k.borrow().cmp(key)).ok()?;
+                keys.remove(i);
+                values.remove(i)
+            }
+            BNode::Inner { .. } => unreachable!(),
+        };
+        self.len -= 1;
+
+        // Refill nodes that got less than half full on the way back up
+        let mut node = leaf;
+        while let Some((parent, i)) = path.pop() {
+            if self.nodes[node].size() >= self.min_size(node) {
+                break;
+            }
+            self.rebalance(parent, i);
+            node = parent;
+        }
+        // The root goes away once it is left with a single child
+        while let BNode::Inner { ref children, .. } = self.nodes[self.root] {
+            if children.len() > 1 {
+                break;
+            }
+            let child = children[0];
+            self.nodes.remove(self.root);
+            self.root = child;
+        }
+        Some(value)
+    }
+
+    /// Get the entry with the smallest key
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    /// Get the entry with the largest key
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.iter().next_back()
+    }
+
+    /// Remove every entry
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = self.nodes.insert(BNode::empty_leaf());
+        self.len = 0;
+    }
+
+    /// Iterate over the entries in order of their keys
+    pub fn iter(&self) -> BPlusIter<'_, K, V> {
+        self.range::<K, _>(..)
+    }
+
+    /// Iterate over the entries with keys inside a range
+    pub fn range<Q, R>(&self, range: R) -> BPlusIter<'_, K, V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized,
+              R: RangeBounds<Q>
+    {
+        let front = match range.start_bound() {
+            Bound::Included(b) => self.lower(|k| k.borrow() < b),
+            Bound::Excluded(b) => self.lower(|k| k.borrow() <= b),
+            Bound::Unbounded => self.lower(|_| false),
+        };
+        let back = match range.end_bound() {
+            Bound::Included(b) => self.upper(|k| k.borrow() <= b),
+            Bound::Excluded(b) => self.upper(|k| k.borrow() < b),
+            Bound::Unbounded => self.upper(|_| true),
+        };
+        let iter = BPlusIter { tree: self, front, back };
+        match (front, back) {
+            (Some(f), Some(b)) if iter.key(f) <= iter.key(b) => iter,
+            _ => BPlusIter { tree: self, front: None, back: None },
+        }
+    }
+
+    /// Go down to the leaf a key belongs in, returning it along with the inner
+    /// nodes on the way and the child taken at each of them
+    fn descend<Q>(&self, key: &Q) -> (usize, Vec<(usize, usize)>)
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let mut path = Vec::new();
+        let mut node = self.root;
+        while let BNode::Inner { ref keys, ref children } = self.nodes[node] {
+            let i = keys.partition_point(|k| k.borrow() <= key);
+            path.push((node, i));
+            node = children[i];
+        }
+        (node, path)
+    }
+
+    /// Find the leaf and position of a key
+    fn find<Q>(&self, key: &Q) -> Option<(usize, usize)>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let (leaf, _) = self.descend(key);
+        let i = self.nodes[leaf].keys().binary_search_by(|k| k.borrow().cmp(key)).ok()?;
+        Some((leaf, i))
+    }
+
+    /// Find the first entry whose key isn't `below` a bound
+    fn lower<F: Fn(&K) -> bool>(&self, below: F) -> Option<(usize, usize)> {
+        let mut node = self.root;
+        loop {
+            match self.nodes[node] {
+                BNode::Inner { ref keys, ref children } => node = children[keys.partition_point(&below)],
+                BNode::Leaf { ref keys, next, .. } => {
+                    let i = keys.partition_point(&below);
+                    return if i < keys.len() { Some((node, i)) } else { next.map(|n| (n, 0)) };
+                }
+            }
+        }
+    }
+
+    /// Find the last entry whose key is `within` a bound
+    fn upper<F: Fn(&K) -> bool>(&self, within: F) -> Option<(usize, usize)> {
+        let mut node = self.root;
+        loop {
+            match self.nodes[node] {
+                BNode::Inner { ref keys, ref children } => node = children[keys.partition_point(&within)],
+                BNode::Leaf { ref keys, prev, .. } => {
+                    return match keys.partition_point(&within) {
+                        0 => prev.map(|p| (p, self.nodes[p].size() - 1)),
+                        i => Some((node, i - 1)),
+                    };
+                }
+            }
+        }
+    }
+
+    /// Get the fewest children or entries a node other than the root can have
+    fn min_size(&self, node: usize) -> usize {
+        match self.nodes[node] {
+            BNode::Inner { .. } => self.fanout.div_ceil(2),
+            BNode::Leaf { .. } => self.fanout / 2,
+        }
+    }
+
+    /// Move the upper half of a node into a new node. Returns the new node along
+    /// with the key that splits the two in their parent.
+    fn split(&mut self, node: usize) -> (K, usize) {
+        let (key, right) = match self.nodes[node] {
+            BNode::Inner { ref mut keys, ref mut children } => {
+                let mid = children.len() / 2;
+                let right = BNode::Inner { keys: keys.split_off(mid), children: children.split_off(mid) };
+                (keys.pop().unwrap(), right)
+            }
+            BNode::Leaf { ref mut keys, ref mut values, next, .. } => {
+                let mid = keys.len() / 2;
+                let keys = keys.split_off(mid);
+                let right = BNode::Leaf { values: values.split_off(mid), prev: Some(node), next, keys };
+                (right.keys()[0].clone(), right)
+            }
+        };
+        let right = self.nodes.insert(right);
+        if let BNode::Leaf { ref mut next, .. } = self.nodes[node] {
+            if let Some(after) = next.replace(right) {
+                if let BNode::Leaf { ref mut prev, .. } = self.nodes[after] {
+                    *prev = Some(right);
+                }
+            }
+        }
+        (key, right)
+    }
+
+    /// Refill child `i` of a node by borrowing from one of its siblings, or
+    /// merge it with a sibling if neither can spare anything
+    fn rebalance(&mut self, parent: usize, i: usize) {
+        let children: Vec<usize> = match self.nodes[parent] {
+            BNode::Inner { ref children, .. } => children.clone(),
+            BNode::Leaf { .. } => unreachable!(),
+        };
+        let node = children[i];
+        let min = self.min_size(node);
+        if i > 0 && self.nodes[children[i - 1]].size() > min {
+            self.borrow_left(parent, i);
+        } else if i + 1 < children.len() && self.nodes[children[i + 1]].size() > min {
+            self.borrow_right(parent, i);
+        } else if i > 0 {
+            self.merge(parent, i - 1);
+        } else {
+            self.merge(parent, i);
+        }
+    }
+
+    /// Move the last entry or child of child `i - 1` to the front of child `i`
+    fn borrow_left(&mut self, parent: usize, i: usize) {
+        let (left, node, split) = self.family(parent, i - 1);
+        let (from, to) = self.nodes.pair_mut(left, node);
+        let split = match (from, to) {
+            (&mut BNode::Leaf { keys: ref mut lk, values: ref mut lv, .. },
+             &mut BNode::Leaf { ref mut keys, ref mut values, .. }) => {
+                keys.insert(0, lk.pop().unwrap());
+                values.insert(0, lv.pop().unwrap());
+                keys[0].clone()
+            }
+            (&mut BNode::Inner { keys: ref mut lk, children: ref mut lc },
+             &mut BNode::Inner { ref mut keys, ref mut children }) => {
+                children.insert(0, lc.pop().unwrap());
+                keys.insert(0, split);
+                lk.pop().unwrap()
+            }
+            _ => unreachable!(),
+        };
+        self.set_key(parent, i - 1, split);
+    }
+
+    /// Move the first entry or child of child `i + 1` to the end of child `i`
+    fn borrow_right(&mut self, parent: usize, i: usize) {
+        let (node, right, split) = self.family(parent, i);
+        let (to, from) = self.nodes.pair_mut(node, right);
+        let split = match (to, from) {
+            (&mut BNode::Leaf { ref mut keys, ref mut values, .. },
+             &mut BNode::Leaf { keys: ref mut rk, values: ref mut rv, .. }) => {
+                keys.push(rk.remove(0));
+                values.push(rv.remove(0));
+                rk[0].clone()
+            }
+            (&mut BNode::Inner { ref mut keys, ref mut children },
+             &mut BNode::Inner { keys: ref mut rk, children: ref mut rc }) => {
+                children.push(rc.remove(0));
+                keys.push(split);
+                rk.remove(0)
+            }
+            _ => unreachable!(),
+        };
+        self.set_key(parent, i, split);
+    }
+
+    /// Merge child `i + 1` of a node into child `i`
+    fn merge(&mut self, parent: usize, i: usize) {
+        let (left, right) = match self.nodes[parent] {
+            BNode::Inner { ref mut keys, ref mut children } => {
+                let split = keys.remove(i);
+                (children[i], (children.remove(i + 1), split))
+            }
+            BNode::Leaf { .. } => unreachable!(),
+        };
+        let (right, split) = right;
+        let after = match (self.nodes.remove(right), &mut self.nodes[left]) {
+            (BNode::Leaf { keys: rk, values: rv, next: rn, .. },
+             &mut BNode::Leaf { ref mut keys, ref mut values, ref mut next, .. }) => {
+                keys.extend(rk);
+                values.extend(rv);
+                *next = rn;
+                rn
+            }
+            (BNode::Inner { keys: rk, children: rc }, &mut BNode::Inner { ref mut keys, ref mut children }) => {
+                keys.push(split);
+                keys.extend(rk);
+                children.extend(rc);
+                None
+            }
+            _ => unreachable!(),
+        };
+        if let Some(after) = after {
+            if let BNode::Leaf { ref mut prev, .. } = self.nodes[after] {
+                *prev = Some(left);
+            }
+        }
+    }
+
+    /// Get children `i` and `i + 1` of a node along with the key between them
+    fn family(&self, parent: usize, i: usize) -> (usize, usize, K) {
+        match self.nodes[parent] {
+            BNode::Inner { ref keys, ref children } => (children[i], children[i + 1], keys[i].clone()),
+            BNode::Leaf { .. } => unreachable!(),
+        }
+    }
+
+    fn set_key(&mut self, parent: usize, i: usize, key: K) {
+        if let BNode::Inner { ref mut keys, .. } = self.nodes[parent] {
+            keys[i] = key;
+        }
+    }
+}
+
+impl<K: Ord + Clone, V> Default for BPlusTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone, V> Extend<(K, V)> for BPlusTree<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Ord + Clone, V> FromIterator<(K, V)> for BPlusTree<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut tree = BPlusTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<'a, K: Ord + Clone, V> IntoIterator for &'a BPlusTree<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = BPlusIter<'a, K, V>;
+
+    fn into_iter(self) -> BPlusIter<'a, K, V> {
+        self.iter()
+    }
+}
+
+/// Iterator over the entries of a `BPlusTree` in order of their keys
+pub struct BPlusIter<'a, K: 'a, V: 'a> {
+    tree: &'a BPlusTree<K, V>,
+    /// Leaf and position of the next entry from either end
+    front: Option<(usize, usize)>,
+    back: Option<(usize, usize)>,
+}
+
+impl<'a, K, V> BPlusIter<'a, K, V> {
+    fn key(&self, (leaf, i): (usize, usize)) -> &'a K {
+        &self.tree.nodes[leaf].keys()[i]
+    }
+
+    fn get(&self, (leaf, i): (usize, usize)) -> (&'a K, &'a V) {
+        match self.tree.nodes[leaf] {
+            BNode::Leaf { ref keys, ref values, .. } => (&keys[i], &values[i]),
+            BNode::Inner { .. } => unreachable!(),
+        }
+    }
+
+    /// Stop once both ends meet, returning whether they had
+    fn meet(&mut self) -> bool {
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<'a, K, V> Clone for BPlusIter<'a, K, V> {
+    fn clone(&self) -> Self {
+        BPlusIter { tree: self.tree, front: self.front, back: self.back }
+    }
+}
+
+impl<'a, K, V> Iterator for BPlusIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let (leaf, i) = self.front?;
+        let entry = self.get((leaf, i));
+        if !self.meet() {
+            self.front = match self.tree.nodes[leaf] {
+                BNode::Leaf { ref keys, next, .. } if i + 1 == keys.len() => next.map(|n| (n, 0)),
+                _ => Some((leaf, i + 1)),
+            };
+        }
+        Some(entry)
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for BPlusIter<'a, K, V> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        let (leaf, i) = self.back?;
+        let entry = self.get((leaf, i));
+        if !self.meet() {
+            self.back = match self.tree.nodes[leaf] {
+                BNode::Leaf { prev, .. } if i == 0 => prev.map(|p| (p, self.tree.nodes[p].size() - 1)),
+                _ => Some((leaf, i - 1)),
+            };
+        }
+        Some(entry)
+    }
+}
+
+impl<'a, K: fmt::Debug, V: fmt::Debug> fmt::Debug for BPlusIter<'a, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+#[test]
+fn bplus() {
+    let mut tree = BPlusTree::with_fanout(3);
+    for k in (0..100).map(|k| (k * 37) % 100) {
+        assert_eq!(tree.insert(k, k * 10), None);
+    }
+    assert_eq!(tree.len(), 100);
+    assert_eq!(tree.insert(7, 71), Some(70));
+    assert_eq!(tree.get(&7), Some(&71));
+    assert!(tree.iter().map(|(&k, _)| k).eq(0..100));
+    assert!(tree.range(10..20).map(|(&k, _)| k).eq(10..20));
+    assert!(tree.range(..=5).rev().map(|(&k, _)| k).eq((0..=5).rev()));
+    assert!(tree.range((Bound::Excluded(97), Bound::Unbounded)).map(|(&k, _)| k).eq(98..100));
+    assert_eq!(tree.range(50..50).count(), 0);
+
+    // Removing keys borrows from and merges leaves until the tree shrinks
+    for k in (0..100).filter(|k| k % 3 != 0) {
+        assert_eq!(tree.remove(&k), Some(if k == 7 { 71 } else { k * 10 }));
+    }
+    assert_eq!(tree.remove(&1), None);
+    assert_eq!(tree.len(), 34);
+    assert!(tree.iter().map(|(&k, _)| k).eq((0..100).step_by(3)));
+    assert!(tree.range(10..=21).map(|(&k, _)| k).eq(vec![12, 15, 18, 21]));
+    for k in (0..100).step_by(3) {
+        tree.remove(&k);
+    }
+    assert!(tree.is_empty());
+    assert!(matches!(tree.nodes[tree.root], BNode::Leaf { .. }));
+    assert_eq!(tree.iter().next(), None);
+}
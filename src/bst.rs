@@ -0,0 +1,405 @@
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::mem;
+use core::ops::{Bound, RangeBounds};
+
+use arena::Arena;
+
+/// Node of a binary search tree. `M` is what a balanced tree keeps around per
+/// node to balance itself, like a height or a color.
+#[derive(Debug, Clone)]
+pub(crate) struct BstNode<K, V, M> {
+    pub(crate) key: K,
+    pub(crate) value: V,
+    pub(crate) meta: M,
+    pub(crate) parent: Option<usize>,
+    pub(crate) left: Option<usize>,
+    pub(crate) right: Option<usize>,
+}
+
+/// Binary search tree the ordered maps of this crate are built on
+///
+/// Handles finding keys, walking the tree in order and the changes to the
+/// links between nodes every kind of tree needs, while the maps decide where
+/// nodes go and how the tree gets balanced.
+#[derive(Debug, Clone)]
+pub(crate) struct Bst<K, V, M> {
+    pub(crate) nodes: Arena<BstNode<K, V, M>>,
+    pub(crate) root: Option<usize>,
+    pub(crate) len: usize,
+}
+
+impl<K, V, M> Bst<K, V, M> {
+    pub(crate) fn new() -> Self {
+        Bst { nodes: Arena::new(), root: None, len: 0 }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = None;
+        self.len = 0;
+    }
+
+    /// Find the node of a key, or the node below which it would go along with
+    /// the side it would go on
+    pub(crate) fn search<Q>(&self, key: &Q) -> Result<usize, Option<(usize, Ordering)>>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let mut current = self.root;
+        let mut last = None;
+        while let Some(x) = current {
+            let node = &self.nodes[x];
+            let side = key.cmp(node.key.borrow());
+            match side {
+                Ordering::Equal => return Ok(x),
+                Ordering::Less => current = node.left,
+                Ordering::Greater => current = node.right,
+            }
+            last = Some((x, side));
+        }
+        Err(last)
+    }
+
+    pub(crate) fn find<Q>(&self, key: &Q) -> Option<usize>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        self.search(key).ok()
+    }
+
+    /// Add a node as a leaf where `search` said its key goes
+    pub(crate) fn attach(&mut self, at: Option<(usize, Ordering)>, key: K, value: V, meta: M) -> usize {
+        let parent = at.map(|(p, _)| p);
+        let x = self.nodes.insert(BstNode { key, value, meta, parent, left: None, right: None });
+        match at {
+            None => self.root = Some(x),
+            Some((p, Ordering::Less)) => self.nodes[p].left = Some(x),
+            Some((p, _)) => self.nodes[p].right = Some(x),
+        }
+        self.len += 1;
+        x
+    }
+
+    /// Take a node with at most one child out of the tree, putting its child
+    /// in its place. Returns the node along with its old parent and the child
+    /// that took its place.
+    pub(crate) fn unlink(&mut self, x: usize) -> (BstNode<K, V, M>, Option<usize>, Option<usize>) {
+        let (parent, child) = {
+            let node = &self.nodes[x];
+            debug_assert!(node.left.is_none() || node.right.is_none());
+            (node.parent, node.left.or(node.right))
+        };
+        self.replace(parent, x, child);
+        if let Some(c) = child {
+            self.nodes[c].parent = parent;
+        }
+        self.len -= 1;
+        (self.nodes.remove(x), parent, child)
+    }
+
+    /// Make sure a node has at most one child by swapping its key and value
+    /// with the ones of its successor, and return the node that ends up
+    /// holding them
+    pub(crate) fn leafward(&mut self, x: usize) -> usize {
+        let right = match (self.nodes[x].left, self.nodes[x].right) {
+            (Some(_), Some(right)) => right,
+            _ => return x,
+        };
+        let next = self.min(right);
+        let (a, b) = self.nodes.pair_mut(x, next);
+        mem::swap(&mut a.key, &mut b.key);
+        mem::swap(&mut a.value, &mut b.value);
+        next
+    }
+
+    /// Put `new` where `old` is below `parent`
+    pub(crate) fn replace(&mut self, parent: Option<usize>, old: usize, new: Option<usize>) {
+        match parent {
+            None => self.root = new,
+            Some(p) if self.nodes[p].left == Some(old) => self.nodes[p].left = new,
+            Some(p) => self.nodes[p].right = new,
+        }
+    }
+
+    /// Rotate the right child of a node up into its place, returning the child
+    pub(crate) fn rotate_left(&mut self, x: usize) -> usize {
+        let y = self.nodes[x].right.expect("rotating left needs a right child");
+        let inner = self.nodes[y].left;
+        let parent = self.nodes[x].parent;
+        self.nodes[x].right = inner;
+        if let Some(c) = inner {
+            self.nodes[c].parent = Some(x);
+        }
+        self.replace(parent, x, Some(y));
+        self.nodes[y].parent = parent;
+        self.nodes[y].left = Some(x);
+        self.nodes[x].parent = Some(y);
+        y
+    }
+
+    /// Rotate the left child of a node up into its place, returning the child
+    pub(crate) fn rotate_right(&mut self, x: usize) -> usize {
+        let y = self.nodes[x].left.expect("rotating right needs a left child");
+        let inner = self.nodes[y].right;
+        let parent = self.nodes[x].parent;
+        self.nodes[x].left = inner;
+        if let Some(c) = inner {
+            self.nodes[c].parent = Some(x);
+        }
+        self.replace(parent, x, Some(y));
+        self.nodes[y].parent = parent;
+        self.nodes[y].right = Some(x);
+        self.nodes[x].parent = Some(y);
+        y
+    }
+
+    /// Get the node with the smallest key below `x`
+    pub(crate) fn min(&self, mut x: usize) -> usize {
+        while let Some(left) = self.nodes[x].left {
+            x = left;
+        }
+        x
+    }
+
+    /// Get the node with the largest key below `x`
+    pub(crate) fn max(&self, mut x: usize) -> usize {
+        while let Some(right) = self.nodes[x].right {
+            x = right;
+        }
+        x
+    }
+
+    /// Get the node with the next larger key
+    pub(crate) fn next(&self, x: usize) -> Option<usize> {
+        if let Some(right) = self.nodes[x].right {
+            return Some(self.min(right));
+        }
+        let mut x = x;
+        while let Some(p) = self.nodes[x].parent {
+            if self.nodes[p].left == Some(x) {
+                return Some(p);
+            }
+            x = p;
+        }
+        None
+    }
+
+    /// Get the node with the next smaller key
+    pub(crate) fn prev(&self, x: usize) -> Option<usize> {
+        if let Some(left) = self.nodes[x].left {
+            return Some(self.max(left));
+        }
+        let mut x = x;
+        while let Some(p) = self.nodes[x].parent {
+            if self.nodes[p].right == Some(x) {
+                return Some(p);
+            }
+            x = p;
+        }
+        None
+    }
+
+    pub(crate) fn iter(&self) -> BstIter<'_, K, V, M> {
+        BstIter {
+            tree: self,
+            front: self.root.map(|r| self.min(r)),
+            back: self.root.map(|r| self.max(r)),
+        }
+    }
+
+    pub(crate) fn range<Q, R>(&self, range: R) -> BstIter<'_, K, V, M>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized,
+              R: RangeBounds<Q>
+    {
+        let front = self.lower(range.start_bound());
+        let back = self.upper(range.end_bound());
+        match (front, back) {
+            (Some(f), Some(b)) if self.nodes[f].key.borrow() <= self.nodes[b].key.borrow() => {
+                BstIter { tree: self, front, back }
+            }
+            _ => BstIter { tree: self, front: None, back: None },
+        }
+    }
+
+    /// Get the node with the smallest key inside a lower bound
+    fn lower<Q>(&self, bound: Bound<&Q>) -> Option<usize>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let mut current = self.root;
+        let mut found = None;
+        while let Some(x) = current {
+            let key = self.nodes[x].key.borrow();
+            let inside = match bound {
+                Bound::Included(b) => key >= b,
+                Bound::Excluded(b) => key > b,
+                Bound::Unbounded => true,
+            };
+            if inside {
+                found = Some(x);
+                current = self.nodes[x].left;
+            } else {
+                current = self.nodes[x].right;
+            }
+        }
+        found
+    }
+
+    /// Get the node with the largest key inside an upper bound
+    fn upper<Q>(&self, bound: Bound<&Q>) -> Option<usize>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let mut current = self.root;
+        let mut found = None;
+        while let Some(x) = current {
+            let key = self.nodes[x].key.borrow();
+            let inside = match bound {
+                Bound::Included(b) => key <= b,
+                Bound::Excluded(b) => key < b,
+                Bound::Unbounded => true,
+            };
+            if inside {
+                found = Some(x);
+                current = self.nodes[x].right;
+            } else {
+                current = self.nodes[x].left;
+            }
+        }
+        found
+    }
+
+    /// Get the number of nodes on the longest path down from the root
+    #[cfg(test)]
+    pub(crate) fn height(&self) -> usize {
+        let mut stack: alloc::vec::Vec<_> = self.root.into_iter().map(|r| (r, 1)).collect();
+        let mut height = 0;
+        while let Some((x, depth)) = stack.pop() {
+            height = height.max(depth);
+            let node = &self.nodes[x];
+            stack.extend(node.left.into_iter().chain(node.right).map(|c| (c, depth + 1)));
+        }
+        height
+    }
+}
+
+/// Iterator over the keys and values of an ordered map in order of their keys
+pub struct BstIter<'a, K: 'a, V: 'a, M: 'a> {
+    tree: &'a Bst<K, V, M>,
+    front: Option<usize>,
+    back: Option<usize>,
+}
+
+impl<'a, K, V, M> Clone for BstIter<'a, K, V, M> {
+    fn clone(&self) -> Self {
+        BstIter { tree: self.tree, front: self.front, back: self.back }
+    }
+}
+
+impl<'a, K, V, M> BstIter<'a, K, V, M> {
+    fn get(&self, x: usize) -> (&'a K, &'a V) {
+        let node = &self.tree.nodes[x];
+        (&node.key, &node.value)
+    }
+}
+
+impl<'a, K, V, M> Iterator for BstIter<'a, K, V, M> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let x = self.front?;
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.front = self.tree.next(x);
+        }
+        Some(self.get(x))
+    }
+}
+
+impl<'a, K, V, M> DoubleEndedIterator for BstIter<'a, K, V, M> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        let x = self.back?;
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.back = self.tree.prev(x);
+        }
+        Some(self.get(x))
+    }
+}
+
+impl<'a, K: fmt::Debug, V: fmt::Debug, M> fmt::Debug for BstIter<'a, K, V, M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// Binary Search Tree Map
+///
+/// An ordered map where every node has a key, the keys to its left are
+/// smaller and the keys to its right are larger. The tree doesn't balance
+/// itself, so it only stays shallow for keys that come in random order.
+#[derive(Clone)]
+pub struct BstMap<K, V> {
+    tree: Bst<K, V, ()>,
+}
+
+impl<K: Ord, V> BstMap<K, V> {
+    /// Create a new empty `BstMap`
+    pub fn new() -> Self {
+        BstMap { tree: Bst::new() }
+    }
+
+    /// Insert a value under a key, returning the value the key had before
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.tree.search(&key) {
+            Ok(x) => Some(mem::replace(&mut self.tree.nodes[x].value, value)),
+            Err(at) => {
+                self.tree.attach(at, key, value, ());
+                None
+            }
+        }
+    }
+
+    /// Remove a key and return its value
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let x = self.tree.find(key)?;
+        let x = self.tree.leafward(x);
+        Some(self.tree.unlink(x).0.value)
+    }
+}
+
+bst_map!(BstMap, ());
+bst_lookup!(BstMap);
+
+#[test]
+fn bst_map() {
+    use alloc::vec::Vec;
+
+    let mut map: BstMap<_, _> = vec![5, 3, 8, 1, 4, 7, 9, 2, 6].into_iter().map(|k| (k, k * 10)).collect();
+    assert_eq!(map.len(), 9);
+    assert_eq!(map.get(&4), Some(&40));
+    assert_eq!(map.insert(4, 41), Some(40));
+    assert_eq!(map.iter().map(|(&k, _)| k).collect::<Vec<_>>(), (1..10).collect::<Vec<_>>());
+    assert_eq!(map.range(3..7).map(|(&k, _)| k).collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+    assert_eq!(map.range(..=2).rev().map(|(&k, _)| k).collect::<Vec<_>>(), vec![2, 1]);
+    assert_eq!(map.range(10..).count(), 0);
+
+    // Removing a node with two children puts its successor in its place
+    assert_eq!(map.remove(&5), Some(50));
+    assert_eq!(map.remove(&5), None);
+    assert_eq!(map.remove(&1), Some(10));
+    assert_eq!(map.iter().map(|(&k, _)| k).collect::<Vec<_>>(), vec![2, 3, 4, 6, 7, 8, 9]);
+    assert_eq!(map.first_key_value(), Some((&2, &20)));
+    assert_eq!(map.last_key_value(), Some((&9, &90)));
+    assert_eq!(map.tree.height(), 3);
+}
@@ -0,0 +1,320 @@
+use alloc::vec::Vec;
+
+use {GroveError, ITree, NodeId};
+
+/// Builder for `ITree`s
+///
+/// Keeps track of a current node so trees can be built top down without
+/// passing `NodeId`s around. `child` goes down a level, `sibling` stays on the
+/// same level and `up` goes back to the parent.
+///
+/// ```
+/// use grove::TreeBuilder;
+///
+/// // 1 -> 2 -> 3
+/// // |--> 4
+/// let tree = TreeBuilder::new(1)
+///     .child(2)
+///         .child(3)
+///     .up()
+///     .sibling(4)
+///     .build();
+/// assert_eq!(tree.root().unwrap().children().len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TreeBuilder<T> {
+    tree: ITree<T>,
+    current: NodeId,
+}
+
+impl<T> TreeBuilder<T> {
+    /// Start building a tree with the given root value
+    pub fn new(root: T) -> Self {
+        let mut tree = ITree::new();
+        let current = tree.add_root(root).unwrap();
+        Self { tree, current }
+    }
+
+    /// Add a child to the current node and make it the current node
+    pub fn child(mut self, value: T) -> Self {
+        self.current = self.tree.add_node(self.current, value);
+        self
+    }
+
+    /// Add a sibling after the current node and make it the current node
+    ///
+    /// # Panics
+    ///
+    /// Panics if the current node is the root.
+    pub fn sibling(mut self, value: T) -> Self {
+        let parent = match self.parent() {
+            Some(parent) => parent,
+            None => panic!("{}", GroveError::MultipleRoots),
+        };
+        self.current = self.tree.add_node(parent, value);
+        self
+    }
+
+    /// Make the parent of the current node the current node
+    ///
+    /// # Panics
+    ///
+    /// Panics if the current node is the root.
+    pub fn up(mut self) -> Self {
+        self.current = self.parent().expect("the root node has no parent");
+        self
+    }
+
+    /// Get the `NodeId` of the current node
+    pub fn current(&self) -> NodeId {
+        self.current
+    }
+
+    /// Finish building and return the tree
+    pub fn build(self) -> ITree<T> {
+        self.tree
+    }
+
+    fn parent(&self) -> Option<NodeId> {
+        self.tree.get(self.current).unwrap().parent()
+    }
+}
+
+/// Push-based builder for `ITree`s
+///
+/// Takes the nodes of a tree one at a time as they come out of a parser,
+/// either with their depth, for formats that list nodes in pre-order like
+/// indented outlines, or with the `NodeId` of their parent. Nothing is kept
+/// besides the tree and the path down to the last node.
+///
+/// ```
+/// use grove::StreamBuilder;
+///
+/// let mut builder = StreamBuilder::new();
+/// for &(depth, name) in &[(0, "/"), (1, "bin"), (2, "sh"), (1, "etc")] {
+///     builder.push(depth, name).unwrap();
+/// }
+/// let tree = builder.build();
+/// assert_eq!(tree.root().unwrap().children().len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StreamBuilder<T> {
+    tree: ITree<T>,
+    /// Nodes from the root down to the last node pushed
+    path: Vec<NodeId>,
+}
+
+impl<T> StreamBuilder<T> {
+    /// Start building an empty tree
+    pub fn new() -> Self {
+        StreamBuilder { tree: ITree::new(), path: Vec::new() }
+    }
+
+    /// Add a node at a depth below the root. Depth zero adds the root, and any
+    /// other depth adds a child to the last node pushed at one level less.
+    /// Returns an error if there is no such node, or for a second root.
+    pub fn push(&mut self, depth: usize, value: T) -> Result<NodeId, GroveError> {
+        if depth == 0 {
+            let root = self.tree.add_root(value)?;
+            self.path.push(root);
+            return Ok(root);
+        }
+        if depth > self.path.len() {
+            return Err(GroveError::InvalidDepth(depth));
+        }
+        self.path.truncate(depth);
+        let node = self.tree.add_node(self.path[depth - 1], value);
+        self.path.push(node);
+        Ok(node)
+    }
+
+    /// Add a node as the last child of a node that has been added before, or
+    /// as the root without a parent
+    pub fn push_child(&mut self, parent: Option<NodeId>, value: T) -> Result<NodeId, GroveError> {
+        let parent = match parent {
+            Some(parent) => parent,
+            None => return self.push(0, value),
+        };
+        let node = self.tree.try_add_node(parent, value)?;
+        // Keep depths working after the node, usually near the end of the path
+        match self.path.iter().rposition(|&p| p == parent) {
+            Some(p) => self.path.truncate(p + 1),
+            None => {
+                self.path.clear();
+                self.path.extend(self.tree.path_to_root(parent).into_iter().rev());
+            }
+        }
+        self.path.push(node);
+        Ok(node)
+    }
+
+    /// Get the tree built so far
+    pub fn tree(&self) -> &ITree<T> {
+        &self.tree
+    }
+
+    /// Finish building and return the tree
+    pub fn build(self) -> ITree<T> {
+        self.tree
+    }
+}
+
+impl<T> Default for StreamBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> ITree<T> {
+    /// Build a tree by expanding a seed. `expand` turns every seed into the
+    /// value of its node and the seeds of the node's children. Nodes are
+    /// expanded depth first and get their `NodeId`s in pre-order.
+    ///
+    /// ```
+    /// use grove::ITree;
+    ///
+    /// // Every number gets its halves as children until they reach 1
+    /// let tree = ITree::build_from(8, |&n| {
+    ///     let children = if n > 1 { vec![n / 2, n / 2] } else { Vec::new() };
+    ///     (n, children)
+    /// });
+    /// assert_eq!(tree.leaves().count(), 8);
+    /// ```
+    ///
+    /// Trees of other crates convert the same way, with their node handles as
+    /// seeds. For `indextree` and `ego_tree`:
+    ///
+    /// ```text
+    /// let tree = ITree::build_from(root, |&id| (arena[id].get().clone(), id.children(&arena).collect()));
+    /// let tree = ITree::build_from(ego.root(), |node| (node.value().clone(), node.children().collect()));
+    /// ```
+    pub fn build_from<S, F>(seed: S, mut expand: F) -> Self
+        where F: FnMut(&S) -> (T, Vec<S>)
+    {
+        let mut tree = ITree::new();
+        let mut stack = vec![(seed, None)];
+        while let Some((seed, parent)) = stack.pop() {
+            let (value, children) = expand(&seed);
+            let id = match parent {
+                Some(parent) => tree.add_node(parent, value),
+                None => tree.add_root(value).unwrap(),
+            };
+            stack.extend(children.into_iter().rev().map(|child| (child, Some(id))));
+        }
+        tree
+    }
+}
+
+impl<T: Ord + Clone> ITree<(usize, T)> {
+    /// Build the Cartesian tree of a sequence in linear time
+    ///
+    /// The smallest value is the root, the values before it make up the
+    /// subtree of its first child and the values after it the one of its
+    /// second child, over and over. Every node holds the position of its value
+    /// in `values` along with a clone of it, which tells the two kinds of
+    /// lone children apart. Of equal values the first one ends up on top.
+    ///
+    /// ```
+    /// use grove::ITree;
+    ///
+    /// let tree = ITree::cartesian_from(&[3, 1, 4, 1, 5]);
+    /// assert_eq!(tree.root().unwrap().value(), &(1, 1));
+    /// ```
+    pub fn cartesian_from(values: &[T]) -> Self {
+        if values.is_empty() {
+            return ITree::new();
+        }
+        let mut left = vec![None; values.len()];
+        let mut right = vec![None; values.len()];
+        // Positions along the right edge of the tree built so far
+        let mut stack: Vec<usize> = Vec::new();
+        for (i, value) in values.iter().enumerate() {
+            let mut last = None;
+            while let Some(&top) = stack.last() {
+                if values[top] <= *value {
+                    break;
+                }
+                last = stack.pop();
+            }
+            left[i] = last;
+            if let Some(&top) = stack.last() {
+                right[top] = Some(i);
+            }
+            stack.push(i);
+        }
+        ITree::build_from(stack[0], |&i| ((i, values[i].clone()), left[i].into_iter().chain(right[i]).collect()))
+    }
+}
+
+#[test]
+fn builder() {
+    // 0 -> 1 -> 2
+    // |    |--> 3 -> 4
+    // |--> 5
+    let builder = TreeBuilder::new(0)
+        .child(1)
+            .child(2)
+            .sibling(3)
+                .child(4)
+            .up()
+        .up()
+        .sibling(5);
+    assert_eq!(builder.current(), NodeId::new(5));
+    assert_eq!(builder.build(), tree!{ 0 => [1 => [2, 3 => [4]], 5] });
+}
+
+#[test]
+#[should_panic]
+fn builder_root_sibling() {
+    TreeBuilder::new(0).sibling(1);
+}
+
+#[test]
+fn stream_builder() {
+    let mut builder = StreamBuilder::new();
+    assert_eq!(builder.push(1, 'x'), Err(GroveError::InvalidDepth(1)));
+    let a = builder.push(0, 'a').unwrap();
+    let b = builder.push(1, 'b').unwrap();
+    builder.push(2, 'c').unwrap();
+    assert_eq!(builder.push(4, 'x'), Err(GroveError::InvalidDepth(4)));
+    builder.push(1, 'd').unwrap();
+    assert_eq!(builder.push(0, 'x'), Err(GroveError::RootExists));
+    // Parents off the path put the depths back under them
+    builder.push_child(Some(b), 'e').unwrap();
+    builder.push(3, 'f').unwrap();
+    builder.push_child(Some(a), 'g').unwrap();
+    assert_eq!(builder.push_child(Some(NodeId::new(9)), 'x'), Err(GroveError::InvalidNodeId(NodeId::new(9))));
+    assert_eq!(builder.push_child(None, 'x'), Err(GroveError::RootExists));
+    assert_eq!(builder.tree().len(), 7);
+    assert!(builder.build().is_identical(&tree!{ 'a' => ['b' => ['c', 'e' => ['f']], 'd', 'g'] }));
+}
+
+#[test]
+fn build_from() {
+    struct Dir {
+        name: &'static str,
+        entries: Vec<Dir>,
+    }
+    let dir = |name, entries| Dir { name, entries };
+
+    let root = dir("/", vec![dir("bin", vec![dir("sh", vec![])]), dir("etc", vec![])]);
+    let tree = ITree::build_from(&root, |d| (d.name, d.entries.iter().collect()));
+    assert_eq!(tree, tree!{ "/" => ["bin" => ["sh"], "etc"] });
+
+    // Deep seeds don't overflow the stack
+    let tree = ITree::build_from(0, |&n| (n, if n < 100_000 { vec![n + 1] } else { vec![] }));
+    assert_eq!(tree.leaves().map(|(_, n)| *n.value()).collect::<Vec<_>>(), vec![100_000]);
+}
+
+#[test]
+fn cartesian_from() {
+    let values = [5, 2, 8, 2, 9, 1, 7];
+    let tree = ITree::cartesian_from(&values);
+    assert_eq!(tree, tree!{ (5, 1) => [(1, 2) => [(0, 5), (3, 2) => [(2, 8), (4, 9)]], (6, 7)] });
+    assert!(ITree::<(usize, u8)>::cartesian_from(&[]).is_empty());
+
+    // Sorted values make a chain of second children that doesn't overflow
+    let values: Vec<_> = (0..100_000).collect();
+    let tree = ITree::cartesian_from(&values);
+    assert_eq!(tree.leaves().map(|(_, n)| n.value().0).collect::<Vec<_>>(), vec![99_999]);
+}
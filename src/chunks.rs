@@ -0,0 +1,303 @@
+use core::fmt;
+use core::iter::FromIterator;
+use core::ops::{Index, Range};
+
+use alloc::sync::Arc;
+use alloc::vec::{self, Vec};
+
+/// Number of elements in a full chunk
+const CHUNK: usize = 1024;
+
+/// Copies the elements of a chunk
+type CopyFn<T> = fn(&[T]) -> Vec<T>;
+
+/// Growable array split into chunks that can be shared between trees
+///
+/// Sharing the array shares the list of chunks along with the chunks, so it
+/// copies nothing. The first change after that copies the list, and adding
+/// to or changing an element of a shared chunk copies the chunk first. Every
+/// chunk but the last one is always full, so elements are found without
+/// searching.
+pub struct Chunks<T> {
+    chunks: Arc<Vec<Arc<Vec<T>>>>,
+    len: usize,
+    /// Set once chunks have been shared, to copy the elements out of chunks
+    /// that are still shared when the array gets taken apart
+    copy: Option<CopyFn<T>>,
+}
+
+impl<T> Chunks<T> {
+    pub fn new() -> Self {
+        Chunks { chunks: Arc::new(Vec::new()), len: 0, copy: None }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut chunks = Chunks::new();
+        chunks.reserve(capacity);
+        chunks
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the number of elements the array can hold without allocating
+    pub fn capacity(&self) -> usize {
+        self.chunks.iter().map(|c| c.capacity().min(CHUNK)).sum()
+    }
+
+    /// Reserve room for at least `additional` more elements
+    pub fn reserve(&mut self, additional: usize) {
+        let target = self.len + additional;
+        let mut k = self.len / CHUNK;
+        while k * CHUNK < target {
+            let wanted = (target - k * CHUNK).min(CHUNK);
+            if k == self.chunks.len() {
+                self.spine().push(Arc::new(Vec::with_capacity(wanted)));
+            } else {
+                let chunk = self.open_chunk(k);
+                let len = chunk.len();
+                chunk.reserve(wanted - len);
+            }
+            k += 1;
+        }
+    }
+
+    /// Free chunks that aren't used and shrink the last one as much as
+    /// possible
+    pub fn shrink_to_fit(&mut self) {
+        let len = self.len;
+        let chunks = self.spine();
+        chunks.truncate(len.div_ceil(CHUNK));
+        chunks.shrink_to_fit();
+        if let Some(chunk) = chunks.last_mut().and_then(Arc::get_mut) {
+            chunk.shrink_to_fit();
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        let k = self.len / CHUNK;
+        if k == self.chunks.len() {
+            self.spine().push(Arc::new(Vec::new()));
+        }
+        self.open_chunk(k).push(value);
+        self.len += 1;
+    }
+
+    pub fn get(&self, x: usize) -> Option<&T> {
+        if x < self.len { Some(&self.chunks[x / CHUNK][x % CHUNK]) } else { None }
+    }
+
+    /// Get a mutable reference to an element, copying its chunk first if it is
+    /// shared
+    pub fn get_mut(&mut self, x: usize) -> Option<&mut T>
+        where T: Clone
+    {
+        if x < self.len { Some(&mut Arc::make_mut(&mut self.spine()[x / CHUNK])[x % CHUNK]) } else { None }
+    }
+
+    /// Iterate mutably over the elements in chunks that aren't shared. Every
+    /// chunk of a shared list is shared as well.
+    pub fn unshared_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        Arc::get_mut(&mut self.chunks)
+            .into_iter()
+            .flat_map(|chunks| chunks.iter_mut().filter_map(Arc::get_mut).flat_map(|c| c.iter_mut()))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { chunks: self, range: 0..self.len }
+    }
+
+    /// Get the chunks, every one but the last is full
+    #[cfg(feature = "rayon")]
+    pub fn chunks(&self) -> &[Arc<Vec<T>>] {
+        &self.chunks
+    }
+
+    /// Put an array back together out of chunks laid out like the ones passed
+    /// out by `chunks`
+    #[cfg(feature = "rayon")]
+    pub fn from_chunks(chunks: Vec<Vec<T>>) -> Self {
+        let len = chunks.iter().map(Vec::len).sum();
+        Chunks { chunks: Arc::new(chunks.into_iter().map(Arc::new).collect()), len, copy: None }
+    }
+
+    /// Create an array with the same elements that shares its list of chunks
+    /// with this one, without copying anything
+    pub fn share(&mut self) -> Chunks<T>
+        where T: Clone
+    {
+        self.copy = Some(copy::<T>);
+        Chunks { chunks: Arc::clone(&self.chunks), len: self.len, copy: self.copy }
+    }
+
+    /// Get the list of chunks to change, copying it first if it is shared
+    fn spine(&mut self) -> &mut Vec<Arc<Vec<T>>> {
+        Arc::make_mut(&mut self.chunks)
+    }
+
+    /// Get a chunk to add elements to, copying it first if it is shared
+    fn open_chunk(&mut self, k: usize) -> &mut Vec<T> {
+        let copy = self.copy;
+        let chunk = &mut self.spine()[k];
+        if Arc::get_mut(chunk).is_none() {
+            // Chunks only get shared after `copy` has been set
+            *chunk = Arc::new(copy.unwrap()(chunk));
+        }
+        Arc::get_mut(chunk).unwrap()
+    }
+}
+
+fn copy<T: Clone>(chunk: &[T]) -> Vec<T> {
+    chunk.to_vec()
+}
+
+impl<T> Default for Chunks<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> Clone for Chunks<T> {
+    /// Copy every element, sharing nothing with this array
+    fn clone(&self) -> Self {
+        Chunks {
+            chunks: Arc::new(self.chunks.iter().map(|c| Arc::new(c.to_vec())).collect()),
+            len: self.len,
+            copy: self.copy,
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for Chunks<T> {
+    fn eq(&self, other: &Chunks<T>) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for Chunks<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Chunks<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> Index<usize> for Chunks<T> {
+    type Output = T;
+
+    fn index(&self, x: usize) -> &T {
+        match self.get(x) {
+            Some(value) => value,
+            None => panic!("index {} is out of bounds for {} elements", x, self.len),
+        }
+    }
+}
+
+impl<T> FromIterator<T> for Chunks<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut chunks = Chunks::with_capacity(iter.size_hint().0);
+        for value in iter {
+            chunks.push(value);
+        }
+        chunks
+    }
+}
+
+impl<T> IntoIterator for Chunks<T> {
+    type Item = T;
+    type IntoIter = vec::IntoIter<T>;
+
+    fn into_iter(self) -> vec::IntoIter<T> {
+        let mut values = Vec::with_capacity(self.len);
+        let chunks = Arc::try_unwrap(self.chunks).unwrap_or_else(|chunks| (*chunks).clone());
+        for chunk in chunks {
+            match Arc::try_unwrap(chunk) {
+                Ok(chunk) => values.extend(chunk),
+                // Chunks only get shared after `copy` has been set
+                Err(chunk) => values.extend(self.copy.unwrap()(&chunk)),
+            }
+        }
+        values.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Chunks<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+/// Iterator over the elements of `Chunks`
+pub struct Iter<'a, T: 'a> {
+    chunks: &'a Chunks<T>,
+    range: Range<usize>,
+}
+
+impl<'a, T> Clone for Iter<'a, T> {
+    fn clone(&self) -> Self {
+        Iter { chunks: self.chunks, range: self.range.clone() }
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for Iter<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.range.next().map(|x| &self.chunks[x])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.range.next_back().map(|x| &self.chunks[x])
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+#[test]
+fn chunks() {
+    let mut chunks: Chunks<usize> = (0..CHUNK * 2 + 10).collect();
+    assert_eq!(chunks.len(), CHUNK * 2 + 10);
+    assert_eq!(chunks[CHUNK + 1], CHUNK + 1);
+    assert_eq!(chunks.iter().next_back(), Some(&(CHUNK * 2 + 9)));
+
+    // Sharing copies nothing, later pushes copy the last chunk and don't show
+    // up in the copy
+    let shared = chunks.share();
+    assert!(Arc::ptr_eq(&shared.chunks, &chunks.chunks));
+    chunks.push(0);
+    assert!(!Arc::ptr_eq(&shared.chunks, &chunks.chunks));
+    assert!(!Arc::ptr_eq(&shared.chunks[2], &chunks.chunks[2]));
+    *chunks.get_mut(1).unwrap() = 100;
+    assert_eq!(shared.len(), CHUNK * 2 + 10);
+    assert_eq!(shared[1], 1);
+    assert_eq!(chunks[1], 100);
+    assert!(!Arc::ptr_eq(&shared.chunks[0], &chunks.chunks[0]));
+    assert!(Arc::ptr_eq(&shared.chunks[1], &chunks.chunks[1]));
+
+    // Taking the array apart copies what is still shared
+    let values: Vec<_> = chunks.into_iter().collect();
+    assert_eq!(values.len(), CHUNK * 2 + 11);
+    assert_eq!(values[CHUNK + 1], CHUNK + 1);
+    assert_eq!(shared.into_iter().count(), CHUNK * 2 + 10);
+}
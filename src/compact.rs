@@ -0,0 +1,204 @@
+use core::convert::TryFrom;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use {GroveError, ITree, NodeId};
+
+/// Values that can go in the compact binary format of `ITree::to_bytes`
+///
+/// Integers are written as varints, seven bits to a byte with the highest bit
+/// set on every byte but the last, and signed integers get zigzagged first
+/// so small negative numbers stay short. Strings get their length in front.
+pub trait Compact: Sized {
+    fn encode(&self, out: &mut Vec<u8>);
+
+    /// Decode a value from the start of `bytes` and move `bytes` past it, or
+    /// return `None` if they don't start with a valid value
+    fn decode(bytes: &mut &[u8]) -> Option<Self>;
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut n = 0u64;
+    for (i, &byte) in bytes.iter().enumerate() {
+        let bits = u64::from(byte & 0x7f);
+        // The tenth byte only has room for the highest bit
+        if (i == 9 && bits > 1) || i > 9 {
+            return None;
+        }
+        n |= bits << (7 * i);
+        if byte & 0x80 == 0 {
+            *bytes = &bytes[i + 1..];
+            return Some(n);
+        }
+    }
+    None
+}
+
+macro_rules! compact_unsigned {
+    ($($t:ty)*) => {$(
+        impl Compact for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                write_varint(out, *self as u64);
+            }
+
+            fn decode(bytes: &mut &[u8]) -> Option<Self> {
+                read_varint(bytes).and_then(|n| <$t>::try_from(n).ok())
+            }
+        }
+    )*};
+}
+
+macro_rules! compact_signed {
+    ($($t:ty)*) => {$(
+        impl Compact for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                let n = *self as i64;
+                write_varint(out, ((n << 1) ^ (n >> 63)) as u64);
+            }
+
+            fn decode(bytes: &mut &[u8]) -> Option<Self> {
+                let n = read_varint(bytes)?;
+                let n = (n >> 1) as i64 ^ -((n & 1) as i64);
+                <$t>::try_from(n).ok()
+            }
+        }
+    )*};
+}
+
+compact_unsigned!(u8 u16 u32 u64 usize);
+compact_signed!(i8 i16 i32 i64 isize);
+
+impl Compact for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        match u8::decode(bytes)? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl Compact for () {
+    fn encode(&self, _: &mut Vec<u8>) {}
+
+    fn decode(_: &mut &[u8]) -> Option<Self> {
+        Some(())
+    }
+}
+
+impl Compact for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        write_varint(out, self.len() as u64);
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        let len = usize::decode(bytes)?;
+        if len > bytes.len() {
+            return None;
+        }
+        let (s, rest) = bytes.split_at(len);
+        let s = String::from_utf8(s.to_vec()).ok()?;
+        *bytes = rest;
+        Some(s)
+    }
+}
+
+impl<T: Compact> ITree<T> {
+    /// Encode the tree in a compact binary format
+    ///
+    /// The format is the number of nodes as a varint followed by the nodes in
+    /// pre-order, every node as the varint number of its children and its
+    /// value. The shape needs no `NodeId`s, so it takes a byte for most nodes
+    /// on top of the values.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_varint(&mut out, self.len() as u64);
+        if let Some(root) = self.root_id() {
+            for (_, node) in self.iter_dfs(root) {
+                write_varint(&mut out, node.children().len() as u64);
+                node.value().encode(&mut out);
+            }
+        }
+        out
+    }
+
+    /// Decode a tree from the format of `to_bytes`. The nodes get their
+    /// `NodeId`s in pre-order. The error has the position of the first byte
+    /// that doesn't fit the format.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, GroveError> {
+        let mut rest = bytes;
+        let at = |rest: &[u8]| GroveError::InvalidEncoding(bytes.len() - rest.len());
+        let len = read_varint(&mut rest).ok_or_else(|| at(rest))? as usize;
+        // Every node takes at least a byte, which bounds what to allocate
+        if len > rest.len() {
+            return Err(at(rest));
+        }
+        let mut tree = ITree::with_capacity(len);
+        // Nodes with the number of their children left to read
+        let mut open: Vec<(NodeId, u64)> = Vec::new();
+        while tree.len() < len {
+            while open.last().is_some_and(|&(_, left)| left == 0) {
+                open.pop();
+            }
+            let parent = open.last_mut().map(|&mut (id, ref mut left)| {
+                *left -= 1;
+                id
+            });
+            if parent.is_none() && !tree.is_empty() {
+                return Err(at(rest));
+            }
+            let children = read_varint(&mut rest).ok_or_else(|| at(rest))?;
+            let start = rest;
+            let value = T::decode(&mut rest).ok_or_else(|| at(start))?;
+            let id = match parent {
+                Some(parent) => tree.add_node(parent, value),
+                None => tree.add_root(value).unwrap(),
+            };
+            open.push((id, children));
+        }
+        if !rest.is_empty() || open.iter().any(|&(_, left)| left > 0) {
+            return Err(at(rest));
+        }
+        Ok(tree)
+    }
+}
+
+#[test]
+fn compact() {
+    let tree = tree!{ 300u32 => [0 => [1, 2], 3] };
+    let bytes = tree.to_bytes();
+    assert_eq!(bytes, [5, 2, 0xac, 0x02, 2, 0, 0, 1, 0, 2, 0, 3]);
+    assert_eq!(ITree::from_bytes(&bytes), Ok(tree));
+
+    let words = tree!{ "a".to_string() => ["bc".to_string()] };
+    assert_eq!(words.to_bytes(), [2, 1, 1, b'a', 0, 2, b'b', b'c']);
+    assert_eq!(ITree::from_bytes(&words.to_bytes()), Ok(words));
+
+    let signed = tree!{ -1i64 => [i64::MIN, i64::MAX, 64] };
+    assert_eq!(ITree::from_bytes(&signed.to_bytes()), Ok(signed));
+    assert_eq!(ITree::<u8>::new().to_bytes(), [0]);
+    assert_eq!(ITree::<u8>::from_bytes(&[0]), Ok(ITree::new()));
+
+    // Truncated, too many nodes, too few nodes, trailing bytes
+    assert_eq!(ITree::<u32>::from_bytes(&bytes[..11]), Err(GroveError::InvalidEncoding(11)));
+    assert_eq!(ITree::<u8>::from_bytes(&[2, 0, 1, 0, 1]), Err(GroveError::InvalidEncoding(3)));
+    assert_eq!(ITree::<u8>::from_bytes(&[1, 1, 1]), Err(GroveError::InvalidEncoding(3)));
+    assert_eq!(ITree::<u8>::from_bytes(&[1, 0, 1, 9]), Err(GroveError::InvalidEncoding(3)));
+    assert_eq!(ITree::<u8>::from_bytes(&[]), Err(GroveError::InvalidEncoding(0)));
+    assert_eq!(ITree::<u8>::from_bytes(&[1, 0, 0x80, 0x02]), Err(GroveError::InvalidEncoding(2)));
+    assert_eq!(ITree::<u8>::from_bytes(&[0xff; 12]), Err(GroveError::InvalidEncoding(0)));
+}
@@ -0,0 +1,278 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+
+use GroveError;
+
+/// Lamport timestamp identifying an operation on a `CrdtTree`
+///
+/// Timestamps are ordered by their counter first and the replica that made
+/// them second, which gives every replica the same order of operations. Nodes
+/// are named by the timestamp of the operation that created them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    counter: u64,
+    replica: u64,
+}
+
+impl Timestamp {
+    /// Get the counter of the timestamp
+    pub fn counter(self) -> u64 {
+        self.counter
+    }
+
+    /// Get the replica that made the timestamp
+    pub fn replica(self) -> u64 {
+        self.replica
+    }
+}
+
+/// Operation on a `CrdtTree`, to be sent to the other replicas
+///
+/// Operations move `child` below `parent` and give it `value`, either of which
+/// can be left out to keep what the child had. Adding a node moves a new node
+/// into the tree and removing one moves it below `CrdtTree::TRASH`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrdtOp<T> {
+    time: Timestamp,
+    parent: Option<Timestamp>,
+    child: Timestamp,
+    value: Option<T>,
+}
+
+impl<T> CrdtOp<T> {
+    /// Get the timestamp of the operation
+    pub fn time(&self) -> Timestamp {
+        self.time
+    }
+
+    /// Get the node the child gets moved below
+    pub fn parent(&self) -> Option<Timestamp> {
+        self.parent
+    }
+
+    /// Get the node the operation changes
+    pub fn child(&self) -> Timestamp {
+        self.child
+    }
+
+    /// Get the value the child gets
+    pub fn value(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+}
+
+/// An operation that has been applied along with what the child looked like
+/// before, so it can be undone
+#[derive(Debug, Clone)]
+struct LogEntry<T> {
+    op: CrdtOp<T>,
+    old: Option<(Timestamp, T)>,
+}
+
+/// Movable Tree CRDT
+///
+/// A tree that is replicated between peers that change it at the same time,
+/// following the move operation of Kleppmann et al. Every replica applies the
+/// operations of the others with `apply`, and replicas that have seen the
+/// same operations end up with the same tree no matter in which order the
+/// operations arrived.
+///
+/// Operations are applied in timestamp order. When an operation arrives late,
+/// the ones after it are undone, the late operation is applied and the rest
+/// are done again. Moves that would make a node its own ancestor are skipped,
+/// so concurrent moves never create cycles.
+#[derive(Debug, Clone)]
+pub struct CrdtTree<T> {
+    replica: u64,
+    counter: u64,
+    /// Parent and value of every node
+    nodes: BTreeMap<Timestamp, (Timestamp, T)>,
+    children: BTreeMap<Timestamp, BTreeSet<Timestamp>>,
+    /// Every applied operation in timestamp order
+    log: Vec<LogEntry<T>>,
+}
+
+impl<T: Clone> CrdtTree<T> {
+    /// The node at the top of the tree, it has no value
+    pub const ROOT: Timestamp = Timestamp { counter: 0, replica: 0 };
+    /// Removed nodes get moved below this node
+    pub const TRASH: Timestamp = Timestamp { counter: 0, replica: 1 };
+
+    /// Create an empty tree for a replica. Every replica needs its own id.
+    pub fn new(replica: u64) -> Self {
+        CrdtTree {
+            replica,
+            counter: 0,
+            nodes: BTreeMap::new(),
+            children: BTreeMap::new(),
+            log: Vec::new(),
+        }
+    }
+
+    /// Get the number of nodes in the tree, including removed ones
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Get the value of a node
+    pub fn get(&self, node: Timestamp) -> Option<&T> {
+        self.nodes.get(&node).map(|n| &n.1)
+    }
+
+    /// Get the parent of a node
+    pub fn parent(&self, node: Timestamp) -> Option<Timestamp> {
+        self.nodes.get(&node).map(|n| n.0)
+    }
+
+    /// Get the children of a node, ordered by the time they were created
+    pub fn children(&self, node: Timestamp) -> impl Iterator<Item = Timestamp> + '_ {
+        self.children.get(&node).into_iter().flatten().cloned()
+    }
+
+    /// Check whether a node has been moved to the trash or below a removed node
+    pub fn is_removed(&self, node: Timestamp) -> bool {
+        self.is_ancestor(Self::TRASH, node)
+    }
+
+    /// Add a node below `parent` and return the operation for the other
+    /// replicas. Its timestamp names the new node.
+    pub fn insert(&mut self, parent: Timestamp, value: T) -> CrdtOp<T> {
+        let time = self.tick();
+        self.local(CrdtOp { time, parent: Some(parent), child: time, value: Some(value) })
+    }
+
+    /// Move a node below `parent`. Moves that would make the node its own
+    /// ancestor are errors.
+    pub fn move_node(&mut self, node: Timestamp, parent: Timestamp) -> Result<CrdtOp<T>, GroveError> {
+        if self.get(node).is_none() {
+            return Err(GroveError::InvalidTimestamp(node));
+        }
+        if node == parent || self.is_ancestor(node, parent) {
+            return Err(GroveError::Cycle);
+        }
+        let time = self.tick();
+        Ok(self.local(CrdtOp { time, parent: Some(parent), child: node, value: None }))
+    }
+
+    /// Replace the value of a node
+    pub fn set(&mut self, node: Timestamp, value: T) -> Result<CrdtOp<T>, GroveError> {
+        if self.get(node).is_none() {
+            return Err(GroveError::InvalidTimestamp(node));
+        }
+        let time = self.tick();
+        Ok(self.local(CrdtOp { time, parent: None, child: node, value: Some(value) }))
+    }
+
+    /// Remove a node along with everything below it by moving it to the trash
+    pub fn remove(&mut self, node: Timestamp) -> Result<CrdtOp<T>, GroveError> {
+        self.move_node(node, Self::TRASH)
+    }
+
+    /// Apply an operation from another replica. Operations that have been
+    /// applied before are ignored.
+    pub fn apply(&mut self, op: CrdtOp<T>) {
+        self.counter = self.counter.max(op.time.counter);
+        // Undo everything that comes after the operation
+        let pos = match self.log.binary_search_by(|e| e.op.time.cmp(&op.time)) {
+            Ok(_) => return,
+            Err(pos) => pos,
+        };
+        let later = self.log.split_off(pos);
+        for entry in later.iter().rev() {
+            self.put(entry.op.child, entry.old.clone());
+        }
+        self.run(op);
+        for entry in later {
+            self.run(entry.op);
+        }
+    }
+
+    /// Apply an operation made by this replica
+    fn local(&mut self, op: CrdtOp<T>) -> CrdtOp<T> {
+        self.run(op.clone());
+        op
+    }
+
+    fn tick(&mut self) -> Timestamp {
+        self.counter += 1;
+        Timestamp { counter: self.counter, replica: self.replica }
+    }
+
+    /// Do the move of an operation that comes after every logged one
+    fn run(&mut self, op: CrdtOp<T>) {
+        let old = self.nodes.get(&op.child).cloned();
+        let parent = op.parent.or(old.as_ref().map(|n| n.0));
+        let value = op.value.clone().or_else(|| old.as_ref().map(|n| n.1.clone()));
+        if let (Some(parent), Some(value)) = (parent, value) {
+            if parent != op.child && !self.is_ancestor(op.child, parent) {
+                self.put(op.child, Some((parent, value)));
+            }
+        }
+        self.log.push(LogEntry { op, old });
+    }
+
+    /// Check whether `node` is below `ancestor`
+    fn is_ancestor(&self, ancestor: Timestamp, node: Timestamp) -> bool {
+        let mut current = self.parent(node);
+        while let Some(id) = current {
+            if id == ancestor {
+                return true;
+            }
+            current = self.parent(id);
+        }
+        false
+    }
+
+    /// Set the parent and value of a node, or take it out of the tree
+    fn put(&mut self, node: Timestamp, place: Option<(Timestamp, T)>) {
+        if let Some((parent, _)) = self.nodes.remove(&node) {
+            let siblings = self.children.get_mut(&parent).unwrap();
+            siblings.remove(&node);
+            if siblings.is_empty() {
+                self.children.remove(&parent);
+            }
+        }
+        if let Some((parent, value)) = place {
+            self.children.entry(parent).or_default().insert(node);
+            self.nodes.insert(node, (parent, value));
+        }
+    }
+}
+
+#[test]
+fn converge() {
+    type Tree = CrdtTree<&'static str>;
+    let mut a = Tree::new(1);
+    let x = a.insert(Tree::ROOT, "x");
+    let y = a.insert(Tree::ROOT, "y");
+    let mut b = Tree::new(2);
+    b.apply(x.clone());
+    b.apply(y.clone());
+
+    // Moving x below y and y below x at the same time would make a cycle, the
+    // later move gets skipped on both replicas
+    let a_move = a.move_node(x.child(), y.child()).unwrap();
+    let b_move = b.move_node(y.child(), x.child()).unwrap();
+    let b_set = b.set(x.child(), "z").unwrap();
+    let a_remove = a.remove(y.child()).unwrap();
+    a.apply(b_move.clone());
+    a.apply(b_set.clone());
+    b.apply(a_remove);
+    b.apply(a_move);
+    // Applying an operation twice changes nothing
+    a.apply(b_set);
+
+    for tree in &[&a, &b] {
+        assert_eq!(tree.parent(x.child()), Some(y.child()));
+        assert_eq!(tree.parent(y.child()), Some(Tree::TRASH));
+        assert_eq!(tree.get(x.child()), Some(&"z"));
+        assert!(tree.is_removed(x.child()));
+        assert_eq!(tree.children(Tree::ROOT).count(), 0);
+    }
+    assert_eq!(a.nodes, b.nodes);
+    assert_eq!(a.move_node(Tree::TRASH, x.child()), Err(GroveError::InvalidTimestamp(Tree::TRASH)));
+}
@@ -0,0 +1,194 @@
+use core::hash::{Hash, Hasher};
+use core::ops::Index;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use hamt::FnvHasher;
+use {GroveError, ITree, NodeId};
+
+/// Hash-Consed Tree
+///
+/// Stores trees as a directed acyclic graph where every distinct subtree
+/// exists only once. Adding a node with a value and children the graph has
+/// already seen hands back the `NodeId` of the existing node instead of
+/// storing a copy, so trees with a lot of repetition take far less memory and
+/// two subtrees are equal exactly when their `NodeId`s are.
+///
+/// Nodes get built from the bottom up, children before their parents, and a
+/// node can be a child of any number of parents.
+#[derive(Debug, Clone)]
+pub struct DagTree<T> {
+    nodes: Vec<DagNode<T>>,
+    /// Nodes by the hash of their value and children
+    index: BTreeMap<u64, Vec<usize>>,
+}
+
+/// A node in a `DagTree`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DagNode<T> {
+    value: T,
+    children: Vec<NodeId>,
+}
+
+impl<T> DagNode<T> {
+    /// Get the node's value
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Get the node's children `NodeId`s
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+impl<T: Hash + Eq> DagTree<T> {
+    /// Create a new empty `DagTree`
+    pub fn new() -> Self {
+        DagTree { nodes: Vec::new(), index: BTreeMap::new() }
+    }
+
+    /// Get the number of distinct nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Get the node of the given `Id` if it exists
+    pub fn get(&self, node: NodeId) -> Option<&DagNode<T>> {
+        self.nodes.get(node.index())
+    }
+
+    /// Add a node with a value and children, or find the node that has them
+    /// already. Returns the `NodeId` of the node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the children is not in the graph. See `try_add` for a
+    /// version that returns an error instead.
+    pub fn add(&mut self, value: T, children: &[NodeId]) -> NodeId {
+        match self.try_add(value, children) {
+            Ok(id) => id,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Add a node like `add`, but returns an error rather than panicking if
+    /// one of the children is not in the graph
+    pub fn try_add(&mut self, value: T, children: &[NodeId]) -> Result<NodeId, GroveError> {
+        if let Some(&child) = children.iter().find(|c| self.get(**c).is_none()) {
+            return Err(GroveError::InvalidNodeId(child));
+        }
+        let mut hasher = FnvHasher::default();
+        value.hash(&mut hasher);
+        children.hash(&mut hasher);
+        let bucket = self.index.entry(hasher.finish()).or_default();
+        let nodes = &self.nodes;
+        if let Some(&x) = bucket.iter().find(|&&x| nodes[x].value == value && nodes[x].children == children) {
+            return Ok(NodeId::new(x));
+        }
+        bucket.push(nodes.len());
+        self.nodes.push(DagNode { value, children: children.to_vec() });
+        Ok(NodeId::new(self.nodes.len() - 1))
+    }
+
+    /// Add every node of a tree, sharing the subtrees the graph has already
+    /// seen. Returns the `NodeId` of the root of the tree, or `None` for an
+    /// empty tree.
+    pub fn add_tree(&mut self, tree: ITree<T>) -> Option<NodeId> {
+        let children = tree.children;
+        let mut values: Vec<_> = tree.values.into_iter().map(Some).collect();
+        let mut ids = vec![NodeId::new(0); values.len()];
+        for x in (0..values.len()).rev() {
+            let kids: Vec<_> = children[x].iter().map(|c| ids[c.index()]).collect();
+            ids[x] = self.add(values[x].take().unwrap(), &kids);
+        }
+        ids.first().cloned()
+    }
+
+    /// Get the number of nodes the subtree of a node would have without any
+    /// sharing, or `None` if the node does not exist. Saturates at
+    /// `usize::MAX`.
+    pub fn expanded_len(&self, node: NodeId) -> Option<usize> {
+        self.get(node)?;
+        // Children always come before their parents
+        let mut sizes = vec![0usize; node.index() + 1];
+        for (x, n) in self.nodes[..=node.index()].iter().enumerate() {
+            sizes[x] = n.children.iter().fold(1usize, |sum, c| sum.saturating_add(sizes[c.index()]));
+        }
+        Some(sizes[node.index()])
+    }
+
+    /// Copy the subtree of a node into an `ITree` without any sharing, or an
+    /// empty tree if the node does not exist
+    pub fn to_itree(&self, node: NodeId) -> ITree<T>
+        where T: Clone
+    {
+        if self.get(node).is_none() {
+            return ITree::new();
+        }
+        ITree::build_from(node, |&id| {
+            let node = &self.nodes[id.index()];
+            (node.value.clone(), node.children.clone())
+        })
+    }
+}
+
+impl<T: Hash + Eq> Default for DagTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<NodeId> for DagTree<T> {
+    type Output = T;
+
+    /// Get the value of a node
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `NodeId` does not refer to a node in the graph.
+    fn index(&self, node: NodeId) -> &T {
+        match self.nodes.get(node.index()) {
+            Some(node) => &node.value,
+            None => panic!("{}", GroveError::InvalidNodeId(node)),
+        }
+    }
+}
+
+#[test]
+fn dag_tree() {
+    // (x + y) * (x + y)
+    let mut dag = DagTree::new();
+    let x = dag.add("x", &[]);
+    let y = dag.add("y", &[]);
+    let sum = dag.add("+", &[x, y]);
+    let again = dag.add("+", &[x, y]);
+    assert_eq!(sum, again);
+    let product = dag.add("*", &[sum, again]);
+    assert_eq!(dag.len(), 4);
+    assert_eq!(dag.expanded_len(product), Some(7));
+    assert_eq!(dag[product], "*");
+    assert_eq!(dag.get(product).unwrap().children(), [sum, sum]);
+    // Order of children matters
+    assert_ne!(dag.add("+", &[y, x]), sum);
+    assert_eq!(dag.try_add("-", &[NodeId::new(9)]), Err(GroveError::InvalidNodeId(NodeId::new(9))));
+
+    let tree = tree!{ "*" => ["+" => ["x", "y"], "+" => ["x", "y"]] };
+    assert_eq!(dag.add_tree(tree.clone()), Some(product));
+    assert_eq!(dag.len(), 5);
+    assert!(dag.to_itree(product).is_identical(&tree));
+    assert_eq!(dag.add_tree(ITree::new()), None);
+
+    // Doubling the tree at every level only adds a node per level
+    let mut top = dag.add("x", &[]);
+    for _ in 0..100 {
+        top = dag.add("+", &[top, top]);
+    }
+    assert_eq!(dag.len(), 105);
+    assert_eq!(dag.expanded_len(top), Some(usize::MAX));
+}
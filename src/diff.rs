@@ -0,0 +1,366 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use {GroveError, ITree, MTree, NodeId, PatchError, SlotId};
+
+/// A single change in an edit script
+///
+/// Created with `ITree::diff`. Edits refer to nodes by their `NodeId`, nodes
+/// with the same `NodeId` in both trees are taken to be the same node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeEdit<T> {
+    /// Add a new node with the given value at `index` among the children of
+    /// `parent`. Nodes without a parent become the root.
+    Insert {
+        id: NodeId,
+        parent: Option<NodeId>,
+        index: usize,
+        value: T,
+    },
+    /// Remove a node along with everything below it
+    Delete {
+        id: NodeId,
+    },
+    /// Replace the value of a node
+    Update {
+        id: NodeId,
+        value: T,
+    },
+    /// Move a node and everything below it to `index` among the children of
+    /// `parent`
+    Move {
+        id: NodeId,
+        parent: NodeId,
+        index: usize,
+    },
+}
+
+impl<T: PartialEq + Clone> ITree<T> {
+    /// Compute the edits that turn this tree into `other`. Applying the edits
+    /// in order to this tree gives a tree with the same structure and values as
+    /// `other`, where every node keeps its `NodeId`.
+    ///
+    /// Nodes are matched up by their `NodeId`, which works best for trees that
+    /// came from the same tree, like the results of `map` or a snapshot of a
+    /// tree that kept growing.
+    pub fn diff(&self, other: &ITree<T>) -> Vec<TreeEdit<T>> {
+        let mut edits = Vec::new();
+        let common = self.len().min(other.len());
+        let size = self.len().max(other.len());
+
+        // Where every node is while the edits so far are applied
+        let mut parents: Vec<Option<NodeId>> = (0..size)
+            .map(|x| self.parents.get(x).cloned().unwrap_or(None))
+            .collect();
+        let mut children: Vec<Vec<NodeId>> = (0..size)
+            .map(|x| self.children.get(x).map(|c| c.to_vec()).unwrap_or_default())
+            .collect();
+
+        // Put every node of `other` in its place, parents first. Once a node is
+        // placed its ancestors already are, so moving a node never creates a
+        // cycle.
+        for (id, node) in other.iter_dfs(NodeId::new(0)) {
+            let x = id.index();
+            if x < common && self.values[x] != *node.value() {
+                edits.push(TreeEdit::Update { id, value: node.value().clone() });
+            }
+            let parent = match node.parent() {
+                Some(parent) => parent,
+                None => {
+                    if x >= common {
+                        edits.push(TreeEdit::Insert { id, parent: None, index: 0, value: node.value().clone() });
+                    }
+                    continue;
+                }
+            };
+            let index = other.children[parent.index()].iter().position(|&c| c == id).unwrap();
+            if x < common {
+                if parents[x] == Some(parent) && children[parent.index()].get(index) == Some(&id) {
+                    continue;
+                }
+                edits.push(TreeEdit::Move { id, parent, index });
+                if let Some(old) = parents[x] {
+                    children[old.index()].retain(|&c| c != id);
+                }
+            } else {
+                edits.push(TreeEdit::Insert { id, parent: Some(parent), index, value: node.value().clone() });
+            }
+            parents[x] = Some(parent);
+            children[parent.index()].insert(index, id);
+        }
+
+        // Whatever is left of this tree only holds nodes `other` doesn't have
+        for (x, parent) in parents.iter().enumerate().take(self.len()).skip(common) {
+            if parent.is_none_or(|parent| parent.index() < common) {
+                edits.push(TreeEdit::Delete { id: NodeId::new(x) });
+            }
+        }
+        edits
+    }
+}
+
+impl<T: Clone> MTree<T> {
+    /// Apply an edit script like the ones made by `ITree::diff` to this tree.
+    ///
+    /// Every edit gets checked before the tree changes at all, so if one of
+    /// them can't be applied the error points at it and the tree is left as it
+    /// was. Edits refer to the nodes of this tree by their position. Nodes
+    /// added by `Insert` edits get new `SlotId`s in this tree, but later edits
+    /// in the script keep referring to them by the `NodeId` given in the edit.
+    pub fn apply(&mut self, edits: &[TreeEdit<T>]) -> Result<(), PatchError> {
+        {
+            let mut check = Check::new(self);
+            for (i, edit) in edits.iter().enumerate() {
+                check.edit(edit).map_err(|e| PatchError::new(i, e))?;
+            }
+        }
+
+        // `SlotId`s the inserted nodes got in this tree
+        let mut ids = BTreeMap::new();
+        let real = |tree: &MTree<T>, ids: &BTreeMap<NodeId, SlotId>, id: NodeId| match ids.get(&id) {
+            Some(&slot) => slot,
+            None => tree.slot(id).unwrap(),
+        };
+        for edit in edits {
+            match *edit {
+                TreeEdit::Insert { id, parent, index, ref value } => {
+                    let new = match parent {
+                        Some(parent) => self.insert_child(real(self, &ids, parent), index, value.clone()),
+                        None => self.add_root(value.clone()).unwrap(),
+                    };
+                    ids.insert(id, new);
+                }
+                TreeEdit::Delete { id } => {
+                    self.remove_subtree(real(self, &ids, id)).unwrap();
+                }
+                TreeEdit::Update { id, ref value } => {
+                    self.set(real(self, &ids, id), value.clone()).unwrap();
+                }
+                TreeEdit::Move { id, parent, index } => {
+                    let (id, parent) = (real(self, &ids, id), real(self, &ids, parent));
+                    self.detach(id).unwrap();
+                    self.attach(parent, id).unwrap();
+                    let children = &mut self.inode_mut(parent).unwrap().children;
+                    children.pop();
+                    children.insert(index, id);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where a node is while an edit script gets checked
+pub(crate) struct Place {
+    pub(crate) parent: Option<NodeId>,
+    pub(crate) children: Vec<NodeId>,
+}
+
+/// The structure of a tree with the edits checked so far applied to it.
+/// Nodes only get copied out of the tree once an edit touches them.
+pub(crate) struct Check<'a, T: 'a> {
+    tree: &'a MTree<T>,
+    /// Nodes touched so far, deleted nodes are `None`
+    nodes: BTreeMap<NodeId, Option<Place>>,
+    root: Option<NodeId>,
+}
+
+impl<'a, T> Check<'a, T> {
+    pub(crate) fn new(tree: &'a MTree<T>) -> Self {
+        Check { tree, nodes: BTreeMap::new(), root: tree.root_id().map(SlotId::id) }
+    }
+
+    /// Make sure an edit can be applied and apply it. Edits that can't be
+    /// applied leave the structure as it was.
+    pub(crate) fn edit(&mut self, edit: &TreeEdit<T>) -> Result<(), GroveError> {
+        match *edit {
+            TreeEdit::Insert { id, parent, index, .. } => {
+                if self.place(id).is_ok() {
+                    return Err(GroveError::NodeExists(id));
+                }
+                match parent {
+                    Some(parent) => {
+                        self.place(parent)?;
+                        self.nodes.insert(id, Some(Place { parent: None, children: Vec::new() }));
+                        self.link(id, parent, index)
+                    }
+                    None if self.root.is_some() => Err(GroveError::RootExists),
+                    None => {
+                        self.nodes.insert(id, Some(Place { parent: None, children: Vec::new() }));
+                        self.root = Some(id);
+                        Ok(())
+                    }
+                }
+            }
+            TreeEdit::Delete { id } => {
+                self.unlink(id)?;
+                let mut stack = vec![id];
+                while let Some(id) = stack.pop() {
+                    stack.extend(self.place(id)?.children.iter().cloned());
+                    self.nodes.insert(id, None);
+                }
+                Ok(())
+            }
+            TreeEdit::Update { id, .. } => self.place(id).map(|_| ()),
+            TreeEdit::Move { id, parent, index } => {
+                self.place(id)?;
+                // Walk up from the new parent to make sure we don't create a cycle
+                let mut current = Some(parent);
+                while let Some(x) = current {
+                    if x == id {
+                        return Err(GroveError::Cycle);
+                    }
+                    current = self.place(x)?.parent;
+                }
+                self.unlink(id)?;
+                self.link(id, parent, index)
+            }
+        }
+    }
+
+    /// Get where a node is, or an error if it isn't in the tree
+    pub(crate) fn place(&mut self, id: NodeId) -> Result<&mut Place, GroveError> {
+        let tree = self.tree;
+        let place = self.nodes.entry(id).or_insert_with(|| {
+            tree.slot(id).and_then(|slot| tree.get(slot)).map(|n| Place {
+                parent: n.parent().map(SlotId::id),
+                children: n.children().iter().map(|c| c.id()).collect(),
+            })
+        });
+        place.as_mut().ok_or(GroveError::InvalidNodeId(id))
+    }
+
+    /// Take a node out of the children of its parent
+    fn unlink(&mut self, id: NodeId) -> Result<(), GroveError> {
+        match self.place(id)?.parent.take() {
+            Some(parent) => self.place(parent)?.children.retain(|&c| c != id),
+            None if self.root == Some(id) => self.root = None,
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Put a node at `index` among the children of `parent`
+    fn link(&mut self, id: NodeId, parent: NodeId, index: usize) -> Result<(), GroveError> {
+        let children = &mut self.place(parent)?.children;
+        if index > children.len() {
+            return Err(GroveError::InvalidIndex(index));
+        }
+        children.insert(index, id);
+        self.place(id)?.parent = Some(parent);
+        Ok(())
+    }
+}
+
+#[test]
+fn diff() {
+    let n = NodeId::new;
+    // 0 -> 1 -> 3
+    // |    |--> 5
+    // |--> 2 -> 4
+    let mut old = ITree::new();
+    let root = old.add_root(0).unwrap();
+    let one = old.add_node(root, 1);
+    let two = old.add_node(root, 2);
+    old.add_node(one, 3);
+    old.add_node(two, 4);
+    old.add_node(one, 5);
+    assert!(old.diff(&old).is_empty());
+
+    // 0 -> 1
+    // |--> 20 -> 3
+    //        |--> 4
+    let mut new = ITree::new();
+    new.add_root(0).unwrap();
+    new.add_node(root, 1);
+    new.add_node(root, 20);
+    new.add_node(two, 3);
+    new.add_node(two, 4);
+    assert_eq!(old.diff(&new), vec![
+        TreeEdit::Update { id: two, value: 20 },
+        TreeEdit::Move { id: n(3), parent: two, index: 0 },
+        TreeEdit::Delete { id: n(5) },
+    ]);
+
+    // 0 -> 1
+    // |--> 20 -> 3 -> 5
+    // |    |--> 4
+    // |--> 6
+    new.add_node(n(3), 5);
+    new.add_node(root, 6);
+    assert_eq!(old.diff(&new), vec![
+        TreeEdit::Update { id: two, value: 20 },
+        TreeEdit::Move { id: n(3), parent: two, index: 0 },
+        TreeEdit::Move { id: n(5), parent: n(3), index: 0 },
+        TreeEdit::Insert { id: n(6), parent: Some(root), index: 2, value: 6 },
+    ]);
+
+    assert_eq!(ITree::new().diff(&new).len(), new.len());
+    assert_eq!(old.diff(&ITree::new()), vec![TreeEdit::Delete { id: root }]);
+}
+
+#[test]
+fn apply() {
+    let n = NodeId::new;
+    // Both trees have the same shape and values
+    fn same(tree: &MTree<i32>, other: &ITree<i32>) -> bool {
+        let mut stack = vec![(tree.root_id().unwrap(), NodeId::new(0))];
+        while let Some((a, b)) = stack.pop() {
+            let (a, b) = (tree.get(a).unwrap(), other.get(b).unwrap());
+            if a.value() != b.value() || a.children().len() != b.children().len() {
+                return false;
+            }
+            stack.extend(a.children().iter().cloned().zip(b.children().iter().cloned()));
+        }
+        tree.len() == other.len()
+    }
+
+    // 0 -> 1 -> 3
+    // |    |--> 5
+    // |--> 2 -> 4
+    let mut old = ITree::new();
+    let root = old.add_root(0).unwrap();
+    let one = old.add_node(root, 1);
+    let two = old.add_node(root, 2);
+    old.add_node(one, 3);
+    old.add_node(two, 4);
+    old.add_node(one, 5);
+
+    // 0 -> 20 -> 4 -> 6
+    //        |--> 3 -> 7
+    //             |--> 5
+    let mut new = ITree::new();
+    new.add_root(0).unwrap();
+    new.add_node(root, 1);
+    new.add_node(root, 20);
+    new.add_node(two, 4);
+    new.add_node(two, 3);
+    new.add_node(n(4), 5);
+    new.add_node(n(3), 6);
+    new.add_node(n(4), 7);
+    let mut tree = MTree::from(old.clone());
+    assert_eq!(tree.apply(&old.diff(&new)), Ok(()));
+    assert!(same(&tree, &new));
+
+    let mut tree = MTree::new();
+    assert_eq!(tree.apply(&ITree::new().diff(&new)), Ok(()));
+    assert!(same(&tree, &new));
+
+    // Nothing changes unless every edit can be applied
+    let mut tree = MTree::from(old.clone());
+    let edits = vec![
+        TreeEdit::Update { id: root, value: 10 },
+        TreeEdit::Move { id: one, parent: n(3), index: 0 },
+    ];
+    let error = tree.apply(&edits).unwrap_err();
+    assert_eq!((error.edit(), error.error()), (1, &GroveError::Cycle));
+    assert!(same(&tree, &old));
+    let edits = vec![
+        TreeEdit::Insert { id: n(6), parent: Some(two), index: 1, value: 6 },
+        TreeEdit::Insert { id: n(7), parent: Some(n(6)), index: 1, value: 7 },
+    ];
+    assert_eq!(tree.apply(&edits), Err(PatchError::new(1, GroveError::InvalidIndex(1))));
+    let edits = vec![TreeEdit::Delete { id: one }, TreeEdit::Update { id: n(5), value: 50 }];
+    assert_eq!(tree.apply(&edits), Err(PatchError::new(1, GroveError::InvalidNodeId(n(5)))));
+    assert!(same(&tree, &old));
+}
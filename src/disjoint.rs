@@ -0,0 +1,179 @@
+use alloc::vec::Vec;
+
+/// Disjoint Set Forest
+///
+/// Splits the elements `0..len` into sets, where every set is a tree whose
+/// root stands for the whole set. Joining sets hangs the root of the lower
+/// tree below the other one, and finding the set of an element points every
+/// node on the way up straight at the root. Together that keeps both close to
+/// constant time.
+///
+/// Besides the trees the elements of every set are linked up in a ring, so
+/// the members of a set can be listed without looking at everything else.
+#[derive(Debug, Clone)]
+pub struct DisjointSet {
+    parents: Vec<usize>,
+    /// Upper bound of the height of the tree of every root
+    ranks: Vec<u8>,
+    /// Number of elements in the set of every root
+    sizes: Vec<usize>,
+    /// Next element in the ring of every set
+    next: Vec<usize>,
+    sets: usize,
+}
+
+impl DisjointSet {
+    /// Create `len` elements that are each in a set of their own
+    pub fn new(len: usize) -> Self {
+        DisjointSet {
+            parents: (0..len).collect(),
+            ranks: vec![0; len],
+            sizes: vec![1; len],
+            next: (0..len).collect(),
+            sets: len,
+        }
+    }
+
+    /// Get the number of elements
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Get the number of sets
+    pub fn sets(&self) -> usize {
+        self.sets
+    }
+
+    /// Add a new element in a set of its own and return it
+    pub fn make_set(&mut self) -> usize {
+        let x = self.len();
+        self.parents.push(x);
+        self.ranks.push(0);
+        self.sizes.push(1);
+        self.next.push(x);
+        self.sets += 1;
+        x
+    }
+
+    /// Get the root of the set of an element
+    ///
+    /// # Panics
+    ///
+    /// If the element is out of bounds.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        let mut x = x;
+        while self.parents[x] != root {
+            let parent = self.parents[x];
+            self.parents[x] = root;
+            x = parent;
+        }
+        root
+    }
+
+    /// Join the sets of two elements, returning whether they were in
+    /// different sets before
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.ranks[a] < self.ranks[b] {
+            core::mem::swap(&mut a, &mut b);
+        }
+        self.parents[b] = a;
+        if self.ranks[a] == self.ranks[b] {
+            self.ranks[a] += 1;
+        }
+        self.sizes[a] += self.sizes[b];
+        // Swapping the successors of two elements of different rings joins
+        // them into one
+        self.next.swap(a, b);
+        self.sets -= 1;
+        true
+    }
+
+    /// Check if two elements are in the same set
+    pub fn same_set(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Get the number of elements in the set of an element
+    pub fn set_len(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.sizes[root]
+    }
+
+    /// Iterate over the elements in the same set as `x`, starting with `x`
+    ///
+    /// # Panics
+    ///
+    /// If the element is out of bounds.
+    pub fn members(&self, x: usize) -> Members<'_> {
+        assert!(x < self.len(), "element {} out of bounds for length {}", x, self.len());
+        Members { next: &self.next, start: x, current: Some(x) }
+    }
+}
+
+/// Iterator over the elements of a set of a `DisjointSet`
+#[derive(Debug, Clone)]
+pub struct Members<'a> {
+    next: &'a [usize],
+    start: usize,
+    current: Option<usize>,
+}
+
+impl<'a> Iterator for Members<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let x = self.current?;
+        let next = self.next[x];
+        self.current = if next == self.start { None } else { Some(next) };
+        Some(x)
+    }
+}
+
+#[test]
+fn disjoint_set() {
+    let mut sets = DisjointSet::new(10);
+    assert_eq!(sets.sets(), 10);
+    assert!(sets.union(0, 1));
+    assert!(sets.union(2, 3));
+    assert!(sets.union(1, 3));
+    assert!(!sets.union(0, 2));
+    assert!(sets.union(5, 9));
+    assert_eq!(sets.sets(), 6);
+    assert!(sets.same_set(0, 3));
+    assert!(!sets.same_set(0, 5));
+    assert_eq!(sets.set_len(2), 4);
+
+    let mut members: Vec<_> = sets.members(3).collect();
+    assert_eq!(members[0], 3);
+    members.sort();
+    assert_eq!(members, [0, 1, 2, 3]);
+    assert_eq!(sets.members(4).collect::<Vec<_>>(), [4]);
+
+    let x = sets.make_set();
+    assert_eq!(x, 10);
+    sets.union(x, 9);
+    let mut members: Vec<_> = sets.members(5).collect();
+    members.sort();
+    assert_eq!(members, [5, 9, 10]);
+
+    // A long chain of unions stays shallow
+    let mut chain = DisjointSet::new(1 << 12);
+    for x in 1..chain.len() {
+        chain.union(x - 1, x);
+    }
+    assert_eq!(chain.sets(), 1);
+    assert!(chain.ranks.iter().all(|&rank| rank <= 12));
+    assert_eq!(chain.members(77).count(), 1 << 12);
+}
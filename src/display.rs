@@ -0,0 +1,246 @@
+use core::fmt::{self, Write};
+
+use alloc::string::{String, ToString};
+
+use visit::Event;
+use {ITree, NodeId};
+
+impl<T> ITree<T> {
+    /// Render the tree with box drawing connectors like the `tree` command,
+    /// formatting every value with `format`.
+    ///
+    /// ```text
+    /// root
+    /// ├── a
+    /// │   └── c
+    /// └── b
+    /// ```
+    pub fn pretty<F>(&self, format: F) -> Pretty<'_, T, F>
+        where F: Fn(&T) -> String
+    {
+        Pretty { tree: self, format }
+    }
+
+    /// Render the tree like `pretty`, but format every value by writing to
+    /// the formatter like a `Display` implementation would, so values don't
+    /// have to implement `Display` themselves
+    ///
+    /// ```
+    /// use grove::ITree;
+    ///
+    /// struct Job { name: &'static str, done: bool }
+    ///
+    /// let mut tree = ITree::new();
+    /// let root = tree.add_root(Job { name: "build", done: false }).unwrap();
+    /// tree.add_node(root, Job { name: "fetch", done: true });
+    /// let shown = tree.display_with(|job, f| {
+    ///     f.write_str(job.name)?;
+    ///     if job.done { f.write_str(" ✓") } else { Ok(()) }
+    /// });
+    /// assert_eq!(shown.to_string(), "build\n└── fetch ✓\n");
+    /// ```
+    pub fn display_with<'a, F>(&'a self, format: F) -> impl fmt::Display + 'a
+        where F: Fn(&T, &mut fmt::Formatter) -> fmt::Result + 'a
+    {
+        self.pretty(move |value| Formatted(value, &format).to_string())
+    }
+}
+
+/// A value shown with a formatting function
+struct Formatted<'a, T: 'a, F: 'a>(&'a T, &'a F);
+
+impl<'a, T, F> fmt::Display for Formatted<'a, T, F>
+    where F: Fn(&T, &mut fmt::Formatter) -> fmt::Result
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.1)(self.0, f)
+    }
+}
+
+/// Renders an `ITree` with box drawing connectors
+///
+/// Created with `ITree::pretty`.
+pub struct Pretty<'a, T: 'a, F> {
+    tree: &'a ITree<T>,
+    format: F,
+}
+
+impl<'a, T, F> fmt::Display for Pretty<'a, T, F>
+    where F: Fn(&T) -> String
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.tree.is_empty() {
+            return Ok(());
+        }
+        // Nodes left to print with the prefix of their line and whether they
+        // are the last of their siblings
+        let mut stack = vec![(NodeId::new(0), String::new(), true)];
+        while let Some((id, prefix, last)) = stack.pop() {
+            let x = id.index();
+            let children = &self.tree.children[x];
+            let (connector, indent) = match (x, last) {
+                (0, _) => ("", ""),
+                (_, true) => ("└── ", "    "),
+                (_, false) => ("├── ", "│   "),
+            };
+            let child_prefix = format!("{}{}", prefix, indent);
+            let value = (self.format)(&self.tree.values[x]);
+            let mut lines = value.lines();
+            writeln!(f, "{}{}{}", prefix, connector, lines.next().unwrap_or(""))?;
+            // Keep the connectors going for values spanning multiple lines
+            let continuation = if children.is_empty() { "" } else { "│" };
+            for line in lines {
+                writeln!(f, "{}{}{}", child_prefix, continuation, line)?;
+            }
+            let count = children.len();
+            for (i, &child) in children.iter().enumerate().rev() {
+                stack.push((child, child_prefix.clone(), i == count - 1));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for ITree<T> {
+    /// Render the tree with box drawing connectors, see `ITree::pretty`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.pretty(|value| value.to_string()).fmt(f)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ITree<T> {
+    /// Show the arrays of the tree, or with `{:#?}` the nodes nested under
+    /// their parents like in the `tree!` macro
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !f.alternate() {
+            return f
+                .debug_struct("ITree")
+                .field("values", &self.values)
+                .field("parents", &self.parents)
+                .field("children", &self.children)
+                .finish();
+        }
+        if self.is_empty() {
+            return f.write_str("ITree {}");
+        }
+        f.write_str("ITree {\n")?;
+        let mut depth = 1;
+        for (id, event) in self.euler_tour() {
+            let leaf = self.children[id.index()].is_empty();
+            match event {
+                Event::Enter => {
+                    let mut out = Indented { f, depth, start: true };
+                    write!(out, "{:#?}", self.values[id.index()])?;
+                    f.write_str(if leaf { ",\n" } else { " => [\n" })?;
+                    if !leaf {
+                        depth += 1;
+                    }
+                }
+                Event::Exit if !leaf => {
+                    depth -= 1;
+                    Indented { f, depth, start: true }.write_str("],\n")?;
+                }
+                Event::Exit => {}
+            }
+        }
+        f.write_str("}")
+    }
+}
+
+/// Writes to a formatter with every line indented by four spaces a level
+struct Indented<'a, 'b: 'a> {
+    f: &'a mut fmt::Formatter<'b>,
+    depth: usize,
+    /// Whether the next character starts a line
+    start: bool,
+}
+
+impl<'a, 'b> fmt::Write for Indented<'a, 'b> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for line in s.split_inclusive('\n') {
+            if self.start {
+                for _ in 0..self.depth {
+                    self.f.write_str("    ")?;
+                }
+            }
+            self.f.write_str(line)?;
+            self.start = line.ends_with('\n');
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn pretty() {
+    let mut tree = ITree::new();
+    let root = tree.add_root("root").unwrap();
+    let a = tree.add_node(root, "a");
+    let b = tree.add_node(root, "b");
+    tree.add_node(a, "c");
+    tree.add_node(a, "d");
+    tree.add_node(b, "e");
+
+    let expected = "root\n\
+                    ├── a\n\
+                    │   ├── c\n\
+                    │   └── d\n\
+                    └── b\n\
+                    \x20   └── e\n";
+    assert_eq!(tree.to_string(), expected);
+
+    let expected = "ROOT\n\
+                    ├── A\n\
+                    │   ├── C\n\
+                    │   └── D\n\
+                    └── B\n\
+                    \x20   └── E\n";
+    assert_eq!(tree.pretty(|v| v.to_uppercase()).to_string(), expected);
+    assert_eq!(ITree::<u8>::new().to_string(), "");
+}
+
+#[test]
+fn display_with() {
+    // Values without a `Display` implementation
+    #[derive(Debug)]
+    struct Point(i32, i32);
+
+    let tree = tree!{ Point(0, 0) => [Point(1, 2) => [Point(3, 4)], Point(-1, 0)] };
+    let expected = "(0, 0)\n\
+                    ├── (1, 2)\n\
+                    │   └── (3, 4)\n\
+                    └── (-1, 0)\n";
+    assert_eq!(tree.display_with(|p, f| write!(f, "({}, {})", p.0, p.1)).to_string(), expected);
+    assert_eq!(tree!{ 1.5 }.display_with(|x, f| write!(f, "{:.3}", x)).to_string(), "1.500\n");
+    assert_eq!(ITree::<Point>::new().display_with(|p, f| write!(f, "{:?}", p)).to_string(), "");
+}
+
+#[test]
+fn debug() {
+    let tree = tree!{ "a" => ["b" => ["c"], "d"] };
+    let expected = "ITree {\n\
+                    \x20   \"a\" => [\n\
+                    \x20       \"b\" => [\n\
+                    \x20           \"c\",\n\
+                    \x20       ],\n\
+                    \x20       \"d\",\n\
+                    \x20   ],\n\
+                    }";
+    assert_eq!(format!("{:#?}", tree), expected);
+    assert!(format!("{:?}", tree).starts_with("ITree { values: "));
+    assert_eq!(format!("{:#?}", ITree::<u8>::new()), "ITree {}");
+
+    // Values spanning lines stay indented
+    let tree = tree!{ (1, 2) => [(3, 4)] };
+    let expected = "ITree {\n\
+                    \x20   (\n\
+                    \x20       1,\n\
+                    \x20       2,\n\
+                    \x20   ) => [\n\
+                    \x20       (\n\
+                    \x20           3,\n\
+                    \x20           4,\n\
+                    \x20       ),\n\
+                    \x20   ],\n\
+                    }";
+    assert_eq!(format!("{:#?}", tree), expected);
+}
@@ -0,0 +1,174 @@
+use alloc::vec::Vec;
+
+use ITree;
+
+/// Costs of the edits `ITree::edit_distance` turns one tree into the other
+/// with
+pub trait EditCosts<T> {
+    /// Cost of adding a node with a value
+    fn insert(&self, value: &T) -> usize;
+
+    /// Cost of removing a node with a value, whose children take its place
+    fn delete(&self, value: &T) -> usize;
+
+    /// Cost of changing the value of a node, which should be zero for equal
+    /// values
+    fn rename(&self, from: &T, to: &T) -> usize;
+}
+
+/// Every insertion, deletion and rename between different values costs one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnitCosts;
+
+impl<T: PartialEq> EditCosts<T> for UnitCosts {
+    fn insert(&self, _: &T) -> usize {
+        1
+    }
+
+    fn delete(&self, _: &T) -> usize {
+        1
+    }
+
+    fn rename(&self, from: &T, to: &T) -> usize {
+        (from != to) as usize
+    }
+}
+
+/// Nodes of a tree in post-order along with the leftmost leaf below every
+/// node, both by position in post-order
+struct PostOrder<'a, T: 'a> {
+    values: Vec<&'a T>,
+    leftmost: Vec<usize>,
+    /// Nodes that are the highest ones with their leftmost leaf, in order
+    keyroots: Vec<usize>,
+}
+
+impl<'a, T> PostOrder<'a, T> {
+    fn new(tree: &'a ITree<T>) -> Self {
+        let mut values = Vec::with_capacity(tree.len());
+        let mut leftmost = Vec::with_capacity(tree.len());
+        let mut position = vec![0; tree.len()];
+        if let Some(root) = tree.root_id() {
+            for (id, node) in tree.iter_post_order(root) {
+                position[id.index()] = values.len();
+                let left = match node.children().first() {
+                    Some(child) => leftmost[position[child.index()]],
+                    None => values.len(),
+                };
+                values.push(node.value());
+                leftmost.push(left);
+            }
+        }
+        let mut seen = vec![false; values.len()];
+        let mut keyroots = Vec::new();
+        for x in (0..values.len()).rev() {
+            if !seen[leftmost[x]] {
+                seen[leftmost[x]] = true;
+                keyroots.push(x);
+            }
+        }
+        keyroots.reverse();
+        PostOrder { values, leftmost, keyroots }
+    }
+}
+
+impl<T> ITree<T> {
+    /// Find the cheapest way to turn this tree into `other` by inserting,
+    /// deleting and renaming nodes, with the Zhang-Shasha algorithm. Order of
+    /// children matters.
+    ///
+    /// Takes `O(n * m)` memory and at most `O(n² * m²)` time for trees of `n`
+    /// and `m` nodes, but far less for trees that are shallow or have few
+    /// leaves.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate grove;
+    /// # fn main() {
+    /// use grove::UnitCosts;
+    ///
+    /// let a = tree!{ "f" => ["a", "b"] };
+    /// let b = tree!{ "f" => ["a", "c"] };
+    /// assert_eq!(a.edit_distance(&b, UnitCosts), 1);
+    /// # }
+    /// ```
+    pub fn edit_distance<C: EditCosts<T>>(&self, other: &Self, costs: C) -> usize {
+        let (a, b) = (PostOrder::new(self), PostOrder::new(other));
+        let (n, m) = (a.values.len(), b.values.len());
+        // Distances between every pair of subtrees
+        let mut trees = vec![vec![0; m]; n];
+        // Distances between the forests of a pair of keyroots, off by one so
+        // the empty forest is at zero
+        let mut forests = vec![vec![0; m + 1]; n + 1];
+        for &i in &a.keyroots {
+            for &j in &b.keyroots {
+                let (li, lj) = (a.leftmost[i], b.leftmost[j]);
+                forests[li][lj] = 0;
+                for x in li..=i {
+                    forests[x + 1][lj] = forests[x][lj] + costs.delete(a.values[x]);
+                }
+                for y in lj..=j {
+                    forests[li][y + 1] = forests[li][y] + costs.insert(b.values[y]);
+                }
+                for x in li..=i {
+                    for y in lj..=j {
+                        let delete = forests[x][y + 1] + costs.delete(a.values[x]);
+                        let insert = forests[x + 1][y] + costs.insert(b.values[y]);
+                        let (lx, ly) = (a.leftmost[x], b.leftmost[y]);
+                        forests[x + 1][y + 1] = if lx == li && ly == lj {
+                            // Both forests are whole trees
+                            let rename = forests[x][y] + costs.rename(a.values[x], b.values[y]);
+                            trees[x][y] = delete.min(insert).min(rename);
+                            trees[x][y]
+                        } else {
+                            delete.min(insert).min(forests[lx][ly] + trees[x][y])
+                        };
+                    }
+                }
+            }
+        }
+        match (n, m) {
+            (0, _) => b.values.iter().map(|v| costs.insert(v)).sum(),
+            (_, 0) => a.values.iter().map(|v| costs.delete(v)).sum(),
+            _ => trees[n - 1][m - 1],
+        }
+    }
+}
+
+#[test]
+fn edit_distance() {
+    // The example from the paper by Zhang and Shasha
+    let a = tree!{ "f" => ["d" => ["a", "c" => ["b"]], "e"] };
+    let b = tree!{ "f" => ["c" => ["d" => ["a", "b"]], "e"] };
+    assert_eq!(a.edit_distance(&b, UnitCosts), 2);
+    assert_eq!(b.edit_distance(&a, UnitCosts), 2);
+    assert_eq!(a.edit_distance(&a, UnitCosts), 0);
+
+    // Deleting an inner node moves its children up
+    assert_eq!(tree!{ 1 => [2 => [3, 4]] }.edit_distance(&tree!{ 1 => [3, 4] }, UnitCosts), 1);
+    assert_eq!(tree!{ 1 => [2, 3] }.edit_distance(&tree!{ 1 => [3, 2] }, UnitCosts), 2);
+    let empty = ITree::new();
+    assert_eq!(a.edit_distance(&empty, UnitCosts), 6);
+    assert_eq!(empty.edit_distance(&b, UnitCosts), 6);
+    assert_eq!(empty.edit_distance(&empty, UnitCosts), 0);
+
+    struct Weighted;
+
+    impl EditCosts<&str> for Weighted {
+        fn insert(&self, _: &&str) -> usize {
+            3
+        }
+
+        fn delete(&self, _: &&str) -> usize {
+            3
+        }
+
+        fn rename(&self, from: &&str, to: &&str) -> usize {
+            if from == to { 0 } else { 5 }
+        }
+    }
+
+    // Renaming is cheaper than deleting one node and inserting another, but
+    // moving "c" up is still cheapest with an insert and a delete
+    assert_eq!(a.edit_distance(&b, Weighted), 6);
+    assert_eq!(tree!{ "x" => ["a"] }.edit_distance(&tree!{ "x" => ["b"] }, Weighted), 5);
+}
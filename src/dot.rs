@@ -0,0 +1,58 @@
+use core::fmt::Write;
+
+use alloc::string::String;
+
+use ITree;
+
+impl<T> ITree<T> {
+    /// Render the tree as a Graphviz digraph in the DOT language. Every node is
+    /// labeled with the string returned by `label` for its value.
+    pub fn to_dot<F>(&self, mut label: F) -> String
+        where F: FnMut(&T) -> String
+    {
+        let mut out = String::from("digraph {\n");
+        for (x, value) in self.values.iter().enumerate() {
+            writeln!(out, "    {} [label=\"{}\"];", x, escape(&label(value))).unwrap();
+        }
+        for (x, children) in self.children.iter().enumerate() {
+            for child in children {
+                writeln!(out, "    {} -> {};", x, child.index()).unwrap();
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Escape a label so it can be put between double quotes
+fn escape(label: &str) -> String {
+    let mut out = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[test]
+fn dot() {
+    let mut tree = ITree::new();
+    // "a" -> "b\"" -> "c"
+    let root = tree.add_root("a").unwrap();
+    let one = tree.add_node(root, "b\"");
+    tree.add_node(one, "c");
+
+    let expected = "digraph {\n\
+                    \x20   0 [label=\"a\"];\n\
+                    \x20   1 [label=\"b\\\"\"];\n\
+                    \x20   2 [label=\"c\"];\n\
+                    \x20   0 -> 1;\n\
+                    \x20   1 -> 2;\n\
+                    }\n";
+    assert_eq!(tree.to_dot(|v| v.to_string()), expected);
+    assert_eq!(ITree::<u8>::new().to_dot(|v| v.to_string()), "digraph {\n}\n");
+}
@@ -0,0 +1,190 @@
+use core::mem;
+use core::ops::Index;
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+
+use {GroveError, ITree, NodeId};
+
+/// Element of a document like HTML or XML
+///
+/// An element has a tag, attributes with string values and optionally the
+/// text right inside it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Element {
+    tag: String,
+    attributes: BTreeMap<String, String>,
+    text: Option<String>,
+}
+
+impl Element {
+    /// Create an element with a tag and no attributes or text
+    pub fn new<S: Into<String>>(tag: S) -> Self {
+        Element { tag: tag.into(), attributes: BTreeMap::new(), text: None }
+    }
+
+    /// Set an attribute while building an element
+    pub fn with_attribute<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.set_attribute(name, value);
+        self
+    }
+
+    /// Set the text while building an element
+    pub fn with_text<S: Into<String>>(mut self, text: S) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Get the value of an attribute
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    /// Get every attribute, ordered by name
+    pub fn attributes(&self) -> &BTreeMap<String, String> {
+        &self.attributes
+    }
+
+    /// Set an attribute, returning the value it had before
+    pub fn set_attribute<K: Into<String>, V: Into<String>>(&mut self, name: K, value: V) -> Option<String> {
+        self.attributes.insert(name.into(), value.into())
+    }
+
+    /// Remove an attribute and return its value
+    pub fn remove_attribute(&mut self, name: &str) -> Option<String> {
+        self.attributes.remove(name)
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    /// Replace the text, returning the text from before
+    pub fn set_text(&mut self, text: Option<String>) -> Option<String> {
+        mem::replace(&mut self.text, text)
+    }
+}
+
+/// Element Tree
+///
+/// An `ITree` of `Element`s, always with a root, that can look up elements
+/// by their tag and attributes. Everything that works on an `ITree` works on
+/// the one from `tree`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElementTree {
+    tree: ITree<Element>,
+}
+
+impl ElementTree {
+    /// Create a new `ElementTree` with only the root element
+    pub fn new(root: Element) -> Self {
+        let mut tree = ITree::new();
+        tree.add_root(root).unwrap();
+        ElementTree { tree }
+    }
+
+    pub fn root_id(&self) -> NodeId {
+        NodeId::new(0)
+    }
+
+    /// Get the tree of the elements
+    pub fn tree(&self) -> &ITree<Element> {
+        &self.tree
+    }
+
+    pub fn get(&self, node: NodeId) -> Option<&Element> {
+        self.tree.get(node).map(|n| n.value())
+    }
+
+    pub fn get_mut(&mut self, node: NodeId) -> Option<&mut Element> {
+        self.tree.get(node)?;
+        self.tree.values.get_mut(node.index())
+    }
+
+    /// Add an element as the last child of a node, returning its `NodeId`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `NodeId` does not refer to an element in the tree. See
+    /// `try_append` for a version that returns an error instead.
+    pub fn append(&mut self, node: NodeId, element: Element) -> NodeId {
+        self.tree.add_node(node, element)
+    }
+
+    /// Add an element as the last child of a node like `append`, but return
+    /// an error rather than panicking if the `NodeId` does not refer to an
+    /// element in the tree
+    pub fn try_append(&mut self, node: NodeId, element: Element) -> Result<NodeId, GroveError> {
+        self.tree.try_add_node(node, element)
+    }
+
+    /// Iterate over the `NodeId`s of the elements with a tag, in document
+    /// order
+    pub fn by_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = NodeId> + 'a {
+        self.tree.find_all(move |e| e.tag == tag)
+    }
+
+    /// Iterate over the `NodeId`s of the elements that have an attribute, in
+    /// document order. With a `value`, the attribute also has to have it.
+    pub fn by_attribute<'a>(&'a self, name: &'a str, value: Option<&'a str>) -> impl Iterator<Item = NodeId> + 'a {
+        self.tree.find_all(move |e| e.attribute(name).is_some_and(|v| value.is_none_or(|value| v == value)))
+    }
+
+    /// Find the first element whose `id` attribute is `id`
+    pub fn by_id(&self, id: &str) -> Option<NodeId> {
+        self.by_attribute("id", Some(id)).next()
+    }
+
+    /// Get the text of a node and all of its descendants, joined in document
+    /// order
+    pub fn text_content(&self, node: NodeId) -> String {
+        self.tree.iter_dfs(node).filter_map(|(_, n)| n.value().text()).collect()
+    }
+}
+
+impl Index<NodeId> for ElementTree {
+    type Output = Element;
+
+    fn index(&self, node: NodeId) -> &Element {
+        &self.tree[node]
+    }
+}
+
+impl From<ElementTree> for ITree<Element> {
+    fn from(tree: ElementTree) -> Self {
+        tree.tree
+    }
+}
+
+#[test]
+fn element_tree() {
+    let mut doc = ElementTree::new(Element::new("html"));
+    let body = doc.append(doc.root_id(), Element::new("body").with_attribute("class", "page"));
+    let list = doc.append(body, Element::new("ul").with_attribute("id", "menu"));
+    for (i, item) in ["Home", "About"].iter().enumerate() {
+        let li = doc.append(list, Element::new("li").with_attribute("class", if i == 0 { "active" } else { "" }));
+        doc.append(li, Element::new("a").with_attribute("href", "#").with_text(*item));
+    }
+    let p = doc.append(body, Element::new("p").with_text("Hi "));
+    doc.append(p, Element::new("b").with_text("there"));
+
+    assert_eq!(doc.tree().len(), 9);
+    assert_eq!(doc.by_tag("li").count(), 2);
+    assert_eq!(doc.by_attribute("class", None).count(), 3);
+    assert_eq!(doc.by_attribute("class", Some("active")).map(|id| doc[id].tag()).collect::<Vec<_>>(), ["li"]);
+    assert_eq!(doc.by_id("menu"), Some(list));
+    assert_eq!(doc.by_id("nope"), None);
+    assert_eq!(doc.text_content(list), "HomeAbout");
+    assert_eq!(doc.text_content(p), "Hi there");
+
+    let menu = doc.get_mut(list).unwrap();
+    assert_eq!(menu.set_attribute("id", "nav"), Some("menu".to_string()));
+    assert_eq!(menu.remove_attribute("id"), Some("nav".to_string()));
+    assert_eq!(doc.by_id("nav"), None);
+    assert!(doc.get_mut(NodeId::new(9)).is_none());
+    assert_eq!(doc.try_append(NodeId::new(9), Element::new("x")), Err(GroveError::InvalidNodeId(NodeId::new(9))));
+}
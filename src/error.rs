@@ -0,0 +1,103 @@
+use core::error::Error;
+use core::fmt;
+
+use {NodeId, Timestamp};
+
+/// Errors that can occur when working with the trees in this crate
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GroveError {
+    /// The given `NodeId` does not refer to a node in the tree
+    InvalidNodeId(NodeId),
+    /// The operation needs a root node but the tree is empty
+    EmptyTree,
+    /// The operation would create a second root node
+    RootExists,
+    /// The operation would leave the tree with more than one root
+    MultipleRoots,
+    /// The node already has a parent or is the root
+    AlreadyAttached(NodeId),
+    /// The operation would make a node its own ancestor
+    Cycle,
+    /// A node with the given `NodeId` is already in the tree
+    NodeExists(NodeId),
+    /// The position is past the end of the children of a node
+    InvalidIndex(usize),
+    /// No node of a `CrdtTree` was created at the given `Timestamp`
+    InvalidTimestamp(Timestamp),
+    /// The selector has a problem at the given byte position
+    InvalidSelector(usize),
+    /// The Newick string has a problem at the given byte position
+    InvalidNewick(usize),
+    /// The s-expression has a problem at the given byte position
+    InvalidSexpr(usize),
+    /// The line with the given number, counting from one, is indented wrong
+    InvalidIndent(usize),
+    /// The encoded tree has a problem at the given position, a byte for byte
+    /// encodings and a bit for `SuccinctTree::from_words`
+    InvalidEncoding(usize),
+    /// The depth is more than one level below the node before
+    InvalidDepth(usize),
+}
+
+impl fmt::Display for GroveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GroveError::InvalidNodeId(id) => write!(f, "invalid node id: {:?}", id),
+            GroveError::EmptyTree => write!(f, "the tree has no root node"),
+            GroveError::RootExists => write!(f, "the tree already has a root node"),
+            GroveError::MultipleRoots => write!(f, "the tree would have more than one root node"),
+            GroveError::AlreadyAttached(id) => write!(f, "node {:?} is already attached", id),
+            GroveError::Cycle => write!(f, "a node can't be its own ancestor"),
+            GroveError::NodeExists(id) => write!(f, "node {:?} already exists", id),
+            GroveError::InvalidIndex(index) => write!(f, "invalid child index: {}", index),
+            GroveError::InvalidTimestamp(time) => write!(f, "invalid timestamp: {:?}", time),
+            GroveError::InvalidSelector(pos) => write!(f, "invalid selector at byte {}", pos),
+            GroveError::InvalidNewick(pos) => write!(f, "invalid Newick string at byte {}", pos),
+            GroveError::InvalidSexpr(pos) => write!(f, "invalid s-expression at byte {}", pos),
+            GroveError::InvalidIndent(line) => write!(f, "invalid indentation on line {}", line),
+            GroveError::InvalidEncoding(pos) => write!(f, "invalid encoding at position {}", pos),
+            GroveError::InvalidDepth(depth) => write!(f, "invalid depth: {}", depth),
+        }
+    }
+}
+
+impl Error for GroveError {}
+
+/// Error returned when an edit script can't be applied to a tree
+///
+/// Carries the position of the first edit that failed in the script and the
+/// reason it failed. The tree is left unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchError {
+    edit: usize,
+    error: GroveError,
+}
+
+impl PatchError {
+    pub(crate) fn new(edit: usize, error: GroveError) -> Self {
+        PatchError { edit, error }
+    }
+
+    /// Get the position of the failed edit in the script
+    pub fn edit(&self) -> usize {
+        self.edit
+    }
+
+    /// Get the reason the edit failed
+    pub fn error(&self) -> &GroveError {
+        &self.error
+    }
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "edit {} can't be applied: {}", self.edit, self.error)
+    }
+}
+
+impl Error for PatchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
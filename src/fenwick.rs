@@ -0,0 +1,127 @@
+use core::ops::{AddAssign, RangeBounds, Sub};
+
+use alloc::vec::Vec;
+
+use segment::bounds;
+
+/// Fenwick Tree
+///
+/// A list of values that can add to any value and sum up any range in
+/// logarithmic time, while taking no more memory than the values themselves.
+/// Node `x` holds the sum of the `x & -x` values ending at it, counting from
+/// one, so every prefix is the sum of at most one node per bit of its length.
+#[derive(Debug, Clone)]
+pub struct FenwickTree<T> {
+    /// Partial sums, where `sums[x - 1]` is node `x`
+    sums: Vec<T>,
+}
+
+impl<T> FenwickTree<T>
+    where T: Copy + Default + AddAssign + Sub<Output = T>
+{
+    /// Create a tree of `len` values that are all `T::default()`
+    pub fn new(len: usize) -> Self {
+        FenwickTree { sums: vec![T::default(); len] }
+    }
+
+    /// Get the number of values in the tree
+    pub fn len(&self) -> usize {
+        self.sums.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sums.is_empty()
+    }
+
+    /// Add to a value
+    ///
+    /// # Panics
+    ///
+    /// If the index is out of bounds.
+    pub fn add(&mut self, index: usize, delta: T) {
+        assert!(index < self.len(), "index {} out of bounds for length {}", index, self.len());
+        let mut x = index + 1;
+        while x <= self.sums.len() {
+            self.sums[x - 1] += delta;
+            x += x & x.wrapping_neg();
+        }
+    }
+
+    /// Sum up the first `len` values
+    ///
+    /// # Panics
+    ///
+    /// If `len` is larger than the length of the tree.
+    pub fn prefix(&self, len: usize) -> T {
+        assert!(len <= self.len(), "prefix {} out of bounds for length {}", len, self.len());
+        let mut sum = T::default();
+        let mut x = len;
+        while x > 0 {
+            sum += self.sums[x - 1];
+            x &= x - 1;
+        }
+        sum
+    }
+
+    /// Sum up a range of values
+    ///
+    /// # Panics
+    ///
+    /// If the range is out of bounds.
+    pub fn sum<R: RangeBounds<usize>>(&self, range: R) -> T {
+        let (start, end) = bounds(range, self.len());
+        self.prefix(end) - self.prefix(start)
+    }
+
+    /// Get a single value
+    pub fn get(&self, index: usize) -> T {
+        self.sum(index..=index)
+    }
+
+    /// Replace a single value
+    pub fn set(&mut self, index: usize, value: T) {
+        let old = self.get(index);
+        self.add(index, value - old);
+    }
+}
+
+impl<T> From<&[T]> for FenwickTree<T>
+    where T: Copy + Default + AddAssign + Sub<Output = T>
+{
+    /// Build a tree over a list of values in linear time
+    fn from(values: &[T]) -> Self {
+        let mut sums = values.to_vec();
+        for x in 1..=sums.len() {
+            let up = x + (x & x.wrapping_neg());
+            if up <= sums.len() {
+                let sum = sums[x - 1];
+                sums[up - 1] += sum;
+            }
+        }
+        FenwickTree { sums }
+    }
+}
+
+#[test]
+fn fenwick_tree() {
+    let mut values: Vec<i64> = (0..50).map(|x| (x * 31) % 17 - 8).collect();
+    let mut tree = FenwickTree::from(&values[..]);
+    assert_eq!(tree.len(), 50);
+    assert_eq!(tree.prefix(0), 0);
+    assert_eq!(tree.sum(..), values.iter().sum::<i64>());
+
+    tree.add(7, 100);
+    values[7] += 100;
+    tree.set(49, -3);
+    values[49] = -3;
+    for start in 0..=50 {
+        for end in start..=50 {
+            assert_eq!(tree.sum(start..end), values[start..end].iter().sum::<i64>());
+        }
+    }
+    assert_eq!(tree.get(7), values[7]);
+
+    let mut empty = FenwickTree::new(3);
+    empty.add(1, 2.5);
+    assert_eq!(empty.sum(1..), 2.5);
+}
@@ -0,0 +1,595 @@
+use core::fmt;
+use core::iter::FromIterator;
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// Values that can be combined associatively and have an identity, like
+/// sizes under addition or priorities under taking the maximum
+pub trait Monoid: Clone {
+    /// Value that doesn't change anything it gets combined with
+    fn identity() -> Self;
+
+    /// Combine a value with the one that comes after it
+    fn combine(&self, other: &Self) -> Self;
+}
+
+/// Elements of a `FingerTree`, which annotates every subtree with the
+/// combined measures of its elements
+pub trait Measured {
+    type Measure: Monoid;
+
+    fn measure(&self) -> Self::Measure;
+}
+
+/// Number of elements, for a `FingerTree` that finds elements by position
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Size(pub usize);
+
+impl Monoid for Size {
+    fn identity() -> Self {
+        Size(0)
+    }
+
+    fn combine(&self, other: &Self) -> Self {
+        Size(self.0 + other.0)
+    }
+}
+
+/// Finger Tree
+///
+/// A persistent sequence that adds and removes elements at both ends in
+/// constant amortized time and concatenates and splits in logarithmic time.
+/// Every subtree caches the combined `Measure` of its elements, and `split`
+/// and `find` use those to locate the first element at which a predicate on
+/// the measure of everything up to it turns true. The measure decides what
+/// the tree is: a `Size` of one per element makes an indexed sequence, the
+/// maximum of priorities makes a priority queue and the largest key seen so
+/// far makes an ordered map.
+///
+/// This is a 2-3 finger tree after Hinze and Paterson. Nodes sit behind an
+/// `Arc`, so clones are cheap and every change only copies the few nodes it
+/// touches.
+pub struct FingerTree<T: Measured> {
+    tree: Tree<T>,
+}
+
+/// Between one and four nodes at an end of a tree, or the children of a branch
+type Digit<T> = Vec<Arc<Node<T>>>;
+
+enum Node<T: Measured> {
+    Leaf(T),
+    /// Node with two or three nodes one level down
+    Branch(T::Measure, Digit<T>),
+}
+
+/// Tree one level down is made of branches of the level above, so every
+/// level only differs in the depth of its nodes
+enum Tree<T: Measured> {
+    Empty,
+    Single(Arc<Node<T>>),
+    /// Between one and four nodes at every end around a deeper tree in the
+    /// middle
+    Deep(T::Measure, Digit<T>, Arc<Tree<T>>, Digit<T>),
+}
+
+impl<T: Measured> Clone for Tree<T> {
+    fn clone(&self) -> Self {
+        match *self {
+            Tree::Empty => Tree::Empty,
+            Tree::Single(ref node) => Tree::Single(node.clone()),
+            Tree::Deep(ref m, ref prefix, ref middle, ref suffix) => {
+                Tree::Deep(m.clone(), prefix.clone(), middle.clone(), suffix.clone())
+            }
+        }
+    }
+}
+
+impl<T: Measured> FingerTree<T> {
+    /// Create a new empty `FingerTree`
+    pub fn new() -> Self {
+        FingerTree { tree: Tree::Empty }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self.tree, Tree::Empty)
+    }
+
+    /// Get the combined measure of every element
+    pub fn measure(&self) -> T::Measure {
+        self.tree.measure()
+    }
+
+    /// Add an element to the front
+    pub fn push_front(&mut self, value: T) {
+        self.tree = self.tree.push_front(Arc::new(Node::Leaf(value)));
+    }
+
+    /// Add an element to the back
+    pub fn push_back(&mut self, value: T) {
+        self.tree = self.tree.push_back(Arc::new(Node::Leaf(value)));
+    }
+
+    /// Get the first element
+    pub fn front(&self) -> Option<&T> {
+        self.iter().next()
+    }
+
+    /// Get the last element
+    pub fn back(&self) -> Option<&T> {
+        match self.tree {
+            Tree::Empty => None,
+            Tree::Single(ref node) => Some(node.last()),
+            Tree::Deep(_, _, _, ref suffix) => Some(suffix.last().unwrap().last()),
+        }
+    }
+
+    /// Remove the first element. Elements still shared with clones of the tree
+    /// get cloned on the way out.
+    pub fn pop_front(&mut self) -> Option<T>
+        where T: Clone
+    {
+        let (node, rest) = self.tree.view_front()?;
+        self.tree = rest;
+        Some(node.into_leaf())
+    }
+
+    /// Remove the last element
+    pub fn pop_back(&mut self) -> Option<T>
+        where T: Clone
+    {
+        let (node, rest) = self.tree.view_back()?;
+        self.tree = rest;
+        Some(node.into_leaf())
+    }
+
+    /// Move every element of `other` to the back of this tree
+    pub fn append(&mut self, other: Self) {
+        self.tree = Tree::concat(&self.tree, Vec::new(), &other.tree);
+    }
+
+    /// Split the tree before the first element where `predicate` turns true
+    /// on the measure of every element up to and including it. The predicate
+    /// has to stay true once it is, and the returned tree starts with that
+    /// element. If it never turns true everything stays in this tree.
+    pub fn split<F: Fn(&T::Measure) -> bool>(&mut self, predicate: F) -> Self {
+        if self.is_empty() || !predicate(&self.measure()) {
+            return FingerTree::new();
+        }
+        let (left, node, right) = self.tree.split(&predicate, T::Measure::identity());
+        self.tree = left;
+        FingerTree { tree: right.push_front(node) }
+    }
+
+    /// Find the first element where `predicate` turns true on the measure of
+    /// every element up to and including it
+    pub fn find<F: Fn(&T::Measure) -> bool>(&self, predicate: F) -> Option<&T> {
+        if self.is_empty() || !predicate(&self.measure()) {
+            return None;
+        }
+        let mut acc = T::Measure::identity();
+        let mut tree = &self.tree;
+        // Find the node at the right level, then go down to its leaf
+        let mut node = loop {
+            match *tree {
+                Tree::Empty => unreachable!("the predicate turns true inside the tree"),
+                Tree::Single(ref node) => break node,
+                Tree::Deep(_, ref prefix, ref middle, ref suffix) => {
+                    let with_prefix = acc.combine(&measure_digit(prefix));
+                    if predicate(&with_prefix) {
+                        break find_digit(&predicate, &mut acc, prefix);
+                    }
+                    let with_middle = with_prefix.combine(&middle.measure());
+                    if predicate(&with_middle) {
+                        acc = with_prefix;
+                        tree = middle;
+                        continue;
+                    }
+                    acc = with_middle;
+                    break find_digit(&predicate, &mut acc, suffix);
+                }
+            }
+        };
+        loop {
+            match **node {
+                Node::Leaf(ref value) => return Some(value),
+                Node::Branch(_, ref children) => node = find_digit(&predicate, &mut acc, children),
+            }
+        }
+    }
+
+    /// Iterate over the elements from front to back
+    pub fn iter(&self) -> FingerIter<'_, T> {
+        FingerIter { stack: vec![Work::Tree(&self.tree)] }
+    }
+}
+
+impl<T: Measured> Node<T> {
+    fn measure(&self) -> T::Measure {
+        match *self {
+            Node::Leaf(ref value) => value.measure(),
+            Node::Branch(ref m, _) => m.clone(),
+        }
+    }
+
+    fn branch(children: Digit<T>) -> Arc<Node<T>> {
+        Arc::new(Node::Branch(measure_digit(&children), children))
+    }
+
+    fn children(&self) -> &[Arc<Node<T>>] {
+        match *self {
+            Node::Branch(_, ref children) => children,
+            Node::Leaf(_) => unreachable!("only the top level holds leaves"),
+        }
+    }
+
+    fn last(&self) -> &T {
+        match *self {
+            Node::Leaf(ref value) => value,
+            Node::Branch(_, ref children) => children.last().unwrap().last(),
+        }
+    }
+}
+
+impl<T: Measured + Clone> Node<T> {
+    fn into_leaf(self: Arc<Self>) -> T {
+        let node = Arc::try_unwrap(self).unwrap_or_else(|node| match *node {
+            Node::Leaf(ref value) => Node::Leaf(value.clone()),
+            Node::Branch(..) => unreachable!("only the top level holds leaves"),
+        });
+        match node {
+            Node::Leaf(value) => value,
+            Node::Branch(..) => unreachable!("only the top level holds leaves"),
+        }
+    }
+}
+
+fn measure_digit<T: Measured>(digit: &[Arc<Node<T>>]) -> T::Measure {
+    digit.iter().fold(T::Measure::identity(), |acc, node| acc.combine(&node.measure()))
+}
+
+/// Find the node of a digit where the predicate turns true, adding the
+/// measures of the nodes before it to `acc`
+fn find_digit<'a, T: Measured, F: Fn(&T::Measure) -> bool>(
+    predicate: &F,
+    acc: &mut T::Measure,
+    digit: &'a [Arc<Node<T>>],
+) -> &'a Arc<Node<T>> {
+    for node in &digit[..digit.len() - 1] {
+        let next = acc.combine(&node.measure());
+        if predicate(&next) {
+            return node;
+        }
+        *acc = next;
+    }
+    digit.last().unwrap()
+}
+
+impl<T: Measured> Tree<T> {
+    fn measure(&self) -> T::Measure {
+        match *self {
+            Tree::Empty => T::Measure::identity(),
+            Tree::Single(ref node) => node.measure(),
+            Tree::Deep(ref m, ..) => m.clone(),
+        }
+    }
+
+    fn deep(prefix: Digit<T>, middle: Arc<Tree<T>>, suffix: Digit<T>) -> Self {
+        let m = measure_digit(&prefix).combine(&middle.measure()).combine(&measure_digit(&suffix));
+        Tree::Deep(m, prefix, middle, suffix)
+    }
+
+    fn from_digit(digit: &[Arc<Node<T>>]) -> Self {
+        digit.iter().fold(Tree::Empty, |tree, node| tree.push_back(node.clone()))
+    }
+
+    fn push_front(&self, node: Arc<Node<T>>) -> Self {
+        match *self {
+            Tree::Empty => Tree::Single(node),
+            Tree::Single(ref other) => Tree::deep(vec![node], Arc::new(Tree::Empty), vec![other.clone()]),
+            Tree::Deep(_, ref prefix, ref middle, ref suffix) => {
+                if prefix.len() == 4 {
+                    // Push three of the nodes down a level as a branch
+                    let middle = middle.push_front(Node::branch(prefix[1..].to_vec()));
+                    Tree::deep(vec![node, prefix[0].clone()], Arc::new(middle), suffix.clone())
+                } else {
+                    let mut prefix = prefix.clone();
+                    prefix.insert(0, node);
+                    Tree::deep(prefix, middle.clone(), suffix.clone())
+                }
+            }
+        }
+    }
+
+    fn push_back(&self, node: Arc<Node<T>>) -> Self {
+        match *self {
+            Tree::Empty => Tree::Single(node),
+            Tree::Single(ref other) => Tree::deep(vec![other.clone()], Arc::new(Tree::Empty), vec![node]),
+            Tree::Deep(_, ref prefix, ref middle, ref suffix) => {
+                if suffix.len() == 4 {
+                    let middle = middle.push_back(Node::branch(suffix[..3].to_vec()));
+                    Tree::deep(prefix.clone(), Arc::new(middle), vec![suffix[3].clone(), node])
+                } else {
+                    let mut suffix = suffix.clone();
+                    suffix.push(node);
+                    Tree::deep(prefix.clone(), middle.clone(), suffix)
+                }
+            }
+        }
+    }
+
+    fn view_front(&self) -> Option<(Arc<Node<T>>, Self)> {
+        match *self {
+            Tree::Empty => None,
+            Tree::Single(ref node) => Some((node.clone(), Tree::Empty)),
+            Tree::Deep(_, ref prefix, ref middle, ref suffix) => {
+                Some((prefix[0].clone(), Tree::deep_front(prefix[1..].to_vec(), middle, suffix.clone())))
+            }
+        }
+    }
+
+    fn view_back(&self) -> Option<(Arc<Node<T>>, Self)> {
+        match *self {
+            Tree::Empty => None,
+            Tree::Single(ref node) => Some((node.clone(), Tree::Empty)),
+            Tree::Deep(_, ref prefix, ref middle, ref suffix) => {
+                let last = suffix.len() - 1;
+                Some((suffix[last].clone(), Tree::deep_back(prefix.clone(), middle, suffix[..last].to_vec())))
+            }
+        }
+    }
+
+    /// Make a deep tree out of a prefix that might be empty, borrowing a
+    /// branch from the middle if it is
+    fn deep_front(prefix: Digit<T>, middle: &Arc<Tree<T>>, suffix: Digit<T>) -> Self {
+        if !prefix.is_empty() {
+            return Tree::deep(prefix, middle.clone(), suffix);
+        }
+        match middle.view_front() {
+            None => Tree::from_digit(&suffix),
+            Some((branch, rest)) => Tree::deep(branch.children().to_vec(), Arc::new(rest), suffix),
+        }
+    }
+
+    fn deep_back(prefix: Digit<T>, middle: &Arc<Tree<T>>, suffix: Digit<T>) -> Self {
+        if !suffix.is_empty() {
+            return Tree::deep(prefix, middle.clone(), suffix);
+        }
+        match middle.view_back() {
+            None => Tree::from_digit(&prefix),
+            Some((branch, rest)) => Tree::deep(prefix, Arc::new(rest), branch.children().to_vec()),
+        }
+    }
+
+    /// Join two trees with some nodes of the same level between them
+    fn concat(left: &Self, between: Digit<T>, right: &Self) -> Self {
+        match (left, right) {
+            (Tree::Empty, _) => between.into_iter().rev().fold(right.clone(), |tree, node| tree.push_front(node)),
+            (_, Tree::Empty) => between.into_iter().fold(left.clone(), |tree, node| tree.push_back(node)),
+            (Tree::Single(node), _) => Tree::concat(&Tree::Empty, between, right).push_front(node.clone()),
+            (_, Tree::Single(node)) => Tree::concat(left, between, &Tree::Empty).push_back(node.clone()),
+            (Tree::Deep(_, prefix, left_middle, left_suffix), Tree::Deep(_, right_prefix, right_middle, suffix)) => {
+                let mut nodes = left_suffix.clone();
+                nodes.extend(between);
+                nodes.extend(right_prefix.iter().cloned());
+                let middle = Tree::concat(left_middle, branches(nodes), right_middle);
+                Tree::deep(prefix.clone(), Arc::new(middle), suffix.clone())
+            }
+        }
+    }
+
+    /// Split a tree around the node where the predicate turns true, given
+    /// the measure of everything before the tree
+    fn split<F: Fn(&T::Measure) -> bool>(&self, predicate: &F, acc: T::Measure) -> (Self, Arc<Node<T>>, Self) {
+        match *self {
+            Tree::Empty => unreachable!("the predicate turns true inside the tree"),
+            Tree::Single(ref node) => (Tree::Empty, node.clone(), Tree::Empty),
+            Tree::Deep(_, ref prefix, ref middle, ref suffix) => {
+                let with_prefix = acc.combine(&measure_digit(prefix));
+                if predicate(&with_prefix) {
+                    let (before, node, after) = split_digit(predicate, acc, prefix);
+                    return (Tree::from_digit(&before), node, Tree::deep_front(after, middle, suffix.clone()));
+                }
+                let with_middle = with_prefix.combine(&middle.measure());
+                if predicate(&with_middle) {
+                    let (left, branch, right) = middle.split(predicate, with_prefix.clone());
+                    let acc = with_prefix.combine(&left.measure());
+                    let (before, node, after) = split_digit(predicate, acc, branch.children());
+                    let (left, right) = (Arc::new(left), Arc::new(right));
+                    return (Tree::deep_back(prefix.clone(), &left, before), node, Tree::deep_front(after, &right, suffix.clone()));
+                }
+                let (before, node, after) = split_digit(predicate, with_middle, suffix);
+                (Tree::deep_back(prefix.clone(), middle, before), node, Tree::from_digit(&after))
+            }
+        }
+    }
+}
+
+/// Group between two and twelve nodes into branches of two or three
+fn branches<T: Measured>(mut nodes: Digit<T>) -> Digit<T> {
+    let mut out = Vec::new();
+    while !nodes.is_empty() {
+        let take = match nodes.len() {
+            2 | 4 => 2,
+            _ => 3,
+        };
+        let rest = nodes.split_off(take);
+        out.push(Node::branch(nodes));
+        nodes = rest;
+    }
+    out
+}
+
+/// Split a digit around the node where the predicate turns true
+fn split_digit<T: Measured, F: Fn(&T::Measure) -> bool>(
+    predicate: &F,
+    mut acc: T::Measure,
+    digit: &[Arc<Node<T>>],
+) -> (Digit<T>, Arc<Node<T>>, Digit<T>) {
+    let mut x = 0;
+    while x + 1 < digit.len() {
+        acc = acc.combine(&digit[x].measure());
+        if predicate(&acc) {
+            break;
+        }
+        x += 1;
+    }
+    (digit[..x].to_vec(), digit[x].clone(), digit[x + 1..].to_vec())
+}
+
+impl<T: Measured> Clone for FingerTree<T> {
+    fn clone(&self) -> Self {
+        FingerTree { tree: self.tree.clone() }
+    }
+}
+
+impl<T: Measured> Default for FingerTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Measured + fmt::Debug> fmt::Debug for FingerTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Measured> Extend<T> for FingerTree<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T: Measured> FromIterator<T> for FingerTree<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = FingerTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<'a, T: Measured> IntoIterator for &'a FingerTree<T> {
+    type Item = &'a T;
+    type IntoIter = FingerIter<'a, T>;
+
+    fn into_iter(self) -> FingerIter<'a, T> {
+        self.iter()
+    }
+}
+
+/// Iterator over the elements of a `FingerTree` from front to back
+pub struct FingerIter<'a, T: Measured + 'a> {
+    /// Trees and nodes left to visit, the next one on top
+    stack: Vec<Work<'a, T>>,
+}
+
+enum Work<'a, T: Measured + 'a> {
+    Tree(&'a Tree<T>),
+    Node(&'a Node<T>),
+}
+
+impl<'a, T: Measured> Iterator for FingerIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            match self.stack.pop()? {
+                Work::Node(Node::Leaf(value)) => return Some(value),
+                Work::Node(Node::Branch(_, children)) => {
+                    self.stack.extend(children.iter().rev().map(|c| Work::Node(c)))
+                }
+                Work::Tree(Tree::Empty) => {}
+                Work::Tree(Tree::Single(node)) => self.stack.push(Work::Node(node)),
+                Work::Tree(Tree::Deep(_, prefix, middle, suffix)) => {
+                    self.stack.extend(suffix.iter().rev().map(|c| Work::Node(c)));
+                    self.stack.push(Work::Tree(middle));
+                    self.stack.extend(prefix.iter().rev().map(|c| Work::Node(c)));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn finger_tree() {
+    /// Element of an indexed sequence
+    #[derive(Debug, Clone, PartialEq)]
+    struct Item(u32);
+
+    impl Measured for Item {
+        type Measure = Size;
+
+        fn measure(&self) -> Size {
+            Size(1)
+        }
+    }
+
+    /// Element of a priority queue, measured by the largest priority
+    #[derive(Debug, Clone, PartialEq)]
+    struct Task(u32);
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Max(Option<u32>);
+
+    impl Monoid for Max {
+        fn identity() -> Self {
+            Max(None)
+        }
+
+        fn combine(&self, other: &Self) -> Self {
+            Max(self.0.max(other.0))
+        }
+    }
+
+    impl Measured for Task {
+        type Measure = Max;
+
+        fn measure(&self) -> Max {
+            Max(Some(self.0))
+        }
+    }
+
+    let mut seq: FingerTree<Item> = (0..1000).map(Item).collect();
+    assert_eq!(seq.measure(), Size(1000));
+    let snapshot = seq.clone();
+    for i in (0..1000).step_by(37) {
+        assert_eq!(seq.find(|s| s.0 > i as usize), Some(&Item(i)));
+    }
+    assert_eq!(seq.find(|s| s.0 > 1000), None);
+
+    // Split at every position and put the halves back together
+    for at in [0, 1, 3, 17, 500, 999, 1000].iter().cloned() {
+        let mut left = seq.clone();
+        let right = left.split(|s| s.0 > at);
+        assert_eq!(left.measure(), Size(at));
+        assert!(left.iter().map(|i| i.0).eq(0..at as u32));
+        assert!(right.iter().map(|i| i.0).eq(at as u32..1000));
+        left.append(right);
+        assert!(left.iter().map(|i| i.0).eq(0..1000));
+    }
+
+    for i in 0..10 {
+        assert_eq!(seq.pop_front(), Some(Item(i)));
+        assert_eq!(seq.pop_back(), Some(Item(999 - i)));
+    }
+    for i in 0..10 {
+        seq.push_front(Item(5000 + i));
+    }
+    assert_eq!(seq.front(), Some(&Item(5009)));
+    assert_eq!(seq.back(), Some(&Item(989)));
+    assert_eq!(seq.measure(), Size(990));
+    // The clone from before is untouched
+    assert!(snapshot.iter().map(|i| i.0).eq(0..1000));
+
+    let queue: FingerTree<Task> = [5, 1, 9, 3, 9, 7].iter().map(|&p| Task(p)).collect();
+    let top = queue.measure();
+    assert_eq!(top, Max(Some(9)));
+    let mut rest = queue.clone();
+    let mut from_top = rest.split(|m| *m == top);
+    assert_eq!(from_top.pop_front(), Some(Task(9)));
+    rest.append(from_top);
+    assert!(rest.iter().map(|t| t.0).eq(vec![5, 1, 3, 9, 7]));
+}
@@ -0,0 +1,189 @@
+use core::ops::Index;
+
+use alloc::vec::Vec;
+
+use iter::{Ancestors, Bfs, Descendants, Dfs, PostOrder};
+use {GroveError, INodeRef, ITree, NodeId};
+
+/// Forest
+///
+/// Any number of trees sharing one arena. Nodes only get added like in an
+/// `ITree`, but `add_root` can be called as often as needed and every call
+/// starts a new tree, so data with more than one top level doesn't need a
+/// made up root above it.
+///
+/// The traversals of `ITree` work the same on a forest and stay within the
+/// tree of the node they start at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forest<T> {
+    /// Nodes of every tree, where the roots are the nodes without a parent
+    tree: ITree<T>,
+    roots: Vec<NodeId>,
+}
+
+impl<T> Forest<T> {
+    /// Create a new empty `Forest`
+    pub fn new() -> Self {
+        Forest { tree: ITree::new(), roots: Vec::new() }
+    }
+
+    /// Get the number of nodes in every tree together
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Get the `NodeId`s of the roots in the order they were added
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /// Get the node of the given `Id` if it exists
+    pub fn get(&self, node: NodeId) -> Option<INodeRef<'_, T>> {
+        self.tree.get(node)
+    }
+
+    /// Start a new tree and return the `NodeId` of its root
+    pub fn add_root(&mut self, value: T) -> NodeId {
+        let root = self.tree.push(value, None);
+        self.roots.push(root);
+        root
+    }
+
+    /// Adds a child to a node in the forest, returning the `NodeId` of the
+    /// inserted node
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `NodeId` does not refer to a node in the forest. See
+    /// `try_add_node` for a version that returns an error instead.
+    pub fn add_node(&mut self, node: NodeId, value: T) -> NodeId {
+        match self.try_add_node(node, value) {
+            Ok(id) => id,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Adds a child to a node in the forest like `add_node`, but returns an
+    /// error rather than panicking if the `NodeId` does not refer to a node in
+    /// the forest
+    pub fn try_add_node(&mut self, node: NodeId, value: T) -> Result<NodeId, GroveError> {
+        let index = NodeId::new(self.tree.len());
+        match self.tree.children.get_mut(node.index()) {
+            Some(children) => children.push(index),
+            None => return Err(GroveError::InvalidNodeId(node)),
+        }
+        Ok(self.tree.push(value, Some(node)))
+    }
+
+    /// Get the root of the tree a node is in
+    pub fn root_of(&self, node: NodeId) -> Option<NodeId> {
+        self.get(node)?;
+        Some(self.ancestors(node).last().unwrap_or(node))
+    }
+
+    /// Iterate over the subtree starting at the given `NodeId` in pre-order.
+    /// See `ITree::iter_dfs`.
+    pub fn iter_dfs(&self, start: NodeId) -> Dfs<'_, T> {
+        self.tree.iter_dfs(start)
+    }
+
+    /// Iterate over the subtree starting at the given `NodeId` in level order.
+    /// See `ITree::iter_bfs`.
+    pub fn iter_bfs(&self, start: NodeId) -> Bfs<'_, T> {
+        self.tree.iter_bfs(start)
+    }
+
+    /// Iterate over the subtree starting at the given `NodeId` in post-order.
+    /// See `ITree::iter_post_order`.
+    pub fn iter_post_order(&self, start: NodeId) -> PostOrder<'_, T> {
+        self.tree.iter_post_order(start)
+    }
+
+    /// Iterate over the `NodeId`s of the ancestors of a node, ending at the
+    /// root of its tree
+    pub fn ancestors(&self, node: NodeId) -> Ancestors<'_, T> {
+        self.tree.ancestors(node)
+    }
+
+    /// Iterate over the `NodeId`s of every node in the subtree rooted at the
+    /// given node in pre-order
+    pub fn descendants(&self, node: NodeId) -> Descendants<'_, T> {
+        self.tree.descendants(node)
+    }
+
+    /// Split the forest into one `ITree` for every root, in the order of
+    /// `roots`
+    pub fn into_trees(self) -> Vec<ITree<T>> {
+        let mut values: Vec<Option<T>> = self.tree.values.into_iter().map(Some).collect();
+        let children = &self.tree.children;
+        self.roots
+            .iter()
+            .map(|&root| {
+                ITree::build_from(root, |&id| {
+                    (values[id.index()].take().unwrap(), children[id.index()].to_vec())
+                })
+            })
+            .collect()
+    }
+}
+
+impl<T> Default for Forest<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<ITree<T>> for Forest<T> {
+    fn from(tree: ITree<T>) -> Self {
+        let roots = tree.root_id().into_iter().collect();
+        Forest { tree, roots }
+    }
+}
+
+impl<T> Index<NodeId> for Forest<T> {
+    type Output = T;
+
+    /// Get the value of a node
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `NodeId` does not refer to a node in the forest.
+    fn index(&self, node: NodeId) -> &T {
+        &self.tree[node]
+    }
+}
+
+#[test]
+fn forest() {
+    let mut forest = Forest::new();
+    let etc = forest.add_root("/etc");
+    let hosts = forest.add_node(etc, "hosts");
+    let home = forest.add_root("/home");
+    let user = forest.add_node(home, "user");
+    let notes = forest.add_node(user, "notes");
+    forest.add_node(etc, "passwd");
+    assert_eq!(forest.len(), 6);
+    assert_eq!(forest.roots(), [etc, home]);
+    assert_eq!(forest[notes], "notes");
+    assert_eq!(forest.get(hosts).unwrap().parent(), Some(etc));
+    assert_eq!(forest.get(home).unwrap().parent(), None);
+
+    // Traversals stay within a tree
+    let names: Vec<_> = forest.iter_dfs(etc).map(|(_, n)| *n.value()).collect();
+    assert_eq!(names, ["/etc", "hosts", "passwd"]);
+    let names: Vec<_> = forest.iter_post_order(home).map(|(_, n)| *n.value()).collect();
+    assert_eq!(names, ["notes", "user", "/home"]);
+    assert_eq!(forest.ancestors(notes).collect::<Vec<_>>(), [user, home]);
+    assert_eq!(forest.root_of(notes), Some(home));
+    assert_eq!(forest.root_of(etc), Some(etc));
+    assert_eq!(forest.try_add_node(NodeId::new(6), "x"), Err(GroveError::InvalidNodeId(NodeId::new(6))));
+
+    let trees = forest.into_trees();
+    assert_eq!(trees, [tree!{ "/etc" => ["hosts", "passwd"] }, tree!{ "/home" => ["user" => ["notes"]] }]);
+    let forest = Forest::from(trees[1].clone());
+    assert_eq!(forest.roots(), [NodeId::new(0)]);
+}
@@ -0,0 +1,87 @@
+use alloc::vec::Vec;
+
+use {GroveError, ITree, NodeId};
+
+impl<T> ITree<T> {
+    /// Iterate over the edges of the tree as pairs of a parent and a child,
+    /// parents before their children
+    ///
+    /// The positions of the pairs fit graph libraries that build a graph out
+    /// of edges between node indices, like `Graph::from_edges` of petgraph:
+    ///
+    /// ```text
+    /// let graph: Graph<(), ()> = Graph::from_edges(tree.edges().map(|(p, c)| (p.index() as u32, c.index() as u32)));
+    /// ```
+    pub fn edges(&self) -> impl Iterator<Item = (NodeId, NodeId)> + '_ {
+        self.parents.iter().enumerate().filter_map(|(x, p)| p.map(|p| (p, NodeId::new(x))))
+    }
+
+    /// Build a tree out of the values of a graph and its edges as pairs of
+    /// positions of a parent and a child, checking that the graph is a tree
+    ///
+    /// Children come in the order of their edges. Returns the tree and the
+    /// `NodeId` of every value, or an error if a position is out of bounds,
+    /// a node has two parents, there is more than one root or the edges go
+    /// around in a cycle.
+    pub fn from_edges<I>(values: Vec<T>, edges: I) -> Result<(Self, Vec<NodeId>), GroveError>
+        where I: IntoIterator<Item = (usize, usize)>
+    {
+        let len = values.len();
+        let mut parents = vec![None; len];
+        let mut children = vec![Vec::new(); len];
+        for (parent, child) in edges {
+            if let Some(&x) = [parent, child].iter().find(|&&x| x >= len) {
+                return Err(GroveError::InvalidNodeId(NodeId::new(x)));
+            }
+            if parents[child].replace(parent).is_some() {
+                return Err(GroveError::AlreadyAttached(NodeId::new(child)));
+            }
+            children[parent].push(child);
+        }
+        let mut roots = (0..len).filter(|&x| parents[x].is_none());
+        let root = match (roots.next(), roots.next()) {
+            (Some(root), None) => root,
+            (Some(_), Some(_)) => return Err(GroveError::MultipleRoots),
+            // Every node has a parent
+            (None, _) if len > 0 => return Err(GroveError::Cycle),
+            (None, _) => return Ok((ITree::new(), Vec::new())),
+        };
+
+        let mut values: Vec<_> = values.into_iter().map(Some).collect();
+        let mut ids = vec![NodeId::new(0); len];
+        let mut tree = ITree::with_capacity(len);
+        let mut stack = vec![root];
+        while let Some(x) = stack.pop() {
+            let value = values[x].take().unwrap();
+            ids[x] = match parents[x] {
+                Some(p) => tree.add_node(ids[p], value),
+                None => tree.add_root(value).unwrap(),
+            };
+            stack.extend(children[x].iter().rev());
+        }
+        // Nodes that can't be reached from the root hang on a cycle
+        if tree.len() < len {
+            return Err(GroveError::Cycle);
+        }
+        Ok((tree, ids))
+    }
+}
+
+#[test]
+fn edges() {
+    let tree = tree!{ 'a' => ['b' => ['c'], 'd'] };
+    let edges: Vec<_> = tree.edges().map(|(p, c)| (tree[p], tree[c])).collect();
+    assert_eq!(edges, [('a', 'b'), ('b', 'c'), ('a', 'd')]);
+
+    let (back, ids) = ITree::from_edges(vec!['c', 'd', 'a', 'b'], vec![(2, 3), (3, 0), (2, 1)]).unwrap();
+    assert_eq!(back, tree);
+    assert!(ids.iter().zip("cdab".chars()).all(|(&id, v)| back[id] == v));
+
+    let from = |len, edges: &[(usize, usize)]| ITree::from_edges(vec![(); len], edges.iter().cloned()).map(|(t, _)| t);
+    assert_eq!(from(0, &[]), Ok(ITree::new()));
+    assert_eq!(from(2, &[(0, 2)]), Err(GroveError::InvalidNodeId(NodeId::new(2))));
+    assert_eq!(from(3, &[(0, 2), (1, 2)]), Err(GroveError::AlreadyAttached(NodeId::new(2))));
+    assert_eq!(from(3, &[(0, 1)]), Err(GroveError::MultipleRoots));
+    assert_eq!(from(2, &[(0, 1), (1, 0)]), Err(GroveError::Cycle));
+    assert_eq!(from(4, &[(0, 1), (2, 3), (3, 2)]), Err(GroveError::Cycle));
+}
@@ -0,0 +1,467 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+use core::iter::FromIterator;
+use core::mem;
+use core::slice;
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// Hash Array Mapped Trie
+///
+/// A persistent hash map on a trie over the bits of the hashes of its keys,
+/// five bits to a level. Branches only keep the children they have, with a
+/// bitmap saying which ones those are. Nodes sit behind an `Arc`, so clones
+/// take constant time and inserting or removing a key only copies the nodes on
+/// the way to it, sharing the rest with the maps it came from.
+///
+/// Keys whose whole hashes are the same end up together in a collision node
+/// at the bottom of the trie.
+pub struct HamtMap<K, V, S = BuildHasherDefault<FnvHasher>> {
+    root: Arc<HamtNode<K, V>>,
+    len: usize,
+    hasher: S,
+}
+
+#[derive(Clone)]
+enum HamtNode<K, V> {
+    /// Children for the set bits of the bitmap in order
+    Branch(u32, Vec<Entry<K, V>>),
+    /// Entries whose keys all have the same hash
+    Collision(u64, Vec<(K, V)>),
+}
+
+#[derive(Clone)]
+enum Entry<K, V> {
+    Leaf(u64, K, V),
+    Node(Arc<HamtNode<K, V>>),
+}
+
+/// Bits of the hash used by every level
+const BITS: u32 = 5;
+
+impl<K: Hash + Eq + Clone, V: Clone> HamtMap<K, V> {
+    /// Create a new empty `HamtMap`
+    pub fn new() -> Self {
+        Self::with_hasher(Default::default())
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone, S: BuildHasher> HamtMap<K, V, S> {
+    /// Create a new empty `HamtMap` hashing keys with `hasher`
+    pub fn with_hasher(hasher: S) -> Self {
+        HamtMap { root: Arc::new(HamtNode::Branch(0, Vec::new())), len: 0, hasher }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the value of a key
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>,
+              Q: Hash + Eq + ?Sized
+    {
+        let hash = self.hash(key);
+        let mut node = &self.root;
+        let mut shift = 0;
+        loop {
+            match **node {
+                HamtNode::Branch(bitmap, ref children) => {
+                    let bit = bit(hash, shift);
+                    if bitmap & bit == 0 {
+                        return None;
+                    }
+                    match children[index(bitmap, bit)] {
+                        Entry::Leaf(_, ref k, ref v) => return if k.borrow() == key { Some(v) } else { None },
+                        Entry::Node(ref child) => node = child,
+                    }
+                    shift += BITS;
+                }
+                HamtNode::Collision(_, ref entries) => {
+                    return entries.iter().find(|entry| entry.0.borrow() == key).map(|entry| &entry.1);
+                }
+            }
+        }
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+        where K: Borrow<Q>,
+              Q: Hash + Eq + ?Sized
+    {
+        self.get(key).is_some()
+    }
+
+    /// Insert a value under a key, returning the value the key had before
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.hash(&key);
+        let old = insert(&mut self.root, 0, hash, key, value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Remove a key and return its value
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>,
+              Q: Hash + Eq + ?Sized
+    {
+        // Removing a missing key must not copy the nodes on the way to it
+        if !self.contains_key(key) {
+            return None;
+        }
+        let hash = self.hash(key);
+        self.len -= 1;
+        remove(&mut self.root, 0, hash, key)
+    }
+
+    /// Remove every entry
+    pub fn clear(&mut self) {
+        self.root = Arc::new(HamtNode::Branch(0, Vec::new()));
+        self.len = 0;
+    }
+
+    /// Iterate over the entries in no particular order
+    pub fn iter(&self) -> HamtIter<'_, K, V> {
+        HamtIter { stack: vec![self.root.entries()], collision: [].iter(), len: self.len }
+    }
+
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        self.hasher.hash_one(key)
+    }
+}
+
+/// Get the bit for the part of a hash a level at `shift` looks at
+fn bit(hash: u64, shift: u32) -> u32 {
+    1 << ((hash >> shift) & ((1 << BITS) - 1))
+}
+
+/// Get the position of the child for a bit among the ones a bitmap has
+fn index(bitmap: u32, bit: u32) -> usize {
+    (bitmap & (bit - 1)).count_ones() as usize
+}
+
+impl<K, V> HamtNode<K, V> {
+    fn entries(&self) -> slice::Iter<'_, Entry<K, V>> {
+        match *self {
+            HamtNode::Branch(_, ref children) => children.iter(),
+            HamtNode::Collision(..) => [].iter(),
+        }
+    }
+
+    fn collision_hash(&self) -> Option<u64> {
+        match *self {
+            HamtNode::Collision(hash, _) => Some(hash),
+            HamtNode::Branch(..) => None,
+        }
+    }
+}
+
+impl<K, V> Entry<K, V> {
+    /// Get the hash of a leaf or a collision node
+    fn hash(&self) -> Option<u64> {
+        match *self {
+            Entry::Leaf(hash, ..) => Some(hash),
+            Entry::Node(ref node) => node.collision_hash(),
+        }
+    }
+}
+
+/// Make a node for the level at `shift` holding two entries whose hashes are
+/// each either a leaf or a collision node
+fn pair<K, V>(shift: u32, a: Entry<K, V>, b: Entry<K, V>) -> HamtNode<K, V> {
+    let (ha, hb) = (a.hash().unwrap(), b.hash().unwrap());
+    match (a, b) {
+        (Entry::Leaf(_, ka, va), Entry::Leaf(_, kb, vb)) if ha == hb => {
+            HamtNode::Collision(ha, vec![(ka, va), (kb, vb)])
+        }
+        (a, b) => {
+            let (bit_a, bit_b) = (bit(ha, shift), bit(hb, shift));
+            if bit_a == bit_b {
+                HamtNode::Branch(bit_a, vec![Entry::Node(Arc::new(pair(shift + BITS, a, b)))])
+            } else if bit_a < bit_b {
+                HamtNode::Branch(bit_a | bit_b, vec![a, b])
+            } else {
+                HamtNode::Branch(bit_a | bit_b, vec![b, a])
+            }
+        }
+    }
+}
+
+fn insert<K: Eq + Clone, V: Clone>(node: &mut Arc<HamtNode<K, V>>, shift: u32, hash: u64, key: K, value: V) -> Option<V> {
+    match *Arc::make_mut(node) {
+        HamtNode::Branch(ref mut bitmap, ref mut children) => {
+            let bit = bit(hash, shift);
+            let i = index(*bitmap, bit);
+            if *bitmap & bit == 0 {
+                *bitmap |= bit;
+                children.insert(i, Entry::Leaf(hash, key, value));
+                return None;
+            }
+            let entry = &mut children[i];
+            match *entry {
+                Entry::Leaf(_, ref k, ref mut v) if *k == key => return Some(mem::replace(v, value)),
+                Entry::Node(ref mut child) if child.collision_hash().is_none_or(|h| h == hash) => {
+                    return insert(child, shift + BITS, hash, key, value);
+                }
+                _ => {}
+            }
+            // Make room for both behind a new node
+            let old = mem::replace(entry, Entry::Node(Arc::new(HamtNode::Branch(0, Vec::new()))));
+            *entry = Entry::Node(Arc::new(pair(shift + BITS, old, Entry::Leaf(hash, key, value))));
+            None
+        }
+        HamtNode::Collision(_, ref mut entries) => {
+            match entries.iter_mut().find(|entry| entry.0 == key) {
+                Some(entry) => Some(mem::replace(&mut entry.1, value)),
+                None => {
+                    entries.push((key, value));
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Remove a key that is in the trie, pulling entries left alone in a node up
+/// in its place
+fn remove<K, V, Q>(node: &mut Arc<HamtNode<K, V>>, shift: u32, hash: u64, key: &Q) -> Option<V>
+    where K: Borrow<Q> + Clone,
+          V: Clone,
+          Q: Eq + ?Sized
+{
+    match *Arc::make_mut(node) {
+        HamtNode::Branch(ref mut bitmap, ref mut children) => {
+            let bit = bit(hash, shift);
+            let i = index(*bitmap, bit);
+            if let Entry::Node(ref mut child) = children[i] {
+                let value = remove(child, shift + BITS, hash, key);
+                if let Some(entry) = lone(child) {
+                    children[i] = entry;
+                }
+                return value;
+            }
+            *bitmap &= !bit;
+            match children.remove(i) {
+                Entry::Leaf(_, _, value) => Some(value),
+                Entry::Node(_) => unreachable!(),
+            }
+        }
+        HamtNode::Collision(_, ref mut entries) => {
+            let i = entries.iter().position(|entry| entry.0.borrow() == key)?;
+            Some(entries.swap_remove(i).1)
+        }
+    }
+}
+
+/// Get the only leaf left in a node
+fn lone<K: Clone, V: Clone>(node: &HamtNode<K, V>) -> Option<Entry<K, V>> {
+    match *node {
+        HamtNode::Branch(_, ref children) if children.len() == 1 => match children[0] {
+            Entry::Leaf(..) => Some(children[0].clone()),
+            Entry::Node(_) => None,
+        },
+        HamtNode::Collision(hash, ref entries) if entries.len() == 1 => {
+            Some(Entry::Leaf(hash, entries[0].0.clone(), entries[0].1.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// Hasher for the 64 bit Fowler-Noll-Vo hash
+///
+/// This is what a `HamtMap` uses by default, since `core` has no hasher of
+/// its own. It's quick for small keys but makes no attempt to resist keys
+/// picked to collide.
+#[derive(Clone, Copy, Debug)]
+pub struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl<K, V, S: Clone> Clone for HamtMap<K, V, S> {
+    fn clone(&self) -> Self {
+        HamtMap { root: self.root.clone(), len: self.len, hasher: self.hasher.clone() }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone, S: BuildHasher + Default> Default for HamtMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for HamtMap<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let iter = HamtIter { stack: vec![self.root.entries()], collision: [].iter(), len: self.len };
+        f.debug_map().entries(iter).finish()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone + PartialEq, S: BuildHasher> PartialEq for HamtMap<K, V, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone, S: BuildHasher> Extend<(K, V)> for HamtMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone, S: BuildHasher + Default> FromIterator<(K, V)> for HamtMap<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = HamtMap::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<'a, K: Hash + Eq + Clone, V: Clone, S: BuildHasher> IntoIterator for &'a HamtMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = HamtIter<'a, K, V>;
+
+    fn into_iter(self) -> HamtIter<'a, K, V> {
+        self.iter()
+    }
+}
+
+/// Iterator over the entries of a `HamtMap`
+pub struct HamtIter<'a, K: 'a, V: 'a> {
+    /// Rest of the children of every branch on the way down
+    stack: Vec<slice::Iter<'a, Entry<K, V>>>,
+    /// Rest of the current collision node
+    collision: slice::Iter<'a, (K, V)>,
+    /// Number of entries left
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for HamtIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            if let Some((k, v)) = self.collision.next() {
+                self.len -= 1;
+                return Some((k, v));
+            }
+            let entry = match self.stack.last_mut()?.next() {
+                Some(entry) => entry,
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+            match *entry {
+                Entry::Leaf(_, ref k, ref v) => {
+                    self.len -= 1;
+                    return Some((k, v));
+                }
+                Entry::Node(ref node) => match **node {
+                    HamtNode::Branch(_, ref children) => self.stack.push(children.iter()),
+                    HamtNode::Collision(_, ref entries) => self.collision = entries.iter(),
+                },
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for HamtIter<'a, K, V> {}
+
+#[test]
+fn hamt_map() {
+    use alloc::string::{String, ToString};
+
+    /// Keeps only the low byte of the hash so that keys collide a lot
+    #[derive(Default)]
+    struct Narrow(FnvHasher);
+
+    impl Hasher for Narrow {
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.write(bytes);
+        }
+
+        fn finish(&self) -> u64 {
+            self.0.finish() & 0xff
+        }
+    }
+
+    let mut map: HamtMap<u32, u32> = (0..2000).map(|k| (k, k * 2)).collect();
+    assert_eq!(map.len(), 2000);
+    assert_eq!(map.get(&1234), Some(&2468));
+    assert_eq!(map.get(&2000), None);
+
+    let snapshot = map.clone();
+    assert_eq!(map.insert(1234, 0), Some(2468));
+    for k in (0..2000).filter(|k| k % 3 == 0) {
+        assert_eq!(map.remove(&k), Some(k * 2));
+    }
+    assert_eq!(map.remove(&0), None);
+    assert_eq!(map.len(), 1333);
+    assert_eq!(map.iter().len(), 1333);
+    assert_eq!(map.get(&1234), Some(&0));
+    assert_eq!(snapshot.get(&1234), Some(&2468));
+    assert_eq!(snapshot.get(&3), Some(&6));
+    let mut keys: Vec<_> = map.iter().map(|(&k, _)| k).collect();
+    keys.sort();
+    assert!(keys.into_iter().eq((0..2000).filter(|k| k % 3 != 0)));
+
+    // Putting everything back gives an equal map
+    for k in (0..2000).step_by(3) {
+        map.insert(k, k * 2);
+    }
+    map.insert(1234, 2468);
+    assert!(map == snapshot);
+
+    let mut narrow: HamtMap<String, usize, BuildHasherDefault<Narrow>> = HamtMap::default();
+    for i in 0..1000 {
+        narrow.insert(i.to_string(), i);
+    }
+    assert_eq!(narrow.get("999"), Some(&999));
+    for i in (0..1000).filter(|i| i % 2 == 0) {
+        assert_eq!(narrow.remove(&*i.to_string()), Some(i));
+    }
+    assert_eq!(narrow.len(), 500);
+    assert_eq!(narrow.iter().count(), 500);
+    for i in 0..1000 {
+        assert_eq!(narrow.get(&*i.to_string()), if i % 2 == 1 { Some(&i) } else { None });
+    }
+    // Removing down to a single key leaves it as a leaf of the root
+    for i in (1..999).step_by(2) {
+        narrow.remove(&*i.to_string());
+    }
+    assert_eq!(narrow.iter().collect::<Vec<_>>(), vec![(&"999".to_string(), &999)]);
+    match *narrow.root {
+        HamtNode::Branch(_, ref children) => assert!(matches!(children[..], [Entry::Leaf(..)])),
+        HamtNode::Collision(..) => panic!("the root is always a branch"),
+    }
+}
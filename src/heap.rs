@@ -0,0 +1,361 @@
+use core::fmt;
+use core::iter::FromIterator;
+use core::mem;
+
+use alloc::vec::Vec;
+
+use {GroveError, SlotId};
+
+/// D-ary Heap
+///
+/// A min-heap where every node has up to `arity` children and no node has a
+/// smaller value than its parent, so the root holds the smallest value. Wrap
+/// values in `core::cmp::Reverse` for a max-heap.
+///
+/// Unlike `BinaryHeap` every value pushed gets a `SlotId` that follows it
+/// around as the heap moves it up and down, so the heap can be walked as a
+/// tree with `root_id`, `parent` and `children` and values can be changed in
+/// place with `decrease_key`. Like in an `MTree` the `SlotId`s of popped or
+/// removed values stop being valid.
+#[derive(Clone)]
+pub struct Heap<T> {
+    slots: Vec<HeapSlot<T>>,
+    /// Slots of removed values that can be reused
+    free: Vec<usize>,
+    /// Slots of the nodes in level order
+    order: Vec<usize>,
+    arity: usize,
+}
+
+#[derive(Clone)]
+struct HeapSlot<T> {
+    generation: u32,
+    /// Value along with the position of its node in `order`
+    entry: Option<(T, usize)>,
+}
+
+impl<T: Ord> Heap<T> {
+    /// Create a new empty binary `Heap`
+    pub fn new() -> Self {
+        Self::with_arity(2)
+    }
+
+    /// Create a new empty `Heap` where nodes have up to `arity` children
+    ///
+    /// # Panics
+    ///
+    /// If the arity is smaller than two.
+    pub fn with_arity(arity: usize) -> Self {
+        assert!(arity >= 2, "a heap needs an arity of at least two");
+        Heap { slots: Vec::new(), free: Vec::new(), order: Vec::new(), arity }
+    }
+
+    /// Get the most children a node can have
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Add a value and return the `SlotId` it can be found under
+    pub fn push(&mut self, value: T) -> SlotId {
+        let pos = self.order.len();
+        let id = match self.free.pop() {
+            Some(x) => {
+                self.slots[x].entry = Some((value, pos));
+                SlotId::new(x, self.slots[x].generation)
+            }
+            None => {
+                self.slots.push(HeapSlot { generation: 0, entry: Some((value, pos)) });
+                SlotId::new(self.slots.len() - 1, 0)
+            }
+        };
+        self.order.push(id.index());
+        self.sift_up(pos);
+        id
+    }
+
+    /// Get the smallest value
+    pub fn peek(&self) -> Option<&T> {
+        self.get(self.root_id()?)
+    }
+
+    /// Remove the smallest value and return it
+    pub fn pop(&mut self) -> Option<T> {
+        let root = self.root_id()?;
+        self.remove(root).ok()
+    }
+
+    /// Get the value of a node
+    pub fn get(&self, node: SlotId) -> Option<&T> {
+        self.entry(node).map(|entry| &entry.0)
+    }
+
+    /// Get the `SlotId` of the root, which holds the smallest value
+    pub fn root_id(&self) -> Option<SlotId> {
+        if self.is_empty() { None } else { Some(self.id(0)) }
+    }
+
+    /// Get the parent of a node, or `None` for the root and nodes that aren't
+    /// in the heap
+    pub fn parent(&self, node: SlotId) -> Option<SlotId> {
+        let pos = self.entry(node)?.1;
+        if pos == 0 { None } else { Some(self.id((pos - 1) / self.arity)) }
+    }
+
+    /// Iterate over the children of a node, which is empty for nodes that
+    /// aren't in the heap
+    pub fn children(&self, node: SlotId) -> HeapChildren<'_, T> {
+        let (start, end) = match self.entry(node) {
+            Some(&(_, pos)) => {
+                let start = (pos * self.arity + 1).min(self.len());
+                (start, (start + self.arity).min(self.len()))
+            }
+            None => (0, 0),
+        };
+        HeapChildren { heap: self, start, end }
+    }
+
+    /// Lower the value of a node, moving it up toward the root as far as it
+    /// needs to go. Returns the old value or an error if the node is not in
+    /// the heap.
+    ///
+    /// # Panics
+    ///
+    /// If the new value is larger than the old one.
+    pub fn decrease_key(&mut self, node: SlotId, value: T) -> Result<T, GroveError> {
+        let entry = match self.entry_mut(node) {
+            Some(entry) => entry,
+            None => return Err(GroveError::InvalidNodeId(node.id())),
+        };
+        assert!(value <= entry.0, "decrease_key can't raise the value of a node");
+        let old = mem::replace(&mut entry.0, value);
+        let pos = entry.1;
+        self.sift_up(pos);
+        Ok(old)
+    }
+
+    /// Remove the value of any node and return it, or an error if the node is
+    /// not in the heap
+    pub fn remove(&mut self, node: SlotId) -> Result<T, GroveError> {
+        let pos = match self.entry(node) {
+            Some(entry) => entry.1,
+            None => return Err(GroveError::InvalidNodeId(node.id())),
+        };
+        let last = self.order.len() - 1;
+        self.swap(pos, last);
+        self.order.pop();
+        let slot = &mut self.slots[node.index()];
+        let (value, _) = slot.entry.take().unwrap();
+        // Slots that ran out of generations are never used again
+        if let Some(generation) = slot.generation.checked_add(1) {
+            slot.generation = generation;
+            self.free.push(node.index());
+        }
+        if pos < last {
+            let pos = self.sift_up(pos);
+            self.sift_down(pos);
+        }
+        Ok(value)
+    }
+
+    /// Remove every value
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Iterate over the nodes in level order, starting at the root
+    pub fn iter(&self) -> HeapIter<'_, T> {
+        HeapIter { heap: self, pos: 0 }
+    }
+
+    fn entry(&self, node: SlotId) -> Option<&(T, usize)> {
+        let slot = self.slots.get(node.index())?;
+        if slot.generation == node.generation() { slot.entry.as_ref() } else { None }
+    }
+
+    fn entry_mut(&mut self, node: SlotId) -> Option<&mut (T, usize)> {
+        let slot = self.slots.get_mut(node.index())?;
+        if slot.generation == node.generation() { slot.entry.as_mut() } else { None }
+    }
+
+    /// Get the `SlotId` of the node at a position
+    fn id(&self, pos: usize) -> SlotId {
+        let x = self.order[pos];
+        SlotId::new(x, self.slots[x].generation)
+    }
+
+    fn value(&self, pos: usize) -> &T {
+        &self.slots[self.order[pos]].entry.as_ref().unwrap().0
+    }
+
+    /// Swap the nodes at two positions
+    fn swap(&mut self, a: usize, b: usize) {
+        self.order.swap(a, b);
+        self.slots[self.order[a]].entry.as_mut().unwrap().1 = a;
+        self.slots[self.order[b]].entry.as_mut().unwrap().1 = b;
+    }
+
+    /// Move the node at a position up while it's smaller than its parent,
+    /// returning where it ends up
+    fn sift_up(&mut self, mut pos: usize) -> usize {
+        while pos > 0 {
+            let parent = (pos - 1) / self.arity;
+            if self.value(parent) <= self.value(pos) {
+                break;
+            }
+            self.swap(parent, pos);
+            pos = parent;
+        }
+        pos
+    }
+
+    /// Move the node at a position down while one of its children is smaller
+    fn sift_down(&mut self, mut pos: usize) {
+        loop {
+            let start = pos * self.arity + 1;
+            let end = (start + self.arity).min(self.len());
+            let smallest = match (start..end).min_by(|&a, &b| self.value(a).cmp(self.value(b))) {
+                Some(child) if self.value(child) < self.value(pos) => child,
+                _ => return,
+            };
+            self.swap(pos, smallest);
+            pos = smallest;
+        }
+    }
+}
+
+impl<T: Ord> Default for Heap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + fmt::Debug> fmt::Debug for Heap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord> Extend<T> for Heap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for Heap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut heap = Heap::new();
+        heap.extend(iter);
+        heap
+    }
+}
+
+/// Iterator over the children of a node of a `Heap`
+pub struct HeapChildren<'a, T: 'a> {
+    heap: &'a Heap<T>,
+    start: usize,
+    end: usize,
+}
+
+impl<'a, T: Ord> Iterator for HeapChildren<'a, T> {
+    type Item = SlotId;
+
+    fn next(&mut self) -> Option<SlotId> {
+        if self.start == self.end {
+            return None;
+        }
+        self.start += 1;
+        Some(self.heap.id(self.start - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.end - self.start, Some(self.end - self.start))
+    }
+}
+
+impl<'a, T: Ord> ExactSizeIterator for HeapChildren<'a, T> {}
+
+/// Iterator over the nodes of a `Heap` in level order
+pub struct HeapIter<'a, T: 'a> {
+    heap: &'a Heap<T>,
+    pos: usize,
+}
+
+impl<'a, T: Ord> Iterator for HeapIter<'a, T> {
+    type Item = (SlotId, &'a T);
+
+    fn next(&mut self) -> Option<(SlotId, &'a T)> {
+        if self.pos == self.heap.len() {
+            return None;
+        }
+        self.pos += 1;
+        Some((self.heap.id(self.pos - 1), self.heap.value(self.pos - 1)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.heap.len() - self.pos;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T: Ord> ExactSizeIterator for HeapIter<'a, T> {}
+
+#[test]
+fn heap() {
+    /// Check that no node is smaller than its parent by walking the tree
+    fn check<T: Ord>(heap: &Heap<T>, node: SlotId) -> usize {
+        let mut count = 1;
+        let children: Vec<_> = heap.children(node).collect();
+        assert!(children.len() <= heap.arity());
+        for child in children {
+            assert_eq!(heap.parent(child), Some(node));
+            assert!(heap.get(child) >= heap.get(node));
+            count += check(heap, child);
+        }
+        count
+    }
+
+    let mut heap = Heap::with_arity(3);
+    let ids: Vec<_> = (0..100u32).map(|k| heap.push(k * 37 % 101)).collect();
+    assert_eq!(heap.len(), 100);
+    assert_eq!(check(&heap, heap.root_id().unwrap()), 100);
+    assert_eq!(heap.parent(heap.root_id().unwrap()), None);
+    assert_eq!(heap.peek(), Some(&0));
+    assert_eq!(heap.iter().len(), 100);
+
+    // Ids follow their values up the heap
+    assert_eq!(heap.get(ids[50]), Some(&32));
+    assert_eq!(heap.decrease_key(ids[50], 0), Ok(32));
+    check(&heap, heap.root_id().unwrap());
+    assert_eq!(heap.get(ids[50]), Some(&0));
+    assert_eq!(heap.remove(ids[50]), Ok(0));
+    assert_eq!(heap.get(ids[50]), None);
+    assert_eq!(heap.remove(ids[50]), Err(GroveError::InvalidNodeId(ids[50].id())));
+    assert_eq!(heap.children(ids[50]).count(), 0);
+    check(&heap, heap.root_id().unwrap());
+
+    // A new value reuses the slot without reviving the old id
+    let id = heap.push(1000);
+    assert_eq!(id.index(), ids[50].index());
+    assert_eq!(heap.get(ids[50]), None);
+
+    let mut popped = Vec::new();
+    while let Some(value) = heap.pop() {
+        popped.push(value);
+    }
+    let mut expected: Vec<_> = (0..100u32).filter(|&k| k != 50).map(|k| k * 37 % 101).collect();
+    expected.push(1000);
+    expected.sort();
+    assert_eq!(popped, expected);
+    assert!(heap.is_empty());
+    assert_eq!(heap.root_id(), None);
+}
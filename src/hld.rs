@@ -0,0 +1,241 @@
+use core::ops::Range;
+
+use alloc::vec::Vec;
+
+use segment::{SegmentOp, SegmentTree};
+use {GroveError, ITree, NodeId};
+
+/// Heavy-Light Decomposition
+///
+/// Splits a tree into chains, where every node goes on in the chain of the
+/// child with the largest subtree. Walking up from any node crosses at most a
+/// logarithmic number of chains, since every chain it leaves was at most half
+/// the size of the one it joins.
+///
+/// The nodes get lined up so that every chain and every subtree is a range of
+/// positions. A `SegmentTree` over values in that order can then answer
+/// queries and take updates for any path with a logarithmic number of ranges.
+///
+/// ```
+/// # #[macro_use] extern crate grove;
+/// # fn main() {
+/// use grove::{HeavyLight, SegmentOp, SegmentTree};
+///
+/// struct Sum;
+///
+/// impl SegmentOp<u64> for Sum {
+///     type Update = u64;
+///     fn identity(&self) -> u64 { 0 }
+///     fn combine(&self, a: &u64, b: &u64) -> u64 { a + b }
+///     fn apply(&self, add: &u64, value: &u64, len: usize) -> u64 { value + add * len as u64 }
+///     fn compose(&self, newer: &u64, older: &u64) -> u64 { newer + older }
+/// }
+///
+/// let tree = tree!{ 1 => [2 => [4, 5], 3] };
+/// let id = |value| tree.find(|&v| v == value).unwrap();
+/// let hld = HeavyLight::new(&tree);
+/// let mut sums = SegmentTree::new(Sum, &hld.arrange(|id| tree[id]));
+/// assert_eq!(hld.path_query(&sums, id(4), id(3)), Some(4 + 2 + 1 + 3));
+/// hld.path_update(&mut sums, id(3), id(3), 10).unwrap();
+/// assert_eq!(hld.path_query(&sums, id(3), id(1)), Some(13 + 1));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct HeavyLight {
+    parents: Vec<Option<usize>>,
+    depths: Vec<usize>,
+    sizes: Vec<usize>,
+    /// Top node of the chain of every node
+    heads: Vec<usize>,
+    /// Position of every node in the line up
+    positions: Vec<usize>,
+    /// Nodes by position
+    order: Vec<NodeId>,
+}
+
+impl HeavyLight {
+    /// Decompose a tree
+    pub fn new<T>(tree: &ITree<T>) -> Self {
+        let len = tree.len();
+        let parents: Vec<_> = tree.parents.iter().map(|p| p.map(NodeId::index)).collect();
+        let sizes = tree.subtree_sizes().into_vec();
+        let mut depths = vec![0; len];
+        let mut heads = vec![0; len];
+        let mut positions = vec![0; len];
+        let mut order = Vec::with_capacity(len);
+        let mut stack: Vec<usize> = tree.root_id().map(NodeId::index).into_iter().collect();
+        while let Some(x) = stack.pop() {
+            positions[x] = order.len();
+            order.push(NodeId::new(x));
+            let children = &tree.children[x];
+            let heavy = children.iter().map(|c| c.index()).max_by_key(|&c| sizes[c]);
+            for child in children.iter().rev().map(|c| c.index()) {
+                depths[child] = depths[x] + 1;
+                heads[child] = if Some(child) == heavy { heads[x] } else { child };
+                if Some(child) != heavy {
+                    stack.push(child);
+                }
+            }
+            // The heavy child comes right after its parent
+            stack.extend(heavy);
+        }
+        HeavyLight { parents, depths, sizes, heads, positions, order }
+    }
+
+    /// Get the position of a node in the line up
+    pub fn position(&self, node: NodeId) -> Option<usize> {
+        self.positions.get(node.index()).cloned()
+    }
+
+    /// Get the nodes in the order of their positions
+    pub fn order(&self) -> &[NodeId] {
+        &self.order
+    }
+
+    /// Line up a value for every node, ready to build a `SegmentTree` over
+    pub fn arrange<U, F>(&self, value: F) -> Vec<U>
+        where F: FnMut(NodeId) -> U
+    {
+        self.order.iter().cloned().map(value).collect()
+    }
+
+    /// Get the range of positions of the subtree of a node
+    pub fn subtree_range(&self, node: NodeId) -> Option<Range<usize>> {
+        let start = self.position(node)?;
+        Some(start..start + self.sizes[node.index()])
+    }
+
+    /// Get the lowest common ancestor of two nodes
+    pub fn lca(&self, a: NodeId, b: NodeId) -> Option<NodeId> {
+        let mut last = None;
+        self.walk(a, b, |range| last = Some(range))?;
+        Some(self.order[last.unwrap().start])
+    }
+
+    /// Get the ranges of positions that cover the path between two nodes,
+    /// including both of them
+    pub fn path_ranges(&self, a: NodeId, b: NodeId) -> Option<Vec<Range<usize>>> {
+        let mut ranges = Vec::new();
+        self.walk(a, b, |range| ranges.push(range))?;
+        Some(ranges)
+    }
+
+    /// Combine the values on the path between two nodes, or `None` if one of
+    /// them is not in the tree. The ranges get combined in no particular
+    /// order, so the combination should be commutative.
+    pub fn path_query<T, Op>(&self, values: &SegmentTree<T, Op>, a: NodeId, b: NodeId) -> Option<T>
+        where T: Clone,
+              Op: SegmentOp<T>
+    {
+        let op = values.op();
+        let mut result = op.identity();
+        self.walk(a, b, |range| result = op.combine(&result, &values.query(range)))?;
+        Some(result)
+    }
+
+    /// Apply an update to every value on the path between two nodes
+    pub fn path_update<T, Op>(&self, values: &mut SegmentTree<T, Op>, a: NodeId, b: NodeId, update: Op::Update)
+        -> Result<(), GroveError>
+        where T: Clone,
+              Op: SegmentOp<T>
+    {
+        let ranges = self.path_ranges(a, b).ok_or_else(|| {
+            let missing = if self.position(a).is_none() { a } else { b };
+            GroveError::InvalidNodeId(missing)
+        })?;
+        for range in ranges {
+            values.update(range, update.clone());
+        }
+        Ok(())
+    }
+
+    /// Climb from both nodes chain by chain until they meet, handing every
+    /// range of positions on the way to `f`. The last range starts at the
+    /// lowest common ancestor.
+    fn walk<F>(&self, a: NodeId, b: NodeId, mut f: F) -> Option<()>
+        where F: FnMut(Range<usize>)
+    {
+        self.position(a)?;
+        self.position(b)?;
+        let (mut a, mut b) = (a.index(), b.index());
+        while self.heads[a] != self.heads[b] {
+            if self.depths[self.heads[a]] < self.depths[self.heads[b]] {
+                core::mem::swap(&mut a, &mut b);
+            }
+            let head = self.heads[a];
+            f(self.positions[head]..self.positions[a] + 1);
+            a = self.parents[head]?;
+        }
+        let (low, high) = (self.positions[a].min(self.positions[b]), self.positions[a].max(self.positions[b]));
+        f(low..high + 1);
+        Some(())
+    }
+}
+
+#[test]
+fn heavy_light() {
+    /// Sums with updates that add to every value
+    struct Sum;
+
+    impl SegmentOp<u64> for Sum {
+        type Update = u64;
+
+        fn identity(&self) -> u64 {
+            0
+        }
+
+        fn combine(&self, left: &u64, right: &u64) -> u64 {
+            left + right
+        }
+
+        fn apply(&self, update: &u64, value: &u64, len: usize) -> u64 {
+            value + update * len as u64
+        }
+
+        fn compose(&self, newer: &u64, older: &u64) -> u64 {
+            newer + older
+        }
+    }
+
+    // A random tree where node `x` hangs below one of the nodes before it
+    let mut tree = ITree::new();
+    tree.add_root(0u64).unwrap();
+    let mut seed = 7u64;
+    for x in 1..500 {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        tree.add_node(NodeId::new((seed >> 33) as usize % x), x as u64);
+    }
+    let hld = HeavyLight::new(&tree);
+    let mut sums = SegmentTree::new(Sum, &hld.arrange(|id| tree[id]));
+    let mut values: Vec<u64> = (0..500).collect();
+
+    // Subtrees are ranges of positions
+    for x in (0..500).step_by(37) {
+        let range = hld.subtree_range(NodeId::new(x)).unwrap();
+        let mut below: Vec<_> = hld.order()[range].to_vec();
+        let mut expected: Vec<_> = tree.descendants(NodeId::new(x)).collect();
+        below.sort();
+        expected.sort();
+        assert_eq!(below, expected);
+    }
+
+    for round in 0..200 {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let a = NodeId::new((seed >> 20) as usize % 500);
+        let b = NodeId::new((seed >> 40) as usize % 500);
+        let path = tree.path_between(a, b).unwrap();
+        assert_eq!(hld.lca(a, b), tree.lca(a, b));
+        assert!(hld.path_ranges(a, b).unwrap().len() <= 2 * 10);
+        if round % 3 == 0 {
+            hld.path_update(&mut sums, a, b, round).unwrap();
+            for id in &path {
+                values[id.index()] += round;
+            }
+        }
+        let expected: u64 = path.iter().map(|id| values[id.index()]).sum();
+        assert_eq!(hld.path_query(&sums, a, b), Some(expected));
+    }
+    assert_eq!(hld.path_query(&sums, NodeId::new(0), NodeId::new(500)), None);
+    let missing = NodeId::new(500);
+    assert_eq!(hld.path_update(&mut sums, missing, NodeId::new(0), 1), Err(GroveError::InvalidNodeId(missing)));
+}
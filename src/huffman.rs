@@ -0,0 +1,141 @@
+//! Huffman Coding
+//!
+//! Build the Huffman tree of a set of symbols from how often each of them
+//! comes up, and read the prefix code of every symbol off the tree.
+
+use core::cmp::Reverse;
+
+use alloc::collections::{BTreeMap, BinaryHeap};
+use alloc::vec::Vec;
+
+use {ITree, NodeId};
+
+/// Node of a Huffman tree
+///
+/// Every branch has two children, where the first one stands for a `0` bit
+/// of a code and the second one for a `1` bit. The weight of a branch is the
+/// sum of the weights of the leaves below it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HuffmanNode<S> {
+    Leaf { symbol: S, weight: u64 },
+    Branch { weight: u64 },
+}
+
+impl<S> HuffmanNode<S> {
+    pub fn weight(&self) -> u64 {
+        match *self {
+            HuffmanNode::Leaf { weight, .. } | HuffmanNode::Branch { weight } => weight,
+        }
+    }
+
+    /// Get the symbol of a leaf
+    pub fn symbol(&self) -> Option<&S> {
+        match *self {
+            HuffmanNode::Leaf { ref symbol, .. } => Some(symbol),
+            HuffmanNode::Branch { .. } => None,
+        }
+    }
+}
+
+/// Build the Huffman tree of symbols along with their frequencies
+///
+/// The two lightest nodes get merged until only the root is left, with ties
+/// going to the nodes that came first, so the same frequencies always build
+/// the same tree. A lone symbol still gets a branch above it so it ends up
+/// with a one bit code, and no symbols build an empty tree.
+///
+/// ```
+/// use grove::huffman;
+///
+/// let tree = huffman::build(vec![('a', 5), ('b', 2), ('c', 1), ('d', 1)]);
+/// let codes = huffman::codes(&tree);
+/// assert_eq!(codes[&'a'], [true]);
+/// assert_eq!(codes[&'c'].len(), 3);
+/// ```
+pub fn build<S, I>(frequencies: I) -> ITree<HuffmanNode<S>>
+    where I: IntoIterator<Item = (S, u64)>
+{
+    // Leaves come first, then every merge with the positions of the two
+    // nodes it merged
+    let mut symbols = Vec::new();
+    let mut weights = Vec::new();
+    let mut merges = Vec::new();
+    let mut heap = BinaryHeap::new();
+    for (symbol, weight) in frequencies {
+        heap.push(Reverse((weight, symbols.len())));
+        symbols.push(Some(symbol));
+        weights.push(weight);
+    }
+    let leaves = symbols.len();
+    match leaves {
+        0 => return ITree::new(),
+        1 => {
+            weights.push(weights[0]);
+            merges.push((0, None));
+        }
+        _ => {}
+    }
+    while heap.len() > 1 {
+        let Reverse((a, x)) = heap.pop().unwrap();
+        let Reverse((b, y)) = heap.pop().unwrap();
+        heap.push(Reverse((a + b, weights.len())));
+        weights.push(a + b);
+        merges.push((x, Some(y)));
+    }
+    ITree::build_from(weights.len() - 1, |&x| {
+        if x < leaves {
+            let symbol = symbols[x].take().unwrap();
+            return (HuffmanNode::Leaf { symbol, weight: weights[x] }, Vec::new());
+        }
+        let (a, b) = merges[x - leaves];
+        let children = Some(a).into_iter().chain(b).collect();
+        (HuffmanNode::Branch { weight: weights[x] }, children)
+    })
+}
+
+/// Get the code of every symbol of a Huffman tree as its bits, `false` for
+/// `0` and `true` for `1`
+pub fn codes<S: Ord>(tree: &ITree<HuffmanNode<S>>) -> BTreeMap<&S, Vec<bool>> {
+    let mut codes = BTreeMap::new();
+    let mut stack: Vec<(NodeId, Vec<bool>)> = tree.root_id().map(|root| (root, Vec::new())).into_iter().collect();
+    while let Some((id, code)) = stack.pop() {
+        let node = tree.get(id).unwrap();
+        if let Some(symbol) = node.value().symbol() {
+            codes.insert(symbol, code);
+            continue;
+        }
+        for (bit, &child) in node.children().iter().enumerate() {
+            let mut code = code.clone();
+            code.push(bit == 1);
+            stack.push((child, code));
+        }
+    }
+    codes
+}
+
+#[test]
+fn huffman() {
+    let frequencies = vec![('a', 45), ('b', 13), ('c', 12), ('d', 16), ('e', 9), ('f', 5)];
+    let tree = build(frequencies.clone());
+    assert_eq!(tree.len(), 11);
+    assert_eq!(tree.root().unwrap().value(), &HuffmanNode::Branch { weight: 100 });
+    let codes = codes(&tree);
+    let lengths: Vec<_> = frequencies.iter().map(|&(s, _)| codes[&s].len()).collect();
+    assert_eq!(lengths, [1, 3, 3, 3, 4, 4]);
+    let bits: usize = frequencies.iter().map(|&(s, f)| f as usize * codes[&s].len()).sum();
+    assert_eq!(bits, 224);
+
+    // Decoding by walking the tree gives back every symbol
+    for (&&symbol, code) in &codes {
+        let mut node = tree.root().unwrap();
+        for &bit in code {
+            node = tree.get(node.children()[bit as usize]).unwrap();
+        }
+        assert_eq!(node.value().symbol(), Some(&symbol));
+    }
+
+    let lone = build(vec![("x", 3)]);
+    assert_eq!(lone.len(), 2);
+    assert_eq!(self::codes(&lone)[&"x"], [false]);
+    assert!(build(Vec::<(u8, u64)>::new()).is_empty());
+}
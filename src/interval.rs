@@ -0,0 +1,247 @@
+use core::fmt;
+use core::iter::FromIterator;
+use core::mem;
+use core::ops::{Bound, Range};
+
+use alloc::vec::Vec;
+
+use avl::AvlMeta;
+use bst::Bst;
+
+/// Interval Tree
+///
+/// A map from half-open ranges to values that finds every range containing a
+/// point or overlapping another range. The ranges sit in an AVL tree ordered
+/// by their start and then their end, where every node also keeps the largest
+/// end below it, so whole subtrees that end too early get skipped.
+///
+/// Ranges are half-open like `Range`, so `1..3` and `3..5` don't overlap and
+/// an empty range never overlaps anything.
+#[derive(Clone)]
+pub struct IntervalTree<K, V> {
+    tree: Bst<(K, K), V, IntervalMeta<K>>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct IntervalMeta<K> {
+    height: u8,
+    /// Largest end of a range in the subtree of the node
+    max: K,
+}
+
+impl<K: Ord + Clone, V> AvlMeta<(K, K), V> for IntervalMeta<K> {
+    fn height(&self) -> u8 {
+        self.height
+    }
+
+    fn update(tree: &mut Bst<(K, K), V, Self>, x: usize) {
+        let node = &tree.nodes[x];
+        let mut max = &node.key.1;
+        for child in node.left.iter().chain(node.right.iter()) {
+            max = max.max(&tree.nodes[*child].meta.max);
+        }
+        let meta = IntervalMeta {
+            height: 1 + tree.height_of(node.left).max(tree.height_of(node.right)),
+            max: max.clone(),
+        };
+        tree.nodes[x].meta = meta;
+    }
+}
+
+impl<K: Ord + Clone, V> IntervalTree<K, V> {
+    /// Create a new empty `IntervalTree`
+    pub fn new() -> Self {
+        IntervalTree { tree: Bst::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.len == 0
+    }
+
+    /// Insert a value under a range, returning the value the exact same range
+    /// had before
+    pub fn insert(&mut self, range: Range<K>, value: V) -> Option<V> {
+        let key = (range.start, range.end);
+        match self.tree.search(&key) {
+            Ok(x) => Some(mem::replace(&mut self.tree.nodes[x].value, value)),
+            Err(at) => {
+                let meta = IntervalMeta { height: 1, max: key.1.clone() };
+                self.tree.attach(at, key, value, meta);
+                self.tree.retrace(at.map(|(p, _)| p));
+                None
+            }
+        }
+    }
+
+    /// Get the value of the exact range
+    pub fn get(&self, range: &Range<K>) -> Option<&V> {
+        let x = self.tree.find(&(range.start.clone(), range.end.clone()))?;
+        Some(&self.tree.nodes[x].value)
+    }
+
+    /// Remove the exact range and return its value
+    pub fn remove(&mut self, range: &Range<K>) -> Option<V> {
+        let x = self.tree.find(&(range.start.clone(), range.end.clone()))?;
+        let x = self.tree.leafward(x);
+        let (node, parent, _) = self.tree.unlink(x);
+        self.tree.retrace(parent);
+        Some(node.value)
+    }
+
+    /// Remove every range
+    pub fn clear(&mut self) {
+        self.tree.clear();
+    }
+
+    /// Iterate over every range containing a point, in order of their start
+    pub fn query_point<'a>(&'a self, point: &'a K) -> IntervalIter<'a, K, V> {
+        IntervalIter::new(&self.tree, Some(point), Bound::Included(point))
+    }
+
+    /// Iterate over every range overlapping `range`, in order of their start
+    pub fn query_overlap<'a>(&'a self, range: Range<&'a K>) -> IntervalIter<'a, K, V> {
+        if range.start >= range.end {
+            return IntervalIter { tree: &self.tree, after: None, before: Bound::Unbounded, stack: Vec::new() };
+        }
+        IntervalIter::new(&self.tree, Some(range.start), Bound::Excluded(range.end))
+    }
+
+    /// Iterate over every range in order of their start
+    pub fn iter(&self) -> IntervalIter<'_, K, V> {
+        IntervalIter::new(&self.tree, None, Bound::Unbounded)
+    }
+}
+
+impl<K: Ord + Clone, V> Default for IntervalTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug + Ord + Clone, V: fmt::Debug> fmt::Debug for IntervalTree<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Ord + Clone, V> Extend<(Range<K>, V)> for IntervalTree<K, V> {
+    fn extend<I: IntoIterator<Item = (Range<K>, V)>>(&mut self, iter: I) {
+        for (range, value) in iter {
+            self.insert(range, value);
+        }
+    }
+}
+
+impl<K: Ord + Clone, V> FromIterator<(Range<K>, V)> for IntervalTree<K, V> {
+    fn from_iter<I: IntoIterator<Item = (Range<K>, V)>>(iter: I) -> Self {
+        let mut tree = IntervalTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+/// Iterator over the ranges of an `IntervalTree` that end after a point and
+/// start before another one
+pub struct IntervalIter<'a, K: 'a, V: 'a> {
+    tree: &'a Bst<(K, K), V, IntervalMeta<K>>,
+    /// Ranges have to end after this
+    after: Option<&'a K>,
+    /// Ranges have to start before this
+    before: Bound<&'a K>,
+    /// Nodes left to visit in order, whose left subtrees have been visited
+    stack: Vec<usize>,
+}
+
+impl<'a, K: Ord, V> IntervalIter<'a, K, V> {
+    fn new(tree: &'a Bst<(K, K), V, IntervalMeta<K>>, after: Option<&'a K>, before: Bound<&'a K>) -> Self {
+        let mut iter = IntervalIter { tree, after, before, stack: Vec::new() };
+        iter.descend(tree.root);
+        iter
+    }
+
+    /// Push the nodes down the left side of a subtree, skipping the ones
+    /// where every range ends too early
+    fn descend(&mut self, mut x: Option<usize>) {
+        while let Some(n) = x {
+            let node = &self.tree.nodes[n];
+            if self.after.is_some_and(|after| node.meta.max <= *after) {
+                break;
+            }
+            self.stack.push(n);
+            x = node.left;
+        }
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for IntervalIter<'a, K, V> {
+    type Item = (Range<&'a K>, &'a V);
+
+    fn next(&mut self) -> Option<(Range<&'a K>, &'a V)> {
+        while let Some(x) = self.stack.pop() {
+            let node = &self.tree.nodes[x];
+            let (ref start, ref end) = node.key;
+            let starts_before = match self.before {
+                Bound::Included(before) => start <= before,
+                Bound::Excluded(before) => start < before,
+                Bound::Unbounded => true,
+            };
+            // Every range left starts even later
+            if !starts_before {
+                self.stack.clear();
+                return None;
+            }
+            self.descend(node.right);
+            if self.after.is_none_or(|after| end > after && end > start) {
+                return Some((start..end, &node.value));
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn interval_tree() {
+    let mut tree: IntervalTree<u32, &str> =
+        vec![(9..12, "standup"), (10..15, "review"), (13..14, "lunch"), (16..18, "demo"), (8..17, "office")]
+            .into_iter()
+            .collect();
+    let at = |tree: &IntervalTree<u32, &'static str>, point| tree.query_point(&point).map(|(_, &v)| v).collect::<Vec<_>>();
+    assert_eq!(at(&tree, 13), vec!["office", "review", "lunch"]);
+    assert_eq!(at(&tree, 12), vec!["office", "review"]);
+    assert_eq!(at(&tree, 18), Vec::<&str>::new());
+    let meetings: Vec<_> = tree.query_overlap(&12..&16).map(|(r, _)| (*r.start, *r.end)).collect();
+    assert_eq!(meetings, vec![(8, 17), (10, 15), (13, 14)]);
+    assert_eq!(tree.query_overlap(&12..&12).count(), 0);
+
+    assert_eq!(tree.insert(10..15, "retro"), Some("review"));
+    assert_eq!(tree.remove(&(8..17)), Some("office"));
+    assert_eq!(tree.get(&(8..17)), None);
+    assert_eq!(at(&tree, 13), vec!["retro", "lunch"]);
+    assert_eq!(tree.len(), 4);
+
+    // Compare against scanning every range
+    use alloc::collections::BTreeMap;
+
+    let mut model = BTreeMap::new();
+    let mut tree = IntervalTree::new();
+    for x in 0..300u32 {
+        let (start, end) = (x * 37 % 101, x * 37 % 101 + x % 13);
+        if x % 4 == 3 {
+            assert_eq!(tree.remove(&(start..end)), model.remove(&(start, end)));
+        } else {
+            assert_eq!(tree.insert(start..end, x), model.insert((start, end), x));
+        }
+    }
+    for point in 0..120 {
+        let found: Vec<_> = tree.query_point(&point).map(|(r, &v)| (*r.start, *r.end, v)).collect();
+        let expected: Vec<_> = model.iter().filter(|&(&(s, e), _)| s <= point && point < e).map(|(&(s, e), &v)| (s, e, v)).collect();
+        assert_eq!(found, expected);
+        let found: Vec<_> = tree.query_overlap(&point..&(point + 5)).map(|(r, _)| (*r.start, *r.end)).collect();
+        let expected: Vec<_> = model.keys().filter(|&&(s, e)| s < point + 5 && point < e && s < e).cloned().collect();
+        assert_eq!(found, expected);
+    }
+}
@@ -0,0 +1,80 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use ITree;
+
+impl<T> ITree<T> {
+    /// Check if two trees have the same shape when the order of children
+    /// doesn't matter. Values are ignored.
+    ///
+    /// Every subtree gets a canonical label from the sorted labels of its
+    /// children, shared by both trees, so the trees are isomorphic exactly
+    /// when their roots get the same label.
+    pub fn is_isomorphic<U>(&self, other: &ITree<U>) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+        let mut labels = BTreeMap::new();
+        match (self.canonical_labels(&mut labels), other.canonical_labels(&mut labels)) {
+            (Some(a), Some(b)) => a == b,
+            _ => self.is_empty(),
+        }
+    }
+
+    /// Check if two trees have the same shape with children in the same order
+    /// and equal values in the same places. Unlike `==` the `NodeId`s of the
+    /// nodes don't have to line up.
+    pub fn is_identical(&self, other: &Self) -> bool
+        where T: PartialEq
+    {
+        if self.len() != other.len() {
+            return false;
+        }
+        let mut stack: Vec<_> = self.root().into_iter().zip(other.root()).collect();
+        while let Some((a, b)) = stack.pop() {
+            if a.value() != b.value() || a.children().len() != b.children().len() {
+                return false;
+            }
+            for (&x, &y) in a.children().iter().zip(b.children()) {
+                stack.push((self.get(x).unwrap(), other.get(y).unwrap()));
+            }
+        }
+        true
+    }
+
+    /// Label every subtree by the sorted labels of its children, returning
+    /// the label of the root
+    fn canonical_labels(&self, labels: &mut BTreeMap<Vec<usize>, usize>) -> Option<usize> {
+        self.fold(|_, mut children| {
+            children.sort_unstable();
+            let next = labels.len();
+            *labels.entry(children).or_insert(next)
+        })
+    }
+}
+
+#[test]
+fn isomorphism() {
+    let a = tree!{ 0 => [1 => [2, 3], 4] };
+    let b = tree!{ "a" => ["b", "c" => ["d", "e"]] };
+    let c = tree!{ 0 => [1 => [2 => [3]], 4] };
+    assert!(a.is_isomorphic(&b));
+    assert!(b.is_isomorphic(&a));
+    assert!(!a.is_isomorphic(&c));
+    assert!(!a.is_isomorphic(&tree!{ 0 => [1, 2, 3, 4] }));
+    assert!(ITree::<u8>::new().is_isomorphic(&ITree::<u8>::new()));
+    assert!(!a.is_isomorphic(&ITree::<u8>::new()));
+
+    // Same shape and values with `NodeId`s in another order
+    let mut d = ITree::new();
+    let root = d.add_root(0).unwrap();
+    let one = d.add_node(root, 1);
+    d.add_node(root, 4);
+    d.add_node(one, 2);
+    d.add_node(one, 3);
+    assert!(a.is_identical(&d));
+    assert_ne!(a, d);
+    assert!(!a.is_identical(&tree!{ 0 => [4, 1 => [2, 3]] }));
+    assert!(!a.is_identical(&tree!{ 0 => [1 => [2, 5], 4] }));
+    assert!(a.is_isomorphic(&tree!{ 0 => [4, 1 => [2, 3]] }));
+}
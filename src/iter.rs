@@ -0,0 +1,459 @@
+use core::iter::Enumerate;
+use core::mem;
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use chunks;
+use {Children, INodeRef, ITree, NodeId};
+
+impl<T> ITree<T> {
+    /// Iterate over the subtree starting at the given `NodeId` in pre-order,
+    /// visiting each node before its children. Yields nothing if the node does
+    /// not exist.
+    pub fn iter_dfs(&self, start: NodeId) -> Dfs<'_, T> {
+        let stack = match self.get(start) {
+            Some(_) => vec![start],
+            None => Vec::new(),
+        };
+        Dfs { tree: self, stack }
+    }
+
+    /// Iterate over the subtree starting at the given `NodeId` in level order,
+    /// visiting every node of a depth before moving on to the next one. Yields
+    /// nothing if the node does not exist.
+    pub fn iter_bfs(&self, start: NodeId) -> Bfs<'_, T> {
+        let mut queue = VecDeque::new();
+        if self.get(start).is_some() {
+            queue.push_back(start);
+        }
+        Bfs { tree: self, queue }
+    }
+
+    /// Iterate over the subtree starting at the given `NodeId` in post-order,
+    /// visiting each node after all of its children. Yields nothing if the
+    /// node does not exist.
+    pub fn iter_post_order(&self, start: NodeId) -> PostOrder<'_, T> {
+        let stack = match self.get(start) {
+            Some(_) => vec![(start, 0)],
+            None => Vec::new(),
+        };
+        PostOrder { tree: self, stack }
+    }
+
+    /// Iterate over the `NodeId`s of the ancestors of a node, starting at its
+    /// parent and ending at the root. Yields nothing for the root or a node
+    /// that does not exist.
+    pub fn ancestors(&self, node: NodeId) -> Ancestors<'_, T> {
+        let next = self.get(node).and_then(|n| n.parent());
+        Ancestors { tree: self, next }
+    }
+
+    /// Iterate over the `NodeId`s of every node in the subtree rooted at the
+    /// given node, including the node itself, in pre-order. Yields nothing if
+    /// the node does not exist.
+    pub fn descendants(&self, node: NodeId) -> Descendants<'_, T> {
+        Descendants { dfs: self.iter_dfs(node) }
+    }
+
+    /// Iterate over the subtree starting at the given `NodeId` in pre-order
+    /// like `iter_dfs`, leaving out every node more than `max_depth` levels
+    /// below the start. Every node comes with its depth below the start, which
+    /// is zero for the start itself. Yields nothing if the node does not exist.
+    pub fn iter_to_depth(&self, start: NodeId, max_depth: usize) -> ToDepth<'_, T> {
+        let stack = match self.get(start) {
+            Some(_) => vec![(start, 0)],
+            None => Vec::new(),
+        };
+        ToDepth { tree: self, stack, max_depth }
+    }
+
+    /// Iterate over the levels of the tree starting at the root, yielding the
+    /// `NodeId`s of every node of a depth at once, from left to right
+    pub fn levels(&self) -> Levels<'_, T> {
+        Levels { tree: self, level: self.root_id().into_iter().collect() }
+    }
+
+    /// Iterate over every node that has no children, in the order they were
+    /// added to the tree
+    pub fn leaves(&self) -> Leaves<'_, T> {
+        Leaves { tree: self, children: self.children.iter().enumerate() }
+    }
+}
+
+/// Pre-order depth first iterator over an `ITree`
+///
+/// Created with `ITree::iter_dfs`.
+#[derive(Debug)]
+pub struct Dfs<'a, T: 'a> {
+    tree: &'a ITree<T>,
+    stack: Vec<NodeId>,
+}
+
+impl<'a, T> Clone for Dfs<'a, T> {
+    fn clone(&self) -> Self {
+        Dfs { tree: self.tree, stack: self.stack.clone() }
+    }
+}
+
+impl<'a, T> Iterator for Dfs<'a, T> {
+    type Item = (NodeId, INodeRef<'a, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.stack.pop()?;
+        let node = self.tree.get(id).unwrap();
+        // Reversed so the first child is on top of the stack
+        self.stack.extend(node.children().iter().rev());
+        Some((id, node))
+    }
+}
+
+/// Breadth first (level order) iterator over an `ITree`
+///
+/// Created with `ITree::iter_bfs`.
+#[derive(Debug)]
+pub struct Bfs<'a, T: 'a> {
+    tree: &'a ITree<T>,
+    queue: VecDeque<NodeId>,
+}
+
+impl<'a, T> Clone for Bfs<'a, T> {
+    fn clone(&self) -> Self {
+        Bfs { tree: self.tree, queue: self.queue.clone() }
+    }
+}
+
+impl<'a, T> Iterator for Bfs<'a, T> {
+    type Item = (NodeId, INodeRef<'a, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.queue.pop_front()?;
+        let node = self.tree.get(id).unwrap();
+        self.queue.extend(node.children().iter());
+        Some((id, node))
+    }
+}
+
+/// Post-order depth first iterator over an `ITree`
+///
+/// Created with `ITree::iter_post_order`.
+#[derive(Debug)]
+pub struct PostOrder<'a, T: 'a> {
+    tree: &'a ITree<T>,
+    /// Nodes on the current path along with the index of the next child to
+    /// visit
+    stack: Vec<(NodeId, usize)>,
+}
+
+impl<'a, T> Clone for PostOrder<'a, T> {
+    fn clone(&self) -> Self {
+        PostOrder { tree: self.tree, stack: self.stack.clone() }
+    }
+}
+
+impl<'a, T> Iterator for PostOrder<'a, T> {
+    type Item = (NodeId, INodeRef<'a, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (id, next) = *self.stack.last()?;
+            let node = self.tree.get(id).unwrap();
+            match node.children().get(next) {
+                Some(&child) => {
+                    self.stack.last_mut().unwrap().1 += 1;
+                    self.stack.push((child, 0));
+                }
+                None => {
+                    self.stack.pop();
+                    return Some((id, node));
+                }
+            }
+        }
+    }
+}
+
+/// Pre-order depth first iterator over the top levels of an `ITree`
+///
+/// Created with `ITree::iter_to_depth`.
+#[derive(Debug)]
+pub struct ToDepth<'a, T: 'a> {
+    tree: &'a ITree<T>,
+    /// Nodes to visit along with their depth
+    stack: Vec<(NodeId, usize)>,
+    max_depth: usize,
+}
+
+impl<'a, T> Clone for ToDepth<'a, T> {
+    fn clone(&self) -> Self {
+        ToDepth { tree: self.tree, stack: self.stack.clone(), max_depth: self.max_depth }
+    }
+}
+
+impl<'a, T> Iterator for ToDepth<'a, T> {
+    type Item = (NodeId, INodeRef<'a, T>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (id, depth) = self.stack.pop()?;
+        let node = self.tree.get(id).unwrap();
+        if depth < self.max_depth {
+            self.stack.extend(node.children().iter().rev().map(|&child| (child, depth + 1)));
+        }
+        Some((id, node, depth))
+    }
+}
+
+/// Iterator over the levels of an `ITree`
+///
+/// Created with `ITree::levels`.
+#[derive(Debug)]
+pub struct Levels<'a, T: 'a> {
+    tree: &'a ITree<T>,
+    /// Nodes of the next level to yield
+    level: Vec<NodeId>,
+}
+
+impl<'a, T> Clone for Levels<'a, T> {
+    fn clone(&self) -> Self {
+        Levels { tree: self.tree, level: self.level.clone() }
+    }
+}
+
+impl<'a, T> Iterator for Levels<'a, T> {
+    type Item = Vec<NodeId>;
+
+    fn next(&mut self) -> Option<Vec<NodeId>> {
+        if self.level.is_empty() {
+            return None;
+        }
+        let tree = self.tree;
+        let below = self.level.iter().flat_map(|id| tree.children[id.index()].iter().cloned()).collect();
+        Some(mem::replace(&mut self.level, below))
+    }
+}
+
+/// Iterator over the ancestors of a node in an `ITree`
+///
+/// Created with `ITree::ancestors`.
+#[derive(Debug)]
+pub struct Ancestors<'a, T: 'a> {
+    tree: &'a ITree<T>,
+    next: Option<NodeId>,
+}
+
+impl<'a, T> Clone for Ancestors<'a, T> {
+    fn clone(&self) -> Self {
+        Ancestors { tree: self.tree, next: self.next }
+    }
+}
+
+impl<'a, T> Iterator for Ancestors<'a, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        let x = id.index();
+        self.next = self.tree.parents[x];
+        Some(id)
+    }
+}
+
+/// Iterator over the subtree of a node in an `ITree`
+///
+/// Created with `ITree::descendants`.
+#[derive(Debug)]
+pub struct Descendants<'a, T: 'a> {
+    dfs: Dfs<'a, T>,
+}
+
+impl<'a, T> Clone for Descendants<'a, T> {
+    fn clone(&self) -> Self {
+        Descendants { dfs: self.dfs.clone() }
+    }
+}
+
+impl<'a, T> Iterator for Descendants<'a, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        self.dfs.next().map(|(id, _)| id)
+    }
+}
+
+/// Iterator over the leaves of an `ITree`
+///
+/// Created with `ITree::leaves`.
+#[derive(Debug)]
+pub struct Leaves<'a, T: 'a> {
+    tree: &'a ITree<T>,
+    children: Enumerate<chunks::Iter<'a, Children>>,
+}
+
+impl<'a, T> Clone for Leaves<'a, T> {
+    fn clone(&self) -> Self {
+        Leaves { tree: self.tree, children: self.children.clone() }
+    }
+}
+
+impl<'a, T> Iterator for Leaves<'a, T> {
+    type Item = (NodeId, INodeRef<'a, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tree = self.tree;
+        self.children
+            .find(|&(_, children)| children.is_empty())
+            .map(|(x, _)| (NodeId::new(x), INodeRef { tree, x }))
+    }
+}
+
+#[test]
+fn dfs() {
+    let mut tree = ITree::new();
+    // 0 -> 1 -> 3
+    // |--> 2 -> 4
+    //      |--> 5
+    let root = tree.add_root(0).unwrap();
+    let one = tree.add_node(root, 1);
+    let two = tree.add_node(root, 2);
+    tree.add_node(one, 3);
+    tree.add_node(two, 4);
+    tree.add_node(two, 5);
+
+    let values: Vec<_> = tree.iter_dfs(root).map(|(_, n)| *n.value()).collect();
+    assert_eq!(values, vec![0, 1, 3, 2, 4, 5]);
+
+    let ids: Vec<_> = tree.iter_dfs(two).map(|(id, _)| id).collect();
+    assert_eq!(ids, vec![NodeId::new(2), NodeId::new(4), NodeId::new(5)]);
+
+    assert_eq!(tree.iter_dfs(NodeId::new(6)).count(), 0);
+
+    // Deep trees don't overflow the stack
+    let mut deep = ITree::new();
+    let mut node = deep.add_root(0).unwrap();
+    for i in 1..100_000 {
+        node = deep.add_node(node, i);
+    }
+    assert_eq!(deep.iter_dfs(NodeId::new(0)).count(), 100_000);
+}
+
+#[test]
+fn bfs() {
+    let mut tree = ITree::new();
+    // 0 -> 1 -> 3 -> 5
+    // |--> 2 -> 4
+    let root = tree.add_root(0).unwrap();
+    let one = tree.add_node(root, 1);
+    let two = tree.add_node(root, 2);
+    let three = tree.add_node(one, 3);
+    tree.add_node(two, 4);
+    tree.add_node(three, 5);
+
+    let values: Vec<_> = tree.iter_bfs(root).map(|(_, n)| *n.value()).collect();
+    assert_eq!(values, vec![0, 1, 2, 3, 4, 5]);
+
+    let values: Vec<_> = tree.iter_bfs(one).map(|(_, n)| *n.value()).collect();
+    assert_eq!(values, vec![1, 3, 5]);
+
+    assert_eq!(tree.iter_bfs(NodeId::new(6)).count(), 0);
+}
+
+#[test]
+fn post_order() {
+    let mut tree = ITree::new();
+    // 0 -> 1 -> 3
+    // |--> 2 -> 4
+    //      |--> 5
+    let root = tree.add_root(0).unwrap();
+    let one = tree.add_node(root, 1);
+    let two = tree.add_node(root, 2);
+    tree.add_node(one, 3);
+    tree.add_node(two, 4);
+    tree.add_node(two, 5);
+
+    let values: Vec<_> = tree.iter_post_order(root).map(|(_, n)| *n.value()).collect();
+    assert_eq!(values, vec![3, 1, 4, 5, 2, 0]);
+
+    let values: Vec<_> = tree.iter_post_order(two).map(|(_, n)| *n.value()).collect();
+    assert_eq!(values, vec![4, 5, 2]);
+
+    assert_eq!(tree.iter_post_order(NodeId::new(6)).count(), 0);
+}
+
+#[test]
+fn ancestors() {
+    let mut tree = ITree::new();
+    // 0 -> 1 -> 2 -> 3
+    // |--> 4
+    let root = tree.add_root(0).unwrap();
+    let one = tree.add_node(root, 1);
+    let two = tree.add_node(one, 2);
+    let three = tree.add_node(two, 3);
+    let four = tree.add_node(root, 4);
+
+    assert_eq!(tree.ancestors(three).collect::<Vec<_>>(), vec![two, one, root]);
+    assert_eq!(tree.ancestors(four).collect::<Vec<_>>(), vec![root]);
+    assert_eq!(tree.ancestors(root).count(), 0);
+    assert_eq!(tree.ancestors(NodeId::new(5)).count(), 0);
+}
+
+#[test]
+fn descendants() {
+    let mut tree = ITree::new();
+    // 0 -> 1 -> 3
+    // |    |--> 4
+    // |--> 2
+    let root = tree.add_root(0).unwrap();
+    let one = tree.add_node(root, 1);
+    let two = tree.add_node(root, 2);
+    let three = tree.add_node(one, 3);
+    let four = tree.add_node(one, 4);
+
+    assert_eq!(tree.descendants(root).collect::<Vec<_>>(), vec![root, one, three, four, two]);
+    assert_eq!(tree.descendants(one).collect::<Vec<_>>(), vec![one, three, four]);
+    assert_eq!(tree.descendants(two).collect::<Vec<_>>(), vec![two]);
+    assert_eq!(tree.descendants(NodeId::new(5)).count(), 0);
+}
+
+#[test]
+fn to_depth() {
+    // 0 -> 1 -> 2 -> 3
+    // |    |--> 4
+    // |--> 5
+    let tree = tree!{ 0 => [1 => [2 => [3], 4], 5] };
+    let top: Vec<_> = tree.iter_to_depth(NodeId::new(0), 1).map(|(_, n, d)| (*n.value(), d)).collect();
+    assert_eq!(top, vec![(0, 0), (1, 1), (5, 1)]);
+    let below: Vec<_> = tree.iter_to_depth(NodeId::new(1), 1).map(|(_, n, d)| (*n.value(), d)).collect();
+    assert_eq!(below, vec![(1, 0), (2, 1), (4, 1)]);
+    assert_eq!(tree.iter_to_depth(NodeId::new(0), 0).count(), 1);
+    assert_eq!(tree.iter_to_depth(NodeId::new(0), 10).count(), 6);
+    assert_eq!(tree.iter_to_depth(NodeId::new(6), 10).count(), 0);
+}
+
+#[test]
+fn levels() {
+    // 0 -> 1 -> 2 -> 3
+    // |    |--> 4
+    // |--> 5 -> 6
+    let tree = tree!{ 0 => [1 => [2 => [3], 4], 5 => [6]] };
+    let levels: Vec<Vec<_>> = tree.levels().map(|level| level.iter().map(|&id| tree[id]).collect()).collect();
+    assert_eq!(levels, vec![vec![0], vec![1, 5], vec![2, 4, 6], vec![3]]);
+    assert_eq!(ITree::<u8>::new().levels().count(), 0);
+}
+
+#[test]
+fn leaves() {
+    let mut tree = ITree::new();
+    assert_eq!(tree.leaves().count(), 0);
+    // 0 -> 1 -> 3
+    // |--> 2 -> 4
+    //      |--> 5
+    let root = tree.add_root(0).unwrap();
+    assert_eq!(tree.leaves().map(|(id, _)| id).collect::<Vec<_>>(), vec![root]);
+    let one = tree.add_node(root, 1);
+    let two = tree.add_node(root, 2);
+    tree.add_node(one, 3);
+    tree.add_node(two, 4);
+    tree.add_node(two, 5);
+
+    let values: Vec<_> = tree.leaves().map(|(_, n)| *n.value()).collect();
+    assert_eq!(values, vec![3, 4, 5]);
+}
@@ -0,0 +1,482 @@
+use core::mem;
+
+use alloc::vec::Vec;
+
+use {Children, GroveError, INode, ITree, MTree, SlotId};
+
+/// Journaled Tree
+///
+/// Wraps an `MTree` and records every insertion, removal, move and value
+/// update made through it, so they can be undone and redone again. Undoing a change puts
+/// the tree back exactly how it was before, including the `SlotId`s of nodes
+/// that come back.
+///
+/// Making a new change after undoing some discards the changes that could
+/// have been redone.
+#[derive(Debug, Clone)]
+pub struct Journal<T> {
+    tree: MTree<T>,
+    done: Vec<Edit<T>>,
+    undone: Vec<Edit<T>>,
+}
+
+/// A change to the tree. Values that aren't in the tree at the moment are
+/// kept in the change.
+#[derive(Debug, Clone)]
+enum Edit<T> {
+    Insert {
+        id: SlotId,
+        parent: Option<SlotId>,
+        value: Option<T>,
+    },
+    Remove {
+        id: SlotId,
+        parent: Option<SlotId>,
+        /// Position of the node among its siblings
+        pos: usize,
+        children: Children<SlotId>,
+        was_root: bool,
+        value: Option<T>,
+    },
+    RemoveSubtree {
+        id: SlotId,
+        parent: Option<SlotId>,
+        pos: usize,
+        was_root: bool,
+        /// Ids of the removed nodes in pre-order, the order the removed tree
+        /// numbers them in
+        ids: Vec<SlotId>,
+        tree: Option<ITree<T>>,
+    },
+    Detach {
+        id: SlotId,
+        parent: Option<SlotId>,
+        pos: usize,
+        was_root: bool,
+    },
+    Attach {
+        id: SlotId,
+        parent: SlotId,
+    },
+    Reroot {
+        id: SlotId,
+        /// Ancestors of the node from its parent up, each with the position of
+        /// the node below it among its children
+        path: Vec<(SlotId, usize)>,
+    },
+    Set {
+        id: SlotId,
+        value: T,
+    },
+}
+
+impl<T> Journal<T> {
+    /// Create a new empty tree with an empty journal
+    pub fn new() -> Self {
+        Journal::from(MTree::new())
+    }
+
+    /// Get the tree as it is right now
+    pub fn tree(&self) -> &MTree<T> {
+        &self.tree
+    }
+
+    /// Take the tree out of the journal, dropping the history
+    pub fn into_tree(self) -> MTree<T> {
+        self.tree
+    }
+
+    /// Check if there is a change to undo
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    /// Check if there is an undone change to redo
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Forget every recorded change, keeping the tree as it is
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+
+    /// Adds the root node to an empty tree like `MTree::add_root`
+    pub fn add_root(&mut self, value: T) -> Result<SlotId, GroveError> {
+        let id = self.tree.add_root(value)?;
+        self.record(Edit::Insert { id, parent: None, value: None });
+        Ok(id)
+    }
+
+    /// Adds a child to a node like `MTree::add_node`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `SlotId` does not refer to a node in the tree. See
+    /// `try_add_node` for a version that returns an error instead.
+    pub fn add_node(&mut self, node: SlotId, value: T) -> SlotId {
+        match self.try_add_node(node, value) {
+            Ok(id) => id,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Adds a child to a node like `MTree::try_add_node`
+    pub fn try_add_node(&mut self, node: SlotId, value: T) -> Result<SlotId, GroveError> {
+        let id = self.tree.try_add_node(node, value)?;
+        let parent = self.tree.get(id).unwrap().parent();
+        self.record(Edit::Insert { id, parent, value: None });
+        Ok(id)
+    }
+
+    /// Removes a single node like `MTree::remove_node`. The journal keeps a
+    /// clone of the value to bring the node back.
+    pub fn remove_node(&mut self, node: SlotId) -> Result<T, GroveError>
+        where T: Clone
+    {
+        let (parent, pos, was_root) = self.place(node)?;
+        let children = Children::<SlotId>::from(self.tree.get(node).unwrap().children());
+        let value = self.tree.remove_node(node)?;
+        self.record(Edit::Remove { id: node, parent, pos, children, was_root, value: Some(value.clone()) });
+        Ok(value)
+    }
+
+    /// Removes a node along with its descendants like `MTree::remove_subtree`.
+    /// The journal keeps a clone of the removed tree to bring the nodes back.
+    pub fn remove_subtree(&mut self, node: SlotId) -> Result<ITree<T>, GroveError>
+        where T: Clone
+    {
+        let (parent, pos, was_root) = self.place(node)?;
+        let mut ids = Vec::new();
+        let mut stack = vec![node];
+        while let Some(id) = stack.pop() {
+            ids.push(id);
+            stack.extend(self.tree.get(id).unwrap().children().iter().rev());
+        }
+        let tree = self.tree.remove_subtree(node)?;
+        self.record(Edit::RemoveSubtree { id: node, parent, pos, was_root, ids, tree: Some(tree.clone()) });
+        Ok(tree)
+    }
+
+    /// Detaches a node from its parent like `MTree::detach`
+    pub fn detach(&mut self, node: SlotId) -> Result<(), GroveError> {
+        let (parent, pos, was_root) = self.place(node)?;
+        self.tree.detach(node)?;
+        self.record(Edit::Detach { id: node, parent, pos, was_root });
+        Ok(())
+    }
+
+    /// Attaches a detached node to a parent like `MTree::attach`
+    pub fn attach(&mut self, parent: SlotId, node: SlotId) -> Result<(), GroveError> {
+        self.tree.attach(parent, node)?;
+        self.record(Edit::Attach { id: node, parent });
+        Ok(())
+    }
+
+    /// Makes a node the root of its tree like `MTree::reroot`. Undoing it puts
+    /// every node back in its old place among its siblings.
+    pub fn reroot(&mut self, new_root: SlotId) -> Result<(), GroveError> {
+        let mut path = Vec::new();
+        let mut below = new_root;
+        while let Some(parent) = self.tree.get(below).and_then(|n| n.parent()) {
+            path.push((parent, self.position(parent, below)));
+            below = parent;
+        }
+        self.tree.reroot(new_root)?;
+        self.record(Edit::Reroot { id: new_root, path });
+        Ok(())
+    }
+
+    /// Replace the value of a node like `MTree::set`, returning the old value.
+    /// The journal keeps a clone of it to put it back.
+    pub fn set(&mut self, node: SlotId, value: T) -> Result<T, GroveError>
+        where T: Clone
+    {
+        let old = self.tree.set(node, value)?;
+        self.record(Edit::Set { id: node, value: old.clone() });
+        Ok(old)
+    }
+
+    /// Undo the last change that hasn't been undone yet. Returns `false` if
+    /// there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.done.pop() {
+            Some(mut edit) => {
+                self.revert(&mut edit);
+                self.undone.push(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redo the last undone change. Returns `false` if there is nothing to
+    /// redo.
+    pub fn redo(&mut self) -> bool {
+        match self.undone.pop() {
+            Some(mut edit) => {
+                self.apply(&mut edit);
+                self.done.push(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn record(&mut self, edit: Edit<T>) {
+        self.undone.clear();
+        self.done.push(edit);
+    }
+
+    /// Get the parent of a node, its position among its siblings and whether
+    /// it is the root of the tree
+    fn place(&self, node: SlotId) -> Result<(Option<SlotId>, usize, bool), GroveError> {
+        let parent = match self.tree.get(node) {
+            Some(n) => n.parent(),
+            None => return Err(GroveError::InvalidNodeId(node.id())),
+        };
+        let pos = parent.map_or(0, |parent| self.position(parent, node));
+        Ok((parent, pos, self.tree.root_id() == Some(node)))
+    }
+
+    fn position(&self, parent: SlotId, child: SlotId) -> usize {
+        self.tree.get(parent).unwrap().children().iter().position(|&c| c == child).unwrap()
+    }
+
+    /// Put a detached node back at `pos` among the children of its old parent
+    fn relink(&mut self, id: SlotId, parent: Option<SlotId>, pos: usize, was_root: bool) {
+        if let Some(parent) = parent {
+            self.tree.inode_mut(parent).unwrap().children.insert(pos, id);
+        }
+        self.tree.inode_mut(id).unwrap().parent = parent;
+        if was_root {
+            self.tree.set_root(Some(id));
+        }
+    }
+
+    /// Take back a change that was made to the tree
+    fn revert(&mut self, edit: &mut Edit<T>) {
+        match *edit {
+            // Changes after the insertion have been undone, so the node is a
+            // leaf again
+            Edit::Insert { id, ref mut value, .. } => {
+                *value = Some(self.tree.remove_node(id).unwrap());
+            }
+            Edit::Remove { id, parent, pos, ref children, was_root, ref mut value } => {
+                let inode = INode {
+                    value: value.take().unwrap(),
+                    parent,
+                    children: children.clone(),
+                };
+                self.tree.restore(Some((id, inode)));
+                for &child in children {
+                    self.tree.inode_mut(child).unwrap().parent = Some(id);
+                }
+                if let Some(parent) = parent {
+                    let siblings = &mut self.tree.inode_mut(parent).unwrap().children;
+                    for _ in 0..children.len() {
+                        siblings.remove(pos);
+                    }
+                    siblings.insert(pos, id);
+                }
+                if was_root {
+                    self.tree.set_root(Some(id));
+                }
+            }
+            Edit::RemoveSubtree { id, parent, pos, was_root, ref ids, ref mut tree } => {
+                let tree = tree.take().unwrap();
+                let nodes = tree.values
+                    .into_iter()
+                    .zip(tree.parents)
+                    .zip(tree.children)
+                    .zip(ids)
+                    .map(|(((value, parent), children), &slot)| {
+                        let inode = INode {
+                            value,
+                            parent: parent.map(|p| ids[p.index()]),
+                            children: children.iter().map(|c| ids[c.index()]).collect(),
+                        };
+                        (slot, inode)
+                    });
+                self.tree.restore(nodes);
+                self.relink(id, parent, pos, was_root);
+            }
+            Edit::Detach { id, parent, pos, was_root } => self.relink(id, parent, pos, was_root),
+            // Later attachments have been undone, so the node is the last child
+            Edit::Attach { id, .. } => self.tree.detach(id).unwrap(),
+            Edit::Reroot { id, ref path } => {
+                let top = match path.last() {
+                    Some(&(top, _)) => top,
+                    None => return,
+                };
+                // Rerooting back at the old top makes every node on the path
+                // the last child of the one below it, which moves them back to
+                // where they were
+                self.tree.reroot(top).unwrap();
+                for (i, &(parent, pos)) in path.iter().enumerate() {
+                    let below = if i == 0 { id } else { path[i - 1].0 };
+                    let children = &mut self.tree.inode_mut(parent).unwrap().children;
+                    children.pop();
+                    children.insert(pos, below);
+                }
+            }
+            Edit::Set { id, ref mut value } => {
+                mem::swap(self.tree.get_mut(id).unwrap(), value);
+            }
+        }
+    }
+
+    /// Make a change to the tree again after it was reverted
+    fn apply(&mut self, edit: &mut Edit<T>) {
+        match *edit {
+            Edit::Insert { id, parent, ref mut value } => {
+                let inode = INode::new(value.take().unwrap(), parent);
+                self.tree.restore(Some((id, inode)));
+                // Later insertions have been undone, so the node goes last
+                match parent {
+                    Some(parent) => self.tree.inode_mut(parent).unwrap().insert(id),
+                    None => self.tree.set_root(Some(id)),
+                }
+            }
+            Edit::Remove { id, ref mut value, .. } => {
+                *value = Some(self.tree.remove_node(id).unwrap());
+            }
+            Edit::RemoveSubtree { id, ref mut tree, .. } => {
+                *tree = Some(self.tree.remove_subtree(id).unwrap());
+            }
+            Edit::Detach { id, .. } => self.tree.detach(id).unwrap(),
+            Edit::Attach { id, parent } => self.tree.attach(parent, id).unwrap(),
+            Edit::Reroot { id, .. } => self.tree.reroot(id).unwrap(),
+            Edit::Set { id, ref mut value } => {
+                mem::swap(self.tree.get_mut(id).unwrap(), value);
+            }
+        }
+    }
+}
+
+impl<T> Default for Journal<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<MTree<T>> for Journal<T> {
+    /// Start recording the changes made to an existing tree
+    fn from(tree: MTree<T>) -> Self {
+        Journal { tree, done: Vec::new(), undone: Vec::new() }
+    }
+}
+
+#[test]
+fn undo_redo() {
+    use NodeId;
+
+    let mut journal = Journal::new();
+    // 0 -> 1 -> 3
+    // |--> 2
+    let root = journal.add_root(0).unwrap();
+    let one = journal.add_node(root, 1);
+    let two = journal.add_node(root, 2);
+    let three = journal.add_node(one, 3);
+    assert_eq!(journal.set(two, 20), Ok(2));
+    // 0 -> 3
+    // |--> 2
+    assert_eq!(journal.remove_node(one), Ok(1));
+    assert_eq!(journal.tree().root().unwrap().children(), &[three, two]);
+    assert!(!journal.can_redo());
+
+    // Removing the node comes undone with the same id in the same place
+    assert!(journal.undo());
+    assert_eq!(journal.tree().root().unwrap().children(), &[one, two]);
+    assert_eq!(journal.tree().get(three).unwrap().parent(), Some(one));
+    assert_eq!(journal.tree()[one], 1);
+    assert!(journal.undo());
+    assert_eq!(journal.tree()[two], 2);
+
+    // Undo everything, then bring it all back
+    while journal.undo() {}
+    assert!(journal.tree().is_empty());
+    assert!(journal.tree().get(root).is_none());
+    while journal.redo() {}
+    assert_eq!(journal.tree().len(), 3);
+    assert_eq!(journal.tree().root().unwrap().children(), &[three, two]);
+    assert_eq!(journal.tree()[two], 20);
+
+    // A new change drops what could have been redone
+    journal.undo();
+    let four = journal.add_node(root, 4);
+    assert!(!journal.can_redo());
+    assert!(!journal.redo());
+    assert_eq!(journal.tree().get(four).unwrap().parent(), Some(root));
+    assert_eq!(journal.remove_node(SlotId::new(9, 0)), Err(GroveError::InvalidNodeId(NodeId::new(9))));
+}
+
+#[test]
+fn undo_moves() {
+    let id = |x| SlotId::new(x, 0);
+    // The parent, children and place among its siblings of every node
+    let shape = |journal: &Journal<i32>| {
+        let tree = journal.tree();
+        let nodes = (0..5).map(|x| tree.get(id(x)).map(|n| (n.parent(), n.children().to_vec(), *n.value())));
+        (nodes.collect::<Vec<_>>(), tree.root_id())
+    };
+    // 0 -> 1 -> 2
+    // |    |--> 3
+    // |--> 4
+    let mut journal = Journal::from(MTree::from(tree!{ 0 => [1 => [2, 3], 4] }));
+    let before = shape(&journal);
+
+    journal.reroot(id(2)).unwrap();
+    assert_eq!(journal.tree().root_id(), Some(id(2)));
+    assert!(journal.undo());
+    assert_eq!(shape(&journal), before);
+    assert!(journal.redo());
+    assert_eq!(journal.tree().get(id(1)).unwrap().children(), &[id(3), id(0)]);
+    journal.undo();
+
+    journal.detach(id(1)).unwrap();
+    journal.attach(id(4), id(1)).unwrap();
+    assert_eq!(journal.tree().get(id(1)).unwrap().parent(), Some(id(4)));
+    journal.undo();
+    journal.undo();
+    assert_eq!(shape(&journal), before);
+
+    let removed = journal.remove_subtree(id(1)).unwrap();
+    assert!(removed.is_identical(&tree!{ 1 => [2, 3] }));
+    assert_eq!(journal.tree().len(), 2);
+    // Bring the nodes back after their slots were taken once more
+    journal.undo();
+    assert_eq!(shape(&journal), before);
+    journal.redo();
+    assert!(journal.tree().get(id(3)).is_none());
+    journal.undo();
+    assert_eq!(shape(&journal), before);
+    assert_eq!(journal.detach(id(9)), Err(GroveError::InvalidNodeId(id(9).id())));
+
+    // Nodes still get added under their parent after the root is detached
+    journal.detach(id(0)).unwrap();
+    let five = journal.add_node(id(1), 5);
+    assert_eq!(journal.tree().get(five).unwrap().parent(), Some(id(1)));
+    assert_eq!(journal.tree().root_id(), None);
+}
+
+#[test]
+fn stale_ids() {
+    let mut journal = Journal::from(MTree::from(tree!{ 0 => [1] }));
+    let (root, a) = (SlotId::new(0, 0), SlotId::new(1, 0));
+    journal.remove_node(a).unwrap();
+    let c = journal.add_node(root, 2);
+    assert_eq!(c.index(), a.index());
+    journal.undo();
+    journal.undo();
+    assert_eq!(journal.tree()[a], 1);
+
+    // The slot comes around again, but the id of the undone node stays stale
+    journal.remove_node(a).unwrap();
+    let d = journal.add_node(root, 3);
+    assert_eq!(d.index(), c.index());
+    assert_ne!(d, c);
+    assert!(journal.tree().get(c).is_none());
+    assert_eq!(journal.tree()[d], 3);
+}
@@ -0,0 +1,308 @@
+use core::fmt;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Number, Value};
+
+use {ITree, NodeId};
+
+/// Node of a JSON document in an `ITree`
+///
+/// Arrays and objects become nodes whose children are their items, and every
+/// node but the root keeps the key or index it has in its parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonNode {
+    edge: Option<JsonEdge>,
+    kind: JsonKind,
+}
+
+/// Place of a JSON value in its parent
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum JsonEdge {
+    /// The key of a value in an object
+    Key(String),
+    /// The index of a value in an array
+    Index(usize),
+}
+
+/// What a JSON value is, without the items of arrays and objects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonKind {
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(String),
+    Array,
+    Object,
+}
+
+impl JsonNode {
+    pub fn new(edge: Option<JsonEdge>, kind: JsonKind) -> Self {
+        JsonNode { edge, kind }
+    }
+
+    /// Get the key or index of the node in its parent, `None` for the root
+    pub fn edge(&self) -> Option<&JsonEdge> {
+        self.edge.as_ref()
+    }
+
+    pub fn kind(&self) -> &JsonKind {
+        &self.kind
+    }
+}
+
+impl ITree<JsonNode> {
+    /// Build a tree out of a JSON value, with the items of every array and
+    /// object as children in the order they come in
+    ///
+    /// Without its `preserve_order` feature a `serde_json::Map` sorts its keys,
+    /// so the order of the document is already gone. Use `from_json_str` to
+    /// keep it.
+    pub fn from_json(value: Value) -> Self {
+        let mut tree = ITree::new();
+        let mut stack = vec![(None, value, None)];
+        while let Some((edge, value, parent)) = stack.pop() {
+            let (kind, items): (_, Vec<_>) = match value {
+                Value::Null => (JsonKind::Null, Vec::new()),
+                Value::Bool(b) => (JsonKind::Bool(b), Vec::new()),
+                Value::Number(n) => (JsonKind::Number(n), Vec::new()),
+                Value::String(s) => (JsonKind::String(s), Vec::new()),
+                Value::Array(items) => {
+                    (JsonKind::Array, items.into_iter().enumerate().map(|(i, v)| (JsonEdge::Index(i), v)).collect())
+                }
+                Value::Object(items) => {
+                    (JsonKind::Object, items.into_iter().map(|(k, v)| (JsonEdge::Key(k), v)).collect())
+                }
+            };
+            let node = JsonNode { edge, kind };
+            let id = match parent {
+                Some(parent) => tree.add_node(parent, node),
+                None => tree.add_root(node).unwrap(),
+            };
+            stack.extend(items.into_iter().rev().map(|(edge, value)| (Some(edge), value, Some(id))));
+        }
+        tree
+    }
+
+    /// Turn the tree back into a JSON value, `Null` if the tree is empty
+    ///
+    /// Arrays take the items in the order of the children. Objects take the
+    /// keys of their children, and the position of a child without one. The
+    /// keys end up in the order `serde_json::Map` keeps them in, which is
+    /// sorted without its `preserve_order` feature. Use `to_json_string` to
+    /// keep the order of the children.
+    pub fn to_json(&self) -> Value {
+        let root = match self.root_id() {
+            Some(root) => root,
+            None => return Value::Null,
+        };
+        // Values of the nodes whose parents haven't been reached yet
+        let mut done: Vec<Option<Value>> = vec![None; self.len()];
+        for (id, node) in self.iter_post_order(root) {
+            let items = node.children().iter().map(|&c: &NodeId| done[c.index()].take().unwrap());
+            let value = match node.value().kind {
+                JsonKind::Null => Value::Null,
+                JsonKind::Bool(b) => Value::Bool(b),
+                JsonKind::Number(ref n) => Value::Number(n.clone()),
+                JsonKind::String(ref s) => Value::String(s.clone()),
+                JsonKind::Array => Value::Array(items.collect()),
+                JsonKind::Object => {
+                    let keys = node.children().iter().enumerate().map(|(i, &c)| self.key(i, c));
+                    let map: Map<String, Value> = keys.zip(items).collect();
+                    Value::Object(map)
+                }
+            };
+            done[id.index()] = Some(value);
+        }
+        done[root.index()].take().unwrap()
+    }
+
+    /// Parse a JSON document straight into a tree like `from_json`, keeping
+    /// the keys of every object in the order of the document
+    pub fn from_json_str(json: &str) -> Result<Self, serde_json::Error> {
+        let mut tree = ITree::new();
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        Seed { tree: &mut tree, edge: None, parent: None }.deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(tree)
+    }
+
+    /// Write the tree as a JSON document like `to_json`, with the keys of
+    /// every object in the order of the children. An empty tree is `null`.
+    pub fn to_json_string(&self) -> String {
+        match self.root_id() {
+            Some(root) => serde_json::to_string(&JsonRef { tree: self, node: root }).unwrap(),
+            None => "null".to_string(),
+        }
+    }
+
+    /// Get the key of the `i`th child of an object
+    fn key(&self, i: usize, child: NodeId) -> String {
+        match self[child].edge {
+            Some(JsonEdge::Key(ref key)) => key.clone(),
+            _ => i.to_string(),
+        }
+    }
+}
+
+/// Adds the JSON value it reads to a tree, below `parent`
+struct Seed<'a> {
+    tree: &'a mut ITree<JsonNode>,
+    edge: Option<JsonEdge>,
+    parent: Option<NodeId>,
+}
+
+impl<'a> Seed<'a> {
+    /// Add the node, handing back the tree to add its items to
+    fn add(self, kind: JsonKind) -> (&'a mut ITree<JsonNode>, NodeId) {
+        let node = JsonNode { edge: self.edge, kind };
+        let id = match self.parent {
+            Some(parent) => self.tree.add_node(parent, node),
+            None => self.tree.add_root(node).unwrap(),
+        };
+        (self.tree, id)
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for Seed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for Seed<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        self.add(JsonKind::Null);
+        Ok(())
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<(), E> {
+        self.add(JsonKind::Bool(b));
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<(), E> {
+        self.add(JsonKind::Number(n.into()));
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<(), E> {
+        self.add(JsonKind::Number(n.into()));
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, n: f64) -> Result<(), E> {
+        // Like `serde_json::Value`, numbers JSON can't hold become null
+        self.add(Number::from_f64(n).map_or(JsonKind::Null, JsonKind::Number));
+        Ok(())
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<(), E> {
+        self.add(JsonKind::String(s.to_string()));
+        Ok(())
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<(), E> {
+        self.add(JsonKind::String(s));
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let (tree, id) = self.add(JsonKind::Array);
+        let mut i = 0;
+        while seq.next_element_seed(Seed { tree: &mut *tree, edge: Some(JsonEdge::Index(i)), parent: Some(id) })?.is_some() {
+            i += 1;
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let (tree, id) = self.add(JsonKind::Object);
+        while let Some(key) = map.next_key::<String>()? {
+            map.next_value_seed(Seed { tree: &mut *tree, edge: Some(JsonEdge::Key(key)), parent: Some(id) })?;
+        }
+        Ok(())
+    }
+}
+
+/// Node of a tree that serializes as the JSON value of its subtree
+struct JsonRef<'a> {
+    tree: &'a ITree<JsonNode>,
+    node: NodeId,
+}
+
+impl<'a> Serialize for JsonRef<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let tree = self.tree;
+        let node = tree.get(self.node).unwrap();
+        let items = node.children().iter().map(|&c| JsonRef { tree, node: c });
+        match node.value().kind {
+            JsonKind::Null => serializer.serialize_unit(),
+            JsonKind::Bool(b) => serializer.serialize_bool(b),
+            JsonKind::Number(ref n) => n.serialize(serializer),
+            JsonKind::String(ref s) => serializer.serialize_str(s),
+            JsonKind::Array => serializer.collect_seq(items),
+            JsonKind::Object => {
+                let keys = node.children().iter().enumerate().map(|(i, &c)| tree.key(i, c));
+                serializer.collect_map(keys.zip(items))
+            }
+        }
+    }
+}
+
+#[test]
+fn json() {
+    let json = r#"{"name":"grove","tags":["tree",null],"stars":{"count":3.5,"ok":true}}"#;
+    let value: Value = serde_json::from_str(json).unwrap();
+    let tree = ITree::from_json(value.clone());
+    assert_eq!(tree.len(), 8);
+    let key = |k: &str| Some(JsonEdge::Key(k.to_string()));
+    let nodes: Vec<_> = tree
+        .iter_dfs(tree.root_id().unwrap())
+        .map(|(_, n)| (n.value().edge().cloned(), n.value().kind().clone()))
+        .collect();
+    // A `Value` sorts its keys, so "stars" comes before "tags"
+    assert_eq!(nodes, vec![
+        (None, JsonKind::Object),
+        (key("name"), JsonKind::String("grove".to_string())),
+        (key("stars"), JsonKind::Object),
+        (key("count"), JsonKind::Number(Number::from_f64(3.5).unwrap())),
+        (key("ok"), JsonKind::Bool(true)),
+        (key("tags"), JsonKind::Array),
+        (Some(JsonEdge::Index(0)), JsonKind::String("tree".to_string())),
+        (Some(JsonEdge::Index(1)), JsonKind::Null),
+    ]);
+    assert_eq!(tree.to_json(), value);
+    assert_eq!(ITree::new().to_json(), Value::Null);
+    assert_eq!(ITree::from_json(Value::Bool(false)).to_json(), Value::Bool(false));
+}
+
+#[test]
+fn json_key_order() {
+    let json = r#"{"zeta":[1,{"y":null,"x":"a"}],"alpha":-2.5,"mid":true}"#;
+    let tree = ITree::from_json_str(json).unwrap();
+    let keys: Vec<_> = tree
+        .iter_dfs(tree.root_id().unwrap())
+        .filter_map(|(_, n)| match n.value().edge() {
+            Some(JsonEdge::Key(key)) => Some(key.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(keys, ["zeta", "y", "x", "alpha", "mid"]);
+    assert_eq!(tree.to_json_string(), json);
+    assert_eq!(tree.to_json(), serde_json::from_str::<Value>(json).unwrap());
+    assert!(ITree::from_json_str(r#"{"a":[1,"#).is_err());
+    assert!(ITree::from_json_str("1 2").is_err());
+    assert_eq!(ITree::new().to_json_string(), "null");
+}
@@ -0,0 +1,264 @@
+use core::cmp::Ordering;
+use core::iter::FromIterator;
+
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+
+/// k-d Tree
+///
+/// A binary tree over points in `D` dimensions, where every node splits the
+/// space below it in two along one axis, cycling through the axes on the way
+/// down. Range searches and nearest neighbor queries skip every subtree whose
+/// side of a split can't hold anything they are looking for.
+///
+/// `build` puts the median of every subtree at its top so the tree starts out
+/// balanced, while `insert` adds points as leaves without rebalancing.
+/// Distances are squared Euclidean distances.
+#[derive(Debug, Clone)]
+pub struct KdTree<const D: usize, V> {
+    nodes: Vec<KdNode<D, V>>,
+    root: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct KdNode<const D: usize, V> {
+    point: [f64; D],
+    value: V,
+    /// Axis the node splits its subtree along
+    axis: usize,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+impl<const D: usize, V> KdTree<D, V> {
+    /// Create a new empty `KdTree`
+    pub fn new() -> Self {
+        assert!(D > 0, "points need at least one dimension");
+        KdTree { nodes: Vec::new(), root: None }
+    }
+
+    /// Build a balanced tree out of a list of points
+    pub fn build(mut points: Vec<([f64; D], V)>) -> Self {
+        assert!(D > 0, "points need at least one dimension");
+        // Partition the points in place so the node of every subtree ends up
+        // in the middle of the points of the subtree
+        let mut links = vec![(0, None, None); points.len()];
+        let root = partition(&mut points, 0, 0, &mut links);
+        let nodes = points
+            .into_iter()
+            .zip(links)
+            .map(|((point, value), (axis, left, right))| KdNode { point, value, axis, left, right })
+            .collect();
+        KdTree { nodes, root }
+    }
+
+    /// Get the number of points in the tree
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Add a point as a new leaf
+    pub fn insert(&mut self, point: [f64; D], value: V) {
+        let x = self.nodes.len();
+        let mut axis = 0;
+        if let Some(mut n) = self.root {
+            loop {
+                let node = &mut self.nodes[n];
+                let next = if point[node.axis] < node.point[node.axis] { &mut node.left } else { &mut node.right };
+                match *next {
+                    Some(child) => n = child,
+                    None => {
+                        *next = Some(x);
+                        axis = (node.axis + 1) % D;
+                        break;
+                    }
+                }
+            }
+        } else {
+            self.root = Some(x);
+        }
+        self.nodes.push(KdNode { point, value, axis, left: None, right: None });
+    }
+
+    /// Iterate over every point inside a box, from `min` to `max` on every
+    /// axis with both ends included
+    pub fn range<'a>(&'a self, min: &'a [f64; D], max: &'a [f64; D]) -> KdRange<'a, D, V> {
+        KdRange { tree: self, min, max, stack: self.root.into_iter().collect() }
+    }
+
+    /// Find the point closest to `target` along with its squared distance
+    pub fn nearest(&self, target: &[f64; D]) -> Option<(f64, &[f64; D], &V)> {
+        self.knn(target, 1).pop()
+    }
+
+    /// Find the `k` points closest to `target`, closest first, along with
+    /// their squared distances
+    pub fn knn(&self, target: &[f64; D], k: usize) -> Vec<(f64, &[f64; D], &V)> {
+        let mut best = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            self.search(self.root, target, k, &mut best);
+        }
+        best.into_sorted_vec()
+            .into_iter()
+            .map(|c| (c.distance, &self.nodes[c.node].point, &self.nodes[c.node].value))
+            .collect()
+    }
+
+    /// Look for closer points below `x`, keeping the `k` closest ones found
+    /// so far in a heap with the farthest one on top
+    fn search(&self, x: Option<usize>, target: &[f64; D], k: usize, best: &mut BinaryHeap<Candidate>) {
+        let x = match x {
+            Some(x) => x,
+            None => return,
+        };
+        let node = &self.nodes[x];
+        let distance = node.point.iter().zip(target).map(|(a, b)| (a - b) * (a - b)).sum();
+        best.push(Candidate { distance, node: x });
+        if best.len() > k {
+            best.pop();
+        }
+
+        let offset = target[node.axis] - node.point[node.axis];
+        let (near, far) = if offset < 0.0 { (node.left, node.right) } else { (node.right, node.left) };
+        self.search(near, target, k, best);
+        // Points on the other side are at least as far as the split itself
+        if best.len() < k || offset * offset < best.peek().unwrap().distance {
+            self.search(far, target, k, best);
+        }
+    }
+}
+
+/// Arrange points into the subtree of a node on `axis` and return the node
+fn partition<const D: usize, V>(
+    points: &mut [([f64; D], V)],
+    start: usize,
+    axis: usize,
+    links: &mut [(usize, Option<usize>, Option<usize>)],
+) -> Option<usize> {
+    if points.is_empty() {
+        return None;
+    }
+    let mid = points.len() / 2;
+    points.select_nth_unstable_by(mid, |a, b| a.0[axis].total_cmp(&b.0[axis]));
+    let (left, rest) = points.split_at_mut(mid);
+    let next = (axis + 1) % D;
+    let left = partition(left, start, next, links);
+    let right = partition(&mut rest[1..], start + mid + 1, next, links);
+    links[start + mid] = (axis, left, right);
+    Some(start + mid)
+}
+
+impl<const D: usize, V> Default for KdTree<D, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const D: usize, V> FromIterator<([f64; D], V)> for KdTree<D, V> {
+    fn from_iter<I: IntoIterator<Item = ([f64; D], V)>>(iter: I) -> Self {
+        KdTree::build(iter.into_iter().collect())
+    }
+}
+
+/// Point found by a nearest neighbor search
+struct Candidate {
+    distance: f64,
+    node: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance.total_cmp(&other.distance).then(self.node.cmp(&other.node))
+    }
+}
+
+/// Iterator over the points of a `KdTree` inside a box
+pub struct KdRange<'a, const D: usize, V: 'a> {
+    tree: &'a KdTree<D, V>,
+    min: &'a [f64; D],
+    max: &'a [f64; D],
+    /// Nodes left to visit
+    stack: Vec<usize>,
+}
+
+impl<'a, const D: usize, V> Iterator for KdRange<'a, D, V> {
+    type Item = (&'a [f64; D], &'a V);
+
+    fn next(&mut self) -> Option<(&'a [f64; D], &'a V)> {
+        while let Some(x) = self.stack.pop() {
+            let node = &self.tree.nodes[x];
+            let split = node.point[node.axis];
+            if let Some(right) = node.right.filter(|_| split <= self.max[node.axis]) {
+                self.stack.push(right);
+            }
+            if let Some(left) = node.left.filter(|_| self.min[node.axis] <= split) {
+                self.stack.push(left);
+            }
+            let inside = (0..D).all(|a| self.min[a] <= node.point[a] && node.point[a] <= self.max[a]);
+            if inside {
+                return Some((&node.point, &node.value));
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn kd_tree() {
+    let points: Vec<_> = (0..400).map(|i| ([(i * 37 % 101) as f64, (i * 53 % 97) as f64], i)).collect();
+    let mut tree: KdTree<2, _> = points.iter().cloned().collect();
+    assert_eq!(tree.len(), 400);
+    tree.insert([50.5, 50.5], 400);
+    tree.insert([-1.0, 3.0], 401);
+
+    let distance = |a: &[f64; 2], b: &[f64; 2]| (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2);
+    let mut all = points.clone();
+    all.push(([50.5, 50.5], 400));
+    all.push(([-1.0, 3.0], 401));
+    for target in &[[50.0, 50.0], [0.0, 0.0], [200.0, -30.0], [13.3, 77.7]] {
+        let mut expected: Vec<_> = all.iter().map(|(p, v)| (distance(p, target), *v)).collect();
+        expected.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        let found: Vec<_> = tree.knn(target, 5).into_iter().map(|(d, _, _)| d).collect();
+        assert!(found.iter().eq(expected[..5].iter().map(|e| &e.0)));
+        assert_eq!(tree.nearest(target).unwrap().0, expected[0].0);
+    }
+    assert_eq!(tree.nearest(&[50.0, 50.0]).map(|(_, _, &v)| v), Some(400));
+
+    let (min, max) = ([10.0, 20.0], [30.0, 25.0]);
+    let mut found: Vec<_> = tree.range(&min, &max).map(|(_, &v)| v).collect();
+    found.sort();
+    let mut expected: Vec<_> = all
+        .iter()
+        .filter(|(p, _)| min[0] <= p[0] && p[0] <= max[0] && min[1] <= p[1] && p[1] <= max[1])
+        .map(|&(_, v)| v)
+        .collect();
+    expected.sort();
+    assert!(!found.is_empty());
+    assert_eq!(found, expected);
+    assert!(KdTree::<3, ()>::new().nearest(&[0.0; 3]).is_none());
+
+    // Points equal to a split on its axis can end up on the left of it
+    let line = [([1.0, 0.0], 0), ([1.0, 1.0], 1), ([1.0, 2.0], 2), ([0.0, 5.0], 3), ([2.0, 5.0], 4)];
+    let tree: KdTree<2, _> = line.iter().cloned().collect();
+    let mut found: Vec<_> = tree.range(&[1.0, 0.0], &[1.0, 9.0]).map(|(_, &v)| v).collect();
+    found.sort();
+    assert_eq!(found, [0, 1, 2]);
+}
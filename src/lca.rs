@@ -0,0 +1,118 @@
+use alloc::vec::Vec;
+
+use {ITree, NodeId};
+
+/// Index for lowest common ancestors
+///
+/// Keeps the ancestor `2^k` levels up of every node for every `k`, built in
+/// `O(n log n)` time and memory. Any ancestor and the lowest common ancestor
+/// of any two nodes can then be found in `O(log n)` steps of up to a power of
+/// two levels each, where `ITree::lca` has to walk up one level at a time.
+#[derive(Debug, Clone)]
+pub struct LcaIndex {
+    depths: Vec<usize>,
+    /// `up[k][x]` is the ancestor of `x` that is `2^k` levels up, or the root
+    /// if there are fewer levels above `x`
+    up: Vec<Vec<usize>>,
+}
+
+impl LcaIndex {
+    /// Build the index of a tree
+    pub fn build<T>(tree: &ITree<T>) -> Self {
+        let len = tree.len();
+        let mut depths = vec![0; len];
+        let mut first: Vec<usize> = (0..len).collect();
+        for x in 1..len {
+            if let Some(p) = tree.parents[x] {
+                first[x] = p.index();
+                depths[x] = depths[p.index()] + 1;
+            }
+        }
+        let mut up = vec![first];
+        let height = depths.iter().cloned().max().unwrap_or(0);
+        while 1 << (up.len() - 1) < height {
+            let last = up.last().unwrap();
+            let next = last.iter().map(|&a| last[a]).collect();
+            up.push(next);
+        }
+        LcaIndex { depths, up }
+    }
+
+    /// Get the depth of a node below the root
+    pub fn depth(&self, node: NodeId) -> Option<usize> {
+        self.depths.get(node.index()).cloned()
+    }
+
+    /// Get the ancestor `k` levels above a node, which is the node itself for
+    /// zero. Returns `None` if the node does not exist or has fewer than `k`
+    /// levels above it.
+    pub fn kth_ancestor(&self, node: NodeId, k: usize) -> Option<NodeId> {
+        if k > self.depth(node)? {
+            return None;
+        }
+        Some(NodeId::new(self.climb(node.index(), k)))
+    }
+
+    /// Get the lowest common ancestor of two nodes, or `None` if one of them
+    /// does not exist
+    pub fn lca(&self, a: NodeId, b: NodeId) -> Option<NodeId> {
+        let (da, db) = (self.depth(a)?, self.depth(b)?);
+        // Get both nodes to the same depth first
+        let (mut a, mut b) = if da >= db {
+            (self.climb(a.index(), da - db), b.index())
+        } else {
+            (a.index(), self.climb(b.index(), db - da))
+        };
+        if a == b {
+            return Some(NodeId::new(a));
+        }
+        // Climb as far as the two paths stay apart
+        for level in self.up.iter().rev() {
+            if level[a] != level[b] {
+                a = level[a];
+                b = level[b];
+            }
+        }
+        Some(NodeId::new(self.up[0][a]))
+    }
+
+    /// Go up `k` levels from a node that has at least that many above it
+    fn climb(&self, mut x: usize, k: usize) -> usize {
+        for (bit, level) in self.up.iter().enumerate() {
+            if k >> bit & 1 == 1 {
+                x = level[x];
+            }
+        }
+        x
+    }
+}
+
+#[test]
+fn lca_index() {
+    // A random tree where node `x` hangs below one of the ten nodes before it,
+    // which makes it deep
+    let mut tree = ITree::new();
+    tree.add_root(()).unwrap();
+    let mut seed = 11u64;
+    for x in 1..2000 {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        tree.add_node(NodeId::new(x - 1 - (seed >> 33) as usize % x.min(10)), ());
+    }
+    let index = LcaIndex::build(&tree);
+    for _ in 0..500 {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let a = NodeId::new((seed >> 20) as usize % 2000);
+        let b = NodeId::new((seed >> 40) as usize % 2000);
+        assert_eq!(index.lca(a, b), tree.lca(a, b));
+        assert_eq!(index.depth(a), tree.depth(a));
+        let path = tree.path_to_root(a);
+        let k = (seed >> 8) as usize % (path.len() + 2);
+        assert_eq!(index.kth_ancestor(a, k), path.get(k).cloned());
+    }
+    assert_eq!(index.lca(NodeId::new(0), NodeId::new(2000)), None);
+
+    let single = LcaIndex::build(&tree!{ 0 });
+    assert_eq!(single.lca(NodeId::new(0), NodeId::new(0)), Some(NodeId::new(0)));
+    assert_eq!(single.kth_ancestor(NodeId::new(0), 1), None);
+    assert!(LcaIndex::build(&ITree::<u8>::new()).lca(NodeId::new(0), NodeId::new(0)).is_none());
+}
@@ -0,0 +1,234 @@
+use alloc::vec::Vec;
+
+use {GroveError, ITree, NodeId};
+
+/// Left-child right-sibling Tree
+///
+/// An immutable tree like `ITree` that doesn't allocate a list of children for
+/// every node. Instead each node only knows its first child and the sibling
+/// after it, so the children of a node form a linked list. This makes every
+/// node the same small size no matter how many children it has, at the cost
+/// of walking the list to get to a specific child.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LcrsTree<T> {
+    nodes: Vec<LcrsNode<T>>,
+}
+
+/// A node in an `LcrsTree`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LcrsNode<T> {
+    value: T,
+    parent: Option<NodeId>,
+    first_child: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+}
+
+impl<T> LcrsNode<T> {
+    fn new(value: T, parent: Option<NodeId>) -> Self {
+        Self { value, parent, first_child: None, next_sibling: None }
+    }
+
+    /// Get the node's value
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Get the node's parent `NodeId`
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    /// Get the `NodeId` of the node's first child
+    pub fn first_child(&self) -> Option<NodeId> {
+        self.first_child
+    }
+
+    /// Get the `NodeId` of the sibling after the node
+    pub fn next_sibling(&self) -> Option<NodeId> {
+        self.next_sibling
+    }
+}
+
+impl<T> LcrsTree<T> {
+    /// Create a new empty `LcrsTree`
+    pub fn new() -> Self {
+        Self { nodes: Vec::new() }
+    }
+
+    /// Get the `NodeId` of the root node if it exists
+    pub fn root_id(&self) -> Option<NodeId> {
+        if self.nodes.is_empty() { None } else { Some(NodeId::new(0)) }
+    }
+
+    /// Get the root node if it exists
+    pub fn root(&self) -> Option<&LcrsNode<T>> {
+        self.nodes.first()
+    }
+
+    /// Get the number of nodes in the tree
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Check if the tree has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Get the node of the given `Id` if it exists
+    pub fn get(&self, node: NodeId) -> Option<&LcrsNode<T>> {
+        self.nodes.get(node.index())
+    }
+
+    /// Iterate over the `NodeId`s of the children of a node. Yields nothing if
+    /// the node does not exist.
+    pub fn children(&self, node: NodeId) -> LcrsChildren<'_, T> {
+        LcrsChildren { tree: self, next: self.get(node).and_then(|n| n.first_child) }
+    }
+
+    /// Adds the root node to an empty tree. Returns the `NodeId` of the root or
+    /// an error if the tree already has one.
+    pub fn add_root(&mut self, value: T) -> Result<NodeId, GroveError> {
+        if self.nodes.is_empty() {
+            self.nodes.push(LcrsNode::new(value, None));
+            Ok(NodeId::new(0))
+        } else {
+            Err(GroveError::RootExists)
+        }
+    }
+
+    /// Adds a child after the last child of a node. If the tree is empty it
+    /// discards the given `NodeId` and creates the root node with the given
+    /// value. Returns the `NodeId` of the inserted node.
+    ///
+    /// Finding the last child walks all the children of the node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `NodeId` does not refer to a node in the tree. See
+    /// `try_add_node` for a version that returns an error instead.
+    pub fn add_node(&mut self, node: NodeId, value: T) -> NodeId {
+        match self.try_add_node(node, value) {
+            Ok(id) => id,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Adds a child to a node in the tree like `add_node`, but returns an error
+    /// rather than panicking if the `NodeId` does not refer to a node in the
+    /// tree.
+    pub fn try_add_node(&mut self, node: NodeId, value: T) -> Result<NodeId, GroveError> {
+        if self.nodes.is_empty() {
+            return self.add_root(value);
+        }
+        if self.get(node).is_none() {
+            return Err(GroveError::InvalidNodeId(node));
+        }
+        let index = NodeId::new(self.nodes.len());
+        let last = self.children(node).last();
+        match last {
+            Some(last) => self.nodes[last.index()].next_sibling = Some(index),
+            None => self.nodes[node.index()].first_child = Some(index),
+        }
+        self.nodes.push(LcrsNode::new(value, Some(node)));
+        Ok(index)
+    }
+}
+
+impl<T> Default for LcrsTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<ITree<T>> for LcrsTree<T> {
+    /// Convert an `ITree` into an `LcrsTree`, every `NodeId` stays the same
+    fn from(tree: ITree<T>) -> Self {
+        let mut links: Vec<_> = tree.children
+            .iter()
+            .map(|children| (children.first().cloned(), None))
+            .collect();
+        for children in &tree.children {
+            for pair in children.windows(2) {
+                links[pair[0].index()].1 = Some(pair[1]);
+            }
+        }
+        let nodes = tree.values
+            .into_iter()
+            .zip(tree.parents)
+            .zip(links)
+            .map(|((value, parent), (first_child, next_sibling))| LcrsNode {
+                value,
+                parent,
+                first_child,
+                next_sibling,
+            })
+            .collect();
+        LcrsTree { nodes }
+    }
+}
+
+impl<T> From<LcrsTree<T>> for ITree<T> {
+    /// Convert an `LcrsTree` into an `ITree`, every `NodeId` stays the same
+    fn from(tree: LcrsTree<T>) -> Self {
+        let children = (0..tree.nodes.len())
+            .map(|x| tree.children(NodeId::new(x)).collect())
+            .collect();
+        let parents = tree.nodes.iter().map(|node| node.parent).collect();
+        let values = tree.nodes.into_iter().map(|node| node.value).collect();
+        ITree { values, parents, children }
+    }
+}
+
+/// Iterator over the children of a node in an `LcrsTree`
+///
+/// Created with `LcrsTree::children`.
+#[derive(Debug)]
+pub struct LcrsChildren<'a, T: 'a> {
+    tree: &'a LcrsTree<T>,
+    next: Option<NodeId>,
+}
+
+impl<'a, T> Clone for LcrsChildren<'a, T> {
+    fn clone(&self) -> Self {
+        LcrsChildren { tree: self.tree, next: self.next }
+    }
+}
+
+impl<'a, T> Iterator for LcrsChildren<'a, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        self.next = self.tree.nodes[id.index()].next_sibling;
+        Some(id)
+    }
+}
+
+#[test]
+fn lcrs() {
+    let n = NodeId::new;
+    let mut tree = LcrsTree::new();
+    // 0 -> 1 -> 4
+    // |--> 2
+    // |--> 3
+    let root = tree.add_root(0).unwrap();
+    let one = tree.add_node(root, 1);
+    tree.add_node(root, 2);
+    tree.add_node(root, 3);
+    tree.add_node(one, 4);
+
+    assert_eq!(tree.children(root).collect::<Vec<_>>(), vec![n(1), n(2), n(3)]);
+    assert_eq!(tree.children(one).collect::<Vec<_>>(), vec![n(4)]);
+    assert_eq!(tree.children(n(9)).count(), 0);
+    assert_eq!(tree.get(n(2)).unwrap().next_sibling(), Some(n(3)));
+    assert_eq!(tree.get(n(4)).unwrap().parent(), Some(one));
+    assert_eq!(tree.try_add_node(n(9), 9), Err(GroveError::InvalidNodeId(n(9))));
+
+    // Conversions keep every id
+    let itree = ITree::from(tree.clone());
+    assert_eq!(itree.get(root).unwrap().children(), &[n(1), n(2), n(3)]);
+    assert_eq!(itree.get(n(4)).unwrap().parent(), Some(one));
+    let itree = tree!{ 0 => [1 => [2, 3], 4 => [5]] };
+    assert_eq!(ITree::from(LcrsTree::from(itree.clone())), itree);
+}
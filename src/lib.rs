@@ -1,71 +1,471 @@
-use std::ops::IndexMut;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[macro_use]
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate core;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(any(feature = "serde", feature = "json"))]
+extern crate serde;
+#[cfg(feature = "json")]
+extern crate serde_json;
+#[cfg(feature = "smallvec")]
+extern crate smallvec;
+
+#[macro_use]
+mod macros;
+
+mod arc;
+mod arena;
+mod avl;
+mod bk;
+mod bplus;
+mod bst;
+mod builder;
+mod chunks;
+mod compact;
+mod crdt;
+mod dag;
+mod diff;
+mod disjoint;
+mod display;
+mod distance;
+mod dot;
+mod element;
+mod error;
+mod fenwick;
+mod finger;
+mod forest;
+mod graph;
+mod hamt;
+mod heap;
+mod hld;
+pub mod huffman;
+mod interval;
+mod iso;
+mod iter;
+mod journal;
+#[cfg(feature = "json")]
+mod json;
+mod kd;
+mod lca;
+mod lcrs;
+mod merge;
+mod merkle;
+mod mmap;
+mod mtree;
+mod newick;
+mod node_ref;
+#[cfg(feature = "rayon")]
+mod par;
+mod octree;
+mod orthtree;
+mod outline;
+mod ptree;
+mod quadtree;
+mod query;
+mod radix;
+mod rb;
+mod rope;
+mod rrb;
+mod rtree;
+mod scapegoat;
+mod segment;
+mod select;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod sexpr;
+#[cfg(feature = "std")]
+mod shared;
+mod snapshot;
+mod splay;
+mod succinct;
+mod suffix;
+mod transform;
+mod treap;
+mod trie;
+mod visit;
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::Index;
+
+use chunks::Chunks;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "smallvec")]
+use smallvec::SmallVec;
+
+pub use arc::ArcTree;
+pub use avl::AvlMap;
+pub use bk::{BkTree, Levenshtein, Metric};
+pub use bplus::{BPlusIter, BPlusTree};
+pub use bst::{BstIter, BstMap};
+pub use builder::{StreamBuilder, TreeBuilder};
+pub use compact::Compact;
+pub use crdt::{CrdtOp, CrdtTree, Timestamp};
+pub use dag::{DagNode, DagTree};
+pub use diff::TreeEdit;
+pub use disjoint::{DisjointSet, Members};
+pub use display::Pretty;
+pub use distance::{EditCosts, UnitCosts};
+pub use element::{Element, ElementTree};
+pub use error::{GroveError, PatchError};
+pub use fenwick::FenwickTree;
+pub use finger::{FingerIter, FingerTree, Measured, Monoid, Size};
+pub use forest::Forest;
+pub use hamt::{FnvHasher, HamtIter, HamtMap};
+pub use heap::{Heap, HeapChildren, HeapIter};
+pub use hld::HeavyLight;
+pub use interval::{IntervalIter, IntervalTree};
+pub use iter::{Ancestors, Bfs, Descendants, Dfs, Leaves, Levels, PostOrder, ToDepth};
+pub use journal::Journal;
+#[cfg(feature = "json")]
+pub use json::{JsonEdge, JsonKind, JsonNode};
+pub use kd::{KdRange, KdTree};
+pub use lca::LcaIndex;
+pub use lcrs::{LcrsChildren, LcrsNode, LcrsTree};
+pub use merge::Conflict;
+pub use merkle::{MerkleHasher, MerkleProof, MerkleTree};
+pub use mmap::{MmapChildren, MmapTree, Pod};
+pub use mtree::{MTree, SlotId};
+pub use newick::NewickNode;
+pub use node_ref::{NodeMut, NodeRef};
+pub use octree::{Frustum, Octree};
+pub use orthtree::{Region, RegionIter};
+pub use ptree::{PTree, PView, PNodeRef, Version, VersionDiff, Versions};
+pub use quadtree::QuadTree;
+pub use query::SubtreeSizes;
+pub use radix::{RadixIter, RadixTree};
+pub use rb::RbMap;
+pub use rope::{Rope, RopeChunks};
+pub use rrb::{RrbIter, RrbVec};
+pub use rtree::{RTree, RTreeQuery};
+pub use scapegoat::ScapegoatMap;
+pub use segment::{SegmentOp, SegmentTree};
+pub use select::{Predicates, Selector};
+pub use sexpr::Atom;
+#[cfg(feature = "std")]
+pub use shared::{ReadGuard, SharedTree, WriteGuard};
+pub use snapshot::ITreeSnapshot;
+pub use splay::SplayMap;
+pub use succinct::{SuccinctChildren, SuccinctTree};
+pub use suffix::SuffixTree;
+pub use treap::{TreapIter, TreapMap};
+pub use trie::{Trie, TriePrefix};
+pub use visit::{Event, Flow, Visitor};
 
 /// Immutable Tree
 ///
 /// A Tree that can only add more nodes but can't update the current
-/// values at all. You can choose between two variants when creating the tree:
-/// `Emphemeral` and `Persistent`. The former discards the previous version of
-/// the tree and the latter keeps previous versions. As of right now only the
-/// `Emphemeral` type is implemented.
-#[derive(Debug, Clone)]
+/// values at all. There are two variants of the tree: `ITree` is the
+/// `Ephemeral` one and `PTree` is the `Persistent` one. The former discards the
+/// previous version of the tree and the latter keeps previous versions.
+///
+/// Values, parents and children of the nodes are stored in separate arrays, so
+/// walking the structure of the tree never touches the values.
+///
+/// Nodes are numbered in the order they are added and a parent has to exist
+/// before its children, so the root is node 0 and every other node comes after
+/// its parent. Going over the nodes backwards reaches every child before its
+/// parent, which the single pass algorithms on `ITree` rely on.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "serde_impl::RawTree<T>"))]
 pub struct ITree<T> {
-    nodes: Vec<INode<T>>,
+    values: Chunks<T>,
+    parents: Chunks<Option<NodeId>>,
+    children: Chunks<Children>,
 }
 
 impl<T> ITree<T> {
     /// Create a new empty `IBTree`
     pub fn new() -> Self{
-        Self { nodes: Vec::new() }
+        Self { values: Chunks::new(), parents: Chunks::new(), children: Chunks::new() }
+    }
+
+    /// Create a new empty tree with room for `capacity` nodes
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            values: Chunks::with_capacity(capacity),
+            parents: Chunks::with_capacity(capacity),
+            children: Chunks::with_capacity(capacity),
+        }
+    }
+
+    /// Get the number of nodes the tree can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        self.values.capacity().min(self.parents.capacity()).min(self.children.capacity())
+    }
+
+    /// Reserve room for at least `additional` more nodes
+    pub fn reserve(&mut self, additional: usize) {
+        self.values.reserve(additional);
+        self.parents.reserve(additional);
+        self.children.reserve(additional);
+    }
+
+    /// Reserve room for at least `additional` more children of a node
+    pub fn reserve_children(&mut self, node: NodeId, additional: usize) -> Result<(), GroveError> {
+        match self.children.get_mut(node.index()) {
+            Some(children) => children.reserve(additional),
+            None => return Err(GroveError::InvalidNodeId(node)),
+        }
+        Ok(())
+    }
+
+    /// Shrink the memory used by the tree and the children of every node as
+    /// much as possible
+    pub fn shrink_to_fit(&mut self) {
+        self.values.shrink_to_fit();
+        self.parents.shrink_to_fit();
+        self.children.shrink_to_fit();
+        for children in self.children.unshared_mut() {
+            children.shrink_to_fit();
+        }
+    }
+
+    /// Get the `NodeId` of the root node if it exists
+    pub fn root_id(&self) -> Option<NodeId> {
+        if self.values.is_empty() { None } else { Some(NodeId::new(0)) }
     }
 
     /// Get the root node if it exists
-    pub fn root(&self) -> Option<&INode<T>> {
-        self.nodes.get(0)
+    pub fn root(&self) -> Option<INodeRef<'_, T>> {
+        self.get(self.root_id()?)
+    }
+
+    /// Get the number of nodes in the tree
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Check if the tree has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Get the node of the given `Id` if it exists
+    pub fn get(&self, node: NodeId) -> Option<INodeRef<'_, T>> {
+        let x = node.index();
+        if x < self.values.len() { Some(INodeRef { tree: self, x }) } else { None }
     }
 
-    /// Get the `INode` of the given `Id` if it exists
-    pub fn get(&self, node: NodeId) -> Option<&INode<T>> {
-        let NodeId(x) = node;
-        self.nodes.get(x)
+    /// Adds the root node to an empty tree. Returns the `NodeId` of the root or
+    /// an error if the tree already has one.
+    pub fn add_root(&mut self, value: T) -> Result<NodeId, GroveError> {
+        if self.values.is_empty() {
+            Ok(self.push(value, None))
+        } else {
+            Err(GroveError::RootExists)
+        }
     }
 
     /// Adds a child to a node in the tree. If the tree is empty it discards the
     /// given `NodeId` and creates the root node with the given value. Returns
     /// the `NodeId` of the inserted node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `NodeId` does not refer to a node in the tree. See
+    /// `try_add_node` for a version that returns an error instead.
     pub fn add_node(&mut self, node: NodeId, value: T) -> NodeId {
-        if self.nodes.is_empty() {
-            self.nodes.push(INode::new(value, None));
-            NodeId(0)
-        } else {
-            let index = NodeId(self.nodes.len());
-            let NodeId(x) = node;
-            // What if index out of bounds?
-            self.nodes.index_mut(x).insert(index);
-            self.nodes.push(INode::new(value, Some(node)));
-            index
+        match self.try_add_node(node, value) {
+            Ok(id) => id,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Adds a child to a node in the tree like `add_node`, but returns an error
+    /// rather than panicking if the `NodeId` does not refer to a node in the
+    /// tree.
+    pub fn try_add_node(&mut self, node: NodeId, value: T) -> Result<NodeId, GroveError> {
+        if self.values.is_empty() {
+            return self.add_root(value);
+        }
+        let index = NodeId::new(self.values.len());
+        let x = node.index();
+        match self.children.get_mut(x) {
+            Some(children) => children.push(index),
+            None => return Err(GroveError::InvalidNodeId(node)),
+        }
+        Ok(self.push(value, Some(node)))
+    }
+
+    /// Store a new node at the end of the tree
+    fn push(&mut self, value: T, parent: Option<NodeId>) -> NodeId {
+        self.values.push(value);
+        self.parents.push(parent);
+        self.children.push(Children::new());
+        NodeId::new(self.values.len() - 1)
+    }
+
+    /// Get the `NodeId` of the first child of a node if it has any
+    pub fn first_child(&self, node: NodeId) -> Option<NodeId> {
+        self.children.get(node.index())?.first().cloned()
+    }
+
+    /// Get the `NodeId` of the last child of a node if it has any
+    pub fn last_child(&self, node: NodeId) -> Option<NodeId> {
+        self.children.get(node.index())?.last().cloned()
+    }
+
+    /// Get the `NodeId` of the sibling right after a node if it exists
+    pub fn next_sibling(&self, node: NodeId) -> Option<NodeId> {
+        let (siblings, pos) = self.siblings(node)?;
+        siblings.get(pos + 1).cloned()
+    }
+
+    /// Get the `NodeId` of the sibling right before a node if it exists
+    pub fn prev_sibling(&self, node: NodeId) -> Option<NodeId> {
+        let (siblings, pos) = self.siblings(node)?;
+        pos.checked_sub(1).map(|prev| siblings[prev])
+    }
+
+    /// Get the children of a node's parent along with the node's position in
+    /// them
+    fn siblings(&self, node: NodeId) -> Option<(&[NodeId], usize)> {
+        let parent = (*self.parents.get(node.index())?)?;
+        let siblings = &self.children[parent.index()];
+        let pos = siblings.iter().position(|&c| c == node)?;
+        Some((siblings, pos))
+    }
+}
+
+impl<T> Default for ITree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<NodeId> for ITree<T> {
+    type Output = T;
+
+    /// Get the value of a node
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `NodeId` does not refer to a node in the tree.
+    fn index(&self, node: NodeId) -> &T {
+        match self.values.get(node.index()) {
+            Some(value) => value,
+            None => panic!("{}", GroveError::InvalidNodeId(node)),
+        }
+    }
+}
+
+/// A node in an `ITree`
+pub struct INodeRef<'a, T: 'a> {
+    tree: &'a ITree<T>,
+    x: usize,
+}
+
+impl<'a, T> Clone for INodeRef<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for INodeRef<'a, T> {}
+
+impl<'a, T: fmt::Debug> fmt::Debug for INodeRef<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("INodeRef")
+            .field("value", self.value())
+            .field("parent", &self.parent())
+            .field("children", &self.children())
+            .finish()
+    }
+}
+
+impl<'a, T> INodeRef<'a, T> {
+    /// Get the node's value
+    pub fn value(&self) -> &'a T {
+        &self.tree.values[self.x]
+    }
+
+    /// Get the node's parent `NodeId`
+    pub fn parent(&self) -> Option<NodeId> {
+        self.tree.parents[self.x]
+    }
+
+    /// Get the node's children `NodeId`s
+    pub fn children(&self) -> &'a [NodeId] {
+        &self.tree.children[self.x]
+    }
+}
+
+/// Identifies a node in a tree
+///
+/// A `NodeId` is the position of the node in its tree. Trees that reuse the
+/// slots of removed nodes hand out a `SlotId` instead, see `MTree`.
+///
+/// With the `u32-ids` feature the position is stored in a `u32` instead of a
+/// `usize`, which halves the size of a `NodeId` on 64 bit targets but limits
+/// trees to `u32::MAX` nodes.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId {
+    index: Idx,
+}
+
+#[cfg(not(feature = "u32-ids"))]
+type Idx = usize;
+#[cfg(feature = "u32-ids")]
+type Idx = u32;
+
+impl NodeId {
+    /// Create a `NodeId` for a position
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index doesn't fit into the index type.
+    fn new(index: usize) -> Self {
+        match Idx::try_from(index) {
+            Ok(index) => NodeId { index },
+            Err(_) => panic!("node index {} is too large for the node id type", index),
         }
     }
+
+    /// Get the position of the node in its tree
+    #[allow(clippy::unnecessary_cast)]
+    fn index(self) -> usize {
+        self.index as usize
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct NodeId(usize);
+impl fmt::Debug for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NodeId({})", self.index)
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct INode<T> {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct INode<T, I = NodeId> {
     value: T,
-    parent: Option<NodeId>,
-    children: Vec<NodeId>,
+    parent: Option<I>,
+    children: Children<I>,
 }
 
-impl<T> INode<T> {
+/// Storage for the children of a node
+#[cfg(not(feature = "smallvec"))]
+type Children<I = NodeId> = alloc::vec::Vec<I>;
+/// Storage for the children of a node, most nodes only have a couple of
+/// children so they are kept inline
+#[cfg(feature = "smallvec")]
+type Children<I = NodeId> = SmallVec<[I; 2]>;
+
+impl<T, I: Copy> INode<T, I> {
 
     /// Create a new `IBTree` node
-    fn new(value: T, parent: Option<NodeId>) -> Self {
-        Self { value, parent, children: Vec::new() }
+    fn new(value: T, parent: Option<I>) -> Self {
+        Self { value, parent, children: Children::new() }
     }
 
     /// Assign the left side value. Only works once
-    fn insert(&mut self, value: NodeId) {
+    fn insert(&mut self, value: I) {
         self.children.push(value);
     }
 
@@ -75,12 +475,12 @@ impl<T> INode<T> {
     }
 
     /// Get the `INode`'s parent `NodeId`
-    pub fn parent(&self) -> Option<NodeId> {
+    pub fn parent(&self) -> Option<I> {
         self.parent
     }
 
     /// Get the `INode`'s children `NodeId`s
-    pub fn children(&self) -> &Vec<NodeId> {
+    pub fn children(&self) -> &[I] {
         &self.children
     }
 }
@@ -91,7 +491,7 @@ fn insert() {
     /// Shorthand for NodeId
     macro_rules! n {
         ($x:expr) => {
-            NodeId($x)
+            NodeId::new($x)
         }
     }
 
@@ -125,3 +525,98 @@ fn insert() {
     assert_eq!(tree.get(n!(2)).unwrap().children(), &vec![n!(3)]);
     assert_eq!(tree.get(n!(3)).unwrap().children(), &vec![]);
 }
+
+#[test]
+fn try_insert() {
+    let mut tree = ITree::new();
+    let root = tree.add_root(0).unwrap();
+    assert_eq!(tree.add_root(1), Err(GroveError::RootExists));
+
+    let child = tree.try_add_node(root, 1).unwrap();
+    assert_eq!(tree.get(child).unwrap().parent(), Some(root));
+
+    // Bad ids are reported and leave the tree untouched
+    assert_eq!(tree.try_add_node(NodeId::new(5), 2), Err(GroveError::InvalidNodeId(NodeId::new(5))));
+    assert!(tree.get(NodeId::new(2)).is_none());
+    assert_eq!(tree.get(root).unwrap().children(), &vec![child]);
+}
+
+#[test]
+fn navigation() {
+    let mut tree = ITree::new();
+    // 0 -> 1
+    // |--> 2
+    // |--> 3
+    let root = tree.add_root(0).unwrap();
+    let one = tree.add_node(root, 1);
+    let two = tree.add_node(root, 2);
+    let three = tree.add_node(root, 3);
+
+    assert_eq!(tree.first_child(root), Some(one));
+    assert_eq!(tree.last_child(root), Some(three));
+    assert_eq!(tree.first_child(one), None);
+    assert_eq!(tree.last_child(NodeId::new(4)), None);
+
+    assert_eq!(tree.next_sibling(one), Some(two));
+    assert_eq!(tree.next_sibling(two), Some(three));
+    assert_eq!(tree.next_sibling(three), None);
+    assert_eq!(tree.prev_sibling(three), Some(two));
+    assert_eq!(tree.prev_sibling(one), None);
+    assert_eq!(tree.next_sibling(root), None);
+    assert_eq!(tree.prev_sibling(NodeId::new(4)), None);
+}
+
+#[test]
+fn index() {
+    let tree = tree!{ "a" => ["b"] };
+    assert_eq!(tree[NodeId::new(0)], "a");
+    assert_eq!(tree[NodeId::new(1)], "b");
+}
+
+#[test]
+#[should_panic]
+fn index_out_of_bounds() {
+    let tree = tree!{ "a" => ["b"] };
+    let _ = tree[NodeId::new(2)];
+}
+
+#[test]
+fn capacity() {
+    let mut tree = ITree::with_capacity(10);
+    assert!(tree.capacity() >= 10);
+    let root = tree.add_root(0).unwrap();
+    tree.reserve_children(root, 20).unwrap();
+    assert!(tree.children[0].capacity() >= 20);
+    assert_eq!(tree.reserve_children(NodeId::new(1), 1), Err(GroveError::InvalidNodeId(NodeId::new(1))));
+
+    tree.reserve(100);
+    assert!(tree.capacity() >= 101);
+    tree.add_node(root, 1);
+    tree.shrink_to_fit();
+    assert_eq!(tree.capacity(), 2);
+    assert!(tree.children[0].capacity() < 20);
+}
+
+#[cfg(feature = "u32-ids")]
+#[test]
+fn u32_ids() {
+    use std::mem::size_of;
+
+    // Links of an `ITree` take four bytes, and eight in an `MTree` with the
+    // generation of the slot
+    assert_eq!(size_of::<NodeId>(), 4);
+    assert_eq!(size_of::<Option<NodeId>>(), 8);
+    assert_eq!(size_of::<SlotId>(), 8);
+    assert_eq!(NodeId::new(u32::MAX as usize).index(), u32::MAX as usize);
+    assert!(::std::panic::catch_unwind(|| NodeId::new(u32::MAX as usize + 1)).is_err());
+}
+
+#[cfg(feature = "smallvec")]
+#[test]
+fn inline_children() {
+    let mut tree = tree!{ 0 => [1, 2] };
+    assert!(!tree.children[0].spilled());
+    tree.add_node(NodeId::new(0), 3);
+    assert!(tree.children[0].spilled());
+    assert_eq!(tree.root().unwrap().children(), &[NodeId::new(1), NodeId::new(2), NodeId::new(3)]);
+}
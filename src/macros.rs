@@ -0,0 +1,192 @@
+/// Build an `ITree` from a literal description of its nodes
+///
+/// Every node is written as a value, optionally followed by `=>` and a list of
+/// its children in square brackets. The first node is the root.
+///
+/// ```
+/// # #[macro_use] extern crate grove;
+/// # fn main() {
+/// // 1 -> 2 -> 4
+/// // |--> 3
+/// let tree = tree!{ 1 => [2 => [4], 3] };
+/// let values: Vec<_> = tree.iter_dfs(tree.root_id().unwrap())
+///     .map(|(_, node)| *node.value())
+///     .collect();
+/// assert_eq!(values, vec![1, 2, 4, 3]);
+/// # }
+/// ```
+///
+/// Every node has a single list of children:
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate grove;
+/// # fn main() {
+/// let tree = tree!{ 1 => [2] => [3] };
+/// # }
+/// ```
+#[macro_export]
+macro_rules! tree {
+    (@children $tree:ident, $parent:expr;) => {};
+    (@children $tree:ident, $parent:expr; $value:expr => [$($children:tt)*] $(, $($rest:tt)*)?) => {
+        let id = $tree.add_node($parent, $value);
+        $crate::tree!(@children $tree, id; $($children)*);
+        $crate::tree!(@children $tree, $parent; $($($rest)*)?);
+    };
+    (@children $tree:ident, $parent:expr; $value:expr $(, $($rest:tt)*)?) => {
+        $tree.add_node($parent, $value);
+        $crate::tree!(@children $tree, $parent; $($($rest)*)?);
+    };
+    () => {
+        $crate::ITree::new()
+    };
+    ($value:expr $(=> [$($children:tt)*])?) => {{
+        let mut tree = $crate::ITree::new();
+        let _root = tree.add_root($value).unwrap();
+        $($crate::tree!(@children tree, _root; $($children)*);)?
+        tree
+    }};
+}
+
+/// Implement the parts of an ordered map every kind of binary search tree
+/// shares, for a map with a `tree: Bst<K, V, M>` field along with `new` and
+/// `insert` methods
+macro_rules! bst_map {
+    ($map:ident, $meta:ty) => {
+        impl<K: Ord, V> $map<K, V> {
+            pub fn len(&self) -> usize {
+                self.tree.len
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.tree.len == 0
+            }
+
+            /// Get the entry with the smallest key
+            pub fn first_key_value(&self) -> Option<(&K, &V)> {
+                self.iter().next()
+            }
+
+            /// Get the entry with the largest key
+            pub fn last_key_value(&self) -> Option<(&K, &V)> {
+                self.iter().next_back()
+            }
+
+            /// Remove every entry
+            pub fn clear(&mut self) {
+                self.tree.clear();
+            }
+
+            /// Iterate over the entries in order of their keys
+            pub fn iter(&self) -> $crate::BstIter<'_, K, V, $meta> {
+                self.tree.iter()
+            }
+
+            /// Iterate over the entries with keys inside a range
+            pub fn range<Q, R>(&self, range: R) -> $crate::BstIter<'_, K, V, $meta>
+                where K: ::core::borrow::Borrow<Q>,
+                      Q: Ord + ?Sized,
+                      R: ::core::ops::RangeBounds<Q>
+            {
+                self.tree.range(range)
+            }
+        }
+
+        impl<K: Ord, V> Default for $map<K, V> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<K: ::core::fmt::Debug + Ord, V: ::core::fmt::Debug> ::core::fmt::Debug for $map<K, V> {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                f.debug_map().entries(self.iter()).finish()
+            }
+        }
+
+        impl<K: Ord, V> Extend<(K, V)> for $map<K, V> {
+            fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+                for (key, value) in iter {
+                    self.insert(key, value);
+                }
+            }
+        }
+
+        impl<K: Ord, V> ::core::iter::FromIterator<(K, V)> for $map<K, V> {
+            fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+                let mut map = Self::new();
+                map.extend(iter);
+                map
+            }
+        }
+
+        impl<'a, K: Ord, V> IntoIterator for &'a $map<K, V> {
+            type Item = (&'a K, &'a V);
+            type IntoIter = $crate::BstIter<'a, K, V, $meta>;
+
+            fn into_iter(self) -> $crate::BstIter<'a, K, V, $meta> {
+                self.iter()
+            }
+        }
+    };
+}
+
+/// Implement looking up keys for an ordered map that doesn't change its tree
+/// while doing so
+macro_rules! bst_lookup {
+    ($map:ident) => {
+        impl<K: Ord, V> $map<K, V> {
+            /// Get the value of a key
+            pub fn get<Q>(&self, key: &Q) -> Option<&V>
+                where K: ::core::borrow::Borrow<Q>,
+                      Q: Ord + ?Sized
+            {
+                self.tree.find(key).map(|x| &self.tree.nodes[x].value)
+            }
+
+            /// Get a mutable reference to the value of a key
+            pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+                where K: ::core::borrow::Borrow<Q>,
+                      Q: Ord + ?Sized
+            {
+                let x = self.tree.find(key)?;
+                Some(&mut self.tree.nodes[x].value)
+            }
+
+            pub fn contains_key<Q>(&self, key: &Q) -> bool
+                where K: ::core::borrow::Borrow<Q>,
+                      Q: Ord + ?Sized
+            {
+                self.tree.find(key).is_some()
+            }
+        }
+    };
+}
+
+#[test]
+fn tree_macro() {
+    use {ITree, NodeId};
+
+    let empty: ITree<u8> = tree!{};
+    assert!(empty.root().is_none());
+
+    let single = tree!{ "a" };
+    assert_eq!(*single.root().unwrap().value(), "a");
+    assert!(single.root().unwrap().children().is_empty());
+
+    // 1 -> 2 -> 4
+    // |    |--> 5 -> 6
+    // |--> 3
+    let tree = tree!{ 1 => [2 => [4, 5 => [6],], 3] };
+    let mut expected = ITree::new();
+    let root = expected.add_root(1).unwrap();
+    let two = expected.add_node(root, 2);
+    expected.add_node(two, 4);
+    let five = expected.add_node(two, 5);
+    expected.add_node(five, 6);
+    expected.add_node(root, 3);
+    assert_eq!(tree, expected);
+
+    // Values can be any expression
+    let tree = tree!{ 1 + 1 => [vec![1].len(), "ab".len()] };
+    assert_eq!(tree.get(NodeId::new(2)).unwrap().value(), &2);
+}
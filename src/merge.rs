@@ -0,0 +1,207 @@
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use diff::Check;
+use {GroveError, ITree, MTree, NodeId, TreeEdit};
+
+/// A change on the right side of a three-way merge that clashes with the left
+/// side
+///
+/// Conflicts are settled in favor of the left side and the right change gets
+/// left out of the merged tree. Nodes are named by their `NodeId` in the trees
+/// passed to `ITree::merge3`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict<T> {
+    /// Both sides changed the value of a node to different values
+    Update {
+        id: NodeId,
+        left: T,
+        right: T,
+    },
+    /// Both sides moved a node to different parents, or the moves would make a
+    /// node its own ancestor when put together
+    Move {
+        id: NodeId,
+    },
+    /// One side removed a node the other side changed, moved or added nodes
+    /// below
+    Delete {
+        id: NodeId,
+    },
+    /// Both sides added a root to an empty tree
+    Insert {
+        id: NodeId,
+    },
+}
+
+impl<T: PartialEq + Clone> ITree<T> {
+    /// Merge the changes `left` and `right` each made to `base` into one tree.
+    /// Returns the merged tree along with the right side changes that clashed
+    /// with the left side.
+    ///
+    /// Like `diff` this takes nodes with the same `NodeId` to be the same node,
+    /// so both sides should have been built from `base`, where nodes past the
+    /// end of `base` are new on each side. Nodes of `base` keep their
+    /// `NodeId`s in the merged tree.
+    pub fn merge3(base: &ITree<T>, left: &ITree<T>, right: &ITree<T>) -> (MTree<T>, Vec<Conflict<T>>) {
+        let mut tree = MTree::from(base.clone());
+        let mut conflicts = Vec::new();
+        let mut edits = base.diff(left);
+
+        // Nodes the left side touched, which the right side can't remove
+        let mut touched = BTreeSet::new();
+        for edit in &edits {
+            match *edit {
+                TreeEdit::Insert { id, .. } | TreeEdit::Update { id, .. } | TreeEdit::Move { id, .. } => {
+                    touched.insert(id);
+                }
+                TreeEdit::Delete { .. } => {}
+            }
+        }
+        // Give the nodes only the right side has `NodeId`s that come after the
+        // ones left uses
+        let offset = left.len().max(base.len()) - base.len();
+        let rename = |id: NodeId| if id.index() < base.len() { id } else { NodeId::new(id.index() + offset) };
+
+        {
+            let mut check = Check::new(&tree);
+            for edit in &edits {
+                check.edit(edit).expect("left edits apply to base");
+            }
+            // Right nodes that were left out, along with everything below them
+            let mut dropped = BTreeSet::new();
+            for edit in base.diff(right) {
+                let edit = match edit {
+                    TreeEdit::Insert { id, parent, index, value } => {
+                        let parent = parent.map(rename);
+                        if parent.is_some_and(|p| dropped.contains(&p)) {
+                            dropped.insert(rename(id));
+                            continue;
+                        }
+                        let index = match parent.map(|p| check.place(p)) {
+                            Some(Ok(place)) => index.min(place.children.len()),
+                            _ => index,
+                        };
+                        let new = TreeEdit::Insert { id: rename(id), parent, index, value };
+                        if let Err(e) = check.edit(&new) {
+                            dropped.insert(rename(id));
+                            conflicts.push(match e {
+                                GroveError::RootExists => Conflict::Insert { id },
+                                _ => Conflict::Delete { id: right.parents[id.index()].unwrap() },
+                            });
+                            continue;
+                        }
+                        new
+                    }
+                    TreeEdit::Delete { id } => {
+                        if check.place(id).is_err() {
+                            // Already gone on the left side
+                            continue;
+                        }
+                        let mut stack = vec![id];
+                        let mut clash = false;
+                        while let Some(x) = stack.pop() {
+                            clash |= touched.contains(&x);
+                            stack.extend(check.place(x).unwrap().children.iter().cloned());
+                        }
+                        if clash {
+                            conflicts.push(Conflict::Delete { id });
+                            continue;
+                        }
+                        check.edit(&edit).unwrap();
+                        edit
+                    }
+                    TreeEdit::Update { id, value } => {
+                        if check.place(id).is_err() {
+                            conflicts.push(Conflict::Delete { id });
+                            continue;
+                        }
+                        if left.values[id.index()] != base.values[id.index()] {
+                            let left = left.values[id.index()].clone();
+                            if left != value {
+                                conflicts.push(Conflict::Update { id, left, right: value });
+                            }
+                            continue;
+                        }
+                        TreeEdit::Update { id, value }
+                    }
+                    TreeEdit::Move { id, parent, index } => {
+                        let parent = rename(parent);
+                        if check.place(id).is_err() {
+                            conflicts.push(Conflict::Delete { id });
+                            continue;
+                        }
+                        if left.parents.get(id.index()).is_some_and(|&p| p != base.parents[id.index()]) {
+                            // The left side moved the node as well
+                            if left.parents[id.index()] != Some(parent) {
+                                conflicts.push(Conflict::Move { id });
+                            }
+                            continue;
+                        }
+                        let index = match check.place(parent) {
+                            Ok(place) => index.min(place.children.iter().filter(|&&c| c != id).count()),
+                            Err(_) => index,
+                        };
+                        let new = TreeEdit::Move { id, parent, index };
+                        if let Err(e) = check.edit(&new) {
+                            conflicts.push(match e {
+                                GroveError::InvalidNodeId(_) => Conflict::Delete { id },
+                                _ => Conflict::Move { id },
+                            });
+                            continue;
+                        }
+                        new
+                    }
+                };
+                edits.push(edit);
+            }
+        }
+        tree.apply(&edits).expect("merged edits were checked");
+        (tree, conflicts)
+    }
+}
+
+#[test]
+fn merge3() {
+    let n = NodeId::new;
+    // 0 -> 1 -> 3
+    // |--> 2 -> 4
+    // The tree macro hands out ids in pre-order, so 3 is n(2) and 2 is n(3)
+    let base = tree!{ 0 => [1 => [3], 2 => [4]] };
+    let values = |tree: &MTree<i32>, id: NodeId| -> Vec<i32> {
+        tree.get(id.into()).unwrap().children().iter().map(|&c| tree[c]).collect()
+    };
+
+    // Changes that don't touch the same nodes all make it in
+    let mut left = base.clone();
+    left.add_node(n(1), 5);
+    let mut right = base.clone();
+    right.add_node(n(3), 6);
+    right.add_node(n(5), 7);
+    let (merged, conflicts) = ITree::merge3(&base, &left, &right);
+    assert!(conflicts.is_empty());
+    assert_eq!(merged.len(), 8);
+    assert_eq!(values(&merged, n(1)), vec![3, 5]);
+    assert_eq!(values(&merged, n(3)), vec![4, 6]);
+
+    // Both sides changing the same value keeps the left one
+    let left = base.clone().map(|&v| if v == 3 { 30 } else { v });
+    let right = base.clone().map(|&v| if v == 3 { 31 } else if v == 4 { 40 } else { v });
+    let (merged, conflicts) = ITree::merge3(&base, &left, &right);
+    assert_eq!(conflicts, vec![Conflict::Update { id: n(2), left: 30, right: 31 }]);
+    assert_eq!((merged[n(2).into()], merged[n(4).into()]), (30, 40));
+
+    // Removing a node the other side added to is a conflict, the left side
+    // removing it wins
+    // 0 -> 1 -> 3
+    // |--> 2
+    let short = tree!{ 0 => [1 => [3], 2] };
+    let mut grown = base.clone();
+    grown.add_node(n(4), 5);
+    let (merged, conflicts) = ITree::merge3(&base, &grown, &short);
+    assert_eq!(conflicts, vec![Conflict::Delete { id: n(4) }]);
+    assert_eq!(values(&merged, n(4)), vec![5]);
+    let (merged, conflicts) = ITree::merge3(&base, &short, &grown);
+    assert_eq!(conflicts, vec![Conflict::Delete { id: n(4) }]);
+    assert_eq!(merged.len(), 4);
+}
@@ -0,0 +1,186 @@
+use alloc::vec::Vec;
+
+use {ITree, NodeId};
+
+/// Hash function for a `MerkleTree`
+///
+/// Leaves and inner nodes are hashed by separate functions, so hashers can
+/// keep the digest of a leaf from ever matching the digest of an inner node.
+pub trait MerkleHasher {
+    /// Result of hashing
+    type Digest: Clone + PartialEq;
+
+    /// Hash the data of a leaf
+    fn hash_leaf(&self, data: &[u8]) -> Self::Digest;
+
+    /// Hash the digests of two nodes next to each other into the digest of
+    /// their parent
+    fn hash_nodes(&self, left: &Self::Digest, right: &Self::Digest) -> Self::Digest;
+}
+
+/// Merkle Tree
+///
+/// A binary hash tree over a list of leaves, stored in an `ITree` of digests.
+/// Every inner node holds the hash of its two children. A level with an odd
+/// number of nodes moves its last node up to the next level as it is, instead
+/// of pairing it with a copy of itself.
+#[derive(Debug, Clone)]
+pub struct MerkleTree<H: MerkleHasher> {
+    hasher: H,
+    tree: ITree<H::Digest>,
+    leaves: Vec<NodeId>,
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    /// Hash the given leaves into a tree
+    pub fn new<I>(hasher: H, leaves: I) -> Self
+        where I: IntoIterator,
+              I::Item: AsRef<[u8]>
+    {
+        let mut levels = vec![leaves.into_iter().map(|l| hasher.hash_leaf(l.as_ref())).collect::<Vec<_>>()];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels.last().unwrap();
+            let up = level
+                .chunks(2)
+                .map(|pair| match *pair {
+                    [ref left, ref right] => hasher.hash_nodes(left, right),
+                    _ => pair[0].clone(),
+                })
+                .collect();
+            levels.push(up);
+        }
+
+        // Add the levels top down, the parent of node `x` on a level is node
+        // `x / 2` on the level above it
+        let mut tree = ITree::with_capacity(levels.iter().map(Vec::len).sum());
+        let mut above: Vec<NodeId> = Vec::new();
+        for level in levels.into_iter().rev() {
+            above = level
+                .into_iter()
+                .enumerate()
+                .map(|(x, digest)| match above.get(x / 2) {
+                    Some(&parent) => tree.add_node(parent, digest),
+                    None => tree.add_root(digest).unwrap(),
+                })
+                .collect();
+        }
+        MerkleTree { hasher, tree, leaves: above }
+    }
+
+    /// Get the hasher of the tree
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// Get the tree of digests
+    pub fn tree(&self) -> &ITree<H::Digest> {
+        &self.tree
+    }
+
+    /// Get the digest of the root, which is missing if there are no leaves
+    pub fn root(&self) -> Option<&H::Digest> {
+        self.tree.root().map(|r| r.value())
+    }
+
+    /// Get the number of leaves
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Get the digest of a leaf
+    pub fn leaf(&self, index: usize) -> Option<&H::Digest> {
+        self.leaves.get(index).map(|&id| &self.tree[id])
+    }
+
+    /// Get the proof that a leaf is part of the tree
+    pub fn proof(&self, index: usize) -> Option<MerkleProof<H::Digest>> {
+        let mut node = *self.leaves.get(index)?;
+        let mut path = Vec::new();
+        while let Some(parent) = self.tree.get(node).unwrap().parent() {
+            if let [left, right] = *self.tree.get(parent).unwrap().children() {
+                if left == node {
+                    path.push((self.tree[right].clone(), false));
+                } else {
+                    path.push((self.tree[left].clone(), true));
+                }
+            }
+            node = parent;
+        }
+        Some(MerkleProof { index, path })
+    }
+}
+
+/// Proof that a leaf is part of a `MerkleTree`
+///
+/// Holds the digests of the siblings on the way from the leaf up to the root,
+/// which is all it takes to hash the leaf up into the root digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof<D> {
+    index: usize,
+    /// Digests of siblings along with whether they are on the left
+    path: Vec<(D, bool)>,
+}
+
+impl<D: Clone + PartialEq> MerkleProof<D> {
+    /// Get the position of the leaf the proof is for
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Get the number of sibling digests in the proof
+    pub fn len(&self) -> usize {
+        self.path.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    /// Check that `data` is the leaf of the tree with the given root digest
+    pub fn verify<H>(&self, hasher: &H, data: &[u8], root: &D) -> bool
+        where H: MerkleHasher<Digest = D>
+    {
+        let digest = self.path.iter().fold(hasher.hash_leaf(data), |digest, &(ref sibling, left)| {
+            if left { hasher.hash_nodes(sibling, &digest) } else { hasher.hash_nodes(&digest, sibling) }
+        });
+        digest == *root
+    }
+}
+
+#[test]
+fn merkle() {
+    /// Writes out what got hashed instead of hashing it
+    struct Spell;
+    impl MerkleHasher for Spell {
+        type Digest = Vec<u8>;
+
+        fn hash_leaf(&self, data: &[u8]) -> Vec<u8> {
+            data.to_vec()
+        }
+
+        fn hash_nodes(&self, left: &Vec<u8>, right: &Vec<u8>) -> Vec<u8> {
+            [&b"("[..], left, &b" "[..], right, &b")"[..]].concat()
+        }
+    }
+
+    let leaves = ["a", "b", "c", "d", "e"];
+    let tree = MerkleTree::new(Spell, &leaves);
+    assert_eq!(tree.len(), 5);
+    assert_eq!(tree.tree().len(), 11);
+    assert_eq!(tree.root().unwrap(), b"(((a b) (c d)) e)");
+    assert_eq!(tree.leaf(2).unwrap(), b"c");
+
+    let root = tree.root().unwrap();
+    for (x, leaf) in leaves.iter().enumerate() {
+        let proof = tree.proof(x).unwrap();
+        assert!(proof.verify(&Spell, leaf.as_bytes(), root));
+        assert!(!proof.verify(&Spell, b"f", root));
+    }
+    assert_eq!(tree.proof(4).unwrap().len(), 1);
+    assert!(tree.proof(5).is_none());
+    assert!(MerkleTree::new(Spell, Vec::<&[u8]>::new()).root().is_none());
+}
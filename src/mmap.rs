@@ -0,0 +1,247 @@
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Range;
+
+use alloc::vec::Vec;
+
+use {GroveError, ITree};
+
+/// Plain values of a fixed size that an `MmapTree` can read in place
+pub trait Pod: Copy {
+    /// Number of bytes of every value
+    const SIZE: usize;
+
+    /// Read a value from exactly `SIZE` bytes
+    fn read(bytes: &[u8]) -> Self;
+
+    fn write(&self, out: &mut Vec<u8>);
+}
+
+macro_rules! pod {
+    ($($t:ty)*) => {$(
+        impl Pod for $t {
+            const SIZE: usize = core::mem::size_of::<$t>();
+
+            fn read(bytes: &[u8]) -> Self {
+                <$t>::from_le_bytes(bytes.try_into().unwrap())
+            }
+
+            fn write(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+    )*};
+}
+
+pod!(u8 u16 u32 u64 u128 i8 i16 i32 i64 i128 f32 f64);
+
+const MAGIC: &[u8; 8] = b"grovemap";
+/// Magic, number of nodes and size of a value
+const HEADER: usize = 8 + 8 + 4;
+/// Parent and subtree length of a node
+const ENTRY: usize = 8;
+const NO_PARENT: u32 = u32::MAX;
+
+impl<T: Pod> ITree<T> {
+    /// Write the tree in the flat format an `MmapTree` reads
+    ///
+    /// After a header every node gets the position of its parent and the
+    /// length of its subtree as little endian `u32`s, in pre-order, and then
+    /// come all values in the same order. Any node can be found from its
+    /// position without reading the ones before it.
+    ///
+    /// # Panics
+    ///
+    /// If the tree has `u32::MAX` nodes or more.
+    pub fn to_mmap_bytes(&self) -> Vec<u8> {
+        let len = self.len();
+        assert!(len < NO_PARENT as usize, "too many nodes for an MmapTree");
+        let mut out = Vec::with_capacity(HEADER + len * (ENTRY + T::SIZE));
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&(len as u64).to_le_bytes());
+        out.extend_from_slice(&(T::SIZE as u32).to_le_bytes());
+        let order: Vec<_> = self.root_id().into_iter().flat_map(|root| self.iter_dfs(root)).collect();
+        let mut positions = vec![0; len];
+        for (k, &(id, _)) in order.iter().enumerate() {
+            positions[id.index()] = k;
+        }
+        let sizes = self.subtree_sizes();
+        for &(id, node) in &order {
+            let parent = node.parent().map_or(NO_PARENT, |p| positions[p.index()] as u32);
+            out.extend_from_slice(&parent.to_le_bytes());
+            out.extend_from_slice(&(sizes.get(id).unwrap() as u32).to_le_bytes());
+        }
+        for &(_, node) in &order {
+            node.value().write(&mut out);
+        }
+        out
+    }
+}
+
+/// Memory-mapped Tree
+///
+/// Read-only view of a tree written by `ITree::to_mmap_bytes` that answers
+/// queries right from the bytes. `new` only checks the header and the length,
+/// and every query only reads the entries it needs, so large trees don't have
+/// to be decoded first. The varint format of `ITree::to_bytes` can only be
+/// read from the start, so the view has a layout of its own with fixed size
+/// entries.
+///
+/// Nothing in this crate opens or maps a file. Map it with a crate for memory
+/// maps, or read it into memory, and hand the bytes to `new`. The entries are
+/// not checked, so a broken file gives wrong answers, but it never leads to a
+/// position past the end of the tree.
+///
+/// Nodes are numbered by their place in pre-order, so the subtree of a node
+/// is a range of positions starting at the node.
+#[derive(Clone, Copy)]
+pub struct MmapTree<'a, T> {
+    len: usize,
+    entries: &'a [u8],
+    values: &'a [u8],
+    value: PhantomData<T>,
+}
+
+impl<'a, T: Pod> MmapTree<'a, T> {
+    /// Open a tree from its bytes, checking the header and the length. The
+    /// error has the position of the first byte that doesn't fit.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, GroveError> {
+        if bytes.len() < HEADER {
+            return Err(GroveError::InvalidEncoding(bytes.len()));
+        }
+        if &bytes[..8] != MAGIC {
+            return Err(GroveError::InvalidEncoding(0));
+        }
+        let len = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        if u32::from_le_bytes(bytes[16..20].try_into().unwrap()) as usize != T::SIZE {
+            return Err(GroveError::InvalidEncoding(16));
+        }
+        let len = match usize::try_from(len) {
+            Ok(len) if len < NO_PARENT as usize => len,
+            _ => return Err(GroveError::InvalidEncoding(8)),
+        };
+        match len.checked_mul(ENTRY + T::SIZE).and_then(|n| n.checked_add(HEADER)) {
+            Some(end) if end == bytes.len() => {}
+            Some(end) => return Err(GroveError::InvalidEncoding(end.min(bytes.len()))),
+            None => return Err(GroveError::InvalidEncoding(8)),
+        }
+        let (entries, values) = bytes[HEADER..].split_at(len * ENTRY);
+        Ok(MmapTree { len, entries, values, value: PhantomData })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the value of a node
+    pub fn get(&self, node: usize) -> Option<T> {
+        if node >= self.len {
+            return None;
+        }
+        Some(T::read(&self.values[node * T::SIZE..(node + 1) * T::SIZE]))
+    }
+
+    /// Get the parent of a node, or `None` for the root or if the node does
+    /// not exist
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        // A parent always comes before its children in pre-order
+        Some(self.entry(node)?.0 as usize).filter(|&parent| parent < node)
+    }
+
+    /// Get the number of nodes in the subtree of a node, counting the node.
+    /// Zero if the node does not exist.
+    pub fn subtree_len(&self, node: usize) -> usize {
+        self.entry(node).map_or(0, |(_, len)| (len as usize).min(self.len - node))
+    }
+
+    /// Get the positions of the subtree of a node in pre-order, the node
+    /// first. Empty if the node does not exist.
+    pub fn descendants(&self, node: usize) -> Range<usize> {
+        node..node + self.subtree_len(node)
+    }
+
+    /// Iterate over the children of a node
+    pub fn children(&self, node: usize) -> MmapChildren<'a, T> {
+        let range = self.descendants(node);
+        MmapChildren { tree: *self, next: range.start + 1, end: range.end }
+    }
+
+    fn entry(&self, node: usize) -> Option<(u32, u32)> {
+        let entry = self.entries.get(node * ENTRY..(node + 1) * ENTRY)?;
+        Some((u32::from_le_bytes(entry[..4].try_into().unwrap()), u32::from_le_bytes(entry[4..].try_into().unwrap())))
+    }
+}
+
+impl<'a, T> fmt::Debug for MmapTree<'a, T> {
+    /// Show the size instead of the possibly huge mapping
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MmapTree").field("len", &self.len).finish()
+    }
+}
+
+/// Iterator over the children of a node of an `MmapTree`
+#[derive(Debug, Clone)]
+pub struct MmapChildren<'a, T> {
+    tree: MmapTree<'a, T>,
+    next: usize,
+    end: usize,
+}
+
+impl<'a, T: Pod> Iterator for MmapChildren<'a, T> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.next >= self.end {
+            return None;
+        }
+        let node = self.next;
+        // A broken file could claim an empty subtree, which must not get stuck
+        self.next += self.tree.subtree_len(node).max(1);
+        Some(node)
+    }
+}
+
+#[test]
+fn mmap_tree() {
+    let mut tree = ITree::new();
+    // 0 -> 1 -> 3
+    // |--> 2
+    let root = tree.add_root(1.5f64).unwrap();
+    let one = tree.add_node(root, 2.5);
+    tree.add_node(root, 3.5);
+    tree.add_node(one, 4.5);
+
+    let bytes = tree.to_mmap_bytes();
+    assert_eq!(bytes.len(), HEADER + 4 * (ENTRY + 8));
+    let mapped = MmapTree::<f64>::new(&bytes).unwrap();
+    assert_eq!(mapped.len(), 4);
+    // Pre-order puts the child of 1 before its sibling
+    let values: Vec<_> = (0..4).map(|k| mapped.get(k).unwrap()).collect();
+    assert_eq!(values, [1.5, 2.5, 4.5, 3.5]);
+    assert!(mapped.children(0).eq(vec![1, 3]));
+    assert_eq!(mapped.children(2).next(), None);
+    assert_eq!(mapped.parent(2), Some(1));
+    assert_eq!(mapped.parent(0), None);
+    assert_eq!(mapped.descendants(1), 1..3);
+    assert_eq!(mapped.get(4), None);
+    assert_eq!(mapped.subtree_len(4), 0);
+
+    assert_eq!(MmapTree::<f32>::new(&bytes).unwrap_err(), GroveError::InvalidEncoding(16));
+    assert_eq!(MmapTree::<f64>::new(&bytes[..50]).unwrap_err(), GroveError::InvalidEncoding(50));
+    assert_eq!(MmapTree::<f64>::new(&bytes[1..]).unwrap_err(), GroveError::InvalidEncoding(0));
+    // Entries that point past the end of the tree get cut off
+    let mut broken = bytes.clone();
+    broken[HEADER + ENTRY + 4..HEADER + 2 * ENTRY].copy_from_slice(&u32::MAX.to_le_bytes());
+    broken[HEADER + 2 * ENTRY..HEADER + 2 * ENTRY + 4].copy_from_slice(&7u32.to_le_bytes());
+    let broken = MmapTree::<f64>::new(&broken).unwrap();
+    assert_eq!(broken.descendants(1), 1..4);
+    assert!(broken.children(1).eq(vec![2, 3]));
+    assert_eq!(broken.parent(2), None);
+    let empty = ITree::<u8>::new().to_mmap_bytes();
+    assert!(MmapTree::<u8>::new(&empty).unwrap().is_empty());
+}
@@ -0,0 +1,669 @@
+use core::fmt;
+use core::mem;
+use core::ops::{Index, IndexMut};
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use {GroveError, INode, ITree, Idx, NodeId};
+
+/// Mutable Tree
+///
+/// A Tree that works like `ITree` but also allows the values of its nodes to
+/// be updated in place, nodes to be removed and subtrees to be moved around
+/// with `detach` and `attach`.
+///
+/// Removing nodes frees their slot in the tree and the `SlotId`s of removed
+/// nodes stop being valid. Freed slots are reused by later insertions, but
+/// every reuse bumps the generation of the slot, so `SlotId`s of removed nodes
+/// never refer to the nodes inserted after them.
+#[derive(Debug, Clone)]
+pub struct MTree<T> {
+    nodes: Vec<Slot<T>>,
+    /// Slots of removed nodes that can be reused
+    free: Vec<usize>,
+    root: Option<SlotId>,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Slot<T> {
+    generation: u32,
+    /// Highest generation the slot ever had. Restoring a node can take the
+    /// generation back, but the next node in the slot still gets a new one.
+    highest: u32,
+    node: Option<INode<T, SlotId>>,
+}
+
+/// Identifies a node in an `MTree`
+///
+/// Besides the position of the node a `SlotId` carries the generation of its
+/// slot, so stale ids of removed nodes don't get mixed up with the nodes that
+/// take their place later on. A `NodeId` of an `ITree` turns into the `SlotId`
+/// its node gets in `MTree::from`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SlotId {
+    index: Idx,
+    generation: u32,
+}
+
+impl SlotId {
+    pub(crate) fn new(index: usize, generation: u32) -> Self {
+        SlotId { index: NodeId::new(index).index, generation }
+    }
+
+    /// Get the position of the slot
+    pub(crate) fn index(self) -> usize {
+        self.id().index()
+    }
+
+    /// Get the position of the slot as a `NodeId`, dropping the generation
+    pub(crate) fn id(self) -> NodeId {
+        NodeId { index: self.index }
+    }
+
+    pub(crate) fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+impl From<NodeId> for SlotId {
+    fn from(id: NodeId) -> Self {
+        SlotId { index: id.index, generation: 0 }
+    }
+}
+
+impl fmt::Debug for SlotId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SlotId({}v{})", self.index, self.generation)
+    }
+}
+
+impl<T> MTree<T> {
+    /// Create a new empty `MTree`
+    pub fn new() -> Self {
+        Self { nodes: Vec::new(), free: Vec::new(), root: None, len: 0 }
+    }
+
+    /// Create a new empty tree with room for `capacity` nodes
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { nodes: Vec::with_capacity(capacity), free: Vec::new(), root: None, len: 0 }
+    }
+
+    /// Get the number of nodes the tree can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity() - self.nodes.len() + self.free.len() + self.len
+    }
+
+    /// Reserve room for at least `additional` more nodes
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional.saturating_sub(self.free.len()));
+    }
+
+    /// Reserve room for at least `additional` more children of a node
+    pub fn reserve_children(&mut self, node: SlotId, additional: usize) -> Result<(), GroveError> {
+        match self.inode_mut(node) {
+            Some(node) => node.children.reserve(additional),
+            None => return Err(GroveError::InvalidNodeId(node.id())),
+        }
+        Ok(())
+    }
+
+    /// Shrink the memory used by the tree and the children of every node as
+    /// much as possible. Free slots are kept so `SlotId`s of removed nodes stay
+    /// invalid.
+    pub fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+        self.free.shrink_to_fit();
+        for node in self.nodes.iter_mut().filter_map(|slot| slot.node.as_mut()) {
+            node.children.shrink_to_fit();
+        }
+    }
+
+    /// Get the `SlotId` of the root node if it exists
+    pub fn root_id(&self) -> Option<SlotId> {
+        self.root
+    }
+
+    /// Get the root node if it exists
+    pub fn root(&self) -> Option<&INode<T, SlotId>> {
+        self.get(self.root?)
+    }
+
+    /// Get the number of nodes in the tree
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if the tree has no nodes, counting detached ones
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Check if the tree has a root. Detaching the root leaves the tree
+    /// without one even though its nodes are still there.
+    pub fn has_root(&self) -> bool {
+        self.root.is_some()
+    }
+
+    /// Get the `INode` of the given `Id` if it exists
+    pub fn get(&self, node: SlotId) -> Option<&INode<T, SlotId>> {
+        let slot = self.nodes.get(node.index())?;
+        if slot.generation != node.generation {
+            return None;
+        }
+        slot.node.as_ref()
+    }
+
+    /// Get a mutable reference to the value of the given `Id` if it exists
+    pub fn get_mut(&mut self, node: SlotId) -> Option<&mut T> {
+        self.inode_mut(node).map(|n| &mut n.value)
+    }
+
+    /// Replace the value of a node, returning the old value or an error if the
+    /// node does not exist
+    pub fn set(&mut self, node: SlotId, value: T) -> Result<T, GroveError> {
+        match self.get_mut(node) {
+            Some(old) => Ok(mem::replace(old, value)),
+            None => Err(GroveError::InvalidNodeId(node.id())),
+        }
+    }
+
+    /// Adds the root node to an empty tree. Returns the `SlotId` of the root or
+    /// an error if the tree already has one.
+    pub fn add_root(&mut self, value: T) -> Result<SlotId, GroveError> {
+        if self.root.is_some() {
+            return Err(GroveError::RootExists);
+        }
+        let id = self.alloc(INode::new(value, None));
+        self.root = Some(id);
+        Ok(id)
+    }
+
+    /// Adds a child to a node in the tree. If the tree is empty it discards the
+    /// given `SlotId` and creates the root node with the given value. Returns
+    /// the `SlotId` of the inserted node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `SlotId` does not refer to a node in the tree. See
+    /// `try_add_node` for a version that returns an error instead.
+    pub fn add_node(&mut self, node: SlotId, value: T) -> SlotId {
+        match self.try_add_node(node, value) {
+            Ok(id) => id,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Adds a child to a node in the tree like `add_node`, but returns an error
+    /// rather than panicking if the `SlotId` does not refer to a node in the
+    /// tree.
+    pub fn try_add_node(&mut self, node: SlotId, value: T) -> Result<SlotId, GroveError> {
+        // Detaching the root leaves nodes behind without a root
+        if self.len == 0 {
+            return self.add_root(value);
+        }
+        if self.get(node).is_none() {
+            return Err(GroveError::InvalidNodeId(node.id()));
+        }
+        let index = self.alloc(INode::new(value, Some(node)));
+        self.inode_mut(node).unwrap().insert(index);
+        Ok(index)
+    }
+
+    /// Removes a single node from the tree and returns its value. The children
+    /// of the removed node take its place in its parent's children. The root
+    /// can only be removed if it has at most one child, which then becomes the
+    /// new root.
+    pub fn remove_node(&mut self, node: SlotId) -> Result<T, GroveError> {
+        let (parent, children) = match self.get(node) {
+            Some(n) => (n.parent, n.children.clone()),
+            None => return Err(GroveError::InvalidNodeId(node.id())),
+        };
+        match parent {
+            Some(parent) => {
+                for &child in &children {
+                    self.inode_mut(child).unwrap().parent = Some(parent);
+                }
+                let siblings = &mut self.inode_mut(parent).unwrap().children;
+                let pos = siblings.iter().position(|&c| c == node).unwrap();
+                siblings.remove(pos);
+                for (i, child) in children.into_iter().enumerate() {
+                    siblings.insert(pos + i, child);
+                }
+            }
+            None => {
+                let is_root = self.root == Some(node);
+                if is_root && children.len() > 1 {
+                    return Err(GroveError::MultipleRoots);
+                }
+                // Children of a detached node become detached themselves
+                for &child in &children {
+                    self.inode_mut(child).unwrap().parent = None;
+                }
+                if is_root {
+                    self.root = children.first().cloned();
+                }
+            }
+        }
+        Ok(self.free_slot(node).value)
+    }
+
+    /// Removes a node along with all of its descendants and returns them as a
+    /// new `ITree` rooted at the removed node. `NodeId`s in the returned tree
+    /// are assigned in pre-order starting from the root.
+    pub fn remove_subtree(&mut self, node: SlotId) -> Result<ITree<T>, GroveError> {
+        let parent = match self.get(node) {
+            Some(n) => n.parent,
+            None => return Err(GroveError::InvalidNodeId(node.id())),
+        };
+        match parent {
+            Some(parent) => self.inode_mut(parent).unwrap().children.retain(|c| *c != node),
+            None if self.root == Some(node) => self.root = None,
+            None => {}
+        }
+
+        let mut tree = ITree::new();
+        // Nodes to move along with their parent in the new tree
+        let mut stack = vec![(node, None)];
+        while let Some((id, new_parent)) = stack.pop() {
+            let removed = self.free_slot(id);
+            let new_id = match new_parent {
+                Some(p) => tree.add_node(p, removed.value),
+                None => tree.add_root(removed.value).unwrap(),
+            };
+            stack.extend(removed.children.iter().rev().map(|&c| (c, Some(new_id))));
+        }
+        Ok(tree)
+    }
+
+    /// Detaches a node and its descendants from its parent. The subtree stays in
+    /// the tree and can be accessed through its `SlotId`s, but isn't reachable
+    /// from the root anymore until it is put back with `attach`. Detaching the
+    /// root leaves the tree without one.
+    pub fn detach(&mut self, node: SlotId) -> Result<(), GroveError> {
+        let parent = match self.inode_mut(node) {
+            Some(n) => n.parent.take(),
+            None => return Err(GroveError::InvalidNodeId(node.id())),
+        };
+        match parent {
+            Some(parent) => self.inode_mut(parent).unwrap().children.retain(|c| *c != node),
+            None if self.root == Some(node) => self.root = None,
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Attaches a detached node as the last child of `parent`. Fails if the node
+    /// isn't detached or if `parent` is part of the node's own subtree.
+    pub fn attach(&mut self, parent: SlotId, node: SlotId) -> Result<(), GroveError> {
+        match self.get(node) {
+            Some(n) if n.parent.is_some() || self.root == Some(node) => {
+                return Err(GroveError::AlreadyAttached(node.id()))
+            }
+            Some(_) => {}
+            None => return Err(GroveError::InvalidNodeId(node.id())),
+        }
+        // Walk up from the new parent to make sure we don't create a cycle
+        let mut current = Some(parent);
+        while let Some(id) = current {
+            if id == node {
+                return Err(GroveError::Cycle);
+            }
+            current = match self.get(id) {
+                Some(n) => n.parent,
+                None => return Err(GroveError::InvalidNodeId(id.id())),
+            };
+        }
+        self.inode_mut(parent).unwrap().insert(node);
+        self.inode_mut(node).unwrap().parent = Some(parent);
+        Ok(())
+    }
+
+    /// Makes a node the root of its tree by turning every parent on the way up
+    /// into a child of the node below it. Old parents go after the other
+    /// children of their new parent. A node in a detached subtree becomes the
+    /// top of that subtree instead.
+    pub fn reroot(&mut self, new_root: SlotId) -> Result<(), GroveError> {
+        if self.get(new_root).is_none() {
+            return Err(GroveError::InvalidNodeId(new_root.id()));
+        }
+        let mut node = new_root;
+        let mut parent = self.inode_mut(node).unwrap().parent.take();
+        while let Some(p) = parent {
+            let p_node = self.inode_mut(p).unwrap();
+            p_node.children.retain(|c| *c != node);
+            parent = p_node.parent.replace(node);
+            self.inode_mut(node).unwrap().insert(p);
+            node = p;
+        }
+        // `node` is the old top of the tree
+        if self.root == Some(node) {
+            self.root = Some(new_root);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn inode_mut(&mut self, node: SlotId) -> Option<&mut INode<T, SlotId>> {
+        let slot = self.nodes.get_mut(node.index())?;
+        if slot.generation != node.generation {
+            return None;
+        }
+        slot.node.as_mut()
+    }
+
+    /// Get the `SlotId` of the node at a position if the slot has one
+    pub(crate) fn slot(&self, x: NodeId) -> Option<SlotId> {
+        let slot = self.nodes.get(x.index())?;
+        slot.node.as_ref().map(|_| SlotId::new(x.index(), slot.generation))
+    }
+
+    /// Put removed nodes back into their old slots, so the `SlotId`s they had
+    /// before are valid again. The caller has to link them up with the rest of
+    /// the tree. Nodes added to the slots after them keep their `SlotId`s
+    /// invalid, since freeing a slot again moves past every generation it had.
+    pub(crate) fn restore<I>(&mut self, nodes: I)
+        where I: IntoIterator<Item = (SlotId, INode<T, SlotId>)>
+    {
+        let mut restored = BTreeSet::new();
+        for (node, inode) in nodes {
+            let x = node.index();
+            restored.insert(x);
+            let slot = &mut self.nodes[x];
+            slot.generation = node.generation;
+            slot.node = Some(inode);
+            self.len += 1;
+        }
+        self.free.retain(|free| !restored.contains(free));
+    }
+
+    /// Add a child at `index` among the children of `parent`, which has to be
+    /// in the tree
+    pub(crate) fn insert_child(&mut self, parent: SlotId, index: usize, value: T) -> SlotId {
+        let id = self.alloc(INode::new(value, Some(parent)));
+        self.inode_mut(parent).unwrap().children.insert(index, id);
+        id
+    }
+
+    /// Make a node the root of the tree
+    pub(crate) fn set_root(&mut self, root: Option<SlotId>) {
+        self.root = root;
+    }
+
+    /// Store a node in a free slot, reusing old slots first
+    fn alloc(&mut self, node: INode<T, SlotId>) -> SlotId {
+        self.len += 1;
+        match self.free.pop() {
+            Some(x) => {
+                let slot = &mut self.nodes[x];
+                slot.node = Some(node);
+                SlotId::new(x, slot.generation)
+            }
+            None => {
+                self.nodes.push(Slot { generation: 0, highest: 0, node: Some(node) });
+                SlotId::new(self.nodes.len() - 1, 0)
+            }
+        }
+    }
+
+    /// Take a node out of its slot and mark the slot as free
+    fn free_slot(&mut self, node: SlotId) -> INode<T, SlotId> {
+        let x = node.index();
+        let slot = &mut self.nodes[x];
+        self.len -= 1;
+        // Slots that ran out of generations are never used again
+        if let Some(generation) = slot.highest.checked_add(1) {
+            slot.generation = generation;
+            slot.highest = generation;
+            self.free.push(x);
+        }
+        slot.node.take().unwrap()
+    }
+}
+
+impl<T> Default for MTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<SlotId> for MTree<T> {
+    type Output = T;
+
+    /// Get the value of a node
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `SlotId` does not refer to a node in the tree.
+    fn index(&self, node: SlotId) -> &T {
+        match self.get(node) {
+            Some(node) => &node.value,
+            None => panic!("{}", GroveError::InvalidNodeId(node.id())),
+        }
+    }
+}
+
+impl<T> IndexMut<SlotId> for MTree<T> {
+    /// Get a mutable reference to the value of a node
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `SlotId` does not refer to a node in the tree.
+    fn index_mut(&mut self, node: SlotId) -> &mut T {
+        match self.get_mut(node) {
+            Some(value) => value,
+            None => panic!("{}", GroveError::InvalidNodeId(node.id())),
+        }
+    }
+}
+
+impl<T> From<ITree<T>> for MTree<T> {
+    /// Convert an `ITree` into an `MTree`, every node keeps its position
+    fn from(tree: ITree<T>) -> Self {
+        Self {
+            root: tree.root_id().map(SlotId::from),
+            len: tree.len(),
+            nodes: tree.values
+                .into_iter()
+                .zip(tree.parents)
+                .zip(tree.children)
+                .map(|((value, parent), children)| Slot {
+                    generation: 0,
+                    highest: 0,
+                    node: Some(INode {
+                        value,
+                        parent: parent.map(SlotId::from),
+                        children: children.iter().map(|&c| SlotId::from(c)).collect(),
+                    }),
+                })
+                .collect(),
+            free: Vec::new(),
+        }
+    }
+}
+
+#[test]
+fn update() {
+    let mut tree = MTree::new();
+    // 0 -> 1
+    // |--> 2
+    let root = tree.add_root(0).unwrap();
+    let one = tree.add_node(root, 1);
+    let two = tree.add_node(root, 2);
+
+    *tree.get_mut(one).unwrap() += 10;
+    assert_eq!(*tree.get(one).unwrap().value(), 11);
+
+    assert_eq!(tree.set(two, 20), Ok(2));
+    assert_eq!(*tree.get(two).unwrap().value(), 20);
+    assert_eq!(tree.set(SlotId::new(3, 0), 30), Err(GroveError::InvalidNodeId(NodeId::new(3))));
+    assert!(tree.get_mut(SlotId::new(3, 0)).is_none());
+
+    // Structure is untouched by updates
+    assert_eq!(tree.root().unwrap().children(), &vec![one, two]);
+
+    let mut itree = ITree::new();
+    let root = itree.add_root("a").unwrap();
+    let child = itree.add_node(root, "b");
+    let mut tree = MTree::from(itree);
+    let (root, child) = (SlotId::from(root), SlotId::from(child));
+    tree.set(child, "c").unwrap();
+    assert_eq!(*tree.get(child).unwrap().value(), "c");
+    assert_eq!(tree.get(child).unwrap().parent(), Some(root));
+}
+
+#[test]
+fn remove() {
+    let mut tree = MTree::new();
+    // 0 -> 1 -> 3
+    // |    |--> 4
+    // |--> 2 -> 5
+    let root = tree.add_root(0).unwrap();
+    let one = tree.add_node(root, 1);
+    let two = tree.add_node(root, 2);
+    let three = tree.add_node(one, 3);
+    let four = tree.add_node(one, 4);
+    let five = tree.add_node(two, 5);
+
+    // Children are spliced into the parent where the removed node was
+    assert_eq!(tree.remove_node(one), Ok(1));
+    assert!(tree.get(one).is_none());
+    assert_eq!(tree.root().unwrap().children(), &vec![three, four, two]);
+    assert_eq!(tree.get(three).unwrap().parent(), Some(root));
+    assert_eq!(tree.len(), 5);
+
+    // Freed slots get reused, but old ids don't work for the new node
+    let six = tree.add_node(five, 6);
+    assert_eq!(six.index(), one.index());
+    assert_ne!(six, one);
+    assert!(tree.get(one).is_none());
+    assert_eq!(*tree.get(six).unwrap().value(), 6);
+
+    let sub = tree.remove_subtree(two).unwrap();
+    assert_eq!(sub.iter_dfs(NodeId::new(0)).map(|(_, n)| *n.value()).collect::<Vec<_>>(), vec![2, 5, 6]);
+    assert_eq!(tree.root().unwrap().children(), &vec![three, four]);
+    assert!(tree.get(five).is_none());
+    assert_eq!(tree.len(), 3);
+
+    // The root can only go if it leaves a single root behind
+    assert_eq!(tree.remove_node(root), Err(GroveError::MultipleRoots));
+    tree.remove_node(four).unwrap();
+    assert_eq!(tree.remove_node(root), Ok(0));
+    assert_eq!(tree.root_id(), Some(three));
+    assert_eq!(tree.root().unwrap().parent(), None);
+
+    assert_eq!(tree.remove_subtree(three).unwrap().iter_dfs(NodeId::new(0)).count(), 1);
+    assert!(tree.is_empty());
+    assert!(!tree.has_root());
+    assert_eq!(tree.remove_subtree(three), Err(GroveError::InvalidNodeId(three.id())));
+}
+
+#[test]
+fn detach_attach() {
+    let mut tree = MTree::new();
+    // 0 -> 1 -> 3
+    // |--> 2
+    let root = tree.add_root(0).unwrap();
+    let one = tree.add_node(root, 1);
+    let two = tree.add_node(root, 2);
+    let three = tree.add_node(one, 3);
+
+    tree.detach(one).unwrap();
+    assert_eq!(tree.root().unwrap().children(), &vec![two]);
+    assert_eq!(tree.get(one).unwrap().parent(), None);
+    assert_eq!(tree.get(one).unwrap().children(), &vec![three]);
+    assert_eq!(tree.len(), 4);
+
+    // 0 -> 2 -> 1 -> 3
+    assert_eq!(tree.attach(three, one), Err(GroveError::Cycle));
+    assert_eq!(tree.attach(one, two), Err(GroveError::AlreadyAttached(two.id())));
+    assert_eq!(tree.attach(root, SlotId::new(9, 0)), Err(GroveError::InvalidNodeId(NodeId::new(9))));
+    tree.attach(two, one).unwrap();
+    assert_eq!(tree.get(two).unwrap().children(), &vec![one]);
+    assert_eq!(tree.get(one).unwrap().parent(), Some(two));
+
+    // Removing a detached node leaves its children detached
+    tree.detach(one).unwrap();
+    tree.remove_node(one).unwrap();
+    assert_eq!(tree.root_id(), Some(root));
+    assert_eq!(tree.get(three).unwrap().parent(), None);
+    tree.attach(root, three).unwrap();
+    assert_eq!(tree.root().unwrap().children(), &vec![two, three]);
+
+    tree.detach(root).unwrap();
+    assert!(!tree.has_root());
+    assert!(!tree.is_empty());
+    assert_eq!(tree.len(), 3);
+    assert_eq!(tree.attach(two, root), Err(GroveError::Cycle));
+    // Adding to a detached node keeps the parent instead of making a root
+    let four = tree.add_node(root, 4);
+    assert_eq!(tree.get(four).unwrap().parent(), Some(root));
+    assert!(!tree.has_root());
+    assert_eq!(tree.try_add_node(SlotId::new(9, 0), 5), Err(GroveError::InvalidNodeId(NodeId::new(9))));
+}
+
+#[test]
+fn reroot() {
+    // 0 -> 1 -> 3 -> 4
+    // |    |--> 5
+    // |--> 2
+    let mut tree = MTree::new();
+    let root = tree.add_root(0).unwrap();
+    let one = tree.add_node(root, 1);
+    tree.add_node(root, 2);
+    let three = tree.add_node(one, 3);
+    tree.add_node(three, 4);
+    tree.add_node(one, 5);
+    let id = |x| SlotId::new(x, 0);
+    tree.reroot(id(3)).unwrap();
+    // 3 -> 4
+    // |--> 1 -> 5
+    //      |--> 0 -> 2
+    assert_eq!(tree.root_id(), Some(id(3)));
+    assert_eq!(tree.root().unwrap().parent(), None);
+    assert_eq!(tree.root().unwrap().children(), &vec![id(4), id(1)]);
+    assert_eq!(tree.get(id(1)).unwrap().children(), &vec![id(5), id(0)]);
+    assert_eq!(tree.get(id(0)).unwrap().children(), &vec![id(2)]);
+    assert_eq!(tree.get(id(0)).unwrap().parent(), Some(id(1)));
+    assert_eq!(tree.get(id(1)).unwrap().parent(), Some(id(3)));
+    assert_eq!(tree.len(), 6);
+
+    // Rerooting at the root changes nothing
+    tree.reroot(id(3)).unwrap();
+    assert_eq!(tree.root().unwrap().children(), &vec![id(4), id(1)]);
+
+    // Detached subtrees get a new top but the root stays
+    tree.detach(id(1)).unwrap();
+    tree.reroot(id(2)).unwrap();
+    assert_eq!(tree.root_id(), Some(id(3)));
+    assert_eq!(tree.get(id(2)).unwrap().children(), &vec![id(0)]);
+    assert_eq!(tree.get(id(1)).unwrap().parent(), Some(id(0)));
+    assert_eq!(tree.reroot(id(9)), Err(GroveError::InvalidNodeId(id(9).id())));
+}
+
+#[test]
+fn index() {
+    let mut tree = MTree::from(tree!{ 1 => [2] });
+    tree[SlotId::from(NodeId::new(1))] += 1;
+    assert_eq!(tree[SlotId::from(NodeId::new(0))], 1);
+    assert_eq!(tree[SlotId::from(NodeId::new(1))], 3);
+    tree.remove_node(SlotId::new(1, 0)).unwrap();
+    assert!(::std::panic::catch_unwind(|| tree[SlotId::from(NodeId::new(1))]).is_err());
+}
+
+#[test]
+fn capacity() {
+    let mut tree = MTree::with_capacity(4);
+    assert!(tree.capacity() >= 4);
+    let root = tree.add_root(0).unwrap();
+    let one = tree.add_node(root, 1);
+    tree.reserve_children(one, 8).unwrap();
+    tree.remove_node(one).unwrap();
+    assert_eq!(tree.reserve_children(one, 1), Err(GroveError::InvalidNodeId(one.id())));
+
+    // Free slots count towards the capacity
+    tree.shrink_to_fit();
+    assert_eq!(tree.capacity(), 2);
+    tree.reserve(3);
+    assert!(tree.capacity() >= 4);
+}
@@ -0,0 +1,230 @@
+use core::fmt::Write;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use visit::Event;
+use {GroveError, ITree, NodeId};
+
+/// Node of a tree in the Newick format
+///
+/// Every node has a label, which is empty for unnamed nodes, and may have the
+/// length of the branch up to its parent.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NewickNode {
+    name: String,
+    length: Option<f64>,
+}
+
+impl NewickNode {
+    pub fn new<S: Into<String>>(name: S, length: Option<f64>) -> Self {
+        NewickNode { name: name.into(), length }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the length of the branch to the parent
+    pub fn length(&self) -> Option<f64> {
+        self.length
+    }
+}
+
+/// Characters that end an unquoted label or length
+const DELIMITERS: &str = "()[]':;,";
+
+impl ITree<NewickNode> {
+    /// Parse a tree in the Newick format, like `(A:0.1,(B,C)D:0.2)E;`
+    ///
+    /// Labels can be quoted with `'` and `''` inside quotes stands for a single
+    /// quote. Underscores in unquoted labels are read as spaces, and comments
+    /// between `[` and `]` are skipped. The error has the byte where the
+    /// string stops being valid Newick.
+    pub fn from_newick(newick: &str) -> Result<Self, GroveError> {
+        let mut parser = Parser { newick, pos: 0 };
+        let mut tree = ITree::new();
+        // Nodes whose children are being read
+        let mut open: Vec<NodeId> = Vec::new();
+        let mut expect_node = true;
+        loop {
+            parser.skip()?;
+            if expect_node {
+                let node = match open.last() {
+                    Some(&parent) => tree.add_node(parent, NewickNode::default()),
+                    None => tree.add_root(NewickNode::default())?,
+                };
+                if parser.eat('(') {
+                    open.push(node);
+                } else {
+                    *tree.values.get_mut(node.index()).unwrap() = parser.node()?;
+                    expect_node = false;
+                }
+            } else if !open.is_empty() && parser.eat(',') {
+                expect_node = true;
+            } else if !open.is_empty() && parser.eat(')') {
+                let node = open.pop().unwrap();
+                *tree.values.get_mut(node.index()).unwrap() = parser.node()?;
+            } else if open.is_empty() && parser.eat(';') {
+                parser.skip()?;
+                if parser.pos != newick.len() {
+                    return Err(GroveError::InvalidNewick(parser.pos));
+                }
+                return Ok(tree);
+            } else {
+                return Err(GroveError::InvalidNewick(parser.pos));
+            }
+        }
+    }
+
+    /// Write the tree in the Newick format, quoting the labels that need it
+    pub fn to_newick(&self) -> String {
+        let mut out = String::new();
+        for (id, event) in self.euler_tour() {
+            let node = self.get(id).unwrap();
+            let leaf = node.children().is_empty();
+            match event {
+                Event::Enter => {
+                    if self.prev_sibling(id).is_some() {
+                        out.push(',');
+                    }
+                    if !leaf {
+                        out.push('(');
+                    }
+                }
+                Event::Exit => {
+                    if !leaf {
+                        out.push(')');
+                    }
+                    write_label(&mut out, &node.value().name);
+                    if let Some(length) = node.value().length {
+                        write!(out, ":{}", length).unwrap();
+                    }
+                }
+            }
+        }
+        out.push(';');
+        out
+    }
+}
+
+fn write_label(out: &mut String, name: &str) {
+    if name.chars().all(|c| !c.is_whitespace() && c != '_' && !DELIMITERS.contains(c)) {
+        out.push_str(name);
+        return;
+    }
+    out.push('\'');
+    for c in name.chars() {
+        if c == '\'' {
+            out.push('\'');
+        }
+        out.push(c);
+    }
+    out.push('\'');
+}
+
+struct Parser<'a> {
+    newick: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.newick[self.pos..]
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.rest().starts_with(c);
+        if found {
+            self.pos += c.len_utf8();
+        }
+        found
+    }
+
+    /// Skip whitespace and comments
+    fn skip(&mut self) -> Result<(), GroveError> {
+        loop {
+            let rest = self.rest();
+            self.pos += rest.len() - rest.trim_start().len();
+            if !self.rest().starts_with('[') {
+                return Ok(());
+            }
+            match self.rest().find(']') {
+                Some(end) => self.pos += end + 1,
+                None => return Err(GroveError::InvalidNewick(self.pos)),
+            }
+        }
+    }
+
+    /// Read the label and the branch length of a node, if it has them
+    fn node(&mut self) -> Result<NewickNode, GroveError> {
+        self.skip()?;
+        let name = if self.eat('\'') {
+            self.quoted()?
+        } else {
+            self.unquoted().replace('_', " ")
+        };
+        self.skip()?;
+        let mut length = None;
+        if self.eat(':') {
+            self.skip()?;
+            let start = self.pos;
+            let number = self.unquoted();
+            length = Some(number.parse().map_err(|_| GroveError::InvalidNewick(start))?);
+        }
+        Ok(NewickNode { name, length })
+    }
+
+    fn unquoted(&mut self) -> &'a str {
+        let rest = self.rest();
+        let end = rest.find(|c: char| c.is_whitespace() || DELIMITERS.contains(c)).unwrap_or(rest.len());
+        self.pos += end;
+        &rest[..end]
+    }
+
+    /// Read a quoted label after its opening quote
+    fn quoted(&mut self) -> Result<String, GroveError> {
+        let start = self.pos - 1;
+        let mut name = String::new();
+        loop {
+            match self.rest().find('\'') {
+                Some(end) => {
+                    name.push_str(&self.rest()[..end]);
+                    self.pos += end + 1;
+                    if !self.eat('\'') {
+                        return Ok(name);
+                    }
+                    name.push('\'');
+                }
+                None => return Err(GroveError::InvalidNewick(start)),
+            }
+        }
+    }
+}
+
+#[test]
+fn newick() {
+    let tree = ITree::from_newick(" (A:0.1, [comment] (B_1 ,'C''s':2)D:0.25, )E;\n").unwrap();
+    let nodes: Vec<_> = tree
+        .iter_dfs(tree.root_id().unwrap())
+        .map(|(_, n)| (n.value().name(), n.value().length()))
+        .collect();
+    assert_eq!(nodes, vec![
+        ("E", None),
+        ("A", Some(0.1)),
+        ("D", Some(0.25)),
+        ("B 1", None),
+        ("C's", Some(2.0)),
+        ("", None),
+    ]);
+    let newick = tree.to_newick();
+    assert_eq!(newick, "(A:0.1,('B 1','C''s':2)D:0.25,)E;");
+    assert_eq!(ITree::from_newick(&newick).unwrap().to_newick(), newick);
+    assert_eq!(ITree::from_newick("A;").unwrap().to_newick(), "A;");
+
+    assert_eq!(ITree::from_newick("(A,B)"), Err(GroveError::InvalidNewick(5)));
+    assert_eq!(ITree::from_newick("(A:x,B);"), Err(GroveError::InvalidNewick(3)));
+    assert_eq!(ITree::from_newick("(A,'B);"), Err(GroveError::InvalidNewick(3)));
+    assert_eq!(ITree::from_newick("(A,B);C"), Err(GroveError::InvalidNewick(6)));
+    assert_eq!(ITree::from_newick("A,B;"), Err(GroveError::InvalidNewick(1)));
+}
@@ -0,0 +1,301 @@
+use core::fmt;
+
+use {INode, INodeRef, ITree, MTree, NodeId, SlotId};
+
+impl<T> ITree<T> {
+    /// Get a `NodeRef` handle to the node of the given `Id` if it exists
+    pub fn node(&self, node: NodeId) -> Option<NodeRef<'_, T>> {
+        self.get(node)?;
+        Some(NodeRef { tree: self, id: node })
+    }
+
+    /// Get a `NodeRef` handle to the root node if it exists
+    pub fn root_node(&self) -> Option<NodeRef<'_, T>> {
+        self.node(self.root_id()?)
+    }
+}
+
+/// A handle to a node in an `ITree`
+///
+/// Bundles the tree with a `NodeId` so navigating the tree doesn't need to go
+/// through the tree for every step.
+///
+/// ```
+/// use grove::ITree;
+///
+/// let mut tree = ITree::new();
+/// let root = tree.add_root("root").unwrap();
+/// let child = tree.add_node(root, "child");
+/// let grandchild = tree.add_node(child, "grandchild");
+///
+/// let node = tree.node(grandchild).unwrap();
+/// assert_eq!(*node.parent().unwrap().parent().unwrap().value(), "root");
+/// ```
+pub struct NodeRef<'a, T: 'a> {
+    tree: &'a ITree<T>,
+    id: NodeId,
+}
+
+impl<'a, T> Clone for NodeRef<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for NodeRef<'a, T> {}
+
+impl<'a, T: fmt::Debug> fmt::Debug for NodeRef<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NodeRef")
+            .field("id", &self.id)
+            .field("value", self.value())
+            .finish()
+    }
+}
+
+impl<'a, T> NodeRef<'a, T> {
+    fn to(&self, id: NodeId) -> NodeRef<'a, T> {
+        NodeRef { tree: self.tree, id }
+    }
+
+    fn inode(&self) -> INodeRef<'a, T> {
+        self.tree.get(self.id).unwrap()
+    }
+
+    /// Get the `NodeId` of the node
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Get the tree the node belongs to
+    pub fn tree(&self) -> &'a ITree<T> {
+        self.tree
+    }
+
+    /// Get the node's value
+    pub fn value(&self) -> &'a T {
+        self.inode().value()
+    }
+
+    /// Get the node's parent if it has one
+    pub fn parent(&self) -> Option<NodeRef<'a, T>> {
+        self.inode().parent().map(|id| self.to(id))
+    }
+
+    /// Iterate over the node's children
+    pub fn children(&self) -> impl Iterator<Item = NodeRef<'a, T>> + 'a {
+        let tree = self.tree;
+        self.inode().children().iter().map(move |&id| NodeRef { tree, id })
+    }
+
+    /// Check if the node has children
+    pub fn has_children(&self) -> bool {
+        !self.inode().children().is_empty()
+    }
+
+    /// Get the node's first child if it has any
+    pub fn first_child(&self) -> Option<NodeRef<'a, T>> {
+        self.tree.first_child(self.id).map(|id| self.to(id))
+    }
+
+    /// Get the node's last child if it has any
+    pub fn last_child(&self) -> Option<NodeRef<'a, T>> {
+        self.tree.last_child(self.id).map(|id| self.to(id))
+    }
+
+    /// Get the sibling right after the node if it exists
+    pub fn next_sibling(&self) -> Option<NodeRef<'a, T>> {
+        self.tree.next_sibling(self.id).map(|id| self.to(id))
+    }
+
+    /// Get the sibling right before the node if it exists
+    pub fn prev_sibling(&self) -> Option<NodeRef<'a, T>> {
+        self.tree.prev_sibling(self.id).map(|id| self.to(id))
+    }
+
+    /// Iterate over the node's ancestors, starting at its parent
+    pub fn ancestors(&self) -> impl Iterator<Item = NodeRef<'a, T>> + 'a {
+        let tree = self.tree;
+        tree.ancestors(self.id).map(move |id| NodeRef { tree, id })
+    }
+
+    /// Iterate over the subtree rooted at the node in pre-order, starting with
+    /// the node itself
+    pub fn descendants(&self) -> impl Iterator<Item = NodeRef<'a, T>> + 'a {
+        let tree = self.tree;
+        tree.descendants(self.id).map(move |id| NodeRef { tree, id })
+    }
+}
+
+impl<T> MTree<T> {
+    /// Get a `NodeMut` handle to the node of the given `Id` if it exists
+    pub fn node_mut(&mut self, node: SlotId) -> Option<NodeMut<'_, T>> {
+        self.get(node)?;
+        Some(NodeMut { tree: self, id: node })
+    }
+
+    /// Get a `NodeMut` handle to the root node if it exists
+    pub fn root_node_mut(&mut self) -> Option<NodeMut<'_, T>> {
+        let root = self.root_id()?;
+        self.node_mut(root)
+    }
+}
+
+/// A mutable handle to a node in an `MTree`
+///
+/// Navigating with a `NodeMut` borrows it, use the `into_` methods to move the
+/// handle itself around the tree.
+pub struct NodeMut<'a, T: 'a> {
+    tree: &'a mut MTree<T>,
+    id: SlotId,
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for NodeMut<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NodeMut")
+            .field("id", &self.id)
+            .field("value", self.value())
+            .finish()
+    }
+}
+
+impl<'a, T> NodeMut<'a, T> {
+    fn inode(&self) -> &INode<T, SlotId> {
+        self.tree.get(self.id).unwrap()
+    }
+
+    /// Get the `SlotId` of the node
+    pub fn id(&self) -> SlotId {
+        self.id
+    }
+
+    /// Get the node's value
+    pub fn value(&self) -> &T {
+        &self.inode().value
+    }
+
+    /// Get a mutable reference to the node's value
+    pub fn value_mut(&mut self) -> &mut T {
+        self.tree.get_mut(self.id).unwrap()
+    }
+
+    /// Get the `SlotId`s of the node's children
+    pub fn children(&self) -> &[SlotId] {
+        &self.inode().children
+    }
+
+    /// Add a child after the node's last child and get a handle to it
+    pub fn append(&mut self, value: T) -> NodeMut<'_, T> {
+        let id = self.tree.add_node(self.id, value);
+        NodeMut { tree: self.tree, id }
+    }
+
+    /// Get a handle to the node's parent if it has one
+    pub fn parent(&mut self) -> Option<NodeMut<'_, T>> {
+        let id = self.inode().parent?;
+        Some(NodeMut { tree: self.tree, id })
+    }
+
+    /// Get a handle to the node's first child if it has any
+    pub fn first_child(&mut self) -> Option<NodeMut<'_, T>> {
+        let id = *self.inode().children.first()?;
+        Some(NodeMut { tree: self.tree, id })
+    }
+
+    /// Get a handle to the node's last child if it has any
+    pub fn last_child(&mut self) -> Option<NodeMut<'_, T>> {
+        let id = *self.inode().children.last()?;
+        Some(NodeMut { tree: self.tree, id })
+    }
+
+    /// Get a handle to the sibling right after the node if it exists
+    pub fn next_sibling(&mut self) -> Option<NodeMut<'_, T>> {
+        let id = self.sibling(1)?;
+        Some(NodeMut { tree: self.tree, id })
+    }
+
+    /// Get a handle to the sibling right before the node if it exists
+    pub fn prev_sibling(&mut self) -> Option<NodeMut<'_, T>> {
+        let id = self.sibling(-1)?;
+        Some(NodeMut { tree: self.tree, id })
+    }
+
+    /// Turn the handle into a handle to the node's parent, or give it back if
+    /// the node has no parent
+    pub fn into_parent(self) -> Result<NodeMut<'a, T>, NodeMut<'a, T>> {
+        match self.inode().parent {
+            Some(id) => Ok(NodeMut { tree: self.tree, id }),
+            None => Err(self),
+        }
+    }
+
+    /// Turn the handle into a handle to the node's child at `index`, or give
+    /// it back if there is no such child
+    pub fn into_child(self, index: usize) -> Result<NodeMut<'a, T>, NodeMut<'a, T>> {
+        match self.inode().children.get(index).cloned() {
+            Some(id) => Ok(NodeMut { tree: self.tree, id }),
+            None => Err(self),
+        }
+    }
+
+    /// Find the sibling `offset` positions away from the node
+    fn sibling(&self, offset: isize) -> Option<SlotId> {
+        let parent = self.tree.get(self.inode().parent?)?;
+        let pos = parent.children.iter().position(|&c| c == self.id)? as isize + offset;
+        if pos < 0 {
+            return None;
+        }
+        parent.children.get(pos as usize).cloned()
+    }
+}
+
+#[test]
+fn node_ref() {
+    // 0 -> 1 -> 2
+    // |    |--> 3
+    // |--> 4
+    let tree = tree!{ 0 => [1 => [2, 3], 4] };
+    let root = tree.root_node().unwrap();
+    assert_eq!(root.id(), NodeId::new(0));
+    assert!(root.parent().is_none());
+    assert_eq!(root.children().map(|n| *n.value()).collect::<Vec<_>>(), vec![1, 4]);
+
+    let one = root.first_child().unwrap();
+    assert_eq!(one.last_child().unwrap().value(), &3);
+    assert_eq!(one.next_sibling().unwrap().value(), &4);
+    assert!(one.prev_sibling().is_none());
+    assert!(one.has_children());
+
+    let three = tree.node(NodeId::new(3)).unwrap();
+    assert_eq!(three.prev_sibling().unwrap().id(), NodeId::new(2));
+    assert_eq!(three.ancestors().map(|n| n.id()).collect::<Vec<_>>(), vec![NodeId::new(1), NodeId::new(0)]);
+    assert_eq!(one.descendants().map(|n| *n.value()).collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert!(tree.node(NodeId::new(5)).is_none());
+}
+
+#[test]
+fn node_mut() {
+    let mut tree = MTree::new();
+    tree.add_root(0).unwrap();
+    {
+        let mut root = tree.root_node_mut().unwrap();
+        *root.append(1).value_mut() += 10;
+        root.append(2).append(3);
+        assert_eq!(root.children().len(), 2);
+
+        let mut one = root.first_child().unwrap();
+        assert_eq!(*one.value(), 11);
+        *one.next_sibling().unwrap().value_mut() = 20;
+    }
+    assert_eq!(*tree.get(SlotId::new(2, 0)).unwrap().value(), 20);
+
+    let three = tree.node_mut(SlotId::new(0, 0)).unwrap()
+        .into_child(1).ok().unwrap()
+        .into_child(0).ok().unwrap();
+    assert_eq!(*three.value(), 3);
+    let two = three.into_parent().ok().unwrap();
+    assert_eq!(two.id(), SlotId::new(2, 0));
+    let mut root = two.into_parent().ok().unwrap();
+    assert!(root.parent().is_none());
+    assert!(root.into_parent().is_err());
+}
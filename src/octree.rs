@@ -0,0 +1,152 @@
+use orthtree::{Orthtree, Region, RegionIter, BUCKET, MAX_DEPTH};
+
+/// Octree
+///
+/// The three dimensional `QuadTree`: a tree over a box that splits into eight
+/// equal boxes once it holds more than a bucket of points. Besides boxes it
+/// can look for points inside any `Region`, like the `Frustum` of a camera.
+#[derive(Debug, Clone)]
+pub struct Octree<V> {
+    tree: Orthtree<3, V>,
+}
+
+impl<V> Octree<V> {
+    /// Create a new empty `Octree` over the box from `min` to `max`
+    pub fn new(min: [f64; 3], max: [f64; 3]) -> Self {
+        Self::with_limits(min, max, BUCKET, MAX_DEPTH)
+    }
+
+    /// Create a new empty `Octree` whose nodes split once they hold more than
+    /// `bucket` points, unless they are `max_depth` levels down
+    pub fn with_limits(min: [f64; 3], max: [f64; 3], bucket: usize, max_depth: usize) -> Self {
+        Octree { tree: Orthtree::new(min, max, bucket, max_depth) }
+    }
+
+    /// Get the corners of the box the tree covers
+    pub fn bounds(&self) -> ([f64; 3], [f64; 3]) {
+        self.tree.bounds()
+    }
+
+    /// Get the number of points in the tree
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.len() == 0
+    }
+
+    /// Add a point. Points outside the bounds of the tree don't get added and
+    /// give their value back.
+    pub fn insert(&mut self, point: [f64; 3], value: V) -> Result<(), V> {
+        self.tree.insert(point, value)
+    }
+
+    /// Remove a point at exactly `point` and return its value
+    pub fn remove(&mut self, point: &[f64; 3]) -> Option<V> {
+        self.tree.remove(point)
+    }
+
+    /// Remove every point
+    pub fn clear(&mut self) {
+        self.tree.clear();
+    }
+
+    /// Iterate over the points inside the box from `min` to `max`, faces
+    /// included
+    pub fn query(&self, min: [f64; 3], max: [f64; 3]) -> RegionIter<'_, 3, V> {
+        self.tree.query((min, max))
+    }
+
+    /// Iterate over the points inside any kind of region
+    pub fn query_region<R: Region<3>>(&self, region: R) -> RegionIter<'_, 3, V, R> {
+        self.tree.query(region)
+    }
+
+    /// Iterate over every point
+    pub fn iter(&self) -> RegionIter<'_, 3, V> {
+        self.tree.query(self.bounds())
+    }
+}
+
+/// Frustum
+///
+/// The space between six planes, like what a camera sees. Every plane is
+/// given as `[a, b, c, d]` where the points with `a x + b y + c z + d >= 0`
+/// are on the inside.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    planes: [[f64; 4]; 6],
+}
+
+impl Frustum {
+    pub fn new(planes: [[f64; 4]; 6]) -> Self {
+        Frustum { planes }
+    }
+
+    pub fn planes(&self) -> &[[f64; 4]; 6] {
+        &self.planes
+    }
+}
+
+impl Region<3> for Frustum {
+    fn contains(&self, point: &[f64; 3]) -> bool {
+        self.planes.iter().all(|p| p[0] * point[0] + p[1] * point[1] + p[2] * point[2] + p[3] >= 0.0)
+    }
+
+    fn intersects(&self, min: &[f64; 3], max: &[f64; 3]) -> bool {
+        // A box is outside if even its corner furthest along the normal of
+        // some plane is outside of it
+        self.planes.iter().all(|p| {
+            let corner: [f64; 3] = [0, 1, 2].map(|a| if p[a] >= 0.0 { max[a] } else { min[a] });
+            p[0] * corner[0] + p[1] * corner[1] + p[2] * corner[2] + p[3] >= 0.0
+        })
+    }
+}
+
+#[test]
+fn octree() {
+    use alloc::vec::Vec;
+
+    let mut tree = Octree::with_limits([0.0; 3], [64.0; 3], 4, 8);
+    let points: Vec<_> = (0..500).map(|i| [(i * 37 % 64) as f64, (i * 53 % 61) as f64, (i * 11 % 59) as f64]).collect();
+    for (i, &point) in points.iter().enumerate() {
+        tree.insert(point, i).unwrap();
+    }
+    assert_eq!(tree.insert([0.0, 0.0, 65.0], 500), Err(500));
+    assert_eq!(tree.len(), 500);
+
+    let (min, max) = ([10.0, 10.0, 10.0], [30.0, 20.0, 40.0]);
+    let mut found: Vec<_> = tree.query(min, max).map(|(_, &v)| v).collect();
+    found.sort();
+    let expected: Vec<_> = (0..500).filter(|&i| (0..3).all(|a| min[a] <= points[i][a] && points[i][a] <= max[a])).collect();
+    assert!(!expected.is_empty());
+    assert_eq!(found, expected);
+
+    // A frustum looking down the z axis from the origin, widening by one for
+    // every step forward and cut off between 5 and 50
+    let frustum = Frustum::new([
+        [1.0, 0.0, 1.0, 0.0],
+        [-1.0, 0.0, 1.0, 0.0],
+        [0.0, 1.0, 1.0, 0.0],
+        [0.0, -1.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0, -5.0],
+        [0.0, 0.0, -1.0, 50.0],
+    ]);
+    let mut found: Vec<_> = tree.query_region(frustum).map(|(_, &v)| v).collect();
+    found.sort();
+    let expected: Vec<_> = (0..500)
+        .filter(|&i| {
+            let [x, y, z] = points[i];
+            x.abs() <= z && y.abs() <= z && (5.0..=50.0).contains(&z)
+        })
+        .collect();
+    assert!(!expected.is_empty());
+    assert_eq!(found, expected);
+
+    for point in &points {
+        assert!(tree.remove(point).is_some());
+    }
+    assert!(tree.is_empty());
+    assert_eq!(tree.iter().count(), 0);
+}
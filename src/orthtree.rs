@@ -0,0 +1,216 @@
+use alloc::vec::Vec;
+
+use arena::Arena;
+
+/// Tree that splits a box in `D` dimensions into `2^D` equal boxes, the shared
+/// core of `QuadTree` and `Octree`
+///
+/// Points live in the leaves. A leaf that gets more than `bucket` points
+/// splits in halves along every axis, unless it is `max_depth` levels down
+/// already, and an inner node whose children hold no more than `bucket`
+/// points between them after a removal turns back into a leaf.
+#[derive(Debug, Clone)]
+pub(crate) struct Orthtree<const D: usize, V> {
+    nodes: Arena<OrthNode<D, V>>,
+    bucket: usize,
+    max_depth: usize,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct OrthNode<const D: usize, V> {
+    min: [f64; D],
+    max: [f64; D],
+    points: Vec<([f64; D], V)>,
+    /// The `2^D` children of an inner node, where bit `a` of the position of
+    /// a child is set if it holds the upper half along axis `a`
+    children: Option<Vec<usize>>,
+}
+
+/// Points a node holds before it splits, unless set with `with_limits`
+pub(crate) const BUCKET: usize = 8;
+/// Deepest a node splits, unless set with `with_limits`
+pub(crate) const MAX_DEPTH: usize = 16;
+
+/// The root node covers the whole box and always exists
+const ROOT: usize = 0;
+
+impl<const D: usize, V> Orthtree<D, V> {
+    pub(crate) fn new(min: [f64; D], max: [f64; D], bucket: usize, max_depth: usize) -> Self {
+        assert!((0..D).all(|a| min[a] <= max[a]), "the minimum of the bounds is above the maximum");
+        assert!(bucket > 0, "buckets have to hold at least one point");
+        let mut nodes = Arena::new();
+        nodes.insert(OrthNode { min, max, points: Vec::new(), children: None });
+        Orthtree { nodes, bucket, max_depth, len: 0 }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn bounds(&self) -> ([f64; D], [f64; D]) {
+        (self.nodes[ROOT].min, self.nodes[ROOT].max)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        let (min, max) = self.bounds();
+        self.nodes.clear();
+        self.nodes.insert(OrthNode { min, max, points: Vec::new(), children: None });
+        self.len = 0;
+    }
+
+    /// Add a point, giving the value back if the point is outside the bounds
+    pub(crate) fn insert(&mut self, point: [f64; D], value: V) -> Result<(), V> {
+        if !contains(&self.nodes[ROOT].min, &self.nodes[ROOT].max, &point) {
+            return Err(value);
+        }
+        let mut x = ROOT;
+        let mut depth = 0;
+        while let Some(ref children) = self.nodes[x].children {
+            x = children[self.orthant(x, &point)];
+            depth += 1;
+        }
+        self.nodes[x].points.push((point, value));
+        self.len += 1;
+        if self.nodes[x].points.len() > self.bucket && depth < self.max_depth {
+            self.split(x);
+        }
+        Ok(())
+    }
+
+    /// Remove a point equal to `point` and return its value
+    pub(crate) fn remove(&mut self, point: &[f64; D]) -> Option<V> {
+        let mut path = vec![ROOT];
+        let mut x = ROOT;
+        while let Some(ref children) = self.nodes[x].children {
+            x = children[self.orthant(x, point)];
+            path.push(x);
+        }
+        let pos = self.nodes[x].points.iter().position(|(p, _)| p == point)?;
+        let (_, value) = self.nodes[x].points.swap_remove(pos);
+        self.len -= 1;
+
+        // Merge the children of nodes that got small enough back up
+        path.pop();
+        while let Some(parent) = path.pop() {
+            if !self.merge(parent) {
+                break;
+            }
+        }
+        Some(value)
+    }
+
+    /// Iterate over the points inside a region
+    pub(crate) fn query<R: Region<D>>(&self, region: R) -> RegionIter<'_, D, V, R> {
+        RegionIter { nodes: &self.nodes, region, stack: vec![ROOT], points: [].iter() }
+    }
+
+    /// Get the position of the child of `x` a point goes into
+    fn orthant(&self, x: usize, point: &[f64; D]) -> usize {
+        let node = &self.nodes[x];
+        (0..D).filter(|&a| point[a] >= (node.min[a] + node.max[a]) / 2.0).map(|a| 1 << a).sum()
+    }
+
+    /// Move the points of a leaf into new children
+    fn split(&mut self, x: usize) {
+        let (min, max) = (self.nodes[x].min, self.nodes[x].max);
+        let children = (0..1 << D)
+            .map(|orthant: usize| {
+                let mut low = min;
+                let mut high = max;
+                for a in 0..D {
+                    let mid = (min[a] + max[a]) / 2.0;
+                    if orthant & 1 << a == 0 {
+                        high[a] = mid;
+                    } else {
+                        low[a] = mid;
+                    }
+                }
+                self.nodes.insert(OrthNode { min: low, max: high, points: Vec::new(), children: None })
+            })
+            .collect::<Vec<_>>();
+        let points = core::mem::take(&mut self.nodes[x].points);
+        for (point, value) in points {
+            let child = children[self.orthant(x, &point)];
+            self.nodes[child].points.push((point, value));
+        }
+        self.nodes[x].children = Some(children);
+    }
+
+    /// Turn an inner node whose children are few enough leaves back into a
+    /// leaf, returning whether it did
+    fn merge(&mut self, x: usize) -> bool {
+        let children = match self.nodes[x].children {
+            Some(ref children) => children,
+            None => return false,
+        };
+        let nodes = &self.nodes;
+        let leaves = children.iter().all(|&c| nodes[c].children.is_none());
+        if !leaves || children.iter().map(|&c| nodes[c].points.len()).sum::<usize>() > self.bucket {
+            return false;
+        }
+        for c in self.nodes[x].children.take().unwrap() {
+            let child = self.nodes.remove(c);
+            self.nodes[x].points.extend(child.points);
+        }
+        true
+    }
+}
+
+fn contains<const D: usize>(min: &[f64; D], max: &[f64; D], point: &[f64; D]) -> bool {
+    (0..D).all(|a| min[a] <= point[a] && point[a] <= max[a])
+}
+
+/// Region of space to look for points in a `QuadTree` or `Octree`
+///
+/// A box is given by its two corners, edges included.
+pub trait Region<const D: usize> {
+    /// Check whether a point is inside the region
+    fn contains(&self, point: &[f64; D]) -> bool;
+
+    /// Check whether the region might overlap the box from `min` to `max`.
+    /// Saying yes for a box that doesn't overlap only costs time.
+    fn intersects(&self, min: &[f64; D], max: &[f64; D]) -> bool;
+}
+
+impl<const D: usize> Region<D> for ([f64; D], [f64; D]) {
+    fn contains(&self, point: &[f64; D]) -> bool {
+        contains(&self.0, &self.1, point)
+    }
+
+    fn intersects(&self, min: &[f64; D], max: &[f64; D]) -> bool {
+        (0..D).all(|a| min[a] <= self.1[a] && self.0[a] <= max[a])
+    }
+}
+
+/// Iterator over the points of a `QuadTree` or `Octree` inside a region
+pub struct RegionIter<'a, const D: usize, V: 'a, R = ([f64; D], [f64; D])> {
+    nodes: &'a Arena<OrthNode<D, V>>,
+    region: R,
+    /// Nodes left to visit
+    stack: Vec<usize>,
+    /// Rest of the points of the leaf being visited
+    points: core::slice::Iter<'a, ([f64; D], V)>,
+}
+
+impl<'a, const D: usize, V, R: Region<D>> Iterator for RegionIter<'a, D, V, R> {
+    type Item = (&'a [f64; D], &'a V);
+
+    fn next(&mut self) -> Option<(&'a [f64; D], &'a V)> {
+        loop {
+            for (point, value) in &mut self.points {
+                if self.region.contains(point) {
+                    return Some((point, value));
+                }
+            }
+            let node = &self.nodes[self.stack.pop()?];
+            if !self.region.intersects(&node.min, &node.max) {
+                continue;
+            }
+            match node.children {
+                Some(ref children) => self.stack.extend(children),
+                None => self.points = node.points.iter(),
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use {GroveError, ITree, NodeId};
+
+/// Characters the `tree` command and `ITree::pretty` draw in front of lines
+const CONNECTORS: &[char] = &[' ', '│', '├', '└', '─'];
+
+impl ITree<String> {
+    /// Parse an outline where every line is a node and lines are indented one
+    /// level deeper than their parent, like the output of the `tree` command
+    ///
+    /// A level is a tab, or as many spaces and box drawing connectors as the
+    /// first indented line has, so two-space outlines work as well as the
+    /// four columns of `tree`. Blank lines are skipped. The error has the
+    /// number of the first line whose indentation doesn't fit, counting from
+    /// one.
+    ///
+    /// ```
+    /// use grove::ITree;
+    ///
+    /// let tree = ITree::from_indented("etc\n├── hosts\n└── ssh\n    └── config\n").unwrap();
+    /// assert_eq!(tree.to_indented("  ", |v| v.clone()), "etc\n  hosts\n  ssh\n    config\n");
+    /// ```
+    pub fn from_indented(text: &str) -> Result<Self, GroveError> {
+        let mut tree = ITree::new();
+        // Nodes from the root down to the last line
+        let mut path: Vec<NodeId> = Vec::new();
+        let mut unit = None;
+        for (number, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line)) {
+            let value = line.trim_start_matches(|c| c == '\t' || CONNECTORS.contains(&c));
+            if value.trim().is_empty() {
+                continue;
+            }
+            let prefix = &line[..line.len() - value.len()];
+            let level = if prefix.chars().all(|c| c == '\t') {
+                prefix.len()
+            } else if prefix.contains('\t') {
+                return Err(GroveError::InvalidIndent(number));
+            } else {
+                let width = prefix.chars().count();
+                let unit = *unit.get_or_insert(width);
+                if !width.is_multiple_of(unit) {
+                    return Err(GroveError::InvalidIndent(number));
+                }
+                width / unit
+            };
+            if level > path.len() || (level == 0 && !tree.is_empty()) {
+                return Err(GroveError::InvalidIndent(number));
+            }
+            path.truncate(level);
+            let value = value.trim_end().into();
+            let node = match path.last() {
+                Some(&parent) => tree.add_node(parent, value),
+                None => tree.add_root(value)?,
+            };
+            path.push(node);
+        }
+        Ok(tree)
+    }
+}
+
+impl<T> ITree<T> {
+    /// Render the tree as an outline, with one line for every node formatted
+    /// with `format` and `indent` repeated once for every level of depth. See
+    /// `pretty` for the connectors of the `tree` command.
+    pub fn to_indented<F>(&self, indent: &str, format: F) -> String
+        where F: Fn(&T) -> String
+    {
+        let mut out = String::new();
+        let mut stack: Vec<_> = self.root_id().into_iter().map(|root| (root, 0)).collect();
+        while let Some((id, depth)) = stack.pop() {
+            for _ in 0..depth {
+                out.push_str(indent);
+            }
+            out.push_str(&format(&self.values[id.index()]));
+            out.push('\n');
+            stack.extend(self.children[id.index()].iter().rev().map(|&child| (child, depth + 1)));
+        }
+        out
+    }
+}
+
+#[test]
+fn indented() {
+    let tree = tree!{ "grove" => ["src" => ["lib.rs", "iter.rs"], "Cargo.toml"] };
+    let tree = tree.map(|v| v.to_string());
+    let two = "grove\n  src\n    lib.rs\n    iter.rs\n  Cargo.toml\n";
+    assert_eq!(tree.to_indented("  ", |v| v.clone()), two);
+    assert_eq!(ITree::from_indented(two).unwrap(), tree);
+    let tabs = "grove\n\n\tsrc\n\t\tlib.rs  \n\t\titer.rs\n\tCargo.toml";
+    assert_eq!(ITree::from_indented(tabs).unwrap(), tree);
+    assert_eq!(ITree::from_indented(&tree.to_string()).unwrap(), tree);
+    assert!(ITree::from_indented("").unwrap().is_empty());
+
+    assert_eq!(ITree::from_indented("a\n  b\n   c"), Err(GroveError::InvalidIndent(3)));
+    assert_eq!(ITree::from_indented("a\n  b\n      c"), Err(GroveError::InvalidIndent(3)));
+    assert_eq!(ITree::from_indented("a\nb"), Err(GroveError::InvalidIndent(2)));
+    assert_eq!(ITree::from_indented("  a"), Err(GroveError::InvalidIndent(1)));
+    assert_eq!(ITree::from_indented("a\n\t b"), Err(GroveError::InvalidIndent(2)));
+}
@@ -0,0 +1,137 @@
+use core::mem;
+
+use alloc::vec::Vec;
+
+use rayon::prelude::*;
+use rayon::Scope;
+
+use chunks::Chunks;
+use {INodeRef, ITree, NodeId, SubtreeSizes};
+
+/// Subtrees with fewer nodes than this get mapped on a single thread
+const SPLIT: usize = 1024;
+
+impl<T: Send + Sync> ITree<T> {
+    /// Iterate over every node of the tree in parallel. Nodes are handed out in
+    /// the order they were added to the tree, so collecting the iterator keeps
+    /// that order.
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = (NodeId, INodeRef<'_, T>)> + '_ {
+        (0..self.len()).into_par_iter().map(move |x| (NodeId::new(x), INodeRef { tree: self, x }))
+    }
+
+    /// Transform every value in the tree in parallel while keeping its
+    /// structure, like `map` does
+    pub fn par_map<U, F>(self, f: F) -> ITree<U>
+        where F: Fn(&T) -> U + Sync + Send,
+              U: Send
+    {
+        // Every chunk of values gets mapped on its own, which keeps the layout
+        // of the chunks intact
+        let chunks = self.values
+            .chunks()
+            .par_iter()
+            .map(|chunk| chunk.iter().map(&f).collect())
+            .collect();
+        let values = Chunks::from_chunks(chunks);
+        ITree { values, parents: self.parents, children: self.children }
+    }
+
+    /// Transform the values of the subtree rooted at a node in parallel into a
+    /// new tree with the same structure. Returns `None` if the node does not
+    /// exist.
+    ///
+    /// Work gets split along child boundaries, the subtrees of the children of
+    /// a node are mapped as tasks of their own all the way down. Nodes of the
+    /// new tree get their `NodeId`s in pre-order.
+    pub fn par_map_subtree<U, F>(&self, node: NodeId, f: F) -> Option<ITree<U>>
+        where F: Fn(&T) -> U + Sync + Send,
+              U: Send
+    {
+        let sizes = self.subtree_sizes();
+        let mut values: Vec<Option<U>> = Vec::new();
+        values.resize_with(sizes.get(node)?, || None);
+        rayon::scope(|scope| self.map_into(scope, &sizes, node, &mut values, &f));
+
+        let mut values = values.into_iter().map(Option::unwrap);
+        let mut tree = ITree::with_capacity(values.len());
+        let mut stack = vec![(node, None)];
+        while let Some((id, parent)) = stack.pop() {
+            let value = values.next().unwrap();
+            let new = match parent {
+                Some(parent) => tree.add_node(parent, value),
+                None => tree.add_root(value).unwrap(),
+            };
+            stack.extend(self.children[id.index()].iter().rev().map(|&c| (c, Some(new))));
+        }
+        Some(tree)
+    }
+
+    /// Map the subtree of a node into `out` in pre-order. Children with large
+    /// subtrees are handed to other threads, the largest one is followed on
+    /// this thread so deep trees don't need a deep stack.
+    fn map_into<'s, U, F>(&'s self, scope: &Scope<'s>, sizes: &'s SubtreeSizes, mut node: NodeId,
+                          mut out: &'s mut [Option<U>], f: &'s F)
+        where F: Fn(&T) -> U + Sync,
+              U: Send
+    {
+        while out.len() >= SPLIT {
+            let (first, mut rest) = out.split_first_mut().unwrap();
+            *first = Some(f(&self.values[node.index()]));
+            // The subtrees of the children come one after another in pre-order
+            let mut parts = Vec::new();
+            for &child in self.children[node.index()].iter() {
+                let (part, tail) = mem::take(&mut rest).split_at_mut(sizes.get(child).unwrap());
+                parts.push((child, part));
+                rest = tail;
+            }
+            let largest = (0..parts.len()).max_by_key(|&i| parts[i].1.len()).unwrap();
+            let (child, part) = parts.swap_remove(largest);
+            // Small children go out in batches so wide nodes don't make a task
+            // for every leaf
+            let mut batch = Vec::new();
+            let mut len = 0;
+            for (c, p) in parts {
+                len += p.len();
+                batch.push((c, p));
+                if len >= SPLIT {
+                    let batch = mem::take(&mut batch);
+                    scope.spawn(move |scope| for (c, p) in batch { self.map_into(scope, sizes, c, p, f) });
+                    len = 0;
+                }
+            }
+            if !batch.is_empty() {
+                scope.spawn(move |scope| for (c, p) in batch { self.map_into(scope, sizes, c, p, f) });
+            }
+            node = child;
+            out = part;
+        }
+        for (slot, id) in out.iter_mut().zip(self.descendants(node)) {
+            *slot = Some(f(&self.values[id.index()]));
+        }
+    }
+}
+
+#[test]
+fn par() {
+    let tree = tree!{ 1 => [2 => [4, 5], 3 => [6]] };
+
+    let sum: i32 = tree.par_iter().map(|(_, node)| *node.value()).sum();
+    assert_eq!(sum, 21);
+    let ids: Vec<_> = tree.par_iter().map(|(id, _)| id).collect();
+    assert_eq!(ids, (0..6).map(NodeId::new).collect::<Vec<_>>());
+    let leaves = tree.par_iter().filter(|(_, node)| node.children().is_empty()).count();
+    assert_eq!(leaves, 3);
+
+    let expected = tree.clone().map(|v| v * 10);
+    assert_eq!(tree.par_map_subtree(NodeId::new(0), |v| v * 10), Some(expected.clone()));
+    assert_eq!(tree.par_map_subtree(NodeId::new(1), |v| v + 1), Some(tree!{ 3 => [5, 6] }));
+    assert_eq!(tree.par_map_subtree(NodeId::new(6), |v| v + 1), None);
+    assert_eq!(tree.par_map(|v| v * 10), expected);
+
+    // Wide and deep trees large enough to be split up
+    let wide = ITree::build_from(0, |&n| (n, if n == 0 { (1..10_000).collect() } else { vec![] }));
+    assert_eq!(wide.par_map_subtree(NodeId::new(0), |v| v * 2), Some(wide.clone().map(|v| v * 2)));
+    let deep = ITree::build_from(0, |&n| (n, if n < 100_000 { vec![n + 1, n + 100_001] } else { vec![] }));
+    let mapped = deep.par_map_subtree(NodeId::new(0), |v| v + 1).unwrap();
+    assert!(mapped.is_identical(&deep.map(|v| v + 1)));
+}
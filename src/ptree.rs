@@ -0,0 +1,445 @@
+use alloc::vec::Vec;
+
+use {GroveError, NodeId};
+
+/// Persistent Tree
+///
+/// Like `ITree` this tree can only grow, but every call to `add_node` creates a
+/// new version of the tree while all previous versions stay readable. Versions
+/// share structure through path copying: inserting a node only copies the
+/// nodes on the path from the root to the new node's parent, everything else is
+/// shared with the previous version.
+#[derive(Debug, Clone)]
+pub struct PTree<T> {
+    /// Values indexed by `NodeId`, shared by every version
+    values: Vec<T>,
+    /// Parent of every node, these never change once a node is inserted
+    parents: Vec<Option<NodeId>>,
+    /// Physical nodes, shared between versions
+    nodes: Vec<PNode>,
+    /// Physical root of every version, version 0 is the empty tree
+    versions: Vec<Option<usize>>,
+}
+
+/// A version of a `PTree`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version(usize);
+
+#[derive(Debug, Clone)]
+struct PNode {
+    id: NodeId,
+    /// Physical indices of the children, ordered by `NodeId`
+    children: Vec<usize>,
+}
+
+impl<T> PTree<T> {
+    /// Create a new empty `PTree`
+    pub fn new() -> Self {
+        Self {
+            values: Vec::new(),
+            parents: Vec::new(),
+            nodes: Vec::new(),
+            versions: vec![None],
+        }
+    }
+
+    /// Adds the root node to an empty tree, creating a new version. Returns the
+    /// `NodeId` of the root or an error if the tree already has one.
+    pub fn add_root(&mut self, value: T) -> Result<NodeId, GroveError> {
+        if self.latest_root().is_some() {
+            return Err(GroveError::RootExists);
+        }
+        let id = NodeId::new(self.values.len());
+        let root = self.push(id, Vec::new());
+        self.parents.push(None);
+        self.values.push(value);
+        self.versions.push(Some(root));
+        Ok(id)
+    }
+
+    /// Adds a child to a node in the latest version of the tree, creating a new
+    /// version. If the tree is empty it discards the given `NodeId` and creates
+    /// the root node with the given value. Returns the `NodeId` of the inserted
+    /// node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `NodeId` does not refer to a node in the tree. See
+    /// `try_add_node` for a version that returns an error instead.
+    pub fn add_node(&mut self, node: NodeId, value: T) -> NodeId {
+        match self.try_add_node(node, value) {
+            Ok(id) => id,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Adds a child to a node in the latest version of the tree like
+    /// `add_node`, but returns an error rather than panicking if the `NodeId`
+    /// does not refer to a node in the tree.
+    pub fn try_add_node(&mut self, node: NodeId, value: T) -> Result<NodeId, GroveError> {
+        let root = match self.latest_root() {
+            Some(root) => root,
+            None => return self.add_root(value),
+        };
+        let path = self.path(root, node).ok_or(GroveError::InvalidNodeId(node))?;
+        let id = NodeId::new(self.values.len());
+        let leaf = self.push(id, Vec::new());
+        self.parents.push(Some(node));
+
+        // Copy the path bottom up, the parent gets the new leaf and every other
+        // node gets the copy of its child on the path
+        let mut child = leaf;
+        for (depth, &phys) in path.iter().enumerate().rev() {
+            let mut children = self.nodes[phys].children.clone();
+            if depth == path.len() - 1 {
+                children.push(child);
+            } else {
+                let old = path[depth + 1];
+                let pos = children.iter().position(|&c| c == old).unwrap();
+                children[pos] = child;
+            }
+            let copied = self.nodes[phys].id;
+            child = self.push(copied, children);
+        }
+        self.values.push(value);
+        self.versions.push(Some(child));
+        Ok(id)
+    }
+
+    /// Get the latest version of the tree
+    pub fn version(&self) -> Version {
+        Version(self.versions.len() - 1)
+    }
+
+    /// Iterate over every version of the tree from oldest to newest. The first
+    /// version is always the empty tree.
+    pub fn versions(&self) -> Versions {
+        Versions { next: 0, end: self.versions.len() }
+    }
+
+    /// Get a view of the tree as it was at the given `Version` if it exists
+    pub fn at_version(&self, version: Version) -> Option<PView<'_, T>> {
+        let Version(v) = version;
+        self.versions.get(v).map(|&root| PView { tree: self, version, root })
+    }
+
+    /// Get the `Version` that added a node if the node exists
+    pub fn version_of(&self, node: NodeId) -> Option<Version> {
+        // Every version adds exactly one node, in the order of their ids
+        let x = node.index();
+        if x < self.values.len() { Some(Version(x + 1)) } else { None }
+    }
+
+    /// Compare two versions of the tree. Returns `None` if either version
+    /// doesn't exist.
+    pub fn diff(&self, from: Version, to: Version) -> Option<VersionDiff> {
+        let (Version(a), Version(b)) = (from, to);
+        if a >= self.versions.len() || b >= self.versions.len() {
+            return None;
+        }
+        let (lo, hi) = if a < b { (a, b) } else { (b, a) };
+        // Version `v` holds exactly the nodes with ids below `v`
+        let ids: Vec<_> = (lo..hi).map(NodeId::new).collect();
+        let mut changed: Vec<_> = ids
+            .iter()
+            .filter_map(|id| self.parents[id.index()])
+            .filter(|parent| parent.index() < lo)
+            .collect();
+        changed.sort();
+        changed.dedup();
+        let (added, removed) = if a < b { (ids, Vec::new()) } else { (Vec::new(), ids) };
+        Some(VersionDiff { from, to, added, removed, changed })
+    }
+
+    /// Get a view of the latest version of the tree
+    pub fn latest(&self) -> PView<'_, T> {
+        PView { tree: self, version: self.version(), root: self.latest_root() }
+    }
+
+    fn latest_root(&self) -> Option<usize> {
+        self.versions[self.versions.len() - 1]
+    }
+
+    fn push(&mut self, id: NodeId, children: Vec<usize>) -> usize {
+        self.nodes.push(PNode { id, children });
+        self.nodes.len() - 1
+    }
+
+    /// Find the physical nodes from `root` down to `node`
+    fn path(&self, root: usize, node: NodeId) -> Option<Vec<usize>> {
+        let x = node.index();
+        if x >= self.parents.len() {
+            return None;
+        }
+        // Logical path from the root is fixed, only the physical nodes differ
+        // between versions
+        let mut ids = vec![node];
+        while let Some(parent) = self.parents[ids[ids.len() - 1].index()] {
+            ids.push(parent);
+        }
+        ids.reverse();
+
+        let mut path = vec![root];
+        for id in &ids[1..] {
+            let current = &self.nodes[path[path.len() - 1]];
+            let nodes = &self.nodes;
+            let pos = current.children
+                .binary_search_by_key(id, |&c| nodes[c].id)
+                .ok()?;
+            path.push(current.children[pos]);
+        }
+        Some(path)
+    }
+}
+
+impl<T> Default for PTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Iterator over the `Version`s of a `PTree`
+#[derive(Debug, Clone)]
+pub struct Versions {
+    next: usize,
+    end: usize,
+}
+
+impl Iterator for Versions {
+    type Item = Version;
+
+    fn next(&mut self) -> Option<Version> {
+        if self.next < self.end {
+            self.next += 1;
+            Some(Version(self.next - 1))
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.next;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for Versions {
+    fn next_back(&mut self) -> Option<Version> {
+        if self.next < self.end {
+            self.end -= 1;
+            Some(Version(self.end))
+        } else {
+            None
+        }
+    }
+}
+
+impl ExactSizeIterator for Versions {}
+
+/// The changes between two versions of a `PTree`
+///
+/// Created with `PTree::diff`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionDiff {
+    from: Version,
+    to: Version,
+    added: Vec<NodeId>,
+    removed: Vec<NodeId>,
+    changed: Vec<NodeId>,
+}
+
+impl VersionDiff {
+    /// Get the version the diff starts from
+    pub fn from(&self) -> Version {
+        self.from
+    }
+
+    /// Get the version the diff goes to
+    pub fn to(&self) -> Version {
+        self.to
+    }
+
+    /// Get the nodes that are in the `to` version but not in the `from` one
+    pub fn added(&self) -> &[NodeId] {
+        &self.added
+    }
+
+    /// Get the nodes that are in the `from` version but not in the `to` one
+    pub fn removed(&self) -> &[NodeId] {
+        &self.removed
+    }
+
+    /// Get the nodes in both versions whose children differ between them
+    pub fn changed(&self) -> &[NodeId] {
+        &self.changed
+    }
+
+    /// Check if both versions are the same
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// A read only view of a single version of a `PTree`
+#[derive(Debug)]
+pub struct PView<'a, T: 'a> {
+    tree: &'a PTree<T>,
+    version: Version,
+    root: Option<usize>,
+}
+
+impl<'a, T> Clone for PView<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for PView<'a, T> {}
+
+impl<'a, T> PView<'a, T> {
+    /// Get the `Version` this view belongs to
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Get the number of nodes in this version
+    pub fn len(&self) -> usize {
+        // Every version adds exactly one node
+        self.version.0
+    }
+
+    /// Check if this version of the tree is empty
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Get the root node if it exists
+    pub fn root(&self) -> Option<PNodeRef<'a, T>> {
+        self.root.map(|phys| PNodeRef { tree: self.tree, phys })
+    }
+
+    /// Get the node of the given `Id` if it exists in this version
+    pub fn get(&self, node: NodeId) -> Option<PNodeRef<'a, T>> {
+        let root = self.root?;
+        let path = self.tree.path(root, node)?;
+        Some(PNodeRef { tree: self.tree, phys: path[path.len() - 1] })
+    }
+}
+
+/// A node in a version of a `PTree`
+#[derive(Debug)]
+pub struct PNodeRef<'a, T: 'a> {
+    tree: &'a PTree<T>,
+    phys: usize,
+}
+
+impl<'a, T> Clone for PNodeRef<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for PNodeRef<'a, T> {}
+
+impl<'a, T> PNodeRef<'a, T> {
+    /// Get the `NodeId` of the node
+    pub fn id(&self) -> NodeId {
+        self.tree.nodes[self.phys].id
+    }
+
+    /// Get the node's value
+    pub fn value(&self) -> &'a T {
+        &self.tree.values[self.id().index()]
+    }
+
+    /// Get the node's parent `NodeId`
+    pub fn parent(&self) -> Option<NodeId> {
+        self.tree.parents[self.id().index()]
+    }
+
+    /// Get the node's children `NodeId`s in this version
+    pub fn children(&self) -> Vec<NodeId> {
+        let nodes = &self.tree.nodes;
+        nodes[self.phys].children.iter().map(|&c| nodes[c].id).collect()
+    }
+}
+
+#[test]
+fn versions() {
+    let mut tree = PTree::new();
+    // 0
+    let root = tree.add_root(0).unwrap();
+    // 0 -> 1
+    let one = tree.add_node(root, 1);
+    // 0 -> 1
+    // |--> 2
+    let two = tree.add_node(root, 2);
+    // 0 -> 1 -> 3
+    // |--> 2
+    let three = tree.add_node(one, 3);
+
+    assert_eq!(tree.version(), Version(4));
+    assert_eq!(tree.versions().count(), 5);
+
+    let empty = tree.at_version(Version(0)).unwrap();
+    assert!(empty.is_empty());
+    assert!(empty.get(root).is_none());
+
+    // Old versions don't see the nodes added after them
+    let v2 = tree.at_version(Version(2)).unwrap();
+    assert_eq!(v2.len(), 2);
+    assert_eq!(v2.root().unwrap().children(), vec![one]);
+    assert!(v2.get(one).unwrap().children().is_empty());
+    assert!(v2.get(two).is_none());
+
+    let v3 = tree.at_version(Version(3)).unwrap();
+    assert_eq!(v3.root().unwrap().children(), vec![one, two]);
+    assert!(v3.get(three).is_none());
+
+    let latest = tree.latest();
+    assert_eq!(latest.root().unwrap().children(), vec![one, two]);
+    assert_eq!(latest.get(one).unwrap().children(), vec![three]);
+    assert_eq!(*latest.get(three).unwrap().value(), 3);
+    assert_eq!(latest.get(three).unwrap().parent(), Some(one));
+
+    // Nodes off the path are shared between versions
+    assert_eq!(tree.at_version(Version(3)).unwrap().get(two).unwrap().phys,
+               latest.get(two).unwrap().phys);
+    assert!(tree.at_version(Version(5)).is_none());
+
+    // Failed insertions don't create a version
+    assert_eq!(tree.try_add_node(NodeId::new(9), 9), Err(GroveError::InvalidNodeId(NodeId::new(9))));
+    assert_eq!(tree.add_root(9), Err(GroveError::RootExists));
+    assert_eq!(tree.version(), Version(4));
+}
+
+#[test]
+fn diff() {
+    let n = NodeId::new;
+    let mut tree = PTree::new();
+    // 0 -> 1 -> 3
+    // |--> 2 -> 4
+    let root = tree.add_root(0).unwrap();
+    let one = tree.add_node(root, 1);
+    let two = tree.add_node(root, 2);
+    tree.add_node(one, 3);
+    tree.add_node(two, 4);
+
+    assert_eq!(tree.version_of(n(3)), Some(Version(4)));
+    assert_eq!(tree.version_of(n(5)), None);
+    assert_eq!(tree.versions().next_back(), Some(tree.version()));
+
+    let diff = tree.diff(Version(2), Version(5)).unwrap();
+    assert_eq!(diff.added(), &[n(2), n(3), n(4)]);
+    assert!(diff.removed().is_empty());
+    assert_eq!(diff.changed(), &[n(0), n(1)]);
+
+    // Going back in time removes the same nodes
+    let back = tree.diff(Version(5), Version(2)).unwrap();
+    assert_eq!(back.removed(), diff.added());
+    assert_eq!(back.changed(), diff.changed());
+    assert_eq!(back.from(), Version(5));
+
+    assert!(tree.diff(Version(3), Version(3)).unwrap().is_empty());
+    assert!(tree.diff(Version(0), Version(6)).is_none());
+}
@@ -0,0 +1,111 @@
+use orthtree::{Orthtree, Region, RegionIter, BUCKET, MAX_DEPTH};
+
+/// Quadtree
+///
+/// A tree over a rectangle that splits into four equal quarters once it holds
+/// more than a bucket of points, so lookups in a region only visit the parts
+/// of the rectangle that overlap with it. Splitting stops at a maximum depth,
+/// which keeps many points at the same spot from splitting forever.
+#[derive(Debug, Clone)]
+pub struct QuadTree<V> {
+    tree: Orthtree<2, V>,
+}
+
+impl<V> QuadTree<V> {
+    /// Create a new empty `QuadTree` over the rectangle from `min` to `max`
+    pub fn new(min: [f64; 2], max: [f64; 2]) -> Self {
+        Self::with_limits(min, max, BUCKET, MAX_DEPTH)
+    }
+
+    /// Create a new empty `QuadTree` whose nodes split once they hold more
+    /// than `bucket` points, unless they are `max_depth` levels down
+    pub fn with_limits(min: [f64; 2], max: [f64; 2], bucket: usize, max_depth: usize) -> Self {
+        QuadTree { tree: Orthtree::new(min, max, bucket, max_depth) }
+    }
+
+    /// Get the corners of the rectangle the tree covers
+    pub fn bounds(&self) -> ([f64; 2], [f64; 2]) {
+        self.tree.bounds()
+    }
+
+    /// Get the number of points in the tree
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.len() == 0
+    }
+
+    /// Add a point. Points outside the bounds of the tree don't get added and
+    /// give their value back.
+    pub fn insert(&mut self, point: [f64; 2], value: V) -> Result<(), V> {
+        self.tree.insert(point, value)
+    }
+
+    /// Remove a point at exactly `point` and return its value
+    pub fn remove(&mut self, point: &[f64; 2]) -> Option<V> {
+        self.tree.remove(point)
+    }
+
+    /// Remove every point
+    pub fn clear(&mut self) {
+        self.tree.clear();
+    }
+
+    /// Iterate over the points inside the rectangle from `min` to `max`,
+    /// edges included
+    pub fn query(&self, min: [f64; 2], max: [f64; 2]) -> RegionIter<'_, 2, V> {
+        self.tree.query((min, max))
+    }
+
+    /// Iterate over the points inside any kind of region
+    pub fn query_region<R: Region<2>>(&self, region: R) -> RegionIter<'_, 2, V, R> {
+        self.tree.query(region)
+    }
+
+    /// Iterate over every point
+    pub fn iter(&self) -> RegionIter<'_, 2, V> {
+        self.tree.query(self.bounds())
+    }
+}
+
+#[test]
+fn quadtree() {
+    use alloc::vec::Vec;
+
+    let mut tree = QuadTree::with_limits([0.0, 0.0], [100.0, 100.0], 4, 6);
+    let points: Vec<_> = (0..300).map(|i| [(i * 37 % 101) as f64, (i * 53 % 97) as f64]).collect();
+    for (i, &point) in points.iter().enumerate() {
+        assert!(tree.insert(point, i).is_ok());
+    }
+    assert_eq!(tree.insert([-1.0, 50.0], 300), Err(300));
+    assert_eq!(tree.insert([100.0, 100.0], 301), Ok(()));
+    assert_eq!(tree.len(), 301);
+
+    let query = |tree: &QuadTree<usize>, min: [f64; 2], max: [f64; 2]| {
+        let mut found: Vec<_> = tree.query(min, max).map(|(_, &v)| v).collect();
+        found.sort();
+        found
+    };
+    let inside = |p: &[f64; 2], min: [f64; 2], max: [f64; 2]| (0..2).all(|a| min[a] <= p[a] && p[a] <= max[a]);
+    let expected: Vec<_> = (0..300).filter(|&i| inside(&points[i], [10.0, 20.0], [40.0, 30.0])).collect();
+    assert!(!expected.is_empty());
+    assert_eq!(query(&tree, [10.0, 20.0], [40.0, 30.0]), expected);
+    assert_eq!(query(&tree, [99.0, 99.0], [100.0, 100.0]), vec![301]);
+
+    // Many points at one spot stop splitting at the maximum depth
+    for i in 0..50 {
+        tree.insert([3.0, 3.0], 400 + i).unwrap();
+    }
+    assert_eq!(tree.query([3.0, 3.0], [3.0, 3.0]).count(), 50 + points.iter().filter(|p| **p == [3.0, 3.0]).count());
+
+    for (i, point) in points.iter().enumerate().filter(|&(i, _)| i % 2 == 0) {
+        assert!(tree.remove(point).is_some(), "point {} is missing", i);
+    }
+    assert_eq!(tree.remove(&[50.5, 50.5]), None);
+    let expected: Vec<_> = (0..300).filter(|&i| i % 2 == 1 && inside(&points[i], [0.0, 0.0], [50.0, 50.0])).collect();
+    let found: Vec<_> = query(&tree, [0.0, 0.0], [50.0, 50.0]).into_iter().filter(|&v| v < 300).collect();
+    assert_eq!(found, expected);
+    assert_eq!(tree.iter().count(), tree.len());
+}
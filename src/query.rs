@@ -0,0 +1,219 @@
+use alloc::vec::Vec;
+
+use {ITree, NodeId};
+
+impl<T> ITree<T> {
+    /// Find the first node, in pre-order, whose value matches the predicate
+    pub fn find<P>(&self, mut pred: P) -> Option<NodeId>
+        where P: FnMut(&T) -> bool
+    {
+        self.iter_dfs(NodeId::new(0)).find(|&(_, node)| pred(node.value())).map(|(id, _)| id)
+    }
+
+    /// Iterate over the `NodeId`s of every node whose value matches the
+    /// predicate, in pre-order
+    pub fn find_all<'a, P>(&'a self, mut pred: P) -> impl Iterator<Item = NodeId> + 'a
+        where P: FnMut(&T) -> bool + 'a
+    {
+        self.iter_dfs(NodeId::new(0)).filter(move |&(_, node)| pred(node.value())).map(|(id, _)| id)
+    }
+
+    /// Get the path from a node up to the root, including both ends. Returns
+    /// an empty path if the node does not exist.
+    pub fn path_to_root(&self, node: NodeId) -> Vec<NodeId> {
+        if self.get(node).is_none() {
+            return Vec::new();
+        }
+        let mut path = vec![node];
+        path.extend(self.ancestors(node));
+        path
+    }
+
+    /// Get the path from `a` to `b`, going up from `a` to their lowest common
+    /// ancestor and back down to `b`. Both ends are included. Returns `None` if
+    /// either node does not exist.
+    pub fn path_between(&self, a: NodeId, b: NodeId) -> Option<Vec<NodeId>> {
+        let lca = self.lca(a, b)?;
+        let mut path = self.path_to_root(a);
+        let up = path.iter().position(|&id| id == lca).unwrap();
+        path.truncate(up + 1);
+        let mut down = self.path_to_root(b);
+        let below = down.iter().position(|&id| id == lca).unwrap();
+        down.truncate(below);
+        path.extend(down.into_iter().rev());
+        Some(path)
+    }
+
+    /// Get the lowest common ancestor of two nodes, the deepest node that has
+    /// both of them in its subtree. A node counts as its own ancestor. Returns
+    /// `None` if either node does not exist.
+    pub fn lca(&self, a: NodeId, b: NodeId) -> Option<NodeId> {
+        let (mut a, mut b) = (a, b);
+        self.get(a)?;
+        self.get(b)?;
+        while a != b {
+            if a > b {
+                a = self.parents[a.index()]?;
+            } else {
+                b = self.parents[b.index()]?;
+            }
+        }
+        Some(a)
+    }
+
+    /// Get the number of edges between a node and the root. Returns `None` if
+    /// the node does not exist.
+    pub fn depth(&self, node: NodeId) -> Option<usize> {
+        self.get(node)?;
+        Some(self.ancestors(node).count())
+    }
+
+    /// Get the number of edges on the longest path from a node down to a leaf.
+    /// Returns `None` if the node does not exist.
+    pub fn height(&self, node: NodeId) -> Option<usize> {
+        self.get(node)?;
+        let mut height = 0;
+        let mut stack = vec![(node, 0)];
+        while let Some((id, depth)) = stack.pop() {
+            height = height.max(depth);
+            stack.extend(self.children[id.index()].iter().map(|&c| (c, depth + 1)));
+        }
+        Some(height)
+    }
+
+    /// Get the number of nodes in the subtree rooted at a node, including the
+    /// node itself. Returns `None` if the node does not exist.
+    pub fn subtree_size(&self, node: NodeId) -> Option<usize> {
+        self.get(node)?;
+        Some(self.descendants(node).count())
+    }
+
+    /// Compute the size of every subtree in the tree in a single pass. Keep the
+    /// result around when asking for a lot of subtree sizes.
+    pub fn subtree_sizes(&self) -> SubtreeSizes {
+        let mut sizes = vec![1; self.len()];
+        for (x, parent) in self.parents.iter().enumerate().rev() {
+            if let Some(parent) = *parent {
+                sizes[parent.index()] += sizes[x];
+            }
+        }
+        SubtreeSizes { sizes }
+    }
+
+    /// Get the number of nodes without children
+    pub fn leaf_count(&self) -> usize {
+        self.leaves().count()
+    }
+}
+
+/// The size of every subtree in an `ITree`
+///
+/// Created with `ITree::subtree_sizes`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubtreeSizes {
+    sizes: Vec<usize>,
+}
+
+impl SubtreeSizes {
+    /// Get the size of the subtree rooted at a node if it exists
+    pub fn get(&self, node: NodeId) -> Option<usize> {
+        let x = node.index();
+        self.sizes.get(x).cloned()
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<usize> {
+        self.sizes
+    }
+}
+
+#[test]
+fn find() {
+    // 1 -> 2 -> 4
+    // |--> 3
+    let tree = tree!{ 1 => [2 => [4], 3] };
+    assert_eq!(tree.find(|&v| v > 1), Some(NodeId::new(1)));
+    assert_eq!(tree.find(|&v| v == 3), Some(NodeId::new(3)));
+    assert_eq!(tree.find(|&v| v > 4), None);
+
+    let even: Vec<_> = tree.find_all(|&v| v % 2 == 0).collect();
+    assert_eq!(even, vec![NodeId::new(1), NodeId::new(2)]);
+    assert_eq!(tree.find_all(|_| true).count(), 4);
+    assert_eq!(ITree::<u8>::new().find_all(|_| true).count(), 0);
+}
+
+#[test]
+fn paths() {
+    // 0 -> 1 -> 2 -> 3
+    // |    |--> 4
+    // |--> 5
+    let tree = tree!{ 0 => [1 => [2 => [3], 4], 5] };
+    let n = NodeId::new;
+    assert_eq!(tree.path_to_root(n(3)), vec![n(3), n(2), n(1), n(0)]);
+    assert_eq!(tree.path_to_root(n(0)), vec![n(0)]);
+    assert!(tree.path_to_root(n(6)).is_empty());
+
+    assert_eq!(tree.path_between(n(3), n(4)), Some(vec![n(3), n(2), n(1), n(4)]));
+    assert_eq!(tree.path_between(n(3), n(5)), Some(vec![n(3), n(2), n(1), n(0), n(5)]));
+    assert_eq!(tree.path_between(n(1), n(3)), Some(vec![n(1), n(2), n(3)]));
+    assert_eq!(tree.path_between(n(3), n(1)), Some(vec![n(3), n(2), n(1)]));
+    assert_eq!(tree.path_between(n(4), n(4)), Some(vec![n(4)]));
+    assert_eq!(tree.path_between(n(4), n(6)), None);
+}
+
+#[test]
+fn lca() {
+    // 0 -> 1 -> 2 -> 3
+    // |    |--> 4
+    // |--> 5 -> 6
+    let tree = tree!{ 0 => [1 => [2 => [3], 4], 5 => [6]] };
+    let n = NodeId::new;
+    assert_eq!(tree.lca(n(3), n(4)), Some(n(1)));
+    assert_eq!(tree.lca(n(4), n(3)), Some(n(1)));
+    assert_eq!(tree.lca(n(3), n(6)), Some(n(0)));
+    assert_eq!(tree.lca(n(2), n(3)), Some(n(2)));
+    assert_eq!(tree.lca(n(6), n(6)), Some(n(6)));
+    assert_eq!(tree.lca(n(0), n(7)), None);
+}
+
+#[test]
+fn depth_height() {
+    // 0 -> 1 -> 2 -> 3
+    // |    |--> 4
+    // |--> 5
+    let tree = tree!{ 0 => [1 => [2 => [3], 4], 5] };
+    let n = NodeId::new;
+    assert_eq!(tree.depth(n(0)), Some(0));
+    assert_eq!(tree.depth(n(3)), Some(3));
+    assert_eq!(tree.depth(n(5)), Some(1));
+    assert_eq!(tree.depth(n(6)), None);
+
+    assert_eq!(tree.height(n(0)), Some(3));
+    assert_eq!(tree.height(n(1)), Some(2));
+    assert_eq!(tree.height(n(4)), Some(0));
+    assert_eq!(tree.height(n(6)), None);
+}
+
+#[test]
+fn sizes() {
+    // 0 -> 1 -> 2 -> 3
+    // |    |--> 4
+    // |--> 5
+    let tree = tree!{ 0 => [1 => [2 => [3], 4], 5] };
+    let n = NodeId::new;
+    assert_eq!(tree.len(), 6);
+    assert_eq!(tree.leaf_count(), 3);
+    assert_eq!(tree.subtree_size(n(0)), Some(6));
+    assert_eq!(tree.subtree_size(n(1)), Some(4));
+    assert_eq!(tree.subtree_size(n(3)), Some(1));
+    assert_eq!(tree.subtree_size(n(6)), None);
+
+    let sizes = tree.subtree_sizes();
+    for x in 0..6 {
+        assert_eq!(sizes.get(n(x)), tree.subtree_size(n(x)));
+    }
+    assert_eq!(sizes.get(n(6)), None);
+
+    let empty = ITree::<u8>::new();
+    assert!(empty.is_empty());
+    assert_eq!(empty.leaf_count(), 0);
+}
@@ -0,0 +1,281 @@
+use core::iter::FromIterator;
+
+use alloc::vec::Vec;
+
+use arena::Arena;
+
+/// Radix Tree
+///
+/// A `Trie` that merges chains of nodes with a single child into one node, so
+/// every node holds a run of bytes of the key instead of a single one. Every
+/// node besides the root either holds a value or splits into more than one
+/// branch, which keeps the tree small for long keys with little in common.
+#[derive(Debug, Clone)]
+pub struct RadixTree<V> {
+    nodes: Arena<RadixNode<V>>,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct RadixNode<V> {
+    /// Bytes of the key on the way from the parent to this node
+    label: Vec<u8>,
+    value: Option<V>,
+    /// Children sorted by the first byte of their label, which differs for
+    /// every child
+    children: Vec<usize>,
+}
+
+impl<V> RadixNode<V> {
+    fn new(label: Vec<u8>, value: Option<V>) -> Self {
+        RadixNode { label, value, children: Vec::new() }
+    }
+}
+
+/// The root node has an empty label and always exists
+const ROOT: usize = 0;
+
+impl<V> RadixTree<V> {
+    /// Create a new empty `RadixTree`
+    pub fn new() -> Self {
+        let mut nodes = Arena::new();
+        nodes.insert(RadixNode::new(Vec::new(), None));
+        RadixTree { nodes, len: 0 }
+    }
+
+    /// Get the number of keys in the tree
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Insert a value under a key, returning the value the key had before
+    pub fn insert<K: AsRef<[u8]>>(&mut self, key: K, value: V) -> Option<V> {
+        let mut node = ROOT;
+        let mut rest = key.as_ref();
+        while !rest.is_empty() {
+            let pos = match self.child(node, rest[0]) {
+                Ok(pos) => pos,
+                Err(pos) => {
+                    let leaf = self.nodes.insert(RadixNode::new(rest.to_vec(), Some(value)));
+                    self.nodes[node].children.insert(pos, leaf);
+                    self.len += 1;
+                    return None;
+                }
+            };
+            let child = self.nodes[node].children[pos];
+            let label = &self.nodes[child].label;
+            let common = label.iter().zip(rest).take_while(|&(a, b)| a == b).count();
+            if common < label.len() {
+                // Split the label of the child where the key branches off
+                let tail = self.nodes[child].label.split_off(common);
+                let head = self.nodes[child].label.clone();
+                self.nodes[child].label = tail;
+                let mut middle = RadixNode::new(head, None);
+                middle.children.push(child);
+                let middle = self.nodes.insert(middle);
+                self.nodes[node].children[pos] = middle;
+                node = middle;
+            } else {
+                node = child;
+            }
+            rest = &rest[common..];
+        }
+        let old = self.nodes[node].value.replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Get the value of a key
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Option<&V> {
+        let node = *self.path(key.as_ref())?.last()?;
+        self.nodes[node].value.as_ref()
+    }
+
+    /// Get a mutable reference to the value of a key
+    pub fn get_mut<K: AsRef<[u8]>>(&mut self, key: K) -> Option<&mut V> {
+        let node = *self.path(key.as_ref())?.last()?;
+        self.nodes[node].value.as_mut()
+    }
+
+    pub fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Find the longest key in the tree that `key` starts with. Returns the
+    /// length of that key along with its value.
+    pub fn longest_prefix<K: AsRef<[u8]>>(&self, key: K) -> Option<(usize, &V)> {
+        let mut rest = key.as_ref();
+        let mut node = ROOT;
+        let mut len = 0;
+        let mut found = self.nodes[ROOT].value.as_ref().map(|v| (0, v));
+        while let Some(&byte) = rest.first() {
+            let child = match self.child(node, byte) {
+                Ok(pos) => self.nodes[node].children[pos],
+                Err(_) => break,
+            };
+            let label = &self.nodes[child].label;
+            if !rest.starts_with(label) {
+                break;
+            }
+            node = child;
+            len += label.len();
+            rest = &rest[label.len()..];
+            if let Some(value) = self.nodes[node].value.as_ref() {
+                found = Some((len, value));
+            }
+        }
+        found
+    }
+
+    /// Remove a key and return its value. Nodes get merged back together so
+    /// the tree stays compressed.
+    pub fn remove<K: AsRef<[u8]>>(&mut self, key: K) -> Option<V> {
+        let path = self.path(key.as_ref())?;
+        let node = *path.last().unwrap();
+        let value = self.nodes[node].value.take()?;
+        self.len -= 1;
+        if node == ROOT {
+            return Some(value);
+        }
+
+        let parent = path[path.len() - 2];
+        match self.nodes[node].children.len() {
+            0 => {
+                self.nodes.remove(node);
+                self.nodes[parent].children.retain(|&c| c != node);
+                // The parent might be left with a single child to merge with
+                if parent != ROOT && self.nodes[parent].value.is_none() && self.nodes[parent].children.len() == 1 {
+                    self.merge(parent);
+                }
+            }
+            1 => self.merge(node),
+            _ => {}
+        }
+        Some(value)
+    }
+
+    /// Remove every key
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.nodes.insert(RadixNode::new(Vec::new(), None));
+        self.len = 0;
+    }
+
+    /// Iterate over every key along with its value in lexicographic order
+    pub fn iter(&self) -> RadixIter<'_, V> {
+        RadixIter { tree: self, key: Vec::new(), stack: vec![(ROOT, 0)] }
+    }
+
+    /// Get the position of the child of a node whose label starts with `byte`,
+    /// or where it would go
+    fn child(&self, node: usize, byte: u8) -> Result<usize, usize> {
+        let nodes = &self.nodes;
+        nodes[node].children.binary_search_by_key(&byte, |&c| nodes[c].label[0])
+    }
+
+    /// Get the nodes on the way down to the node a key ends at
+    fn path(&self, mut key: &[u8]) -> Option<Vec<usize>> {
+        let mut path = vec![ROOT];
+        while let Some(&byte) = key.first() {
+            let node = *path.last().unwrap();
+            let child = self.nodes[node].children[self.child(node, byte).ok()?];
+            key = key.strip_prefix(&self.nodes[child].label[..])?;
+            path.push(child);
+        }
+        Some(path)
+    }
+
+    /// Merge a node without a value into its only child
+    fn merge(&mut self, node: usize) {
+        let child = self.nodes[node].children[0];
+        let child = self.nodes.remove(child);
+        let merged = &mut self.nodes[node];
+        merged.label.extend(child.label);
+        merged.value = child.value;
+        merged.children = child.children;
+    }
+}
+
+impl<V> Default for RadixTree<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: AsRef<[u8]>, V> Extend<(K, V)> for RadixTree<V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: AsRef<[u8]>, V> FromIterator<(K, V)> for RadixTree<V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut tree = RadixTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+/// Iterator over the keys of a `RadixTree` in lexicographic order
+pub struct RadixIter<'a, V: 'a> {
+    tree: &'a RadixTree<V>,
+    key: Vec<u8>,
+    /// Nodes left to visit along with the length of the key above them
+    stack: Vec<(usize, usize)>,
+}
+
+impl<'a, V> Iterator for RadixIter<'a, V> {
+    type Item = (Vec<u8>, &'a V);
+
+    fn next(&mut self) -> Option<(Vec<u8>, &'a V)> {
+        let nodes = &self.tree.nodes;
+        while let Some((node, len)) = self.stack.pop() {
+            let node = &nodes[node];
+            self.key.truncate(len);
+            self.key.extend_from_slice(&node.label);
+            let len = self.key.len();
+            self.stack.extend(node.children.iter().rev().map(|&c| (c, len)));
+            if let Some(value) = node.value.as_ref() {
+                return Some((self.key.clone(), value));
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn radix() {
+    let mut tree: RadixTree<u32> = vec![("/api", 1), ("/api/users", 2), ("/app", 3)].into_iter().collect();
+    // "/ap" -> "i" -> "/users"
+    //     |--> "p"
+    assert_eq!(tree.nodes[ROOT].children.len(), 1);
+    assert_eq!(tree.len(), 3);
+    assert_eq!(tree.get("/api/users"), Some(&2));
+    assert_eq!(tree.get("/ap"), None);
+    assert_eq!(tree.get("/api/user"), None);
+
+    assert_eq!(tree.longest_prefix("/api/users/7"), Some((10, &2)));
+    assert_eq!(tree.longest_prefix("/api/u"), Some((4, &1)));
+    assert_eq!(tree.longest_prefix("/a"), None);
+
+    // Removing "/api" merges its node with "/users"
+    assert_eq!(tree.remove("/api"), Some(1));
+    assert_eq!(tree.remove("/api"), None);
+    let ap = tree.nodes[ROOT].children[0];
+    let labels: Vec<_> = tree.nodes[ap].children.iter().map(|&c| tree.nodes[c].label.clone()).collect();
+    assert_eq!(labels, vec![b"i/users".to_vec(), b"p".to_vec()]);
+    assert_eq!(tree.longest_prefix("/api/u"), None);
+
+    tree.insert("", 0);
+    let keys: Vec<_> = tree.iter().map(|(k, &v)| (k, v)).collect();
+    assert_eq!(keys, vec![(b"".to_vec(), 0), (b"/api/users".to_vec(), 2), (b"/app".to_vec(), 3)]);
+    assert_eq!(tree.longest_prefix("/x"), Some((0, &0)));
+}
@@ -0,0 +1,181 @@
+use core::borrow::Borrow;
+use core::mem;
+
+use bst::Bst;
+
+/// Color of a red node, every other node is black
+const RED: bool = true;
+const BLACK: bool = false;
+
+/// Red-Black Tree Map
+///
+/// An ordered map on a binary search tree whose nodes are colored red or
+/// black. Red nodes never have red children and every path from a node down to
+/// a leaf passes the same number of black nodes, so no path is more than twice
+/// as long as another. Keeping that up takes at most two rotations per
+/// insertion and three per removal, fewer than an `AvlMap` needs.
+#[derive(Clone)]
+pub struct RbMap<K, V> {
+    tree: Bst<K, V, bool>,
+}
+
+impl<K: Ord, V> RbMap<K, V> {
+    /// Create a new empty `RbMap`
+    pub fn new() -> Self {
+        RbMap { tree: Bst::new() }
+    }
+
+    /// Insert a value under a key, returning the value the key had before
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let at = match self.tree.search(&key) {
+            Ok(x) => return Some(mem::replace(&mut self.tree.nodes[x].value, value)),
+            Err(at) => at,
+        };
+        let mut x = self.tree.attach(at, key, value, RED);
+
+        // Push red nodes with red parents up until the colors line up again
+        while let Some(parent) = self.parent(x).filter(|&p| self.is_red(Some(p))) {
+            let grand = self.parent(parent).expect("the root is black");
+            let left = self.tree.nodes[grand].left == Some(parent);
+            let uncle = if left { self.tree.nodes[grand].right } else { self.tree.nodes[grand].left };
+            if self.is_red(uncle) {
+                self.paint(Some(parent), BLACK);
+                self.paint(uncle, BLACK);
+                self.paint(Some(grand), RED);
+                x = grand;
+                continue;
+            }
+            let mut parent = parent;
+            if left && self.tree.nodes[parent].right == Some(x) {
+                x = parent;
+                parent = self.tree.rotate_left(x);
+            } else if !left && self.tree.nodes[parent].left == Some(x) {
+                x = parent;
+                parent = self.tree.rotate_right(x);
+            }
+            self.paint(Some(parent), BLACK);
+            self.paint(Some(grand), RED);
+            if left {
+                self.tree.rotate_right(grand);
+            } else {
+                self.tree.rotate_left(grand);
+            }
+        }
+        let root = self.tree.root;
+        self.paint(root, BLACK);
+        None
+    }
+
+    /// Remove a key and return its value
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let x = self.tree.find(key)?;
+        let x = self.tree.leafward(x);
+        let (node, parent, child) = self.tree.unlink(x);
+        if node.meta == BLACK {
+            self.fix_removal(child, parent);
+        }
+        Some(node.value)
+    }
+
+    /// Make up for a black node that was taken out above `x`, whose parent is
+    /// `parent`
+    fn fix_removal(&mut self, mut x: Option<usize>, mut parent: Option<usize>) {
+        while x != self.tree.root && !self.is_red(x) {
+            let p = parent.expect("only the root has no parent");
+            let left = self.tree.nodes[p].left == x;
+            let sibling = |map: &Self| {
+                let node = &map.tree.nodes[p];
+                if left { node.right } else { node.left }.expect("a black node has a sibling")
+            };
+            let mut s = sibling(self);
+            if self.is_red(Some(s)) {
+                self.paint(Some(s), BLACK);
+                self.paint(Some(p), RED);
+                if left { self.tree.rotate_left(p) } else { self.tree.rotate_right(p) };
+                s = sibling(self);
+            }
+            let (near, far) = {
+                let node = &self.tree.nodes[s];
+                if left { (node.left, node.right) } else { (node.right, node.left) }
+            };
+            if !self.is_red(near) && !self.is_red(far) {
+                self.paint(Some(s), RED);
+                x = Some(p);
+                parent = self.parent(p);
+                continue;
+            }
+            if !self.is_red(far) {
+                self.paint(near, BLACK);
+                self.paint(Some(s), RED);
+                if left { self.tree.rotate_right(s) } else { self.tree.rotate_left(s) };
+                s = sibling(self);
+            }
+            let color = self.tree.nodes[p].meta;
+            self.paint(Some(s), color);
+            self.paint(Some(p), BLACK);
+            let far = if left { self.tree.nodes[s].right } else { self.tree.nodes[s].left };
+            self.paint(far, BLACK);
+            if left { self.tree.rotate_left(p) } else { self.tree.rotate_right(p) };
+            x = self.tree.root;
+            parent = None;
+        }
+        self.paint(x, BLACK);
+    }
+
+    fn parent(&self, x: usize) -> Option<usize> {
+        self.tree.nodes[x].parent
+    }
+
+    /// Missing children count as black
+    fn is_red(&self, x: Option<usize>) -> bool {
+        x.is_some_and(|x| self.tree.nodes[x].meta == RED)
+    }
+
+    fn paint(&mut self, x: Option<usize>, color: bool) {
+        if let Some(x) = x {
+            self.tree.nodes[x].meta = color;
+        }
+    }
+}
+
+bst_map!(RbMap, bool);
+bst_lookup!(RbMap);
+
+#[test]
+fn rb_map() {
+    /// Check the colors below a node, returning the number of black nodes on
+    /// every path down from it
+    fn check<K: Ord, V>(map: &RbMap<K, V>, x: Option<usize>) -> usize {
+        let x = match x {
+            Some(x) => x,
+            None => return 1,
+        };
+        let node = &map.tree.nodes[x];
+        if node.meta == RED {
+            assert!(!map.is_red(node.left) && !map.is_red(node.right));
+        }
+        let black = check(map, node.left);
+        assert_eq!(black, check(map, node.right));
+        black + (node.meta == BLACK) as usize
+    }
+
+    let mut map: RbMap<_, _> = (0..1000).map(|k| (k, k)).collect();
+    assert!(!map.is_red(map.tree.root));
+    check(&map, map.tree.root);
+    assert!(map.tree.height() <= 2 * 10);
+    assert_eq!(map.insert(500, 0), Some(500));
+    for k in (0..1000).filter(|k| k % 4 != 0) {
+        assert_eq!(map.remove(&k), Some(k));
+        check(&map, map.tree.root);
+    }
+    assert_eq!(map.remove(&1), None);
+    assert_eq!(map.len(), 250);
+    assert!(map.iter().map(|(&k, _)| k).eq((0..1000).step_by(4)));
+    for k in (0..1000).step_by(4) {
+        map.remove(&k);
+    }
+    assert!(map.is_empty());
+}
@@ -0,0 +1,480 @@
+use core::fmt;
+use core::ops::RangeBounds;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use arena::Arena;
+use segment::bounds;
+use treap::{xorshift, SEED};
+
+/// Rope
+///
+/// A string stored as a balanced tree of chunks, so inserting and removing
+/// text anywhere takes logarithmic time no matter how long the string is.
+/// The chunks sit in a treap keyed by their position, where every node keeps
+/// the number of characters and line breaks below it. Those counts find a
+/// character or a line in logarithmic time as well.
+///
+/// Every position is an index of a `char`, not a byte, and lines are split at
+/// `'\n'`.
+#[derive(Debug, Clone)]
+pub struct Rope {
+    nodes: Arena<RopeNode>,
+    root: Option<usize>,
+    /// State of the generator for priorities
+    seed: u64,
+}
+
+#[derive(Debug, Clone)]
+struct RopeNode {
+    text: String,
+    /// Characters and line breaks of the text of this node
+    chars: usize,
+    newlines: usize,
+    /// Characters and line breaks in the subtree of this node
+    total_chars: usize,
+    total_newlines: usize,
+    priority: u64,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+/// Most bytes a chunk gets when text is added
+const CHUNK: usize = 512;
+
+impl Rope {
+    /// Create a new empty `Rope`
+    pub fn new() -> Self {
+        Rope { nodes: Arena::new(), root: None, seed: SEED }
+    }
+
+    /// Get the number of characters
+    pub fn len_chars(&self) -> usize {
+        self.root.map_or(0, |x| self.nodes[x].total_chars)
+    }
+
+    /// Get the number of lines, which is one more than the number of line
+    /// breaks
+    pub fn len_lines(&self) -> usize {
+        1 + self.root.map_or(0, |x| self.nodes[x].total_newlines)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len_chars() == 0
+    }
+
+    /// Insert text before the character at `index`
+    ///
+    /// # Panics
+    ///
+    /// If `index` is larger than the number of characters.
+    pub fn insert(&mut self, index: usize, text: &str) {
+        assert!(index <= self.len_chars(), "index {} out of bounds for length {}", index, self.len_chars());
+        if text.is_empty() {
+            return;
+        }
+        // Small insertions go straight into the chunk they land in
+        if self.insert_in_chunk(index, text) {
+            return;
+        }
+        let (left, right) = self.split(self.root, index);
+        let middle = self.build(text);
+        let left = self.join(left, middle);
+        self.root = self.join(left, right);
+    }
+
+    /// Remove a range of characters
+    ///
+    /// # Panics
+    ///
+    /// If the range is out of bounds.
+    pub fn remove<R: RangeBounds<usize>>(&mut self, range: R) {
+        let (start, end) = bounds(range, self.len_chars());
+        if start == end {
+            return;
+        }
+        let (rest, right) = self.split(self.root, end);
+        let (left, middle) = self.split(rest, start);
+        let mut stack: Vec<_> = middle.into_iter().collect();
+        while let Some(x) = stack.pop() {
+            let node = self.nodes.remove(x);
+            stack.extend(node.left);
+            stack.extend(node.right);
+        }
+        self.root = self.join(left, right);
+    }
+
+    /// Copy a range of characters out into a `String`
+    ///
+    /// # Panics
+    ///
+    /// If the range is out of bounds.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> String {
+        let (start, end) = bounds(range, self.len_chars());
+        let mut out = String::new();
+        self.collect(self.root, start, end, &mut out);
+        out
+    }
+
+    /// Get the character at `index`
+    pub fn char(&self, index: usize) -> Option<char> {
+        let (x, offset) = self.find_char(index)?;
+        self.nodes[x].text.chars().nth(offset)
+    }
+
+    /// Get the line a character is on, counting from zero. The index can be
+    /// one past the last character.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is larger than the number of characters.
+    pub fn char_to_line(&self, index: usize) -> usize {
+        assert!(index <= self.len_chars(), "index {} out of bounds for length {}", index, self.len_chars());
+        let (mut x, mut index, mut line) = (self.root, index, 0);
+        while let Some(n) = x {
+            let node = &self.nodes[n];
+            let left = node.left.map_or(0, |l| self.nodes[l].total_chars);
+            if index < left {
+                x = node.left;
+                continue;
+            }
+            line += node.left.map_or(0, |l| self.nodes[l].total_newlines);
+            index -= left;
+            if index < node.chars {
+                return line + node.text.chars().take(index).filter(|&c| c == '\n').count();
+            }
+            line += node.newlines;
+            index -= node.chars;
+            x = node.right;
+        }
+        line
+    }
+
+    /// Get the index of the first character of a line
+    ///
+    /// # Panics
+    ///
+    /// If the line is out of bounds.
+    pub fn line_to_char(&self, line: usize) -> usize {
+        assert!(line < self.len_lines(), "line {} out of bounds for {} lines", line, self.len_lines());
+        if line == 0 {
+            return 0;
+        }
+        // Find the line break before the line
+        let (mut x, mut breaks, mut index) = (self.root, line, 0);
+        while let Some(n) = x {
+            let node = &self.nodes[n];
+            let left = node.left.map_or(0, |l| self.nodes[l].total_newlines);
+            if breaks <= left {
+                x = node.left;
+                continue;
+            }
+            breaks -= left;
+            index += node.left.map_or(0, |l| self.nodes[l].total_chars);
+            if breaks <= node.newlines {
+                let (pos, _) = node.text.chars().enumerate().filter(|&(_, c)| c == '\n').nth(breaks - 1).unwrap();
+                return index + pos + 1;
+            }
+            breaks -= node.newlines;
+            index += node.chars;
+            x = node.right;
+        }
+        unreachable!("the line exists")
+    }
+
+    /// Get the line and column of a character, both counting from zero
+    pub fn char_to_line_col(&self, index: usize) -> (usize, usize) {
+        let line = self.char_to_line(index);
+        (line, index - self.line_to_char(line))
+    }
+
+    /// Get the index of the character at a line and column, both counting
+    /// from zero
+    ///
+    /// # Panics
+    ///
+    /// If the line is out of bounds or the column lies past its end.
+    pub fn line_col_to_char(&self, line: usize, col: usize) -> usize {
+        let start = self.line_to_char(line);
+        let end = if line + 1 < self.len_lines() { self.line_to_char(line + 1) - 1 } else { self.len_chars() };
+        assert!(start + col <= end, "column {} out of bounds for line {}", col, line);
+        start + col
+    }
+
+    /// Iterate over the chunks of text in order
+    pub fn chunks(&self) -> RopeChunks<'_> {
+        let mut chunks = RopeChunks { rope: self, stack: Vec::new() };
+        chunks.descend(self.root);
+        chunks
+    }
+
+    /// Find the node holding a character along with the position of the
+    /// character in its text
+    fn find_char(&self, mut index: usize) -> Option<(usize, usize)> {
+        let mut x = self.root;
+        while let Some(n) = x {
+            let node = &self.nodes[n];
+            let left = node.left.map_or(0, |l| self.nodes[l].total_chars);
+            if index < left {
+                x = node.left;
+            } else if index < left + node.chars {
+                return Some((n, index - left));
+            } else {
+                index -= left + node.chars;
+                x = node.right;
+            }
+        }
+        None
+    }
+
+    /// Add text to the chunk holding the character at `index` or the one
+    /// right before it, if it stays small enough
+    fn insert_in_chunk(&mut self, mut index: usize, text: &str) -> bool {
+        let mut path = Vec::new();
+        let mut x = self.root;
+        while let Some(n) = x {
+            path.push(n);
+            let node = &self.nodes[n];
+            let left = node.left.map_or(0, |l| self.nodes[l].total_chars);
+            if index < left {
+                x = node.left;
+            } else if index <= left + node.chars {
+                if node.text.len() + text.len() > CHUNK {
+                    return false;
+                }
+                let at = node.text.char_indices().nth(index - left).map_or(node.text.len(), |(at, _)| at);
+                let node = &mut self.nodes[n];
+                node.text.insert_str(at, text);
+                node.chars += text.chars().count();
+                node.newlines += text.matches('\n').count();
+                for &p in path.iter().rev() {
+                    self.update(p);
+                }
+                return true;
+            } else {
+                index -= left + node.chars;
+                x = node.right;
+            }
+        }
+        false
+    }
+
+    /// Make a subtree out of text, cut into chunks
+    fn build(&mut self, mut text: &str) -> Option<usize> {
+        let mut root = None;
+        while !text.is_empty() {
+            let mut end = text.len().min(CHUNK);
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            let (chunk, rest) = text.split_at(end);
+            text = rest;
+            let node = RopeNode {
+                text: String::from(chunk),
+                chars: chunk.chars().count(),
+                newlines: chunk.matches('\n').count(),
+                total_chars: 0,
+                total_newlines: 0,
+                priority: xorshift(&mut self.seed),
+                left: None,
+                right: None,
+            };
+            let x = self.nodes.insert(node);
+            self.update(x);
+            root = self.join(root, Some(x));
+        }
+        root
+    }
+
+    /// Split a subtree into the first `index` characters and the rest,
+    /// cutting a chunk in two if needed
+    fn split(&mut self, x: Option<usize>, index: usize) -> (Option<usize>, Option<usize>) {
+        let n = match x {
+            Some(n) => n,
+            None => return (None, None),
+        };
+        let (left, right) = (self.nodes[n].left, self.nodes[n].right);
+        let left_chars = left.map_or(0, |l| self.nodes[l].total_chars);
+        if index <= left_chars {
+            let (a, b) = self.split(left, index);
+            self.nodes[n].left = b;
+            self.update(n);
+            return (a, Some(n));
+        }
+        let index = index - left_chars;
+        if index >= self.nodes[n].chars {
+            let (a, b) = self.split(right, index - self.nodes[n].chars);
+            self.nodes[n].right = a;
+            self.update(n);
+            return (Some(n), b);
+        }
+        // Cut the chunk of this node, moving the tail into a new node
+        let at = self.nodes[n].text.char_indices().nth(index).unwrap().0;
+        let tail = self.nodes[n].text.split_off(at);
+        let node = &mut self.nodes[n];
+        node.chars = index;
+        node.newlines = node.text.matches('\n').count();
+        node.right = None;
+        self.update(n);
+        let tail = self.build(&tail);
+        let tail = self.join(tail, right);
+        (Some(n), tail)
+    }
+
+    /// Join two subtrees, all of `left` coming before `right`
+    fn join(&mut self, left: Option<usize>, right: Option<usize>) -> Option<usize> {
+        let (l, r) = match (left, right) {
+            (None, x) | (x, None) => return x,
+            (Some(l), Some(r)) => (l, r),
+        };
+        if self.nodes[l].priority > self.nodes[r].priority {
+            let joined = self.join(self.nodes[l].right, Some(r));
+            self.nodes[l].right = joined;
+            self.update(l);
+            Some(l)
+        } else {
+            let joined = self.join(Some(l), self.nodes[r].left);
+            self.nodes[r].left = joined;
+            self.update(r);
+            Some(r)
+        }
+    }
+
+    /// Work out the totals of a node from its children
+    fn update(&mut self, x: usize) {
+        let (mut chars, mut newlines) = (self.nodes[x].chars, self.nodes[x].newlines);
+        for child in [self.nodes[x].left, self.nodes[x].right].iter().flatten() {
+            chars += self.nodes[*child].total_chars;
+            newlines += self.nodes[*child].total_newlines;
+        }
+        let node = &mut self.nodes[x];
+        node.total_chars = chars;
+        node.total_newlines = newlines;
+    }
+
+    /// Add the part of `start..end` inside a subtree to `out`
+    fn collect(&self, x: Option<usize>, start: usize, end: usize, out: &mut String) {
+        let n = match x {
+            Some(n) if start < end => n,
+            _ => return,
+        };
+        let node = &self.nodes[n];
+        let left = node.left.map_or(0, |l| self.nodes[l].total_chars);
+        if start < left {
+            self.collect(node.left, start, end.min(left), out);
+        }
+        let (from, to) = (start.max(left), end.min(left + node.chars));
+        if from < to {
+            out.extend(node.text.chars().skip(from - left).take(to - from));
+        }
+        let right = left + node.chars;
+        if end > right {
+            self.collect(node.right, start.max(right) - right, end - right, out);
+        }
+    }
+}
+
+impl Default for Rope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> From<&'a str> for Rope {
+    fn from(text: &'a str) -> Self {
+        let mut rope = Rope::new();
+        rope.root = rope.build(text);
+        rope
+    }
+}
+
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for chunk in self.chunks() {
+            f.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+/// Iterator over the chunks of text of a `Rope`
+pub struct RopeChunks<'a> {
+    rope: &'a Rope,
+    /// Nodes on the way down to the next chunk
+    stack: Vec<usize>,
+}
+
+impl<'a> RopeChunks<'a> {
+    fn descend(&mut self, mut x: Option<usize>) {
+        while let Some(n) = x {
+            self.stack.push(n);
+            x = self.rope.nodes[n].left;
+        }
+    }
+}
+
+impl<'a> Iterator for RopeChunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let x = self.stack.pop()?;
+        let node = &self.rope.nodes[x];
+        self.descend(node.right);
+        Some(&node.text)
+    }
+}
+
+#[test]
+fn rope() {
+    use alloc::string::ToString;
+
+    let line = "the quick brown fox jumps over the lazy dög\n";
+    let mut text: String = line.repeat(100);
+    let mut rope = Rope::from(&text[..]);
+    assert_eq!(rope.len_chars(), text.chars().count());
+    assert_eq!(rope.len_lines(), 101);
+    assert!(rope.chunks().count() > 1);
+    assert_eq!(rope.to_string(), text);
+
+    // Edit both through the rope and through a plain string of characters
+    let mut chars: Vec<char> = text.chars().collect();
+    let mut seed = 7u64;
+    for step in 0..300 {
+        let at = xorshift(&mut seed) as usize % (chars.len() + 1);
+        if step % 3 == 0 && !chars.is_empty() {
+            let end = (at + xorshift(&mut seed) as usize % 700).min(chars.len());
+            rope.remove(at..end);
+            chars.drain(at..end);
+        } else {
+            let insert = if step % 5 == 0 { line.repeat(20) } else { "ä\nx".to_string() };
+            rope.insert(at, &insert);
+            let tail = chars.split_off(at);
+            chars.extend(insert.chars());
+            chars.extend(tail);
+        }
+    }
+    text = chars.iter().collect();
+    assert_eq!(rope.to_string(), text);
+    assert_eq!(rope.len_chars(), chars.len());
+    assert_eq!(rope.len_lines(), 1 + text.matches('\n').count());
+    assert_eq!(rope.slice(100..400), chars[100..400].iter().collect::<String>());
+    assert_eq!(rope.char(250), Some(chars[250]));
+    assert_eq!(rope.char(chars.len()), None);
+
+    let starts: Vec<usize> = Some(0).into_iter().chain(chars.iter().enumerate().filter(|&(_, &c)| c == '\n').map(|(i, _)| i + 1)).collect();
+    for (line, &start) in starts.iter().enumerate() {
+        assert_eq!(rope.line_to_char(line), start);
+        assert_eq!(rope.char_to_line(start), line);
+    }
+    let index = starts[7] + 3;
+    assert_eq!(rope.char_to_line_col(index), (7, 3));
+    assert_eq!(rope.line_col_to_char(7, 3), index);
+    assert_eq!(rope.char_to_line(chars.len()), starts.len() - 1);
+
+    rope.remove(..);
+    assert!(rope.is_empty());
+    assert_eq!(rope.len_lines(), 1);
+    rope.insert(0, "a\nb");
+    assert_eq!(rope.line_col_to_char(1, 1), 3);
+}
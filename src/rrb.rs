@@ -0,0 +1,371 @@
+use core::fmt;
+use core::iter::FromIterator;
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// Persistent Vector
+///
+/// A relaxed radix balanced tree: a shallow tree of nodes with up to `WIDTH`
+/// children, where the elements sit in the leaves in order. Nodes sit behind
+/// an `Arc`, so clones take constant time and every change only copies the
+/// nodes on its path, sharing everything else with the vectors it came from.
+/// Getting, updating and pushing elements, and splitting and concatenating
+/// vectors, all take logarithmic time.
+///
+/// Every branch keeps the running totals of the sizes of its children, which
+/// lets nodes hold fewer than `WIDTH` children. Concatenation only merges the
+/// nodes along the seam between the two vectors, so vectors put together from
+/// many small pieces can end up with more, smaller nodes than pushing the same
+/// elements would make.
+pub struct RrbVec<T> {
+    root: Node<T>,
+    /// Levels of branches above the leaves
+    height: usize,
+    len: usize,
+}
+
+#[derive(Clone)]
+enum RrbNode<T> {
+    Leaf(Vec<T>),
+    /// Children along with the number of elements up to and including every
+    /// child
+    Branch(Vec<Node<T>>, Vec<usize>),
+}
+
+type Node<T> = Arc<RrbNode<T>>;
+
+/// Most elements of a leaf and children of a branch
+const WIDTH: usize = 32;
+
+impl<T: Clone> RrbVec<T> {
+    /// Create a new empty `RrbVec`
+    pub fn new() -> Self {
+        RrbVec { root: Arc::new(RrbNode::Leaf(Vec::new())), height: 0, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the element at an index
+    pub fn get(&self, mut index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        let mut node = &self.root;
+        loop {
+            match **node {
+                RrbNode::Leaf(ref values) => return values.get(index),
+                RrbNode::Branch(ref children, ref sizes) => {
+                    let child = position(sizes, index);
+                    index -= child.checked_sub(1).map_or(0, |c| sizes[c]);
+                    node = &children[child];
+                }
+            }
+        }
+    }
+
+    /// Replace the element at an index, returning the old one
+    ///
+    /// # Panics
+    ///
+    /// If the index is out of bounds.
+    pub fn set(&mut self, index: usize, value: T) -> T {
+        assert!(index < self.len, "index {} out of bounds for length {}", index, self.len);
+        set(&mut self.root, index, value)
+    }
+
+    /// Add an element to the back
+    pub fn push_back(&mut self, value: T) {
+        let single = RrbVec { root: Arc::new(RrbNode::Leaf(vec![value])), height: 0, len: 1 };
+        self.append(single);
+    }
+
+    /// Move every element of `other` to the back of this vector
+    pub fn append(&mut self, other: Self) {
+        if other.is_empty() {
+            return;
+        }
+        if self.is_empty() {
+            *self = other;
+            return;
+        }
+        let mut nodes = merge(&self.root, self.height, &other.root, other.height);
+        self.height = self.height.max(other.height);
+        self.len += other.len;
+        self.root = if nodes.len() == 1 {
+            nodes.pop().unwrap()
+        } else {
+            self.height += 1;
+            branch(nodes)
+        };
+    }
+
+    /// Split the vector in two at an index, keeping the elements before it
+    /// and returning the rest
+    ///
+    /// # Panics
+    ///
+    /// If the index is larger than the length.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len, "index {} out of bounds for length {}", at, self.len);
+        if at == self.len {
+            return RrbVec::new();
+        }
+        if at == 0 {
+            return core::mem::take(self);
+        }
+        let (left, right) = split(&self.root, at);
+        let mut right = RrbVec { root: right.unwrap(), height: self.height, len: self.len - at };
+        self.root = left.unwrap();
+        self.len = at;
+        self.shrink();
+        right.shrink();
+        right
+    }
+
+    /// Iterate over the elements in order
+    pub fn iter(&self) -> RrbIter<'_, T> {
+        RrbIter { stack: vec![(&*self.root, 0)], values: [].iter() }
+    }
+
+    /// Drop roots with a single child
+    fn shrink(&mut self) {
+        while self.height > 0 {
+            let child = match *self.root {
+                RrbNode::Branch(ref children, _) if children.len() == 1 => children[0].clone(),
+                _ => break,
+            };
+            self.root = child;
+            self.height -= 1;
+        }
+    }
+}
+
+impl<T> RrbNode<T> {
+    fn len(&self) -> usize {
+        match *self {
+            RrbNode::Leaf(ref values) => values.len(),
+            RrbNode::Branch(_, ref sizes) => *sizes.last().unwrap(),
+        }
+    }
+
+    fn children(&self) -> &[Node<T>] {
+        match *self {
+            RrbNode::Branch(ref children, _) => children,
+            RrbNode::Leaf(_) => unreachable!("leaves sit at the bottom"),
+        }
+    }
+}
+
+fn branch<T>(children: Vec<Node<T>>) -> Node<T> {
+    let sizes = children
+        .iter()
+        .scan(0, |total, child| {
+            *total += child.len();
+            Some(*total)
+        })
+        .collect();
+    Arc::new(RrbNode::Branch(children, sizes))
+}
+
+/// Get the position of the child holding the element at `index`
+fn position(sizes: &[usize], index: usize) -> usize {
+    sizes.partition_point(|&size| size <= index)
+}
+
+fn set<T: Clone>(node: &mut Node<T>, mut index: usize, value: T) -> T {
+    match *Arc::make_mut(node) {
+        RrbNode::Leaf(ref mut values) => core::mem::replace(&mut values[index], value),
+        RrbNode::Branch(ref mut children, ref sizes) => {
+            let child = position(sizes, index);
+            index -= child.checked_sub(1).map_or(0, |c| sizes[c]);
+            set(&mut children[child], index, value)
+        }
+    }
+}
+
+/// Merge two nodes into one or two nodes at the level of the higher one,
+/// merging the nodes along the seam in between where they fit
+fn merge<T: Clone>(left: &Node<T>, lh: usize, right: &Node<T>, rh: usize) -> Vec<Node<T>> {
+    if lh == 0 && rh == 0 {
+        return match (&**left, &**right) {
+            (RrbNode::Leaf(l), RrbNode::Leaf(r)) if l.len() + r.len() <= WIDTH => {
+                let mut values = l.clone();
+                values.extend(r.iter().cloned());
+                vec![Arc::new(RrbNode::Leaf(values))]
+            }
+            _ => vec![left.clone(), right.clone()],
+        };
+    }
+    let mut children = Vec::new();
+    if lh >= rh {
+        let l = left.children();
+        children.extend(l[..l.len() - 1].iter().cloned());
+        if lh > rh {
+            children.extend(merge(&l[l.len() - 1], lh - 1, right, rh));
+        } else {
+            let r = right.children();
+            children.extend(merge(&l[l.len() - 1], lh - 1, &r[0], rh - 1));
+            children.extend(r[1..].iter().cloned());
+        }
+    } else {
+        let r = right.children();
+        children.extend(merge(left, lh, &r[0], rh - 1));
+        children.extend(r[1..].iter().cloned());
+    }
+    if children.len() <= WIDTH {
+        vec![branch(children)]
+    } else {
+        let rest = children.split_off(WIDTH);
+        vec![branch(children), branch(rest)]
+    }
+}
+
+/// Split a node before `at`, leaving out the halves that end up empty
+fn split<T: Clone>(node: &Node<T>, at: usize) -> (Option<Node<T>>, Option<Node<T>>) {
+    match **node {
+        _ if at == 0 => (None, Some(node.clone())),
+        _ if at >= node.len() => (Some(node.clone()), None),
+        RrbNode::Leaf(ref values) => {
+            let (left, right) = values.split_at(at);
+            (Some(Arc::new(RrbNode::Leaf(left.to_vec()))), Some(Arc::new(RrbNode::Leaf(right.to_vec()))))
+        }
+        RrbNode::Branch(ref children, ref sizes) => {
+            let child = position(sizes, at);
+            let before = child.checked_sub(1).map_or(0, |c| sizes[c]);
+            let (l, r) = split(&children[child], at - before);
+            let left = children[..child].iter().cloned().chain(l).collect();
+            let right = r.into_iter().chain(children[child + 1..].iter().cloned()).collect();
+            (Some(branch(left)), Some(branch(right)))
+        }
+    }
+}
+
+impl<T> Clone for RrbVec<T> {
+    fn clone(&self) -> Self {
+        RrbVec { root: self.root.clone(), height: self.height, len: self.len }
+    }
+}
+
+impl<T: Clone> Default for RrbVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + fmt::Debug> fmt::Debug for RrbVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone + PartialEq> PartialEq for RrbVec<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Clone> Extend<T> for RrbVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T: Clone> FromIterator<T> for RrbVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = RrbVec::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<'a, T: Clone> IntoIterator for &'a RrbVec<T> {
+    type Item = &'a T;
+    type IntoIter = RrbIter<'a, T>;
+
+    fn into_iter(self) -> RrbIter<'a, T> {
+        self.iter()
+    }
+}
+
+/// Iterator over the elements of an `RrbVec` in order
+pub struct RrbIter<'a, T: 'a> {
+    /// Branches on the way down to the current leaf along with the position
+    /// of the next child to visit
+    stack: Vec<(&'a RrbNode<T>, usize)>,
+    /// Rest of the current leaf
+    values: core::slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for RrbIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            if let Some(value) = self.values.next() {
+                return Some(value);
+            }
+            let (node, pos) = self.stack.pop()?;
+            match *node {
+                RrbNode::Leaf(ref values) => self.values = values.iter(),
+                RrbNode::Branch(ref children, _) => {
+                    if let Some(child) = children.get(pos) {
+                        self.stack.push((node, pos + 1));
+                        self.stack.push((child, 0));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn rrb_vec() {
+    let mut vec: RrbVec<u32> = (0..5000).collect();
+    assert_eq!(vec.len(), 5000);
+    assert_eq!(vec.height, 2);
+    assert_eq!(vec.get(1234), Some(&1234));
+    assert_eq!(vec.get(5000), None);
+
+    // Changes leave clones alone
+    let snapshot = vec.clone();
+    assert_eq!(vec.set(1234, 0), 1234);
+    assert_eq!(vec.get(1234), Some(&0));
+    assert_eq!(snapshot.get(1234), Some(&1234));
+    vec.set(1234, 1234);
+
+    for &at in &[0, 1, 31, 32, 1000, 4999, 5000] {
+        let mut left = vec.clone();
+        let right = left.split_off(at);
+        assert_eq!(left.len(), at);
+        assert!(left.iter().cloned().eq(0..at as u32));
+        assert!(right.iter().cloned().eq(at as u32..5000));
+        assert_eq!(right.get(0).cloned(), if at < 5000 { Some(at as u32) } else { None });
+        left.append(right);
+        assert!(left == snapshot);
+    }
+
+    // Put a vector together out of pieces of different sizes
+    let mut pieces = RrbVec::new();
+    let mut expected = Vec::new();
+    for size in 0..120u32 {
+        let piece: RrbVec<u32> = (0..size * 7 % 90).map(|x| x + size * 1000).collect();
+        expected.extend(piece.iter().cloned());
+        pieces.append(piece);
+    }
+    assert_eq!(pieces.len(), expected.len());
+    assert!(pieces.iter().eq(expected.iter()));
+    for (i, value) in expected.iter().enumerate().step_by(97) {
+        assert_eq!(pieces.get(i), Some(value));
+    }
+    let tail = pieces.split_off(expected.len() / 3);
+    assert!(tail.iter().eq(expected[expected.len() / 3..].iter()));
+}
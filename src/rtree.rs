@@ -0,0 +1,514 @@
+use core::iter::FromIterator;
+use core::mem;
+
+use alloc::vec::Vec;
+
+use arena::Arena;
+
+/// Corners of a box with the smallest coordinates first
+type Rect<const D: usize> = ([f64; D], [f64; D]);
+
+/// R-Tree
+///
+/// A balanced tree of boxes in `D` dimensions, which finds every stored box
+/// that overlaps a query box. Every node keeps the bounding box of everything
+/// below it and holds up to a fixed number of entries, so queries skip the
+/// nodes whose bounding box stays clear of the query.
+///
+/// Boxes are given by their two corners, `(min, max)`, edges included.
+/// Inserting boxes one by one splits nodes that overflow, while `bulk_load`
+/// packs a list of boxes into full nodes with sort-tile-recursive packing,
+/// which builds a tighter tree much faster.
+#[derive(Debug, Clone)]
+pub struct RTree<const D: usize, V> {
+    nodes: Arena<RNode<D, V>>,
+    root: usize,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct RNode<const D: usize, V> {
+    bounds: Rect<D>,
+    parent: Option<usize>,
+    entries: Entries<D, V>,
+}
+
+#[derive(Debug, Clone)]
+enum Entries<const D: usize, V> {
+    Leaf(Vec<(Rect<D>, V)>),
+    Inner(Vec<usize>),
+}
+
+/// Most entries a node holds
+const MAX: usize = 16;
+/// Fewest entries a node besides the root holds
+const MIN: usize = MAX * 2 / 5;
+
+impl<const D: usize, V> RTree<D, V> {
+    /// Create a new empty `RTree`
+    pub fn new() -> Self {
+        let mut nodes = Arena::new();
+        let root = nodes.insert(RNode { bounds: empty(), parent: None, entries: Entries::Leaf(Vec::new()) });
+        RTree { nodes, root, len: 0 }
+    }
+
+    /// Build a tree out of a list of boxes at once
+    pub fn bulk_load(items: Vec<(Rect<D>, V)>) -> Self {
+        let mut tree = RTree::new();
+        if items.is_empty() {
+            return tree;
+        }
+        tree.nodes.clear();
+        tree.len = items.len();
+
+        // Pack the boxes into leaves, then pack every level of nodes into the
+        // level above it until a single node is left
+        let mut level: Vec<usize> = tile(items, 0, &|item: &(Rect<D>, V)| item.0)
+            .into_iter()
+            .map(|entries| {
+                let bounds = cover(entries.iter().map(|e| &e.0));
+                tree.nodes.insert(RNode { bounds, parent: None, entries: Entries::Leaf(entries) })
+            })
+            .collect();
+        while level.len() > 1 {
+            let nodes = &tree.nodes;
+            let groups = tile(level, 0, &|&x: &usize| nodes[x].bounds);
+            level = groups.into_iter().map(|children| tree.add_inner(children)).collect();
+        }
+        tree.root = level[0];
+        tree
+    }
+
+    /// Get the number of boxes in the tree
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Add a box
+    pub fn insert(&mut self, bounds: Rect<D>, value: V) {
+        let mut x = self.root;
+        while let Entries::Inner(ref children) = self.nodes[x].entries {
+            // Go where the box grows the bounding box the least
+            let nodes = &self.nodes;
+            x = *children
+                .iter()
+                .min_by(|&&a, &&b| {
+                    let (a, b) = (&nodes[a].bounds, &nodes[b].bounds);
+                    // Unbounded boxes grow by inf - inf, which is NaN
+                    enlargement(a, &bounds)
+                        .total_cmp(&enlargement(b, &bounds))
+                        .then(volume(a).total_cmp(&volume(b)))
+                })
+                .unwrap();
+        }
+        match self.nodes[x].entries {
+            Entries::Leaf(ref mut entries) => entries.push((bounds, value)),
+            Entries::Inner(_) => unreachable!(),
+        }
+        self.len += 1;
+        self.adjust(x);
+    }
+
+    /// Remove a box with exactly the given corners and return its value
+    pub fn remove(&mut self, bounds: &Rect<D>) -> Option<V> {
+        let (leaf, pos) = self.find(self.root, bounds)?;
+        let value = match self.nodes[leaf].entries {
+            Entries::Leaf(ref mut entries) => entries.swap_remove(pos).1,
+            Entries::Inner(_) => unreachable!(),
+        };
+        self.len -= 1;
+
+        // Take nodes that got too small out of the tree and add their boxes
+        // back in afterwards
+        let mut orphans = Vec::new();
+        let mut x = leaf;
+        while let Some(parent) = self.nodes[x].parent {
+            if self.nodes[x].entries.len() < MIN {
+                if let Entries::Inner(ref mut children) = self.nodes[parent].entries {
+                    children.retain(|&c| c != x);
+                }
+                self.take_subtree(x, &mut orphans);
+            } else {
+                self.update(x);
+            }
+            x = parent;
+        }
+        self.update(x);
+        // Drop roots that point to a single child
+        while let Entries::Inner(ref children) = self.nodes[self.root].entries {
+            if children.len() != 1 {
+                break;
+            }
+            let child = children[0];
+            self.nodes.remove(self.root);
+            self.nodes[child].parent = None;
+            self.root = child;
+        }
+        if let Entries::Inner(ref children) = self.nodes[self.root].entries {
+            if children.is_empty() {
+                self.nodes[self.root].entries = Entries::Leaf(Vec::new());
+            }
+        }
+        self.len -= orphans.len();
+        for (bounds, value) in orphans {
+            self.insert(bounds, value);
+        }
+        Some(value)
+    }
+
+    /// Remove every box
+    pub fn clear(&mut self) {
+        *self = RTree::new();
+    }
+
+    /// Iterate over every box overlapping `bounds`, edges included
+    pub fn query(&self, bounds: Rect<D>) -> RTreeQuery<'_, D, V> {
+        RTreeQuery { tree: self, bounds: Some(bounds), stack: vec![self.root], entries: [].iter() }
+    }
+
+    /// Iterate over every box
+    pub fn iter(&self) -> RTreeQuery<'_, D, V> {
+        RTreeQuery { tree: self, bounds: None, stack: vec![self.root], entries: [].iter() }
+    }
+
+    fn add_inner(&mut self, children: Vec<usize>) -> usize {
+        let bounds = cover(children.iter().map(|&c| &self.nodes[c].bounds));
+        let x = self.nodes.insert(RNode { bounds, parent: None, entries: Entries::Inner(Vec::new()) });
+        for &c in &children {
+            self.nodes[c].parent = Some(x);
+        }
+        self.nodes[x].entries = Entries::Inner(children);
+        x
+    }
+
+    /// Split the nodes from `x` up that hold too many entries and update the
+    /// bounding boxes on the way
+    fn adjust(&mut self, mut x: usize) {
+        loop {
+            let sibling = if self.nodes[x].entries.len() > MAX { Some(self.split(x)) } else { None };
+            self.update(x);
+            match (self.nodes[x].parent, sibling) {
+                (Some(parent), Some(sibling)) => {
+                    self.nodes[sibling].parent = Some(parent);
+                    if let Entries::Inner(ref mut children) = self.nodes[parent].entries {
+                        children.push(sibling);
+                    }
+                    x = parent;
+                }
+                (Some(parent), None) => x = parent,
+                (None, Some(sibling)) => {
+                    self.root = self.add_inner(vec![x, sibling]);
+                    return;
+                }
+                (None, None) => return,
+            }
+        }
+    }
+
+    /// Move about half of the entries of a node into a new sibling
+    fn split(&mut self, x: usize) -> usize {
+        let (keep, moved) = match mem::replace(&mut self.nodes[x].entries, Entries::Inner(Vec::new())) {
+            Entries::Leaf(entries) => {
+                let (keep, moved) = quadratic_split(entries, |e| e.0);
+                (Entries::Leaf(keep), Entries::Leaf(moved))
+            }
+            Entries::Inner(children) => {
+                let nodes = &self.nodes;
+                let (keep, moved) = quadratic_split(children, |&c| nodes[c].bounds);
+                (Entries::Inner(keep), Entries::Inner(moved))
+            }
+        };
+        self.nodes[x].entries = keep;
+        let sibling = self.nodes.insert(RNode { bounds: empty(), parent: None, entries: Entries::Inner(Vec::new()) });
+        if let Entries::Inner(ref children) = moved {
+            for &c in children {
+                self.nodes[c].parent = Some(sibling);
+            }
+        }
+        self.nodes[sibling].entries = moved;
+        self.update(sibling);
+        sibling
+    }
+
+    /// Work out the bounding box of a node again
+    fn update(&mut self, x: usize) {
+        let bounds = match self.nodes[x].entries {
+            Entries::Leaf(ref entries) => cover(entries.iter().map(|e| &e.0)),
+            Entries::Inner(ref children) => cover(children.iter().map(|&c| &self.nodes[c].bounds)),
+        };
+        self.nodes[x].bounds = bounds;
+    }
+
+    /// Find the leaf holding a box along with its position there
+    fn find(&self, x: usize, bounds: &Rect<D>) -> Option<(usize, usize)> {
+        if !overlaps(&self.nodes[x].bounds, bounds) {
+            return None;
+        }
+        match self.nodes[x].entries {
+            Entries::Leaf(ref entries) => entries.iter().position(|e| e.0 == *bounds).map(|pos| (x, pos)),
+            Entries::Inner(ref children) => children.iter().find_map(|&c| self.find(c, bounds)),
+        }
+    }
+
+    /// Remove a subtree and collect its boxes
+    fn take_subtree(&mut self, x: usize, boxes: &mut Vec<(Rect<D>, V)>) {
+        match self.nodes.remove(x).entries {
+            Entries::Leaf(entries) => boxes.extend(entries),
+            Entries::Inner(children) => {
+                for c in children {
+                    self.take_subtree(c, boxes);
+                }
+            }
+        }
+    }
+}
+
+impl<const D: usize, V> Entries<D, V> {
+    fn len(&self) -> usize {
+        match *self {
+            Entries::Leaf(ref entries) => entries.len(),
+            Entries::Inner(ref children) => children.len(),
+        }
+    }
+}
+
+/// Sort-tile-recursive packing: sort items along an axis, cut them into
+/// slabs and tile every slab along the next axis, ending in groups of at most
+/// `MAX`. Runs are cut evenly, so only a lone group can have less than `MIN`.
+fn tile<T, F: Fn(&T) -> Rect<D>, const D: usize>(mut items: Vec<T>, axis: usize, bounds: &F) -> Vec<Vec<T>> {
+    let center = |item: &T| {
+        let (min, max) = bounds(item);
+        min[axis] + max[axis]
+    };
+    items.sort_by(|a, b| center(a).total_cmp(&center(b)));
+    let groups = items.len().div_ceil(MAX);
+    if axis + 1 == D || groups <= 1 {
+        return spread(items, groups);
+    }
+    // Cut into the `D - axis`th root of the number of groups to make slabs
+    let mut slabs: usize = 1;
+    while slabs.checked_pow((D - axis) as u32).is_some_and(|p| p < groups) {
+        slabs += 1;
+    }
+    spread(items, slabs).into_iter().flat_map(|slab| tile(slab, axis + 1, bounds)).collect()
+}
+
+/// Cut items into `parts` runs whose lengths differ by at most one
+fn spread<T>(items: Vec<T>, parts: usize) -> Vec<Vec<T>> {
+    let len = items.len();
+    let mut items = items.into_iter();
+    (0..parts).map(|k| items.by_ref().take(len / parts + usize::from(k < len % parts)).collect()).collect()
+}
+
+/// Split entries in two groups with Guttman's quadratic split
+fn quadratic_split<T, F: Fn(&T) -> Rect<D>, const D: usize>(mut entries: Vec<T>, bounds: F) -> (Vec<T>, Vec<T>) {
+    // Start from the pair that would waste the most space together
+    let mut seeds = (0, 1);
+    let mut worst = f64::NEG_INFINITY;
+    for i in 0..entries.len() {
+        for j in i + 1..entries.len() {
+            let (a, b) = (bounds(&entries[i]), bounds(&entries[j]));
+            let waste = volume(&union(&a, &b)) - volume(&a) - volume(&b);
+            if waste > worst {
+                worst = waste;
+                seeds = (i, j);
+            }
+        }
+    }
+    let second = entries.swap_remove(seeds.1);
+    let first = entries.swap_remove(seeds.0);
+    let (mut a_bounds, mut b_bounds) = (bounds(&first), bounds(&second));
+    let (mut a, mut b) = (vec![first], vec![second]);
+
+    while !entries.is_empty() {
+        // Give the rest to a group that would be too small without them
+        if a.len() + entries.len() == MIN {
+            a.append(&mut entries);
+            break;
+        }
+        if b.len() + entries.len() == MIN {
+            b.append(&mut entries);
+            break;
+        }
+        // Place the entry that cares the most about its group next
+        let (pos, _) = entries
+            .iter()
+            .map(|e| {
+                let e = bounds(e);
+                (enlargement(&a_bounds, &e) - enlargement(&b_bounds, &e)).abs()
+            })
+            .enumerate()
+            .max_by(|x, y| x.1.total_cmp(&y.1))
+            .unwrap();
+        let entry = entries.swap_remove(pos);
+        let e = bounds(&entry);
+        let to_a = (enlargement(&a_bounds, &e), volume(&a_bounds), a.len())
+            <= (enlargement(&b_bounds, &e), volume(&b_bounds), b.len());
+        if to_a {
+            a_bounds = union(&a_bounds, &e);
+            a.push(entry);
+        } else {
+            b_bounds = union(&b_bounds, &e);
+            b.push(entry);
+        }
+    }
+    (a, b)
+}
+
+/// Box that covers nothing, which any box covers when joined with it
+fn empty<const D: usize>() -> Rect<D> {
+    ([f64::INFINITY; D], [f64::NEG_INFINITY; D])
+}
+
+fn union<const D: usize>(a: &Rect<D>, b: &Rect<D>) -> Rect<D> {
+    let mut out = *a;
+    for axis in 0..D {
+        out.0[axis] = a.0[axis].min(b.0[axis]);
+        out.1[axis] = a.1[axis].max(b.1[axis]);
+    }
+    out
+}
+
+fn cover<'a, I: Iterator<Item = &'a Rect<D>>, const D: usize>(boxes: I) -> Rect<D> {
+    boxes.fold(empty(), |acc, b| union(&acc, b))
+}
+
+fn volume<const D: usize>(b: &Rect<D>) -> f64 {
+    (0..D).map(|axis| (b.1[axis] - b.0[axis]).max(0.0)).product()
+}
+
+/// How much the volume of `b` grows when it has to cover `new` as well
+fn enlargement<const D: usize>(b: &Rect<D>, new: &Rect<D>) -> f64 {
+    volume(&union(b, new)) - volume(b)
+}
+
+fn overlaps<const D: usize>(a: &Rect<D>, b: &Rect<D>) -> bool {
+    (0..D).all(|axis| a.0[axis] <= b.1[axis] && b.0[axis] <= a.1[axis])
+}
+
+impl<const D: usize, V> Default for RTree<D, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const D: usize, V> FromIterator<(Rect<D>, V)> for RTree<D, V> {
+    fn from_iter<I: IntoIterator<Item = (Rect<D>, V)>>(iter: I) -> Self {
+        RTree::bulk_load(iter.into_iter().collect())
+    }
+}
+
+/// Iterator over the boxes of an `RTree` overlapping a box
+pub struct RTreeQuery<'a, const D: usize, V: 'a> {
+    tree: &'a RTree<D, V>,
+    /// Box to look in, or nothing to go through every box
+    bounds: Option<Rect<D>>,
+    /// Nodes left to visit
+    stack: Vec<usize>,
+    /// Rest of the entries of the leaf being visited
+    entries: core::slice::Iter<'a, (Rect<D>, V)>,
+}
+
+impl<'a, const D: usize, V> Iterator for RTreeQuery<'a, D, V> {
+    type Item = (&'a Rect<D>, &'a V);
+
+    fn next(&mut self) -> Option<(&'a Rect<D>, &'a V)> {
+        loop {
+            for (bounds, value) in &mut self.entries {
+                if self.bounds.as_ref().is_none_or(|b| overlaps(b, bounds)) {
+                    return Some((bounds, value));
+                }
+            }
+            let node = &self.tree.nodes[self.stack.pop()?];
+            if self.bounds.as_ref().is_some_and(|b| !overlaps(b, &node.bounds)) {
+                continue;
+            }
+            match node.entries {
+                Entries::Leaf(ref entries) => self.entries = entries.iter(),
+                Entries::Inner(ref children) => self.stack.extend(children),
+            }
+        }
+    }
+}
+
+#[test]
+fn rtree() {
+    /// Check the bounding boxes, parents, fill and depth of every node,
+    /// returning the depth of the leaves
+    fn check<V>(tree: &RTree<2, V>, x: usize) -> usize {
+        let node = &tree.nodes[x];
+        if x != tree.root {
+            assert!(node.entries.len() >= MIN && node.entries.len() <= MAX);
+        }
+        match node.entries {
+            Entries::Leaf(ref entries) => {
+                assert_eq!(node.bounds, cover(entries.iter().map(|e| &e.0)));
+                0
+            }
+            Entries::Inner(ref children) => {
+                assert_eq!(node.bounds, cover(children.iter().map(|&c| &tree.nodes[c].bounds)));
+                let depths: Vec<_> = children
+                    .iter()
+                    .map(|&c| {
+                        assert_eq!(tree.nodes[c].parent, Some(x));
+                        check(tree, c)
+                    })
+                    .collect();
+                assert!(depths.iter().all(|&d| d == depths[0]));
+                depths[0] + 1
+            }
+        }
+    }
+
+    let rect = |i: u32| {
+        let (x, y) = ((i * 37 % 101) as f64, (i * 53 % 97) as f64);
+        ([x, y], [x + (i % 5) as f64, y + (i % 3) as f64])
+    };
+    let query = |tree: &RTree<2, u32>, bounds: Rect<2>| {
+        let mut found: Vec<_> = tree.query(bounds).map(|(_, &v)| v).collect();
+        found.sort();
+        found
+    };
+    let expected = |ids: &mut dyn Iterator<Item = u32>, bounds: Rect<2>| ids.filter(|&i| overlaps(&rect(i), &bounds)).collect::<Vec<_>>();
+
+    let mut inserted = RTree::new();
+    for i in 0..1000 {
+        inserted.insert(rect(i), i);
+    }
+    let loaded: RTree<2, _> = (0..1000).map(|i| (rect(i), i)).collect();
+    check(&inserted, inserted.root);
+    // Bulk loading fills every node
+    assert_eq!(check(&loaded, loaded.root), 2);
+    let area = ([20.0, 30.0], [45.0, 41.5]);
+    assert!(!expected(&mut (0..1000), area).is_empty());
+    assert_eq!(query(&inserted, area), expected(&mut (0..1000), area));
+    assert_eq!(query(&loaded, area), expected(&mut (0..1000), area));
+
+    let mut tree = loaded;
+    for i in (0..1000).filter(|i| i % 3 != 0) {
+        assert_eq!(tree.remove(&rect(i)), Some(i));
+    }
+    assert_eq!(tree.remove(&([500.0, 500.0], [501.0, 501.0])), None);
+    assert_eq!(tree.len(), 334);
+    check(&tree, tree.root);
+    assert_eq!(query(&tree, area), expected(&mut (0..1000).filter(|i| i % 3 == 0), area));
+    assert_eq!(tree.iter().count(), 334);
+    for i in (0..1000).filter(|i| i % 3 == 0) {
+        assert_eq!(tree.remove(&rect(i)), Some(i));
+    }
+    assert!(tree.is_empty());
+    assert_eq!(tree.iter().count(), 0);
+
+    // 897 boxes don't fit evenly into leaves of `MAX`
+    let mut uneven: RTree<2, _> = (0..897).map(|i| (rect(i), i)).collect();
+    check(&uneven, uneven.root);
+    // Boxes without an end or with NaN corners don't break inserting
+    uneven.insert(([f64::NEG_INFINITY, 0.0], [f64::INFINITY, 1.0]), 897);
+    uneven.insert(([f64::NAN; 2], [f64::NAN; 2]), 898);
+    assert_eq!(uneven.len(), 899);
+    assert!(uneven.query(([-1e9, 0.5], [-1e9, 0.5])).any(|(_, &v)| v == 897));
+}
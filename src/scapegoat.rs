@@ -0,0 +1,166 @@
+use core::borrow::Borrow;
+use core::mem;
+
+use alloc::vec::Vec;
+
+use bst::Bst;
+
+/// Scapegoat Tree Map
+///
+/// An ordered map on a binary search tree that keeps no balance information
+/// in its nodes. When an insertion ends up too deep the tree looks for a node
+/// on the way up whose children are far apart in size, the scapegoat, and
+/// rebuilds the subtree below it into a perfectly balanced one. Removing
+/// enough keys rebuilds the whole tree. This keeps the depth logarithmic with
+/// nodes that hold nothing besides their key, value and links.
+#[derive(Clone)]
+pub struct ScapegoatMap<K, V> {
+    tree: Bst<K, V, ()>,
+    /// Largest length the map had since the whole tree was last rebuilt
+    max_len: usize,
+}
+
+/// A child with more than `ALPHA` of the nodes of its parent makes the parent
+/// a scapegoat
+const ALPHA: f64 = 2.0 / 3.0;
+
+impl<K: Ord, V> ScapegoatMap<K, V> {
+    /// Create a new empty `ScapegoatMap`
+    pub fn new() -> Self {
+        ScapegoatMap { tree: Bst::new(), max_len: 0 }
+    }
+
+    /// Insert a value under a key, returning the value the key had before
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let at = match self.tree.search(&key) {
+            Ok(x) => return Some(mem::replace(&mut self.tree.nodes[x].value, value)),
+            Err(at) => at,
+        };
+        let x = self.tree.attach(at, key, value, ());
+        self.max_len = self.max_len.max(self.tree.len);
+        if self.depth(x) > depth_limit(self.tree.len) {
+            self.rebalance(x);
+        }
+        None
+    }
+
+    /// Remove a key and return its value
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let x = self.tree.find(key)?;
+        let x = self.tree.leafward(x);
+        let value = self.tree.unlink(x).0.value;
+        if (self.tree.len as f64) < ALPHA * self.max_len as f64 {
+            if let Some(root) = self.tree.root {
+                self.rebuild(root, self.tree.len);
+            }
+            self.max_len = self.tree.len;
+        }
+        Some(value)
+    }
+
+    fn depth(&self, mut x: usize) -> usize {
+        let mut depth = 0;
+        while let Some(p) = self.tree.nodes[x].parent {
+            depth += 1;
+            x = p;
+        }
+        depth
+    }
+
+    /// Find the scapegoat above a node that ended up too deep and rebuild it
+    fn rebalance(&mut self, mut x: usize) {
+        let mut size = 1;
+        while let Some(p) = self.tree.nodes[x].parent {
+            let node = &self.tree.nodes[p];
+            let sibling = if node.left == Some(x) { node.right } else { node.left };
+            let parent_size = size + 1 + self.size(sibling);
+            if size as f64 > ALPHA * parent_size as f64 {
+                self.rebuild(p, parent_size);
+                return;
+            }
+            x = p;
+            size = parent_size;
+        }
+    }
+
+    /// Count the nodes of a subtree
+    fn size(&self, x: Option<usize>) -> usize {
+        let mut stack: Vec<_> = x.into_iter().collect();
+        let mut size = 0;
+        while let Some(x) = stack.pop() {
+            size += 1;
+            stack.extend(self.tree.nodes[x].left);
+            stack.extend(self.tree.nodes[x].right);
+        }
+        size
+    }
+
+    /// Rebuild the subtree of a node with `size` nodes into a balanced one
+    fn rebuild(&mut self, x: usize, size: usize) {
+        let parent = self.tree.nodes[x].parent;
+        let mut order = Vec::with_capacity(size);
+        let mut next = Some(self.tree.min(x));
+        while order.len() < size {
+            let node = next.expect("subtree has fewer nodes than its size");
+            order.push(node);
+            next = self.tree.next(node);
+        }
+        let top = self.build(&order, parent);
+        self.tree.replace(parent, x, top);
+    }
+
+    /// Link nodes sorted by key into a balanced subtree below `parent` and
+    /// return its top
+    fn build(&mut self, order: &[usize], parent: Option<usize>) -> Option<usize> {
+        if order.is_empty() {
+            return None;
+        }
+        let mid = order.len() / 2;
+        let x = order[mid];
+        let left = self.build(&order[..mid], Some(x));
+        let right = self.build(&order[mid + 1..], Some(x));
+        let node = &mut self.tree.nodes[x];
+        node.parent = parent;
+        node.left = left;
+        node.right = right;
+        Some(x)
+    }
+}
+
+/// Get the deepest a node may be in a tree of `len` nodes, rounded down
+fn depth_limit(len: usize) -> usize {
+    let mut limit = 0;
+    let mut nodes = 1.0 / ALPHA;
+    while nodes <= len as f64 {
+        nodes /= ALPHA;
+        limit += 1;
+    }
+    limit
+}
+
+bst_map!(ScapegoatMap, ());
+bst_lookup!(ScapegoatMap);
+
+#[test]
+fn scapegoat_map() {
+    let mut map: ScapegoatMap<_, _> = (0..1000).map(|k| (k, k * 10)).collect();
+    // The limit for 1000 keys is 17
+    assert!(map.tree.height() <= 18);
+    assert_eq!(map.len(), 1000);
+    assert_eq!(map.get(&500), Some(&5000));
+    assert_eq!(map.insert(500, 1), Some(5000));
+    assert_eq!(map.max_len, 1000);
+
+    // Removing a third of the keys rebuilds the whole tree
+    for k in 0..334 {
+        assert_eq!(map.remove(&k), Some(k * 10));
+    }
+    assert_eq!(map.max_len, 666);
+    assert_eq!(map.tree.height(), 10);
+    assert_eq!(map.remove(&0), None);
+    assert!(map.iter().map(|(&k, _)| k).eq(334..1000));
+    assert_eq!(map.range(998..).count(), 2);
+}
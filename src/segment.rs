@@ -0,0 +1,315 @@
+use core::ops::{Bound, RangeBounds};
+
+use alloc::vec::Vec;
+
+/// Operations of a `SegmentTree`
+///
+/// `combine` has to be associative with `identity` as its identity, so values
+/// can be combined in any grouping. Updates have to distribute over
+/// `combine`: applying an update to the combination of two values must give
+/// the same result as combining the two updated values.
+pub trait SegmentOp<T> {
+    /// Update applied to every value of a range at once
+    type Update: Clone;
+
+    /// Value that doesn't change any value it gets combined with
+    fn identity(&self) -> T;
+
+    /// Combine the values of two neighboring ranges
+    fn combine(&self, left: &T, right: &T) -> T;
+
+    /// Apply an update to the combined value of `len` values
+    fn apply(&self, update: &Self::Update, value: &T, len: usize) -> T;
+
+    /// Combine two updates into one that does the same as applying `older`
+    /// and then `newer`
+    fn compose(&self, newer: &Self::Update, older: &Self::Update) -> Self::Update;
+}
+
+/// Segment Tree
+///
+/// A binary tree over a list of values where every node holds the combined
+/// value of a range of the list, so any range can be combined from a
+/// logarithmic number of nodes. Updates to a range are kept at the highest
+/// nodes that cover it and only pushed down once a query or update needs the
+/// nodes below. The nodes live in flat vectors, where the children of node
+/// `x` are `2x` and `2x + 1`.
+#[derive(Debug, Clone)]
+pub struct SegmentTree<T, Op: SegmentOp<T>> {
+    op: Op,
+    len: usize,
+    values: Vec<T>,
+    /// Updates that still need to be applied to the children of a node
+    pending: Vec<Option<Op::Update>>,
+}
+
+impl<T: Clone, Op: SegmentOp<T>> SegmentTree<T, Op> {
+    /// Build a tree over a list of values
+    pub fn new(op: Op, items: &[T]) -> Self {
+        let nodes = 4 * items.len().max(1);
+        let mut tree = SegmentTree {
+            values: vec![op.identity(); nodes],
+            pending: vec![None; nodes],
+            len: items.len(),
+            op,
+        };
+        if !items.is_empty() {
+            tree.build(1, 0, items.len(), items);
+        }
+        tree
+    }
+
+    /// Get the operations of the tree
+    pub fn op(&self) -> &Op {
+        &self.op
+    }
+
+    /// Get the number of values in the tree
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Combine the values of a range. Empty ranges give the identity.
+    ///
+    /// # Panics
+    ///
+    /// If the range is out of bounds.
+    pub fn query<R: RangeBounds<usize>>(&self, range: R) -> T {
+        let (start, end) = bounds(range, self.len);
+        if start == end {
+            return self.op.identity();
+        }
+        self.query_at(1, 0, self.len, start, end)
+    }
+
+    /// Get a single value
+    ///
+    /// # Panics
+    ///
+    /// If the index is out of bounds.
+    pub fn get(&self, index: usize) -> T {
+        self.query(index..=index)
+    }
+
+    /// Apply an update to every value of a range
+    ///
+    /// # Panics
+    ///
+    /// If the range is out of bounds.
+    pub fn update<R: RangeBounds<usize>>(&mut self, range: R, update: Op::Update) {
+        let (start, end) = bounds(range, self.len);
+        if start < end {
+            self.update_at(1, 0, self.len, start, end, &update);
+        }
+    }
+
+    /// Replace a single value
+    ///
+    /// # Panics
+    ///
+    /// If the index is out of bounds.
+    pub fn set(&mut self, index: usize, value: T) {
+        assert!(index < self.len, "index {} out of bounds for length {}", index, self.len);
+        let (mut node, mut lo, mut hi) = (1, 0, self.len);
+        let mut path = Vec::new();
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            self.push(node, lo, mid, hi);
+            path.push(node);
+            if index < mid {
+                node *= 2;
+                hi = mid;
+            } else {
+                node = 2 * node + 1;
+                lo = mid;
+            }
+        }
+        self.values[node] = value;
+        for &node in path.iter().rev() {
+            self.pull(node);
+        }
+    }
+
+    fn build(&mut self, node: usize, lo: usize, hi: usize, items: &[T]) {
+        if hi - lo == 1 {
+            self.values[node] = items[lo].clone();
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        self.build(2 * node, lo, mid, items);
+        self.build(2 * node + 1, mid, hi, items);
+        self.pull(node);
+    }
+
+    /// Combine the part of `start..end` inside the range `lo..hi` of a node
+    fn query_at(&self, node: usize, lo: usize, hi: usize, start: usize, end: usize) -> T {
+        if start <= lo && hi <= end {
+            return self.values[node].clone();
+        }
+        let mid = lo + (hi - lo) / 2;
+        let value = if end <= mid {
+            self.query_at(2 * node, lo, mid, start, end)
+        } else if mid <= start {
+            self.query_at(2 * node + 1, mid, hi, start, end)
+        } else {
+            let left = self.query_at(2 * node, lo, mid, start, end);
+            let right = self.query_at(2 * node + 1, mid, hi, start, end);
+            self.op.combine(&left, &right)
+        };
+        // The children don't know about the updates still pending here
+        match self.pending[node] {
+            Some(ref update) => self.op.apply(update, &value, end.min(hi) - start.max(lo)),
+            None => value,
+        }
+    }
+
+    fn update_at(&mut self, node: usize, lo: usize, hi: usize, start: usize, end: usize, update: &Op::Update) {
+        if end <= lo || hi <= start {
+            return;
+        }
+        if start <= lo && hi <= end {
+            self.apply(node, hi - lo, update);
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        self.push(node, lo, mid, hi);
+        self.update_at(2 * node, lo, mid, start, end, update);
+        self.update_at(2 * node + 1, mid, hi, start, end, update);
+        self.pull(node);
+    }
+
+    /// Apply an update to a node with `len` values and remember it for its
+    /// children
+    fn apply(&mut self, node: usize, len: usize, update: &Op::Update) {
+        self.values[node] = self.op.apply(update, &self.values[node], len);
+        if len > 1 {
+            self.pending[node] = Some(match self.pending[node].take() {
+                Some(older) => self.op.compose(update, &older),
+                None => update.clone(),
+            });
+        }
+    }
+
+    /// Move the pending update of a node down to its children
+    fn push(&mut self, node: usize, lo: usize, mid: usize, hi: usize) {
+        if let Some(update) = self.pending[node].take() {
+            self.apply(2 * node, mid - lo, &update);
+            self.apply(2 * node + 1, hi - mid, &update);
+        }
+    }
+
+    /// Recompute the value of a node from its children
+    fn pull(&mut self, node: usize) {
+        self.values[node] = self.op.combine(&self.values[2 * node], &self.values[2 * node + 1]);
+    }
+}
+
+/// Turn a range of indices into a list of `len` values into its start and end
+///
+/// # Panics
+///
+/// If the range is out of bounds.
+pub(crate) fn bounds<R: RangeBounds<usize>>(range: R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&s) => s,
+        Bound::Excluded(&s) => s + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&e) => e + 1,
+        Bound::Excluded(&e) => e,
+        Bound::Unbounded => len,
+    };
+    assert!(start <= end, "range starts at {} but ends at {}", start, end);
+    assert!(end <= len, "range end {} out of bounds for length {}", end, len);
+    (start, end)
+}
+
+#[test]
+fn segment_tree() {
+    /// Sums with updates that add to every value
+    struct SumAdd;
+
+    impl SegmentOp<i64> for SumAdd {
+        type Update = i64;
+
+        fn identity(&self) -> i64 {
+            0
+        }
+
+        fn combine(&self, left: &i64, right: &i64) -> i64 {
+            left + right
+        }
+
+        fn apply(&self, update: &i64, value: &i64, len: usize) -> i64 {
+            value + update * len as i64
+        }
+
+        fn compose(&self, newer: &i64, older: &i64) -> i64 {
+            newer + older
+        }
+    }
+
+    /// Minimums with updates that assign to every value
+    struct MinAssign;
+
+    impl SegmentOp<i64> for MinAssign {
+        type Update = i64;
+
+        fn identity(&self) -> i64 {
+            i64::MAX
+        }
+
+        fn combine(&self, left: &i64, right: &i64) -> i64 {
+            *left.min(right)
+        }
+
+        fn apply(&self, update: &i64, _: &i64, _: usize) -> i64 {
+            *update
+        }
+
+        fn compose(&self, newer: &i64, _: &i64) -> i64 {
+            *newer
+        }
+    }
+
+    let mut values: Vec<i64> = (0..37).map(|x| (x * 7919) % 101).collect();
+    let mut sums = SegmentTree::new(SumAdd, &values);
+    let mut mins = SegmentTree::new(MinAssign, &values);
+    assert_eq!(sums.query(..), values.iter().sum::<i64>());
+    assert_eq!(sums.query(3..3), 0);
+
+    let mut seed = 1u64;
+    for _ in 0..500 {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let a = (seed >> 33) as usize % 38;
+        let b = (seed >> 17) as usize % 38;
+        let (start, end) = (a.min(b), a.max(b));
+        let x = (seed >> 50) as i64 % 50 - 25;
+        match seed % 4 {
+            0 => {
+                sums.update(start..end, x);
+                values[start..end].iter_mut().for_each(|v| *v += x);
+                mins = SegmentTree::new(MinAssign, &values);
+            }
+            1 if start < 37 => {
+                sums.set(start, x);
+                values[start] = x;
+                mins.set(start, x);
+            }
+            2 => {
+                mins.update(start..end, x);
+                values[start..end].iter_mut().for_each(|v| *v = x);
+                sums = SegmentTree::new(SumAdd, &values);
+            }
+            _ => {}
+        }
+        assert_eq!(sums.query(start..end), values[start..end].iter().sum::<i64>());
+        assert_eq!(mins.query(start..end), values[start..end].iter().copied().min().unwrap_or(i64::MAX));
+        assert_eq!(sums.get(end.min(36)), values[end.min(36)]);
+    }
+}
@@ -0,0 +1,227 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use {GroveError, ITree, NodeId};
+
+/// Named predicates on values that selectors can refer to
+///
+/// ```
+/// use grove::{ITree, Predicates};
+///
+/// let mut tree = ITree::new();
+/// let root = tree.add_root("list").unwrap();
+/// let item = tree.add_node(root, "item");
+/// tree.add_node(item, "big item");
+///
+/// let predicates = Predicates::new()
+///     .with("list", |v: &&str| *v == "list")
+///     .with("item", |v: &&str| v.ends_with("item"))
+///     .with("big", |v: &&str| v.starts_with("big"));
+/// let selector = predicates.compile("list item[big]").unwrap();
+/// assert_eq!(tree.select(&selector).len(), 1);
+/// ```
+pub struct Predicates<'a, T> {
+    predicates: Vec<(&'a str, Predicate<'a, T>)>,
+}
+
+type Predicate<'a, T> = Box<dyn Fn(&T) -> bool + 'a>;
+
+impl<'a, T> Predicates<'a, T> {
+    /// Create an empty set of predicates
+    pub fn new() -> Self {
+        Predicates { predicates: Vec::new() }
+    }
+
+    /// Add a predicate under a name, replacing any predicate with the same
+    /// name
+    pub fn with<F>(mut self, name: &'a str, predicate: F) -> Self
+        where F: Fn(&T) -> bool + 'a
+    {
+        self.predicates.retain(|&(n, _)| n != name);
+        self.predicates.push((name, Box::new(predicate)));
+        self
+    }
+
+    /// Compile a selector, or return the byte position of the first problem
+    /// with it
+    ///
+    /// A selector is a list of steps that match nodes, where every step is
+    /// the name of a predicate or `*` for any node, followed by any number of
+    /// more predicates in brackets, like `item[big][red]`. Steps are joined by
+    /// whitespace if the next step has to match a descendant of the node the
+    /// step before matched, or by `>` for a child. Like in CSS the selector
+    /// picks the nodes the last step matches.
+    pub fn compile(&self, selector: &str) -> Result<Selector<'_, 'a, T>, GroveError> {
+        let bytes = selector.as_bytes();
+        let mut steps = Vec::new();
+        let mut pos = 0;
+        let skip_space = |pos: &mut usize| {
+            while bytes.get(*pos).is_some_and(u8::is_ascii_whitespace) {
+                *pos += 1;
+            }
+        };
+        skip_space(&mut pos);
+        let mut combinator = Combinator::Descendant;
+        loop {
+            let mut step = Step { combinator, predicates: Vec::new() };
+            let start = pos;
+            if bytes.get(pos) == Some(&b'*') {
+                pos += 1;
+            } else if let Some(predicate) = self.name(selector, &mut pos)? {
+                step.predicates.push(predicate);
+            }
+            while bytes.get(pos) == Some(&b'[') {
+                pos += 1;
+                match self.name(selector, &mut pos)? {
+                    Some(predicate) => step.predicates.push(predicate),
+                    None => return Err(GroveError::InvalidSelector(pos)),
+                }
+                if bytes.get(pos) != Some(&b']') {
+                    return Err(GroveError::InvalidSelector(pos));
+                }
+                pos += 1;
+            }
+            if pos == start {
+                return Err(GroveError::InvalidSelector(pos));
+            }
+            steps.push(step);
+            let end = pos;
+            skip_space(&mut pos);
+            match bytes.get(pos) {
+                None => break,
+                Some(b'>') => {
+                    pos += 1;
+                    skip_space(&mut pos);
+                    combinator = Combinator::Child;
+                }
+                Some(_) if pos > end => combinator = Combinator::Descendant,
+                Some(_) => return Err(GroveError::InvalidSelector(pos)),
+            }
+        }
+        Ok(Selector { predicates: self, steps })
+    }
+
+    /// Read the name of a predicate, returning its position
+    fn name(&self, selector: &str, pos: &mut usize) -> Result<Option<usize>, GroveError> {
+        let start = *pos;
+        let rest = &selector.as_bytes()[start..];
+        let len = rest.iter().take_while(|&&b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-').count();
+        if len == 0 {
+            return Ok(None);
+        }
+        *pos += len;
+        let name = &selector[start..*pos];
+        match self.predicates.iter().position(|&(n, _)| n == name) {
+            Some(predicate) => Ok(Some(predicate)),
+            None => Err(GroveError::InvalidSelector(start)),
+        }
+    }
+}
+
+impl<'a, T> Default for Predicates<'a, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A compiled selector
+///
+/// Created with `Predicates::compile` and used with `ITree::select`.
+pub struct Selector<'p, 'a: 'p, T: 'p> {
+    predicates: &'p Predicates<'a, T>,
+    steps: Vec<Step>,
+}
+
+struct Step {
+    /// How the node of this step relates to the one of the step before
+    combinator: Combinator,
+    /// Positions of the predicates every matched value has to pass
+    predicates: Vec<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+impl<'p, 'a, T> Selector<'p, 'a, T> {
+    fn matches(&self, step: &Step, value: &T) -> bool {
+        step.predicates.iter().all(|&p| (self.predicates.predicates[p].1)(value))
+    }
+}
+
+impl<T> ITree<T> {
+    /// Find every node a selector picks, in pre-order
+    pub fn select(&self, selector: &Selector<'_, '_, T>) -> Vec<NodeId> {
+        let steps = selector.steps.len();
+        let mut found = Vec::new();
+        // Along with every node the steps matched at its parent, and the steps
+        // matched at any of its ancestors
+        let mut stack = match self.root_id() {
+            Some(root) => vec![(root, vec![false; steps], vec![false; steps])],
+            None => Vec::new(),
+        };
+        while let Some((id, parent, above)) = stack.pop() {
+            let value = &self[id];
+            let matched: Vec<bool> = selector
+                .steps
+                .iter()
+                .enumerate()
+                .map(|(i, step)| {
+                    let placed = i == 0 || match step.combinator {
+                        Combinator::Child => parent[i - 1],
+                        Combinator::Descendant => above[i - 1],
+                    };
+                    placed && selector.matches(step, value)
+                })
+                .collect();
+            if matched[steps - 1] {
+                found.push(id);
+            }
+            let above: Vec<bool> = above.iter().zip(&matched).map(|(&a, &m)| a || m).collect();
+            for &child in self.children[id.index()].iter().rev() {
+                stack.push((child, matched.clone(), above.clone()));
+            }
+        }
+        found
+    }
+}
+
+#[test]
+fn select() {
+    // doc -> section -> para "a"
+    // |      |--> section -> para "b"
+    // |--> para "c"
+    let tree = tree!{
+        "doc" => [
+            "section" => ["para a", "section" => ["para b"]],
+            "para c",
+        ]
+    };
+    let predicates = Predicates::new()
+        .with("doc", |v: &&str| *v == "doc")
+        .with("section", |v: &&str| *v == "section")
+        .with("para", |v: &&str| v.starts_with("para"))
+        .with("b", |v: &&str| v.ends_with('b'));
+    let select = |selector| {
+        let selector = predicates.compile(selector).unwrap();
+        tree.select(&selector).into_iter().map(|id| tree[id]).collect::<Vec<_>>()
+    };
+    assert_eq!(select("para"), ["para a", "para b", "para c"]);
+    assert_eq!(select("doc > para"), ["para c"]);
+    assert_eq!(select("section para"), ["para a", "para b"]);
+    assert_eq!(select("section > section > para"), ["para b"]);
+    assert_eq!(select("doc>section>*"), ["para a", "section"]);
+    assert_eq!(select(" section  * "), ["para a", "section", "para b"]);
+    assert_eq!(select("*[para][b]"), ["para b"]);
+    assert_eq!(select("doc > section > para[b]"), Vec::<&str>::new());
+
+    let error = |selector| predicates.compile(selector).err();
+    assert_eq!(error("para >"), Some(GroveError::InvalidSelector(6)));
+    assert_eq!(error("para > list"), Some(GroveError::InvalidSelector(7)));
+    assert_eq!(error("para[b"), Some(GroveError::InvalidSelector(6)));
+    assert_eq!(error("para[]"), Some(GroveError::InvalidSelector(5)));
+    assert_eq!(error(""), Some(GroveError::InvalidSelector(0)));
+    assert_eq!(error("para*"), Some(GroveError::InvalidSelector(4)));
+}
@@ -0,0 +1,137 @@
+use core::convert::TryFrom;
+
+use alloc::vec::Vec;
+
+use serde::de::Error;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use {GroveError, INode, ITree, Idx, NodeId};
+
+impl Serialize for NodeId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.index().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let index: usize = Deserialize::deserialize(deserializer)?;
+        match Idx::try_from(index) {
+            Ok(index) => Ok(NodeId { index }),
+            Err(_) => Err(D::Error::custom("node index is too large for the node id type")),
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for ITree<T> {
+    /// Serialize the tree as a list of nodes, the same way it would look if
+    /// the nodes were stored together
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ITree", 1)?;
+        state.serialize_field("nodes", &Nodes(self))?;
+        state.end()
+    }
+}
+
+/// The nodes of an `ITree` in their serialized form
+struct Nodes<'a, T: 'a>(&'a ITree<T>);
+
+impl<'a, T: Serialize> Serialize for Nodes<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let tree = self.0;
+        serializer.collect_seq((0..tree.len()).map(|x| RawNode {
+            value: &tree.values[x],
+            parent: tree.parents[x],
+            children: &tree.children[x],
+        }))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename = "INode")]
+struct RawNode<'a, T: 'a> {
+    value: &'a T,
+    parent: Option<NodeId>,
+    children: &'a [NodeId],
+}
+
+/// The serialized form of an `ITree` before it has been checked
+#[derive(Deserialize)]
+#[serde(rename = "ITree")]
+pub struct RawTree<T> {
+    nodes: Vec<INode<T>>,
+}
+
+impl<T> TryFrom<RawTree<T>> for ITree<T> {
+    type Error = GroveError;
+
+    /// Make sure the deserialized nodes form the same kind of tree `add_node`
+    /// would have built
+    fn try_from(raw: RawTree<T>) -> Result<Self, GroveError> {
+        let nodes = raw.nodes;
+        let mut links = 0;
+        for (x, node) in nodes.iter().enumerate() {
+            let id = NodeId::new(x);
+            match node.parent {
+                // Parents are always inserted before their children
+                Some(p) if p.index() >= x => {
+                    return Err(GroveError::InvalidNodeId(id))
+                }
+                Some(_) if x == 0 => return Err(GroveError::InvalidNodeId(id)),
+                None if x != 0 => return Err(GroveError::MultipleRoots),
+                _ => {}
+            }
+            let mut last = id;
+            for &child in &node.children {
+                let c = child.index();
+                // Children are stored in insertion order and point back to us
+                if child <= last || nodes.get(c).is_none_or(|n| n.parent != Some(id)) {
+                    return Err(GroveError::InvalidNodeId(child));
+                }
+                last = child;
+            }
+            links += node.children.len();
+        }
+        // Every node but the root is the child of exactly one node
+        if links + 1 != nodes.len() && !nodes.is_empty() {
+            return Err(GroveError::InvalidNodeId(NodeId::new(nodes.len() - 1)));
+        }
+        let children = nodes.iter().map(|node| node.children.clone()).collect();
+        let parents = nodes.iter().map(|node| node.parent).collect();
+        let values = nodes.into_iter().map(|node| node.value).collect();
+        Ok(ITree { values, parents, children })
+    }
+}
+
+#[test]
+fn round_trip() {
+    extern crate serde_json;
+
+    let mut tree = ITree::new();
+    // 0 -> 1 -> 3
+    // |--> 2
+    let root = tree.add_root("a".to_string()).unwrap();
+    let one = tree.add_node(root, "b".to_string());
+    tree.add_node(root, "c".to_string());
+    let three = tree.add_node(one, "d".to_string());
+
+    let json = serde_json::to_string(&tree).unwrap();
+    let back: ITree<String> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, tree);
+    assert_eq!(back.get(three).unwrap().parent(), Some(one));
+
+    let empty: ITree<u8> = serde_json::from_str(r#"{"nodes":[]}"#).unwrap();
+    assert!(empty.root().is_none());
+
+    // Structures add_node couldn't have built are rejected
+    let bad = [
+        r#"{"nodes":[{"value":0,"parent":null,"children":[1]}]}"#,
+        r#"{"nodes":[{"value":0,"parent":null,"children":[]},{"value":1,"parent":0,"children":[]}]}"#,
+        r#"{"nodes":[{"value":0,"parent":null,"children":[1,1]},{"value":1,"parent":0,"children":[]}]}"#,
+        r#"{"nodes":[{"value":0,"parent":1,"children":[]},{"value":1,"parent":null,"children":[0]}]}"#,
+    ];
+    for json in &bad {
+        assert!(serde_json::from_str::<ITree<u8>>(json).is_err(), "{}", json);
+    }
+}
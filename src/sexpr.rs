@@ -0,0 +1,206 @@
+use core::fmt::{self, Write};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use visit::Event;
+use {GroveError, ITree, NodeId};
+
+/// Atom of an s-expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Atom {
+    Symbol(String),
+    Integer(i64),
+    Float(f64),
+    /// String between double quotes
+    String(String),
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Atom::Symbol(ref s) => f.write_str(s),
+            Atom::Integer(i) => write!(f, "{}", i),
+            // Debug keeps the point in floats without a fraction
+            Atom::Float(x) => write!(f, "{:?}", x),
+            Atom::String(ref s) => {
+                f.write_char('"')?;
+                for c in s.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\t' => f.write_str("\\t")?,
+                        c => f.write_char(c)?,
+                    }
+                }
+                f.write_char('"')
+            }
+        }
+    }
+}
+
+impl ITree<Atom> {
+    /// Parse an s-expression like `(html (head) (body (p "hi")))`
+    ///
+    /// The first atom of a list is the node and the rest of the list are its
+    /// children, so `(a)` is the same as `a`. Lists have to start with an
+    /// atom. Comments go from `;` to the end of the line. The error has the
+    /// byte where the string stops being a single s-expression.
+    pub fn from_sexpr(sexpr: &str) -> Result<Self, GroveError> {
+        let bytes = sexpr.as_bytes();
+        let mut tree = ITree::new();
+        // Nodes of the open lists, `None` until their first atom
+        let mut open: Vec<Option<NodeId>> = Vec::new();
+        let mut pos = 0;
+        loop {
+            while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b';') {
+                if bytes[pos] == b';' {
+                    pos = sexpr[pos..].find('\n').map_or(bytes.len(), |end| pos + end);
+                } else {
+                    pos += 1;
+                }
+            }
+            let done = open.is_empty() && !tree.is_empty();
+            if pos == bytes.len() {
+                if done {
+                    return Ok(tree);
+                }
+                return Err(GroveError::InvalidSexpr(pos));
+            }
+            if done || (open.last() == Some(&None) && matches!(bytes[pos], b'(' | b')')) {
+                return Err(GroveError::InvalidSexpr(pos));
+            }
+            match bytes[pos] {
+                b'(' => {
+                    open.push(None);
+                    pos += 1;
+                }
+                b')' => {
+                    if open.pop().is_none() {
+                        return Err(GroveError::InvalidSexpr(pos));
+                    }
+                    pos += 1;
+                }
+                _ => {
+                    let atom = atom(sexpr, &mut pos)?;
+                    // The node of the list around a new list is the parent
+                    let parent = match open.last() {
+                        Some(&None) => open.len().checked_sub(2).map(|p| open[p].unwrap()),
+                        Some(&Some(node)) => Some(node),
+                        None => None,
+                    };
+                    let node = match parent {
+                        Some(parent) => tree.add_node(parent, atom),
+                        None => tree.add_root(atom)?,
+                    };
+                    if let Some(head @ &mut None) = open.last_mut() {
+                        *head = Some(node);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Write the tree as an s-expression, or an empty string for an empty
+    /// tree. Symbols are written as they are, so they should not have
+    /// whitespace, parentheses or quotes to come back the same.
+    pub fn to_sexpr(&self) -> String {
+        let mut out = String::new();
+        for (id, event) in self.euler_tour() {
+            let node = self.get(id).unwrap();
+            let leaf = node.children().is_empty();
+            match event {
+                Event::Enter => {
+                    // Every child comes after the node of its list or a sibling
+                    if node.parent().is_some() {
+                        out.push(' ');
+                    }
+                    if !leaf {
+                        out.push('(');
+                    }
+                    write!(out, "{}", node.value()).unwrap();
+                }
+                Event::Exit if !leaf => out.push(')'),
+                Event::Exit => {}
+            }
+        }
+        out
+    }
+}
+
+/// Read the atom at `pos`
+fn atom(sexpr: &str, pos: &mut usize) -> Result<Atom, GroveError> {
+    let start = *pos;
+    let rest = &sexpr[start..];
+    if rest.starts_with('"') {
+        let mut s = String::new();
+        let mut chars = rest.char_indices().skip(1);
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    *pos += i + 1;
+                    return Ok(Atom::String(s));
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => s.push('\n'),
+                    Some((_, 't')) => s.push('\t'),
+                    Some((_, c @ '"')) | Some((_, c @ '\\')) => s.push(c),
+                    Some((i, _)) => return Err(GroveError::InvalidSexpr(start + i)),
+                    None => break,
+                },
+                c => s.push(c),
+            }
+        }
+        return Err(GroveError::InvalidSexpr(start));
+    }
+    let end = rest.find(|c: char| c.is_whitespace() || "()\";".contains(c)).unwrap_or(rest.len());
+    let token = &rest[..end];
+    *pos += end;
+    // Only tokens that start like numbers are numbers, so `inf` stays a symbol
+    let digits = token.trim_start_matches(['+', '-']).trim_start_matches('.');
+    if digits.starts_with(|c: char| c.is_ascii_digit()) {
+        if let Ok(i) = token.parse() {
+            return Ok(Atom::Integer(i));
+        }
+        if let Ok(x) = token.parse() {
+            return Ok(Atom::Float(x));
+        }
+    }
+    Ok(Atom::Symbol(token.into()))
+}
+
+#[test]
+fn sexpr() {
+    let source = "(define ; a comment\n  (square x) (* x x)\n  \"a \\\"doc\\\"\" 1.5 -2 1.0 +inf)";
+    let tree = ITree::from_sexpr(source).unwrap();
+    let symbol = |s: &str| Atom::Symbol(s.into());
+    let atoms: Vec<_> = tree.iter_dfs(NodeId::new(0)).map(|(_, n)| n.value().clone()).collect();
+    assert_eq!(atoms, vec![
+        symbol("define"),
+        symbol("square"),
+        symbol("x"),
+        symbol("*"),
+        symbol("x"),
+        symbol("x"),
+        Atom::String("a \"doc\"".into()),
+        Atom::Float(1.5),
+        Atom::Integer(-2),
+        Atom::Float(1.0),
+        symbol("+inf"),
+    ]);
+    let sexpr = tree.to_sexpr();
+    assert_eq!(sexpr, "(define (square x) (* x x) \"a \\\"doc\\\"\" 1.5 -2 1.0 +inf)");
+    assert_eq!(ITree::from_sexpr(&sexpr).unwrap(), tree);
+    assert_eq!(ITree::from_sexpr("(a)").unwrap().to_sexpr(), "a");
+    assert_eq!(ITree::from_sexpr("((a) b)"), Err(GroveError::InvalidSexpr(1)));
+    assert_eq!(ITree::from_sexpr(" x ").unwrap(), tree!{ symbol("x") });
+    assert_eq!(ITree::<Atom>::new().to_sexpr(), "");
+
+    assert_eq!(ITree::from_sexpr(""), Err(GroveError::InvalidSexpr(0)));
+    assert_eq!(ITree::from_sexpr("(a (b)"), Err(GroveError::InvalidSexpr(6)));
+    assert_eq!(ITree::from_sexpr("(a) b"), Err(GroveError::InvalidSexpr(4)));
+    assert_eq!(ITree::from_sexpr("(a ())"), Err(GroveError::InvalidSexpr(4)));
+    assert_eq!(ITree::from_sexpr("(a \"b)"), Err(GroveError::InvalidSexpr(3)));
+    assert_eq!(ITree::from_sexpr("a)"), Err(GroveError::InvalidSexpr(1)));
+}
@@ -0,0 +1,161 @@
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use {ITree, ITreeSnapshot};
+
+/// Shared Tree
+///
+/// A handle to an `ITree` that can be cloned and sent to other threads. Any
+/// number of threads can read the tree at the same time while writes get
+/// exclusive access, so one thread can keep adding nodes while others query
+/// it. Clones of a handle all refer to the same tree.
+///
+/// A panic while holding a guard doesn't lock other threads out of the tree,
+/// nodes that were added before the panic stay in the tree.
+pub struct SharedTree<T> {
+    tree: Arc<RwLock<ITree<T>>>,
+}
+
+impl<T> SharedTree<T> {
+    /// Create a new handle to an empty tree
+    pub fn new() -> Self {
+        SharedTree::from(ITree::new())
+    }
+
+    /// Lock the tree for reading, blocking the current thread until no other
+    /// thread is writing to it
+    pub fn read(&self) -> ReadGuard<'_, T> {
+        ReadGuard { guard: self.tree.read().unwrap_or_else(PoisonError::into_inner) }
+    }
+
+    /// Lock the tree for writing, blocking the current thread until no other
+    /// thread is reading or writing it
+    pub fn write(&self) -> WriteGuard<'_, T> {
+        WriteGuard { guard: self.tree.write().unwrap_or_else(PoisonError::into_inner) }
+    }
+
+    /// Take a snapshot of the tree as it is right now, so it can be read for
+    /// as long as needed without keeping writers waiting. The tree is only
+    /// locked for writing while `ITree::snapshot` shares its chunks, which
+    /// copies nothing.
+    pub fn snapshot(&self) -> ITreeSnapshot<T>
+        where T: Clone
+    {
+        self.write().snapshot()
+    }
+}
+
+impl<T> Clone for SharedTree<T> {
+    /// Create another handle to the same tree
+    fn clone(&self) -> Self {
+        SharedTree { tree: Arc::clone(&self.tree) }
+    }
+}
+
+impl<T> Default for SharedTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<ITree<T>> for SharedTree<T> {
+    /// Share an existing tree
+    fn from(tree: ITree<T>) -> Self {
+        SharedTree { tree: Arc::new(RwLock::new(tree)) }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SharedTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedTree").field("tree", &*self.read()).finish()
+    }
+}
+
+/// Read access to a `SharedTree`
+///
+/// Created with `SharedTree::read`. The tree stays locked for reading until the
+/// guard is dropped.
+pub struct ReadGuard<'a, T: 'a> {
+    guard: RwLockReadGuard<'a, ITree<T>>,
+}
+
+impl<'a, T> Deref for ReadGuard<'a, T> {
+    type Target = ITree<T>;
+
+    fn deref(&self) -> &ITree<T> {
+        &self.guard
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for ReadGuard<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// Write access to a `SharedTree`
+///
+/// Created with `SharedTree::write`. The tree stays locked for writing until
+/// the guard is dropped.
+pub struct WriteGuard<'a, T: 'a> {
+    guard: RwLockWriteGuard<'a, ITree<T>>,
+}
+
+impl<'a, T> Deref for WriteGuard<'a, T> {
+    type Target = ITree<T>;
+
+    fn deref(&self) -> &ITree<T> {
+        &self.guard
+    }
+}
+
+impl<'a, T> DerefMut for WriteGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut ITree<T> {
+        &mut self.guard
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for WriteGuard<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[test]
+fn shared() {
+    use std::thread;
+
+    let shared = SharedTree::new();
+    let root = shared.write().add_root(0).unwrap();
+
+    let writer = {
+        let shared = shared.clone();
+        thread::spawn(move || {
+            for x in 1..100 {
+                shared.write().add_node(root, x);
+            }
+        })
+    };
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    let tree = shared.read();
+                    // Every node but the root is a child of the root
+                    assert_eq!(tree.get(root).unwrap().children().len() + 1, tree.len());
+                }
+            })
+        })
+        .collect();
+    writer.join().unwrap();
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    let snapshot = shared.snapshot();
+    shared.write().add_node(root, 100);
+    assert_eq!(snapshot.len(), 100);
+    assert_eq!(shared.read().len(), 101);
+}
@@ -0,0 +1,89 @@
+use core::ops::Deref;
+
+use alloc::sync::Arc;
+
+use ITree;
+
+impl<T: Clone> ITree<T> {
+    /// Take a snapshot of the tree as it is right now. The snapshot stays the
+    /// same while this tree keeps growing and can be sent to other threads.
+    ///
+    /// Nodes are stored in chunks and the snapshot shares the list of chunks
+    /// with this tree, so taking it copies nothing. The next change to the
+    /// tree copies the list, one pointer for every 1024 nodes, and adding a
+    /// node or a child to a shared chunk copies that chunk, at most once per
+    /// snapshot.
+    pub fn snapshot(&mut self) -> ITreeSnapshot<T> {
+        let tree = ITree {
+            values: self.values.share(),
+            parents: self.parents.share(),
+            children: self.children.share(),
+        };
+        ITreeSnapshot { tree: Arc::new(tree) }
+    }
+}
+
+/// Snapshot of an `ITree`
+///
+/// Created with `ITree::snapshot`. Derefs to the tree as it was when the
+/// snapshot was taken. Clones of a snapshot share the same nodes.
+#[derive(Debug)]
+pub struct ITreeSnapshot<T> {
+    tree: Arc<ITree<T>>,
+}
+
+impl<T> ITreeSnapshot<T> {
+    /// Turn the snapshot into a tree that can grow on its own, copying the
+    /// nodes only if other clones of the snapshot are still around
+    pub fn into_tree(self) -> ITree<T>
+        where T: Clone
+    {
+        Arc::try_unwrap(self.tree).unwrap_or_else(|tree| (*tree).clone())
+    }
+}
+
+impl<T> Clone for ITreeSnapshot<T> {
+    fn clone(&self) -> Self {
+        ITreeSnapshot { tree: Arc::clone(&self.tree) }
+    }
+}
+
+impl<T> Deref for ITreeSnapshot<T> {
+    type Target = ITree<T>;
+
+    fn deref(&self) -> &ITree<T> {
+        &self.tree
+    }
+}
+
+#[test]
+fn snapshot() {
+    use NodeId;
+
+    let mut tree = ITree::new();
+    let root = tree.add_root(0).unwrap();
+    for x in 1..5000 {
+        tree.add_node(NodeId::new(x / 2), x);
+    }
+    let snapshot = tree.snapshot();
+
+    // Growing the tree doesn't change the snapshot
+    tree.add_node(root, 5000);
+    tree.add_node(NodeId::new(4999), 5001);
+    assert_eq!(tree.len(), 5002);
+    assert_eq!(snapshot.len(), 5000);
+    assert_eq!(tree.get(root).unwrap().children().len(), 2);
+    assert_eq!(snapshot.get(root).unwrap().children().len(), 1);
+    assert_eq!(snapshot[NodeId::new(4999)], 4999);
+    assert!(snapshot.get(NodeId::new(5000)).is_none());
+
+    let copy = snapshot.clone().into_tree();
+    assert_eq!(copy, *snapshot);
+    let mut grown = snapshot.into_tree();
+    grown.add_node(root, 5000);
+    assert_eq!(grown.len(), 5001);
+
+    // Trees that still share chunks can be taken apart
+    let mtree = ::MTree::from(tree);
+    assert_eq!(mtree.len(), 5002);
+}
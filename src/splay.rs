@@ -0,0 +1,170 @@
+use core::borrow::Borrow;
+use core::mem;
+
+use bst::Bst;
+
+/// Splay Tree Map
+///
+/// An ordered map on a binary search tree that moves every key it looks up or
+/// inserts to the root with a series of rotations. Keys that get used often
+/// stay close to the root, so lookups of recently used keys are cheap.
+/// Sequences of operations take logarithmic time per operation on average,
+/// even though single operations can take longer.
+///
+/// Since lookups change the tree `get` needs a mutable reference. `peek` looks
+/// up a key without moving it.
+#[derive(Clone)]
+pub struct SplayMap<K, V> {
+    tree: Bst<K, V, ()>,
+}
+
+impl<K: Ord, V> SplayMap<K, V> {
+    /// Create a new empty `SplayMap`
+    pub fn new() -> Self {
+        SplayMap { tree: Bst::new() }
+    }
+
+    /// Insert a value under a key, returning the value the key had before.
+    /// The key ends up at the root.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.tree.search(&key) {
+            Ok(x) => {
+                self.splay(x);
+                Some(mem::replace(&mut self.tree.nodes[x].value, value))
+            }
+            Err(at) => {
+                let x = self.tree.attach(at, key, value, ());
+                self.splay(x);
+                None
+            }
+        }
+    }
+
+    /// Get the value of a key and move the key to the root. If the key isn't
+    /// there the last key on the way down moves to the root instead.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let x = self.access(key)?;
+        Some(&self.tree.nodes[x].value)
+    }
+
+    /// Get a mutable reference to the value of a key and move the key to the
+    /// root
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let x = self.access(key)?;
+        Some(&mut self.tree.nodes[x].value)
+    }
+
+    /// Get the value of a key without changing the tree
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        self.tree.find(key).map(|x| &self.tree.nodes[x].value)
+    }
+
+    /// Check whether a key is in the map without changing the tree
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        self.tree.find(key).is_some()
+    }
+
+    /// Remove a key and return its value
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let x = self.access(key)?;
+        let x = self.tree.leafward(x);
+        Some(self.tree.unlink(x).0.value)
+    }
+
+    /// Find a key and splay it, or the last node on the way down if the key
+    /// isn't there
+    fn access<Q>(&mut self, key: &Q) -> Option<usize>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        match self.tree.search(key) {
+            Ok(x) => {
+                self.splay(x);
+                Some(x)
+            }
+            Err(at) => {
+                if let Some((last, _)) = at {
+                    self.splay(last);
+                }
+                None
+            }
+        }
+    }
+
+    /// Rotate a node up to the root
+    fn splay(&mut self, x: usize) {
+        while let Some(parent) = self.tree.nodes[x].parent {
+            let left = self.tree.nodes[parent].left == Some(x);
+            match self.tree.nodes[parent].parent {
+                // Zig
+                None => self.rotate_up(parent, left),
+                Some(grand) => {
+                    let parent_left = self.tree.nodes[grand].left == Some(parent);
+                    if left == parent_left {
+                        // Zig-zig
+                        self.rotate_up(grand, parent_left);
+                        self.rotate_up(parent, left);
+                    } else {
+                        // Zig-zag
+                        self.rotate_up(parent, left);
+                        self.rotate_up(grand, parent_left);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Rotate the left or right child of a node up
+    fn rotate_up(&mut self, x: usize, left: bool) {
+        if left {
+            self.tree.rotate_right(x);
+        } else {
+            self.tree.rotate_left(x);
+        }
+    }
+}
+
+bst_map!(SplayMap, ());
+
+#[test]
+fn splay_map() {
+    let mut map: SplayMap<_, _> = (0..100).map(|k| (k, k * 10)).collect();
+    // Inserting keys in order leaves a chain down to the smallest key, and
+    // splaying that key roughly halves the depth of the nodes on the way
+    assert_eq!(map.tree.root, map.tree.find(&99));
+    assert_eq!(map.tree.height(), 100);
+    assert_eq!(map.get(&0), Some(&0));
+    assert_eq!(map.tree.root, map.tree.find(&0));
+    assert!(map.tree.height() <= 52);
+
+    assert_eq!(map.get(&10), Some(&100));
+    assert_eq!(map.tree.root, map.tree.find(&10));
+    assert_eq!(map.peek(&50), Some(&500));
+    assert_eq!(map.tree.root, map.tree.find(&10));
+    assert_eq!(map.get(&1000), None);
+    assert_eq!(map.tree.root, map.tree.find(&99));
+
+    *map.get_mut(&20).unwrap() = 0;
+    assert_eq!(map.insert(20, 1), Some(0));
+    for k in (0..100).filter(|k| k % 2 == 1) {
+        assert_eq!(map.remove(&k), Some(k * 10));
+    }
+    assert_eq!(map.remove(&1), None);
+    assert_eq!(map.len(), 50);
+    assert!(map.iter().map(|(&k, _)| k).eq((0..100).step_by(2)));
+}
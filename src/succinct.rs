@@ -0,0 +1,366 @@
+use alloc::vec::Vec;
+
+use {GroveError, ITree};
+
+/// Number of bits in a block of the search index
+const BLOCK: usize = 512;
+const WORDS: usize = BLOCK / 64;
+
+/// Succinct Tree
+///
+/// The shape of a tree in balanced parentheses: walking the tree in pre-order
+/// writes an opening bit when entering a node and a closing bit when leaving
+/// it, two bits per node. A small index on top, at most a third of a bit per
+/// bit, finds the matching parentheses in logarithmic time for the queries.
+///
+/// The structure can't change, and keeps no values. Nodes are numbered by
+/// their place in the pre-order of the tree, the order of `ITree::iter_dfs`
+/// from the root, so values can go in a plain `Vec` in that order next to it.
+///
+/// ```
+/// # #[macro_use] extern crate grove;
+/// # fn main() {
+/// use grove::SuccinctTree;
+///
+/// let tree = tree!{ "a" => ["b" => ["c"], "d"] };
+/// let shape = SuccinctTree::new(&tree);
+/// let values: Vec<_> = tree.iter_dfs(tree.root_id().unwrap()).map(|(_, n)| *n.value()).collect();
+/// let d = shape.children(0).nth(1).unwrap();
+/// assert_eq!(values[d], "d");
+/// assert_eq!(shape.parent(2).map(|b| values[b]), Some("b"));
+/// assert_eq!(shape.subtree_len(1), 2);
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuccinctTree {
+    /// Balanced parentheses with opening bits set, starting from the lowest
+    /// bit of the first word
+    words: Vec<u64>,
+    len: usize,
+    /// Number of opening bits before every block
+    ranks: Vec<u32>,
+    /// Lowest excess after any bit of a block, for a tree over the blocks
+    /// with the leaves in the second half
+    mins: Vec<i32>,
+}
+
+impl SuccinctTree {
+    /// Encode the shape of a tree
+    pub fn new<T>(tree: &ITree<T>) -> Self {
+        let mut words = vec![0; (2 * tree.len()).div_ceil(64)];
+        let mut bit = 0;
+        let mut stack: Vec<_> = tree.root_id().into_iter().map(|root| (root, 0)).collect();
+        if !stack.is_empty() {
+            words[0] = 1;
+            bit = 1;
+        }
+        while let Some(&mut (node, ref mut next)) = stack.last_mut() {
+            match tree.children[node.index()].get(*next) {
+                Some(&child) => {
+                    *next += 1;
+                    words[bit / 64] |= 1 << (bit % 64);
+                    stack.push((child, 0));
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+            bit += 1;
+        }
+        Self::index(words, tree.len())
+    }
+
+    /// Decode a tree from the words of `words`, checking that the `len` nodes
+    /// make up a single tree. The error has the first bit that doesn't fit.
+    pub fn from_words(mut words: Vec<u64>, len: usize) -> Result<Self, GroveError> {
+        let bits = 2 * len;
+        if words.len() < bits.div_ceil(64) {
+            return Err(GroveError::InvalidEncoding(words.len() * 64));
+        }
+        words.truncate(bits.div_ceil(64));
+        let mut excess = 0isize;
+        for bit in 0..bits {
+            excess += if words[bit / 64] >> (bit % 64) & 1 == 1 { 1 } else { -1 };
+            // Only the last bit may close the root
+            if excess < 0 || (excess == 0 && bit + 1 != bits) {
+                return Err(GroveError::InvalidEncoding(bit));
+            }
+        }
+        // Every node that was opened has to be closed again
+        if excess != 0 {
+            return Err(GroveError::InvalidEncoding(bits));
+        }
+        if let Some(last) = words.last_mut() {
+            if !bits.is_multiple_of(64) {
+                *last &= (1 << (bits % 64)) - 1;
+            }
+        }
+        Ok(Self::index(words, len))
+    }
+
+    fn index(words: Vec<u64>, len: usize) -> Self {
+        assert!(2 * len <= i32::MAX as usize, "too many nodes for a succinct tree");
+        let blocks = words.len().div_ceil(WORDS);
+        let leaves = blocks.next_power_of_two();
+        let mut ranks = vec![0];
+        let mut mins = vec![i32::MAX; 2 * leaves];
+        let mut excess = 0;
+        for (b, block) in words.chunks(WORDS).enumerate() {
+            let mut min = i32::MAX;
+            for bit in 0..(2 * len - b * BLOCK).min(BLOCK) {
+                excess += if block[bit / 64] >> (bit % 64) & 1 == 1 { 1 } else { -1 };
+                min = min.min(excess);
+            }
+            let opens: u32 = block.iter().map(|w| w.count_ones()).sum();
+            ranks.push(ranks[b] + opens);
+            mins[leaves + b] = min;
+        }
+        for x in (1..leaves).rev() {
+            mins[x] = mins[2 * x].min(mins[2 * x + 1]);
+        }
+        SuccinctTree { words, len, ranks, mins }
+    }
+
+    /// Get the number of nodes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the parentheses two bits per node, to store the tree and get it
+    /// back with `from_words`
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Get the parent of a node, or `None` for the root or if the node does
+    /// not exist
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        let open = self.open(node)?;
+        if open == 0 {
+            return None;
+        }
+        // The bit before the parent is the last one further out
+        let before = self.backward(open, self.excess(open) - 2);
+        Some(self.rank(before.map_or(0, |b| b + 1)))
+    }
+
+    /// Get the first child of a node
+    pub fn first_child(&self, node: usize) -> Option<usize> {
+        let open = self.open(node)?;
+        if self.bit(open + 1) {
+            Some(node + 1)
+        } else {
+            None
+        }
+    }
+
+    /// Get the sibling after a node
+    pub fn next_sibling(&self, node: usize) -> Option<usize> {
+        let after = self.close(self.open(node)?) + 1;
+        if after < 2 * self.len && self.bit(after) {
+            Some(node + self.subtree_len(node))
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over the children of a node
+    pub fn children(&self, node: usize) -> SuccinctChildren<'_> {
+        SuccinctChildren { tree: self, next: self.first_child(node) }
+    }
+
+    /// Get the number of nodes in the subtree of a node, counting the node.
+    /// Zero if the node does not exist.
+    pub fn subtree_len(&self, node: usize) -> usize {
+        self.open(node).map_or(0, |open| (self.close(open) - open).div_ceil(2))
+    }
+
+    /// Get the depth of a node below the root
+    pub fn depth(&self, node: usize) -> Option<usize> {
+        self.open(node).map(|open| self.excess(open) as usize - 1)
+    }
+
+    /// Get the opening bit of a node
+    fn open(&self, node: usize) -> Option<usize> {
+        if node >= self.len {
+            return None;
+        }
+        // Last block with at most `node` opening bits before it
+        let b = self.ranks.partition_point(|&r| r as usize <= node) - 1;
+        let mut left = node - self.ranks[b] as usize;
+        for w in b * WORDS.. {
+            let ones = self.words[w].count_ones() as usize;
+            if left < ones {
+                let mut word = self.words[w];
+                for _ in 0..left {
+                    word &= word - 1;
+                }
+                return Some(w * 64 + word.trailing_zeros() as usize);
+            }
+            left -= ones;
+        }
+        unreachable!()
+    }
+
+    /// Get the closing bit of the node opening at `open`
+    fn close(&self, open: usize) -> usize {
+        self.forward(open, self.excess(open) - 1)
+    }
+
+    /// Number of opening bits before a bit
+    fn rank(&self, bit: usize) -> usize {
+        let (b, w) = (bit / BLOCK, bit / 64);
+        let words = &self.words[b * WORDS..w];
+        let mut rank = self.ranks[b] as usize + words.iter().map(|w| w.count_ones() as usize).sum::<usize>();
+        if !bit.is_multiple_of(64) {
+            rank += (self.words[w] & ((1 << (bit % 64)) - 1)).count_ones() as usize;
+        }
+        rank
+    }
+
+    fn bit(&self, bit: usize) -> bool {
+        self.words[bit / 64] >> (bit % 64) & 1 == 1
+    }
+
+    /// Opening bits minus closing bits up to and including a bit
+    fn excess(&self, bit: usize) -> i32 {
+        2 * self.rank(bit + 1) as i32 - bit as i32 - 1
+    }
+
+    /// Find the first bit after `from` where the excess comes down to
+    /// `target`, which has to be lower than the excess at `from`
+    fn forward(&self, from: usize, target: i32) -> usize {
+        let mut excess = self.excess(from);
+        let end = (2 * self.len).min((from / BLOCK + 1) * BLOCK);
+        for bit in from + 1..end {
+            excess += if self.bit(bit) { 1 } else { -1 };
+            if excess == target {
+                return bit;
+            }
+        }
+        // The matching bit is in the first later block that gets low enough
+        let leaves = self.mins.len() / 2;
+        let mut x = leaves + from / BLOCK;
+        while !x.is_multiple_of(2) || self.mins[x + 1] > target {
+            x /= 2;
+        }
+        x += 1;
+        while x < leaves {
+            x = if self.mins[2 * x] <= target { 2 * x } else { 2 * x + 1 };
+        }
+        let start = (x - leaves) * BLOCK;
+        let mut excess = if start == 0 { 0 } else { self.excess(start - 1) };
+        for bit in start.. {
+            excess += if self.bit(bit) { 1 } else { -1 };
+            if excess == target {
+                return bit;
+            }
+        }
+        unreachable!()
+    }
+
+    /// Find the last bit before `from` where the excess is down to `target`,
+    /// which has to be lower than the excess at `from`. `None` stands for the
+    /// start, where the excess is zero.
+    fn backward(&self, from: usize, target: i32) -> Option<usize> {
+        let mut excess = self.excess(from);
+        let start = from / BLOCK * BLOCK;
+        for bit in (start..from).rev() {
+            excess -= if self.bit(bit + 1) { 1 } else { -1 };
+            if excess == target {
+                return Some(bit);
+            }
+        }
+        // The matching bit is in the last earlier block that gets low enough
+        let leaves = self.mins.len() / 2;
+        let mut x = leaves + from / BLOCK;
+        while x > 1 && (x.is_multiple_of(2) || self.mins[x - 1] > target) {
+            x /= 2;
+        }
+        if x == 1 {
+            return None;
+        }
+        x -= 1;
+        while x < leaves {
+            x = if self.mins[2 * x + 1] <= target { 2 * x + 1 } else { 2 * x };
+        }
+        let end = (x - leaves + 1) * BLOCK - 1;
+        let mut excess = self.excess(end);
+        for bit in (0..=end).rev() {
+            if excess == target {
+                return Some(bit);
+            }
+            excess -= if self.bit(bit) { 1 } else { -1 };
+        }
+        unreachable!()
+    }
+}
+
+/// Iterator over the children of a node of a `SuccinctTree`
+#[derive(Debug, Clone)]
+pub struct SuccinctChildren<'a> {
+    tree: &'a SuccinctTree,
+    next: Option<usize>,
+}
+
+impl<'a> Iterator for SuccinctChildren<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let node = self.next?;
+        self.next = self.tree.next_sibling(node);
+        Some(node)
+    }
+}
+
+#[test]
+fn succinct_tree() {
+    use NodeId;
+
+    // A random tree big enough for many blocks, where nodes hang low or near
+    // the root
+    let mut tree = ITree::new();
+    tree.add_root(()).unwrap();
+    let mut seed = 7u64;
+    for x in 1..5000 {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let parent = if seed >> 63 == 0 {
+            x - 1 - (seed >> 33) as usize % x.min(5)
+        } else {
+            (seed >> 33) as usize % x.min(20)
+        };
+        tree.add_node(NodeId::new(parent), ());
+    }
+    let shape = SuccinctTree::new(&tree);
+    assert_eq!(shape.len(), 5000);
+    assert_eq!(shape.words().len(), 157);
+
+    let order: Vec<_> = tree.iter_dfs(NodeId::new(0)).map(|(id, _)| id).collect();
+    let mut number = vec![0; order.len()];
+    for (k, id) in order.iter().enumerate() {
+        number[id.index()] = k;
+    }
+    for (k, &id) in order.iter().enumerate() {
+        assert_eq!(shape.parent(k), tree.get(id).unwrap().parent().map(|p| number[p.index()]));
+        let children: Vec<_> = tree.get(id).unwrap().children().iter().map(|c| number[c.index()]).collect();
+        assert!(shape.children(k).eq(children));
+        assert_eq!(shape.subtree_len(k), tree.descendants(id).count());
+        assert_eq!(shape.depth(k), tree.depth(id));
+    }
+    assert_eq!(shape.parent(5000), None);
+    assert_eq!(shape.subtree_len(5000), 0);
+
+    let back = SuccinctTree::from_words(shape.words().to_vec(), 5000).unwrap();
+    assert_eq!(back, shape);
+    assert_eq!(SuccinctTree::from_words(vec![0b0101], 2), Err(GroveError::InvalidEncoding(1)));
+    assert_eq!(SuccinctTree::from_words(vec![], 1), Err(GroveError::InvalidEncoding(0)));
+    // Opens more nodes than it closes
+    assert_eq!(SuccinctTree::from_words(vec![14600303307411132159], 10), Err(GroveError::InvalidEncoding(20)));
+    let empty = SuccinctTree::new(&ITree::<()>::new());
+    assert!(empty.is_empty());
+    assert_eq!(empty.first_child(0), None);
+}
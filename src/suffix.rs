@@ -0,0 +1,287 @@
+use core::ops::Range;
+
+use alloc::vec::Vec;
+
+/// Suffix Tree
+///
+/// A compressed trie of every suffix of a byte string, built in linear time
+/// with Ukkonen's algorithm. Edges hold a range of the text instead of a copy
+/// of their label, so the tree takes linear space as well. Once it's built,
+/// finding a pattern only takes time in the length of the pattern, not the
+/// text.
+///
+/// The text gets a terminator past its last byte, so every suffix ends at a
+/// leaf of its own.
+#[derive(Debug, Clone)]
+pub struct SuffixTree {
+    /// The text as symbols, where symbols from `256` up are terminators
+    text: Vec<u16>,
+    nodes: Vec<SuffixNode>,
+}
+
+#[derive(Debug, Clone)]
+struct SuffixNode {
+    /// Range of the text on the edge from the parent to this node
+    start: usize,
+    end: usize,
+    /// Node of the path label of this node without its first symbol
+    link: usize,
+    /// Children along with the first symbol of their edge, sorted by symbol
+    children: Vec<(u16, usize)>,
+}
+
+/// The root node always exists
+const ROOT: usize = 0;
+
+/// End of the edges of leaves while the tree gets built, which grow along
+/// with the text
+const OPEN: usize = usize::MAX;
+
+impl SuffixTree {
+    /// Build the suffix tree of a text
+    pub fn new<T: AsRef<[u8]>>(text: T) -> Self {
+        let mut symbols: Vec<u16> = text.as_ref().iter().map(|&b| u16::from(b)).collect();
+        symbols.push(256);
+        SuffixTree::build(symbols)
+    }
+
+    /// Get the length of the text without the terminator
+    pub fn len(&self) -> usize {
+        self.text.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check if a pattern is a substring of the text
+    pub fn contains<P: AsRef<[u8]>>(&self, pattern: P) -> bool {
+        self.locate(pattern.as_ref()).is_some()
+    }
+
+    /// Find every position where a pattern starts in the text, in order. Like
+    /// `str::match_indices` the empty pattern matches at the end as well.
+    pub fn find_all<P: AsRef<[u8]>>(&self, pattern: P) -> Vec<usize> {
+        let pattern = pattern.as_ref();
+        let (node, depth) = match self.locate(pattern) {
+            Some(found) => found,
+            None => return Vec::new(),
+        };
+        let mut starts = Vec::new();
+        let mut stack = vec![(node, depth)];
+        while let Some((x, depth)) = stack.pop() {
+            let node = &self.nodes[x];
+            if node.children.is_empty() {
+                starts.push(self.text.len() - depth);
+            }
+            for &(_, child) in &node.children {
+                stack.push((child, depth + self.nodes[child].end - self.nodes[child].start));
+            }
+        }
+        starts.sort_unstable();
+        starts
+    }
+
+    /// Find the longest byte string both `a` and `b` contain, returning the
+    /// first place it shows up in `a`
+    pub fn longest_common_substring<'a>(a: &'a [u8], b: &[u8]) -> &'a [u8] {
+        // Both texts go into one tree with a terminator of their own
+        let mut symbols: Vec<u16> = a.iter().map(|&b| u16::from(b)).collect();
+        symbols.push(256);
+        symbols.extend(b.iter().map(|&b| u16::from(b)));
+        symbols.push(257);
+        let tree = SuffixTree::build(symbols);
+
+        let (order, depths) = tree.preorder();
+        // First suffix of `a` below every node and whether a suffix of `b` is
+        // below it as well
+        let mut in_a = vec![None; tree.nodes.len()];
+        let mut in_b = vec![false; tree.nodes.len()];
+        let mut best: Range<usize> = 0..0;
+        for &x in order.iter().rev() {
+            let node = &tree.nodes[x];
+            if node.children.is_empty() {
+                let start = tree.text.len() - depths[x];
+                if start <= a.len() {
+                    in_a[x] = Some(start);
+                } else {
+                    in_b[x] = true;
+                }
+                continue;
+            }
+            for &(_, child) in &node.children {
+                in_a[x] = match (in_a[x], in_a[child]) {
+                    (Some(s), Some(c)) => Some(c.min(s)),
+                    (s, c) => s.or(c),
+                };
+                in_b[x] |= in_b[child];
+            }
+            // Only leaves have a terminator in their path label
+            if let (Some(start), true) = (in_a[x], in_b[x]) {
+                let len = depths[x];
+                if len > best.len() || len == best.len() && start < best.start {
+                    best = start..start + len;
+                }
+            }
+        }
+        &a[best]
+    }
+
+    /// Build the tree of a text ending in a terminator
+    fn build(text: Vec<u16>) -> Self {
+        let mut tree = SuffixTree { text, nodes: Vec::new() };
+        tree.add(0, 0);
+        // The active point is where the next suffix goes in, `len` symbols
+        // down the edge of `node` starting with the symbol at `edge`
+        let (mut node, mut edge, mut len) = (ROOT, 0, 0);
+        // Suffixes left to put in
+        let mut remainder = 0;
+        for i in 0..tree.text.len() {
+            remainder += 1;
+            let mut last_branch: Option<usize> = None;
+            while remainder > 0 {
+                if len == 0 {
+                    edge = i;
+                }
+                match tree.child(node, tree.text[edge]) {
+                    Err(pos) => {
+                        let leaf = tree.add(i, OPEN);
+                        tree.nodes[node].children.insert(pos, (tree.text[i], leaf));
+                        if let Some(branch) = last_branch.take() {
+                            tree.nodes[branch].link = node;
+                        }
+                    }
+                    Ok(pos) => {
+                        let next = tree.nodes[node].children[pos].1;
+                        let edge_len = tree.nodes[next].end.min(i + 1) - tree.nodes[next].start;
+                        if len >= edge_len {
+                            // Walk down to the next node
+                            edge += edge_len;
+                            len -= edge_len;
+                            node = next;
+                            continue;
+                        }
+                        if tree.text[tree.nodes[next].start + len] == tree.text[i] {
+                            // The suffix is in the tree already, and so are all
+                            // the shorter ones
+                            if let Some(branch) = last_branch.take() {
+                                if node != ROOT {
+                                    tree.nodes[branch].link = node;
+                                }
+                            }
+                            len += 1;
+                            break;
+                        }
+                        // Split the edge where the suffix branches off
+                        let start = tree.nodes[next].start;
+                        let branch = tree.add(start, start + len);
+                        tree.nodes[node].children[pos].1 = branch;
+                        let leaf = tree.add(i, OPEN);
+                        tree.nodes[next].start += len;
+                        let mut children = vec![(tree.text[i], leaf), (tree.text[start + len], next)];
+                        children.sort_unstable();
+                        tree.nodes[branch].children = children;
+                        if let Some(last) = last_branch.replace(branch) {
+                            tree.nodes[last].link = branch;
+                        }
+                    }
+                }
+                remainder -= 1;
+                if node == ROOT && len > 0 {
+                    len -= 1;
+                    edge = i + 1 - remainder;
+                } else if node != ROOT {
+                    node = tree.nodes[node].link;
+                }
+            }
+        }
+        let end = tree.text.len();
+        for node in &mut tree.nodes {
+            if node.end == OPEN {
+                node.end = end;
+            }
+        }
+        tree
+    }
+
+    fn add(&mut self, start: usize, end: usize) -> usize {
+        self.nodes.push(SuffixNode { start, end, link: ROOT, children: Vec::new() });
+        self.nodes.len() - 1
+    }
+
+    /// Find the child of a node whose edge starts with a symbol, or where it
+    /// would go
+    fn child(&self, x: usize, symbol: u16) -> Result<usize, usize> {
+        self.nodes[x].children.binary_search_by_key(&symbol, |&(s, _)| s)
+    }
+
+    /// Find the node at or below the end of a pattern along with the length
+    /// of its path label
+    fn locate(&self, pattern: &[u8]) -> Option<(usize, usize)> {
+        let (mut x, mut depth, mut matched) = (ROOT, 0, 0);
+        while matched < pattern.len() {
+            let pos = self.child(x, u16::from(pattern[matched])).ok()?;
+            x = self.nodes[x].children[pos].1;
+            let label = &self.text[self.nodes[x].start..self.nodes[x].end];
+            for &symbol in label.iter().take(pattern.len() - matched) {
+                if symbol != u16::from(pattern[matched]) {
+                    return None;
+                }
+                matched += 1;
+            }
+            depth += label.len();
+        }
+        Some((x, depth))
+    }
+
+    /// Get the nodes in pre-order along with the length of the path label of
+    /// every node
+    fn preorder(&self) -> (Vec<usize>, Vec<usize>) {
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut depths = vec![0; self.nodes.len()];
+        let mut stack = vec![ROOT];
+        while let Some(x) = stack.pop() {
+            order.push(x);
+            for &(_, child) in &self.nodes[x].children {
+                depths[child] = depths[x] + self.nodes[child].end - self.nodes[child].start;
+                stack.push(child);
+            }
+        }
+        (order, depths)
+    }
+}
+
+#[test]
+fn suffix_tree() {
+    let text = b"mississippi";
+    let tree = SuffixTree::new(text);
+    assert_eq!(tree.len(), 11);
+    // One leaf for every suffix and the terminator alone
+    assert_eq!(tree.nodes.iter().filter(|n| n.children.is_empty()).count(), 12);
+    for start in 0..text.len() {
+        for end in start + 1..=text.len() {
+            let pattern = &text[start..end];
+            let expected: Vec<_> = (0..=text.len() - pattern.len())
+                .filter(|&i| &text[i..i + pattern.len()] == pattern)
+                .collect();
+            assert_eq!(tree.find_all(pattern), expected);
+        }
+    }
+    assert!(tree.contains("ssip"));
+    assert!(!tree.contains("ssipp i"));
+    assert!(!tree.contains("pim"));
+    assert_eq!(tree.find_all("issi"), [1, 4]);
+    assert_eq!(tree.find_all("").len(), 12);
+    assert!(SuffixTree::new("").find_all("a").is_empty());
+
+    let lcs = SuffixTree::longest_common_substring;
+    assert_eq!(lcs(b"xabxac", b"abcabxabcd"), b"abxa");
+    assert_eq!(lcs(b"banana", b"ananas"), b"anana");
+    assert_eq!(lcs(b"abc", b"xyz"), b"");
+    assert_eq!(lcs(b"", b"abc"), b"");
+
+    // Long runs of the same byte don't overflow the stack
+    let long = vec![b'a'; 100_000];
+    let tree = SuffixTree::new(&long);
+    assert_eq!(tree.find_all(&long[..99_998]), [0, 1, 2]);
+}
@@ -0,0 +1,182 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use chunks::Chunks;
+use {ITree, NodeId};
+
+impl<T> ITree<T> {
+    /// Transform every value in the tree while keeping its structure, so every
+    /// `NodeId` of this tree refers to the same node in the new one
+    pub fn map<U, F>(self, mut f: F) -> ITree<U>
+        where F: FnMut(&T) -> U
+    {
+        // The structure of the tree doesn't change, so it is reused as is
+        let values = self.values.iter().map(&mut f).collect();
+        ITree { values, parents: self.parents, children: self.children }
+    }
+
+    /// Transform every value in the tree like `map`, stopping at the first
+    /// value `f` fails on. Values are visited in the order they were added to
+    /// the tree and the error is returned along with the `NodeId` of the node
+    /// that caused it.
+    pub fn try_map<U, E, F>(self, mut f: F) -> Result<ITree<U>, (NodeId, E)>
+        where F: FnMut(&T) -> Result<U, E>
+    {
+        let mut values = Chunks::with_capacity(self.values.len());
+        for (x, value) in self.values.iter().enumerate() {
+            values.push(f(value).map_err(|e| (NodeId::new(x), e))?);
+        }
+        Ok(ITree { values, parents: self.parents, children: self.children })
+    }
+
+    /// Copy the tree without the nodes failing `keep`. When a node is pruned
+    /// its whole subtree goes with it, and `keep` isn't called for its
+    /// descendants. Returns the new tree along with a map from the `NodeId`s of
+    /// the kept nodes in this tree to their `NodeId`s in the new one.
+    pub fn filter_prune<F>(&self, mut keep: F) -> (ITree<T>, BTreeMap<NodeId, NodeId>)
+        where F: FnMut(&T) -> bool,
+              T: Clone
+    {
+        let mut tree = ITree::new();
+        // New id of every node in this tree if it was kept
+        let mut new_ids: Vec<Option<NodeId>> = Vec::with_capacity(self.len());
+        for (value, parent) in self.values.iter().zip(&self.parents) {
+            let parent = match *parent {
+                Some(p) => match new_ids[p.index()] {
+                    Some(parent) => Some(parent),
+                    None => {
+                        new_ids.push(None);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            if !keep(value) {
+                new_ids.push(None);
+                continue;
+            }
+            let value = value.clone();
+            new_ids.push(Some(match parent {
+                Some(parent) => tree.add_node(parent, value),
+                None => tree.add_root(value).unwrap(),
+            }));
+        }
+        let map = new_ids
+            .into_iter()
+            .enumerate()
+            .filter_map(|(x, new)| new.map(|new| (NodeId::new(x), new)))
+            .collect();
+        (tree, map)
+    }
+
+    /// Reduce the tree from the leaves up to the root. `f` gets called for every
+    /// node with its value and the results of its children, in order. Returns
+    /// the result for the root, or `None` if the tree is empty.
+    ///
+    /// ```
+    /// use grove::ITree;
+    ///
+    /// let mut tree = ITree::new();
+    /// let root = tree.add_root(1).unwrap();
+    /// tree.add_node(root, 2);
+    /// tree.add_node(root, 3);
+    ///
+    /// let sum = tree.fold(|value, children| value + children.iter().sum::<i32>());
+    /// assert_eq!(sum, Some(6));
+    /// ```
+    pub fn fold<R, F>(&self, mut f: F) -> Option<R>
+        where F: FnMut(&T, Vec<R>) -> R
+    {
+        let mut results: Vec<Option<R>> = Vec::with_capacity(self.len());
+        results.resize_with(self.len(), || None);
+        for (x, children) in self.children.iter().enumerate().rev() {
+            let children = children
+                .iter()
+                .map(|c| results[c.index()].take().unwrap())
+                .collect();
+            results[x] = Some(f(&self.values[x], children));
+        }
+        results.into_iter().next().and_then(|root| root)
+    }
+}
+
+#[test]
+fn map() {
+    let tree = tree!{ 1 => [2 => [4], 3] };
+    let ids: Vec<_> = tree.iter_dfs(tree.root_id().unwrap()).map(|(id, _)| id).collect();
+
+    let mapped = tree.map(|v| v.to_string());
+    assert_eq!(mapped, tree!{ "1".to_string() => ["2".to_string() => ["4".to_string()], "3".to_string()] });
+    let mapped_ids: Vec<_> = mapped.iter_dfs(mapped.root_id().unwrap()).map(|(id, _)| id).collect();
+    assert_eq!(ids, mapped_ids);
+}
+
+#[test]
+fn try_map() {
+    let tree = tree!{ "1" => ["2" => ["4"], "3"] };
+    assert_eq!(tree.clone().try_map(|v| v.parse::<u8>()), Ok(tree!{ 1 => [2 => [4], 3] }));
+
+    let tree = tree!{ "1" => ["2" => ["x"], "y"] };
+    let mut seen = Vec::new();
+    let err = tree.try_map(|v| {
+        seen.push(*v);
+        v.parse::<u8>()
+    }).unwrap_err();
+    assert_eq!(err.0, NodeId::new(2));
+    assert_eq!(seen, vec!["1", "2", "x"]);
+}
+
+#[test]
+fn filter_prune() {
+    // 1 -> 2 -> 4
+    // |    |--> 5
+    // |--> 3 -> 6
+    let tree = tree!{ 1 => [2 => [4, 5], 3 => [6]] };
+    let mut checked = Vec::new();
+    let (pruned, map) = tree.filter_prune(|&v| {
+        checked.push(v);
+        v != 2
+    });
+    assert_eq!(pruned, tree!{ 1 => [3 => [6]] });
+    // The pruned subtree isn't visited
+    assert_eq!(checked, vec![1, 2, 3, 6]);
+
+    let expected: BTreeMap<_, _> = vec![(NodeId::new(0), NodeId::new(0)), (NodeId::new(4), NodeId::new(1)), (NodeId::new(5), NodeId::new(2))]
+        .into_iter()
+        .collect();
+    assert_eq!(map, expected);
+    for (old, new) in map {
+        assert_eq!(tree.get(old).unwrap().value(), pruned.get(new).unwrap().value());
+    }
+
+    let (empty, map) = tree.filter_prune(|_| false);
+    assert!(empty.root().is_none());
+    assert!(map.is_empty());
+}
+
+#[test]
+fn fold() {
+    #[derive(Debug)]
+    enum Expr {
+        Num(i64),
+        Add,
+        Mul,
+    }
+
+    // (1 + 2) * (3 + 4 + 5)
+    let tree = tree!{ Expr::Mul => [Expr::Add => [Expr::Num(1), Expr::Num(2)],
+                                    Expr::Add => [Expr::Num(3), Expr::Num(4), Expr::Num(5)]] };
+    let value = tree.fold(|expr, args: Vec<i64>| match *expr {
+        Expr::Num(n) => n,
+        Expr::Add => args.iter().sum(),
+        Expr::Mul => args.iter().product(),
+    });
+    assert_eq!(value, Some(36));
+
+    // Children are handed over in order
+    let tree = tree!{ "a" => ["b" => ["c"], "d"] };
+    let order = tree.fold(|v, children: Vec<String>| format!("{}({})", v, children.join(",")));
+    assert_eq!(order.unwrap(), "a(b(c()),d())");
+
+    assert_eq!(ITree::<u8>::new().fold(|_, _: Vec<u8>| 0), None);
+}
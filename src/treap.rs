@@ -0,0 +1,393 @@
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::iter::FromIterator;
+use core::mem;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// Treap
+///
+/// An ordered map on a binary search tree where every node also gets a random
+/// priority, and no node has a higher priority than its parent. The random
+/// priorities keep the tree shallow no matter the order the keys come in.
+///
+/// On top of the usual map operations a treap can `split` itself in two at a
+/// key and `merge` two maps whose keys don't overlap, both in logarithmic
+/// time. Unlike the other maps of this crate the nodes are boxed instead of
+/// living in an arena, since moving a whole subtree between maps must not
+/// copy its nodes.
+#[derive(Clone)]
+pub struct TreapMap<K, V> {
+    root: Link<K, V>,
+    /// State of the generator for priorities
+    seed: u64,
+}
+
+type Link<K, V> = Option<Box<TreapNode<K, V>>>;
+
+#[derive(Clone)]
+struct TreapNode<K, V> {
+    key: K,
+    value: V,
+    priority: u64,
+    /// Number of nodes in the subtree of this node
+    size: usize,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+pub(crate) const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+impl<K: Ord, V> TreapMap<K, V> {
+    /// Create a new empty `TreapMap`
+    pub fn new() -> Self {
+        TreapMap { root: None, seed: SEED }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Insert a value under a key, returning the value the key had before
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(old) = self.get_mut(&key) {
+            return Some(mem::replace(old, value));
+        }
+        let node = Box::new(TreapNode {
+            key,
+            value,
+            priority: self.priority(),
+            size: 1,
+            left: None,
+            right: None,
+        });
+        let (left, right) = split(self.root.take(), &node.key);
+        self.root = join(join(left, Some(node)), right);
+        None
+    }
+
+    /// Get the value of a key
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let mut link = &self.root;
+        while let Some(node) = link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Less => &node.left,
+                Ordering::Greater => &node.right,
+                Ordering::Equal => return Some(&node.value),
+            };
+        }
+        None
+    }
+
+    /// Get a mutable reference to the value of a key
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let mut link = &mut self.root;
+        while let Some(node) = link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Less => &mut node.left,
+                Ordering::Greater => &mut node.right,
+                Ordering::Equal => return Some(&mut node.value),
+            };
+        }
+        None
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        self.get(key).is_some()
+    }
+
+    /// Remove a key and return its value
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        remove(&mut self.root, key)
+    }
+
+    /// Get the entry with the smallest key
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    /// Get the entry with the largest key
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.iter().next_back()
+    }
+
+    /// Remove every entry
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    /// Iterate over the entries in order of their keys
+    pub fn iter(&self) -> TreapIter<'_, K, V> {
+        let mut iter = TreapIter { front: Vec::new(), back: Vec::new(), len: self.len() };
+        iter.descend_left(&self.root);
+        iter.descend_right(&self.root);
+        iter
+    }
+
+    /// Split the map in two at a key. Every entry with a key smaller than
+    /// `key` stays, the rest get moved to the returned map.
+    pub fn split<Q>(&mut self, key: &Q) -> Self
+        where K: Borrow<Q>,
+              Q: Ord + ?Sized
+    {
+        let (left, right) = split(self.root.take(), key);
+        self.root = left;
+        TreapMap { root: right, seed: self.priority() }
+    }
+
+    /// Move every entry of `other` into this map. This only takes logarithmic
+    /// time because the keys of `other` have to be larger than every key of
+    /// this map.
+    ///
+    /// # Panics
+    ///
+    /// If the keys of the two maps overlap.
+    pub fn merge(&mut self, other: Self) {
+        if let (Some((last, _)), Some((first, _))) = (self.last_key_value(), other.first_key_value()) {
+            assert!(last < first, "the keys of the merged maps overlap");
+        }
+        self.root = join(self.root.take(), other.root);
+    }
+
+    fn priority(&mut self) -> u64 {
+        xorshift(&mut self.seed)
+    }
+}
+
+/// Draw the next random number from the state of a xorshift generator
+pub(crate) fn xorshift(seed: &mut u64) -> u64 {
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 7;
+    *seed ^= *seed << 17;
+    *seed
+}
+
+fn size<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+impl<K, V> TreapNode<K, V> {
+    fn update(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+/// Split a subtree into the keys smaller than `key` and the rest
+fn split<K, V, Q>(link: Link<K, V>, key: &Q) -> (Link<K, V>, Link<K, V>)
+    where K: Borrow<Q>,
+          Q: Ord + ?Sized
+{
+    match link {
+        None => (None, None),
+        Some(mut node) => {
+            if node.key.borrow() < key {
+                let (left, right) = split(node.right.take(), key);
+                node.right = left;
+                node.update();
+                (Some(node), right)
+            } else {
+                let (left, right) = split(node.left.take(), key);
+                node.left = right;
+                node.update();
+                (left, Some(node))
+            }
+        }
+    }
+}
+
+/// Join two subtrees where every key of `left` is smaller than every key of
+/// `right`
+fn join<K, V>(left: Link<K, V>, right: Link<K, V>) -> Link<K, V> {
+    match (left, right) {
+        (None, link) | (link, None) => link,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.right = join(left.right.take(), Some(right));
+                left.update();
+                Some(left)
+            } else {
+                right.left = join(Some(left), right.left.take());
+                right.update();
+                Some(right)
+            }
+        }
+    }
+}
+
+fn remove<K, V, Q>(link: &mut Link<K, V>, key: &Q) -> Option<V>
+    where K: Borrow<Q>,
+          Q: Ord + ?Sized
+{
+    let node = link.as_mut()?;
+    let value = match key.cmp(node.key.borrow()) {
+        Ordering::Less => remove(&mut node.left, key),
+        Ordering::Greater => remove(&mut node.right, key),
+        Ordering::Equal => {
+            let node = *link.take().unwrap();
+            *link = join(node.left, node.right);
+            return Some(node.value);
+        }
+    };
+    if value.is_some() {
+        node.update();
+    }
+    value
+}
+
+impl<K: Ord, V> Default for TreapMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug + Ord, V: fmt::Debug> fmt::Debug for TreapMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for TreapMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for TreapMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = TreapMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a TreapMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = TreapIter<'a, K, V>;
+
+    fn into_iter(self) -> TreapIter<'a, K, V> {
+        self.iter()
+    }
+}
+
+/// Iterator over the entries of a `TreapMap` in order of their keys
+pub struct TreapIter<'a, K: 'a, V: 'a> {
+    /// Nodes on the way down to the next entry from the front, and the back
+    front: Vec<&'a TreapNode<K, V>>,
+    back: Vec<&'a TreapNode<K, V>>,
+    /// Number of entries left
+    len: usize,
+}
+
+impl<'a, K, V> TreapIter<'a, K, V> {
+    fn descend_left(&mut self, mut link: &'a Link<K, V>) {
+        while let Some(node) = link {
+            self.front.push(node);
+            link = &node.left;
+        }
+    }
+
+    fn descend_right(&mut self, mut link: &'a Link<K, V>) {
+        while let Some(node) = link {
+            self.back.push(node);
+            link = &node.right;
+        }
+    }
+}
+
+impl<'a, K, V> Clone for TreapIter<'a, K, V> {
+    fn clone(&self) -> Self {
+        TreapIter { front: self.front.clone(), back: self.back.clone(), len: self.len }
+    }
+}
+
+impl<'a, K, V> Iterator for TreapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let node = self.front.pop()?;
+        self.descend_left(&node.right);
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for TreapIter<'a, K, V> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let node = self.back.pop()?;
+        self.descend_right(&node.left);
+        Some((&node.key, &node.value))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for TreapIter<'a, K, V> {}
+
+impl<'a, K: fmt::Debug, V: fmt::Debug> fmt::Debug for TreapIter<'a, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+#[test]
+fn treap_map() {
+    fn height<K, V>(link: &Link<K, V>) -> usize {
+        link.as_ref().map_or(0, |node| 1 + height(&node.left).max(height(&node.right)))
+    }
+
+    let mut map: TreapMap<_, _> = (0..1000).map(|k| (k, k * 10)).collect();
+    // Sorted keys don't leave the tree as a chain
+    assert!(height(&map.root) < 40);
+    assert_eq!(map.len(), 1000);
+    assert_eq!(map.get(&500), Some(&5000));
+    assert_eq!(map.insert(500, 1), Some(5000));
+    for k in (0..1000).filter(|k| k % 3 == 0) {
+        assert_eq!(map.remove(&k), Some(k * 10));
+    }
+    assert_eq!(map.remove(&0), None);
+    assert_eq!(map.len(), 666);
+
+    let mut high = map.split(&600);
+    assert_eq!(map.len(), 400);
+    assert_eq!(high.len(), 266);
+    assert_eq!(map.last_key_value(), Some((&599, &5990)));
+    assert_eq!(high.first_key_value(), Some((&601, &6010)));
+    let rest = high.split(&5000);
+    assert!(rest.is_empty());
+
+    map.merge(high);
+    assert_eq!(map.len(), 666);
+    assert!(map.iter().map(|(&k, _)| k).eq((0..1000).filter(|k| k % 3 != 0)));
+    assert!(map.iter().rev().map(|(&k, _)| k).eq((0..1000).rev().filter(|k| k % 3 != 0)));
+    assert_eq!(map.iter().len(), 666);
+    assert_eq!(map.get(&500), Some(&1));
+}
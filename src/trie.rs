@@ -0,0 +1,238 @@
+use core::iter::FromIterator;
+
+use alloc::vec::Vec;
+
+use arena::Arena;
+
+/// Prefix Tree
+///
+/// A map keyed by byte strings where every node stands for one byte of a key,
+/// so keys sharing a prefix share the nodes of that prefix. `str` keys work
+/// as their UTF-8 bytes. Keys come back out as byte vectors in lexicographic
+/// order.
+#[derive(Debug, Clone)]
+pub struct Trie<V> {
+    nodes: Arena<TrieNode<V>>,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct TrieNode<V> {
+    value: Option<V>,
+    /// Next byte of the key along with the node it leads to, sorted by byte
+    children: Vec<(u8, usize)>,
+}
+
+impl<V> TrieNode<V> {
+    fn new() -> Self {
+        TrieNode { value: None, children: Vec::new() }
+    }
+
+    fn child(&self, byte: u8) -> Option<usize> {
+        self.children.binary_search_by_key(&byte, |c| c.0).ok().map(|x| self.children[x].1)
+    }
+}
+
+/// The root node always exists, even in an empty trie
+const ROOT: usize = 0;
+
+impl<V> Trie<V> {
+    /// Create a new empty `Trie`
+    pub fn new() -> Self {
+        let mut nodes = Arena::new();
+        nodes.insert(TrieNode::new());
+        Trie { nodes, len: 0 }
+    }
+
+    /// Get the number of keys in the trie
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Insert a value under a key, returning the value the key had before
+    pub fn insert<K: AsRef<[u8]>>(&mut self, key: K, value: V) -> Option<V> {
+        let mut node = ROOT;
+        for &byte in key.as_ref() {
+            node = match self.nodes[node].children.binary_search_by_key(&byte, |c| c.0) {
+                Ok(x) => self.nodes[node].children[x].1,
+                Err(x) => {
+                    let child = self.nodes.insert(TrieNode::new());
+                    self.nodes[node].children.insert(x, (byte, child));
+                    child
+                }
+            };
+        }
+        let old = self.nodes[node].value.replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Get the value of a key
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Option<&V> {
+        self.find(key.as_ref()).and_then(|node| self.nodes[node].value.as_ref())
+    }
+
+    /// Get a mutable reference to the value of a key
+    pub fn get_mut<K: AsRef<[u8]>>(&mut self, key: K) -> Option<&mut V> {
+        match self.find(key.as_ref()) {
+            Some(node) => self.nodes[node].value.as_mut(),
+            None => None,
+        }
+    }
+
+    pub fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Remove a key and return its value. Nodes that no longer lead to any key
+    /// get removed as well.
+    pub fn remove<K: AsRef<[u8]>>(&mut self, key: K) -> Option<V> {
+        let key = key.as_ref();
+        let mut path = Vec::with_capacity(key.len() + 1);
+        let mut node = ROOT;
+        path.push(node);
+        for &byte in key {
+            node = self.nodes[node].child(byte)?;
+            path.push(node);
+        }
+        let value = self.nodes[node].value.take()?;
+        self.len -= 1;
+
+        // Prune the nodes at the end of the key that have nothing left below
+        for (x, &byte) in key.iter().enumerate().rev() {
+            let node = path[x + 1];
+            if self.nodes[node].value.is_some() || !self.nodes[node].children.is_empty() {
+                break;
+            }
+            self.nodes.remove(node);
+            self.nodes[path[x]].children.retain(|c| c.0 != byte);
+        }
+        Some(value)
+    }
+
+    /// Remove every key
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.nodes.insert(TrieNode::new());
+        self.len = 0;
+    }
+
+    /// Iterate over every key that starts with `prefix` along with its value
+    pub fn iter_prefix<K: AsRef<[u8]>>(&self, prefix: K) -> TriePrefix<'_, V> {
+        let prefix = prefix.as_ref();
+        let start = self.find(prefix);
+        TriePrefix {
+            trie: self,
+            key: prefix.to_vec(),
+            stack: start.into_iter().map(|node| (node, 0)).collect(),
+            start,
+        }
+    }
+
+    /// Iterate over every key along with its value
+    pub fn iter(&self) -> TriePrefix<'_, V> {
+        self.iter_prefix(b"")
+    }
+
+    /// Find the node a key ends at
+    fn find(&self, key: &[u8]) -> Option<usize> {
+        let mut node = ROOT;
+        for &byte in key {
+            node = self.nodes[node].child(byte)?;
+        }
+        Some(node)
+    }
+}
+
+impl<V> Default for Trie<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: AsRef<[u8]>, V> Extend<(K, V)> for Trie<V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: AsRef<[u8]>, V> FromIterator<(K, V)> for Trie<V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut trie = Trie::new();
+        trie.extend(iter);
+        trie
+    }
+}
+
+/// Iterator over the keys of a `Trie` that start with a prefix, in
+/// lexicographic order
+pub struct TriePrefix<'a, V: 'a> {
+    trie: &'a Trie<V>,
+    key: Vec<u8>,
+    /// Nodes on the way down from the prefix along with the position of the
+    /// next child to visit
+    stack: Vec<(usize, usize)>,
+    /// Node of the prefix, until its own value has been seen
+    start: Option<usize>,
+}
+
+impl<'a, V> Iterator for TriePrefix<'a, V> {
+    type Item = (Vec<u8>, &'a V);
+
+    fn next(&mut self) -> Option<(Vec<u8>, &'a V)> {
+        let nodes = &self.trie.nodes;
+        if let Some(value) = self.start.take().and_then(|node| nodes[node].value.as_ref()) {
+            return Some((self.key.clone(), value));
+        }
+        loop {
+            let (node, pos) = self.stack.last_mut()?;
+            match nodes[*node].children.get(*pos) {
+                Some(&(byte, child)) => {
+                    *pos += 1;
+                    self.key.push(byte);
+                    self.stack.push((child, 0));
+                    if let Some(value) = nodes[child].value.as_ref() {
+                        return Some((self.key.clone(), value));
+                    }
+                }
+                None => {
+                    self.stack.pop();
+                    if !self.stack.is_empty() {
+                        self.key.pop();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn trie() {
+    let mut trie: Trie<u32> = vec![("foo", 1), ("foobar", 2), ("fob", 3), ("bar", 4)].into_iter().collect();
+    assert_eq!(trie.len(), 4);
+    assert_eq!(trie.get("foo"), Some(&1));
+    assert_eq!(trie.get("fo"), None);
+    assert_eq!(trie.insert("foo", 10), Some(1));
+
+    let found: Vec<_> = trie.iter_prefix("fo").collect();
+    assert_eq!(found, vec![(b"fob".to_vec(), &3), (b"foo".to_vec(), &10), (b"foobar".to_vec(), &2)]);
+    assert_eq!(trie.iter_prefix("foo").count(), 2);
+    assert_eq!(trie.iter_prefix("x").count(), 0);
+
+    // Removing a key prunes the nodes only it used
+    assert_eq!(trie.remove("foobar"), Some(2));
+    assert!(trie.find(b"foob").is_none());
+    assert_eq!(trie.remove("foo"), Some(10));
+    assert_eq!(trie.remove("foo"), None);
+    assert_eq!(trie.get("fob"), Some(&3));
+    assert_eq!(trie.iter().map(|(k, _)| k).collect::<Vec<_>>(), vec![b"bar".to_vec(), b"fob".to_vec()]);
+    assert_eq!(trie.len(), 2);
+}
@@ -0,0 +1,197 @@
+use alloc::vec::Vec;
+
+use {INodeRef, ITree, NodeId};
+
+/// Callbacks for walking a tree with `ITree::visit`
+///
+/// `enter_node` gets called for a node before any of its descendants and
+/// `exit_node` after all of them, so the calls pair up like brackets. Both do
+/// nothing unless implemented.
+pub trait Visitor<T> {
+    fn enter_node(&mut self, id: NodeId, node: INodeRef<'_, T>) {
+        let _ = (id, node);
+    }
+
+    fn exit_node(&mut self, id: NodeId, node: INodeRef<'_, T>) {
+        let _ = (id, node);
+    }
+}
+
+/// What `ITree::walk` does after visiting a node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Flow {
+    /// Go on with the children of the node
+    Continue,
+    /// Leave out the descendants of the node and go on with the rest
+    SkipSubtree,
+    /// Stop the walk
+    Break,
+}
+
+/// Step of an Euler tour made by `ITree::euler_tour`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Event {
+    /// The tour gets to a node, before any of its descendants
+    Enter,
+    /// The tour leaves a node, after all of its descendants
+    Exit,
+}
+
+impl<T> ITree<T> {
+    /// Walk the subtree starting at the given `NodeId` depth first, calling
+    /// the visitor when entering and leaving every node. Children are visited
+    /// in order. Does nothing if the node does not exist.
+    ///
+    /// The walk keeps its own stack, so deep trees don't overflow the call
+    /// stack.
+    pub fn visit<V: Visitor<T>>(&self, start: NodeId, visitor: &mut V) {
+        if self.get(start).is_none() {
+            return;
+        }
+        // Nodes along with whether they were entered already
+        let mut stack = vec![(start, false)];
+        while let Some((id, entered)) = stack.pop() {
+            let node = self.get(id).unwrap();
+            if entered {
+                visitor.exit_node(id, node);
+                continue;
+            }
+            visitor.enter_node(id, node);
+            stack.push((id, true));
+            stack.extend(node.children().iter().rev().map(|&child| (child, false)));
+        }
+    }
+
+    /// Get the Euler tour of the tree, which enters and exits every node once
+    /// in depth first order. Entering and exiting a node bracket the steps
+    /// of its descendants, so the subtree of a node is the part of the tour in
+    /// between.
+    pub fn euler_tour(&self) -> Vec<(NodeId, Event)> {
+        struct Tour(Vec<(NodeId, Event)>);
+
+        impl<T> Visitor<T> for Tour {
+            fn enter_node(&mut self, id: NodeId, _: INodeRef<'_, T>) {
+                self.0.push((id, Event::Enter));
+            }
+
+            fn exit_node(&mut self, id: NodeId, _: INodeRef<'_, T>) {
+                self.0.push((id, Event::Exit));
+            }
+        }
+
+        let mut tour = Tour(Vec::with_capacity(2 * self.len()));
+        if let Some(root) = self.root_id() {
+            self.visit(root, &mut tour);
+        }
+        tour.0
+    }
+
+    /// Visit the nodes in pre-order starting at the root, letting `f` decide
+    /// after every node whether to go below it or to stop right there. Returns
+    /// the `NodeId` of the node `f` broke off at, if any.
+    pub fn walk<F>(&self, mut f: F) -> Option<NodeId>
+        where F: FnMut(NodeId, &T) -> Flow
+    {
+        let mut stack: Vec<NodeId> = self.root_id().into_iter().collect();
+        while let Some(id) = stack.pop() {
+            match f(id, &self[id]) {
+                Flow::Continue => stack.extend(self.children[id.index()].iter().rev()),
+                Flow::SkipSubtree => {}
+                Flow::Break => return Some(id),
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn visit() {
+    use alloc::string::String;
+
+    /// Prints the tree as nested brackets
+    struct Brackets(String);
+
+    impl Visitor<char> for Brackets {
+        fn enter_node(&mut self, _: NodeId, node: INodeRef<'_, char>) {
+            self.0.push(*node.value());
+            self.0.push('(');
+        }
+
+        fn exit_node(&mut self, _: NodeId, _: INodeRef<'_, char>) {
+            self.0.push(')');
+        }
+    }
+
+    let tree = tree!{ 'a' => ['b' => ['c'], 'd'] };
+    let mut brackets = Brackets(String::new());
+    tree.visit(tree.root_id().unwrap(), &mut brackets);
+    assert_eq!(brackets.0, "a(b(c())d())");
+
+    brackets.0.clear();
+    tree.visit(NodeId::new(1), &mut brackets);
+    assert_eq!(brackets.0, "b(c())");
+    tree.visit(NodeId::new(9), &mut brackets);
+    assert_eq!(brackets.0, "b(c())");
+
+    /// Only counts how deep it gets
+    struct Depth(usize, usize);
+
+    impl<T> Visitor<T> for Depth {
+        fn enter_node(&mut self, _: NodeId, _: INodeRef<'_, T>) {
+            self.0 += 1;
+            self.1 = self.1.max(self.0);
+        }
+
+        fn exit_node(&mut self, _: NodeId, _: INodeRef<'_, T>) {
+            self.0 -= 1;
+        }
+    }
+
+    let deep = ITree::build_from(0, |&n| (n, if n < 100_000 { vec![n + 1] } else { Vec::new() }));
+    let mut depth = Depth(0, 0);
+    deep.visit(NodeId::new(0), &mut depth);
+    assert_eq!((depth.0, depth.1), (0, 100_001));
+}
+
+#[test]
+fn walk() {
+    // 0 -> 1 -> 2
+    // |    |--> 3
+    // |--> 4 -> 5
+    let tree = tree!{ 0 => [1 => [2, 3], 4 => [5]] };
+    let mut seen = Vec::new();
+    let broke = tree.walk(|_, &v| {
+        seen.push(v);
+        if v == 1 { Flow::SkipSubtree } else { Flow::Continue }
+    });
+    assert_eq!(broke, None);
+    assert_eq!(seen, [0, 1, 4, 5]);
+
+    seen.clear();
+    let broke = tree.walk(|_, &v| {
+        seen.push(v);
+        if v == 3 { Flow::Break } else { Flow::Continue }
+    });
+    assert_eq!(broke, Some(NodeId::new(3)));
+    assert_eq!(seen, [0, 1, 2, 3]);
+    assert_eq!(ITree::<u8>::new().walk(|_, _| Flow::Break), None);
+}
+
+#[test]
+fn euler_tour() {
+    use self::Event::*;
+
+    let tree = tree!{ 0 => [1 => [2], 3] };
+    let n = NodeId::new;
+    assert_eq!(tree.euler_tour(), vec![
+        (n(0), Enter),
+        (n(1), Enter),
+        (n(2), Enter),
+        (n(2), Exit),
+        (n(1), Exit),
+        (n(3), Enter),
+        (n(3), Exit),
+        (n(0), Exit),
+    ]);
+    assert!(ITree::<u8>::new().euler_tour().is_empty());
+}